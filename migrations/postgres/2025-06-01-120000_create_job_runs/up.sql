@@ -1,7 +1,7 @@
 /*
  *  ONVP Backend - Backend API provider for the ONVP website
  *
- * Copyright (c) 2024.  Sjoerd van Leent
+ * Copyright (c) 2025.  Sjoerd van Leent
  *
  * This program is free software: you can redistribute it and/or modify
  * it under the terms of the GNU Affero General Public License as
@@ -17,17 +17,15 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use dotenv::dotenv;
-use onvp_backend::commands;
-use onvp_backend::generic::storage::database;
-use std::error::Error;
+CREATE TABLE job_runs
+(
+    id          SERIAL PRIMARY KEY,
+    job_name    VARCHAR   NOT NULL,
+    started_at  TIMESTAMP NOT NULL,
+    finished_at TIMESTAMP NULL,
+    succeeded   BOOLEAN   NULL,
+    error       TEXT      NULL,
+    manual      BOOLEAN   NOT NULL
+);
 
-fn main() -> Result<(), Box<dyn Error>> {
-    env_logger::init();
-    dotenv().ok();
-    let pool = database::initialize_database_connection_pool();
-
-    commands::jobs::clean_late_non_activated_members(pool)?;
-
-    Ok(())
-}
+CREATE INDEX idx_job_runs ON job_runs (job_name, started_at);
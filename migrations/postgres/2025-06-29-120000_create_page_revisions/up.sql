@@ -0,0 +1,32 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2024-2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+CREATE TABLE page_revisions
+(
+    id            SERIAL PRIMARY KEY,
+    page_id       INTEGER   NOT NULL,
+    author_id     INTEGER   NULL,
+    creation_time TIMESTAMP NOT NULL,
+    title         VARCHAR   NOT NULL,
+    content       TEXT      NOT NULL,
+    CONSTRAINT fk_page_revision_page FOREIGN KEY (page_id) REFERENCES pages (id) ON DELETE CASCADE,
+    CONSTRAINT fk_page_revision_author FOREIGN KEY (author_id) REFERENCES members (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_page_revisions ON page_revisions (page_id, creation_time);
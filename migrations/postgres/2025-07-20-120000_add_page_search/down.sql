@@ -0,0 +1,26 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2024-2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+DROP FUNCTION page_search_snippet(TEXT, TSQUERY);
+DROP FUNCTION page_search_query(TEXT);
+DROP INDEX idx_pages_search_vector;
+DROP TRIGGER pages_search_vector_trigger ON pages;
+DROP FUNCTION pages_search_vector_update();
+ALTER TABLE pages DROP COLUMN search_vector;
+ALTER TABLE pages DROP COLUMN search_text;
@@ -0,0 +1,60 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2024-2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+-- The plain text of the published content, maintained by the backend when a page is published,
+-- pages which are not indexed yet are indexed by the index_page_content job
+ALTER TABLE pages ADD COLUMN search_text TEXT NULL;
+ALTER TABLE pages ADD COLUMN search_vector TSVECTOR NOT NULL DEFAULT ''::tsvector;
+
+-- Content is written in Dutch as well as in English, so both configurations are indexed. The
+-- vector is maintained by a trigger rather than being a generated column, such that backups can
+-- be restored using COPY
+CREATE FUNCTION pages_search_vector_update() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('dutch', NEW.title), 'A') ||
+        setweight(to_tsvector('english', NEW.title), 'A') ||
+        setweight(to_tsvector('dutch', coalesce(NEW.search_text, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(NEW.search_text, '')), 'B');
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER pages_search_vector_trigger
+    BEFORE INSERT OR UPDATE ON pages
+    FOR EACH ROW EXECUTE FUNCTION pages_search_vector_update();
+
+UPDATE pages SET search_vector = DEFAULT;
+
+CREATE INDEX idx_pages_search_vector ON pages USING GIN (search_vector);
+
+CREATE FUNCTION page_search_query(term TEXT) RETURNS TSQUERY AS $$
+    SELECT websearch_to_tsquery('dutch', term) || websearch_to_tsquery('english', term)
+$$ LANGUAGE SQL IMMUTABLE STRICT;
+
+-- Highlighted terms are marked using the control characters STX and ETX, which are never part of
+-- the search text, such that the backend can escape the snippet before marking them up
+CREATE FUNCTION page_search_snippet(document TEXT, query TSQUERY) RETURNS TEXT AS $$
+    SELECT ts_headline(
+        'dutch',
+        coalesce(document, ''),
+        query,
+        'StartSel=' || chr(2) || ', StopSel=' || chr(3) || ', MaxWords=30, MinWords=10, MaxFragments=2'
+    )
+$$ LANGUAGE SQL IMMUTABLE;
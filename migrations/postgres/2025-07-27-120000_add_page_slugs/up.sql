@@ -0,0 +1,52 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2024-2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+-- Existing pages receive a slug derived from their title, like the backend derives it, cut off
+-- at the maximum length of 80 characters
+ALTER TABLE pages ADD COLUMN slug VARCHAR NULL;
+UPDATE pages SET slug = trim(BOTH '-' FROM left(trim(LEADING '-' FROM regexp_replace(
+    translate(lower(title), 'àáâãäåèéêëìíîïòóôõöùúûüýÿñç', 'aaaaaaeeeeiiiiooooouuuuyync'),
+    '[^a-z0-9]+', '-', 'g'
+)), 80));
+UPDATE pages SET slug = 'page-' || id WHERE slug = '';
+
+-- Pages sharing a slug below the same parent page are told apart by their identifier, for which
+-- the slug is shortened if needed
+UPDATE pages SET slug = rtrim(left(slug, 79 - length(id::text)), '-') || '-' || id
+WHERE id IN (
+    SELECT id FROM (
+        SELECT id, row_number() OVER (PARTITION BY coalesce(parent_id, 0), slug ORDER BY id) AS n
+        FROM pages
+    ) AS numbered
+    WHERE n > 1
+);
+
+ALTER TABLE pages ALTER COLUMN slug SET NOT NULL;
+CREATE UNIQUE INDEX idx_pages_parent_slug ON pages (coalesce(parent_id, 0), slug);
+
+-- The former paths of pages, which are permanently redirected to the current path of the page
+CREATE TABLE page_redirects
+(
+    path          VARCHAR PRIMARY KEY,
+    page_id       INTEGER   NOT NULL,
+    creation_time TIMESTAMP NOT NULL,
+    CONSTRAINT fk_page_redirect_page FOREIGN KEY (page_id) REFERENCES pages (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_page_redirects ON page_redirects (page_id);
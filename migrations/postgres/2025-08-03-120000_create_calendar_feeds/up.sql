@@ -0,0 +1,32 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2024-2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+-- Incremented on every change of a page, such that calendar clients replace their copy of the event
+ALTER TABLE pages ADD COLUMN change_sequence INTEGER NOT NULL DEFAULT 0;
+
+-- Only the SHA-256 hash of the token of a feed is stored, as the token grants access by itself
+CREATE TABLE calendar_feeds
+(
+    member_id     INTEGER PRIMARY KEY,
+    token_hash    VARCHAR   NOT NULL,
+    creation_time TIMESTAMP NOT NULL,
+    CONSTRAINT fk_calendar_feed_member FOREIGN KEY (member_id) REFERENCES members (id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_calendar_feeds_token_hash ON calendar_feeds (token_hash);
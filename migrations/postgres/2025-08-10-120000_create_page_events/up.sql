@@ -0,0 +1,53 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2024-2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+CREATE TABLE venues
+(
+    id      SERIAL PRIMARY KEY,
+    name    VARCHAR NOT NULL,
+    address VARCHAR NULL,
+    etag    VARCHAR NOT NULL DEFAULT substr(md5(random()::text), 1, 16)
+);
+
+-- The times are local times in the time zone of the event, such that recurring events keep their
+-- time of day when summer time starts or ends
+CREATE TABLE page_events
+(
+    page_id         INTEGER PRIMARY KEY,
+    starts_at       TIMESTAMP NOT NULL,
+    ends_at         TIMESTAMP NOT NULL,
+    time_zone       VARCHAR   NOT NULL,
+    venue_id        INTEGER   NULL,
+    recurrence_rule VARCHAR   NULL,
+    -- The last day of the last occurrence, not set if the event recurs endlessly
+    last_date       DATE      NULL,
+    CONSTRAINT fk_page_event_page FOREIGN KEY (page_id) REFERENCES pages (id) ON DELETE CASCADE,
+    CONSTRAINT fk_page_event_venue FOREIGN KEY (venue_id) REFERENCES venues (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_page_events_venue ON page_events (venue_id);
+
+-- The days on which a recurring event does not take place
+CREATE TABLE page_event_exceptions
+(
+    page_id         INTEGER NOT NULL,
+    occurrence_date DATE    NOT NULL,
+    PRIMARY KEY (page_id, occurrence_date),
+    CONSTRAINT fk_page_event_exception_event FOREIGN KEY (page_id) REFERENCES page_events (page_id) ON DELETE CASCADE
+);
@@ -0,0 +1,47 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2024-2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+-- The responses of members to the occurrences of events, the occurrence date is the event date of
+-- events which do not recur
+CREATE TABLE event_attendances
+(
+    page_id         INTEGER   NOT NULL,
+    occurrence_date DATE      NOT NULL,
+    member_id       INTEGER   NOT NULL,
+    status          VARCHAR   NOT NULL,
+    note            TEXT      NULL,
+    response_time   TIMESTAMP NOT NULL,
+    PRIMARY KEY (page_id, occurrence_date, member_id),
+    CONSTRAINT fk_event_attendance_page FOREIGN KEY (page_id) REFERENCES pages (id) ON DELETE CASCADE,
+    CONSTRAINT fk_event_attendance_member FOREIGN KEY (member_id) REFERENCES members (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_event_attendances_member ON event_attendances (member_id);
+
+-- The members who were reminded to respond to an occurrence, such that they are reminded once
+CREATE TABLE event_attendance_reminders
+(
+    page_id         INTEGER   NOT NULL,
+    occurrence_date DATE      NOT NULL,
+    member_id       INTEGER   NOT NULL,
+    sending_time    TIMESTAMP NOT NULL,
+    PRIMARY KEY (page_id, occurrence_date, member_id),
+    CONSTRAINT fk_event_attendance_reminder_page FOREIGN KEY (page_id) REFERENCES pages (id) ON DELETE CASCADE,
+    CONSTRAINT fk_event_attendance_reminder_member FOREIGN KEY (member_id) REFERENCES members (id) ON DELETE CASCADE
+);
@@ -0,0 +1,34 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2024-2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+-- The periods in which members are away, the start and end dates are included
+CREATE TABLE member_absences
+(
+    id         SERIAL PRIMARY KEY,
+    member_id  INTEGER NOT NULL,
+    start_date DATE    NOT NULL,
+    end_date   DATE    NOT NULL,
+    reason     TEXT    NULL,
+    visibility VARCHAR NOT NULL,
+    CONSTRAINT fk_member_absence_member FOREIGN KEY (member_id) REFERENCES members (id) ON DELETE CASCADE,
+    CONSTRAINT chk_member_absence_period CHECK (start_date <= end_date)
+);
+
+CREATE INDEX idx_member_absences_member ON member_absences (member_id);
+CREATE INDEX idx_member_absences_period ON member_absences (start_date, end_date);
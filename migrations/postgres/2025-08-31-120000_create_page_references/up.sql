@@ -0,0 +1,41 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2024-2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+-- The images and event pages referred to by shortcodes in the content of pages, such that the
+-- stored renderings of the content are discarded when a referred entity changes. The referred
+-- entities may not exist (anymore), as shortcodes are written by hand.
+CREATE TABLE page_image_references
+(
+    page_id  INTEGER NOT NULL,
+    image_id INTEGER NOT NULL,
+    PRIMARY KEY (page_id, image_id),
+    CONSTRAINT fk_page_image_reference_page FOREIGN KEY (page_id) REFERENCES pages (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_page_image_references_image ON page_image_references (image_id);
+
+CREATE TABLE page_event_references
+(
+    page_id       INTEGER NOT NULL,
+    event_page_id INTEGER NOT NULL,
+    PRIMARY KEY (page_id, event_page_id),
+    CONSTRAINT fk_page_event_reference_page FOREIGN KEY (page_id) REFERENCES pages (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_page_event_references_event_page ON page_event_references (event_page_id);
@@ -20,11 +20,20 @@ use crate::api::middleware::authority::config::AuthorityConfig;
 use crate::api::middleware::authority::Allowance::{Any, LoggedInMember, RoleAuthority};
 use crate::generic::http::Method::{Del, Get, Post, Put};
 use crate::model::primitives::{Role, RoleComposition};
+use std::collections::HashSet;
 
 pub fn configure_authority() -> AuthorityConfig {
     let director_authority = RoleAuthority(RoleComposition::from(Role::Director));
+    let committee_authority = RoleAuthority(RoleComposition::from(HashSet::from([
+        Role::OrchestraCommittee,
+        Role::Director,
+        Role::Operator,
+    ])));
+    let operator_authority = RoleAuthority(RoleComposition::from(Role::Operator));
     AuthorityConfig::new()
         .allow(Get, "/docs", Any)
+        .allow(Get, "/health/**", Any)
+        .allow(Get, "/metrics", Any)
         .allow(Get, "/api/facebook/v1/**", Any)
         .allow(Get, "/api/setup/v1/**", Any)
         .allow(Post, "/api/setup/v1/**", Any)
@@ -35,18 +44,47 @@ pub fn configure_authority() -> AuthorityConfig {
         .allow(Post, "/api/members/v1/activation/activate", Any)
         .allow(Get, "/api/members/v1/picture_asset", LoggedInMember)
         .allow(Get, "/api/members/v1/picture", LoggedInMember)
+        .allow(Get, "/api/members/v1/me/absences", LoggedInMember)
+        .allow(Post, "/api/members/v1/me/absences", LoggedInMember)
+        .allow(Put, "/api/members/v1/me/absences/*", LoggedInMember)
+        .allow(Del, "/api/members/v1/me/absences/*", LoggedInMember)
         .allow(Get, "/api/workgroups/v1/search", LoggedInMember)
         .allow(Get, "/api/workgroups/v1/**", LoggedInMember)
         .allow(Get, "/api/source_code_details/v1/**", Any)
         .allow(Get, "/api/pages/v1/main-menu", Any)
         .allow(Get, "/api/pages/v1/sub-menu/**", Any)
+        .allow(Get, "/api/pages/v1/tree", Any)
+        .allow(Get, "/api/pages/v1/by-path/**", Any)
         .allow(Get, "/api/pages/v1/default", Any)
         .allow(Get, "/api/pages/v1/search", Any)
+        .allow(
+            Get,
+            "/api/pages/v1/page/*/revisions*",
+            operator_authority.clone(),
+        )
+        .allow(
+            Get,
+            "/api/pages/v1/page/*/draft",
+            operator_authority.clone(),
+        )
         .allow(Get, "/api/pages/v1/page/**", Any)
         .allow(Get, "/api/pages/v1/events", Any)
+        .allow(
+            Get,
+            "/api/attendance/v1/event/*/*/report*",
+            committee_authority,
+        )
+        .allow(Get, "/api/attendance/v1/event/*/*", LoggedInMember)
+        .allow(Put, "/api/attendance/v1/event/*/*", LoggedInMember)
+        .allow(Get, "/api/calendar/v1/events.ics", Any)
+        .allow(Get, "/api/calendar/v1/feed/*/events.ics", Any)
+        .allow(Get, "/api/calendar/v1/feed", LoggedInMember)
+        .allow(Post, "/api/calendar/v1/feed", LoggedInMember)
+        .allow(Del, "/api/calendar/v1/feed", LoggedInMember)
         .allow(Get, "/api/images/v1/image/**", Any)
         .allow(Get, "/api/images/v1/asset/**", Any)
         .allow(Get, "/api/musical-instruments/v1/**", Any)
+        .allow(Get, "/api/venues/v1/list", Any)
         .allow(Get, "api/mail-templates/v1/**", director_authority.clone())
         .allow(Post, "api/mail-templates/v1/**", director_authority.clone())
         .allow(Put, "api/mail-templates/v1/**", director_authority.clone())
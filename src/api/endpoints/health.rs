@@ -0,0 +1,189 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Operational endpoints, used by orchestrators and monitoring. These are deliberately not
+//! versioned, as their paths are configured in external systems.
+
+use crate::api;
+use crate::generic::metrics::Metrics;
+use crate::generic::settings::Settings;
+use crate::generic::storage::assets::AssetStore;
+use crate::generic::storage::database::DatabaseConnectionPool;
+use actix_web::http::header;
+use actix_web::web::Data;
+use actix_web::{get, HttpRequest, HttpResponse};
+use diesel::RunQueryDsl;
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Maximum time to wait for a database connection when checking readiness
+const DATABASE_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Shows whether the backend is running, without checking any of its dependencies
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "The backend is running")
+    )
+)]
+#[get("/live")]
+pub async fn live() -> HttpResponse {
+    HttpResponse::Ok().finish()
+}
+
+/// Shows whether the backend is able to serve requests, by checking the database, the asset
+/// store, the JWT keys and the SMTP configuration
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "The backend is ready", body = ReadinessResponse),
+        (status = 503, description = "One or more checks failed", body = ReadinessResponse)
+    )
+)]
+#[get("/ready")]
+pub async fn ready(
+    pool: Data<DatabaseConnectionPool>,
+    settings: Data<Settings>,
+    asset_store: Data<dyn AssetStore>,
+) -> HttpResponse {
+    let checks = vec![
+        ReadinessCheck::from_result("database", check_database(&pool)),
+        ReadinessCheck::from_result("assets", check_assets(asset_store.get_ref())),
+        ReadinessCheck::from_result("jwtKeys", check_jwt_keys(&settings.security.jwt_keys)),
+        ReadinessCheck::from_result("smtp", check_smtp(&settings)),
+    ];
+    let response = ReadinessResponse {
+        ready: checks.iter().all(|check| check.ok),
+        checks,
+    };
+    if response.ready {
+        HttpResponse::Ok().json(response)
+    } else {
+        HttpResponse::ServiceUnavailable().json(response)
+    }
+}
+
+/// Shows the metrics of the backend in the Prometheus text exposition format, to clients
+/// presenting the configured bearer token
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "The metrics", content_type = "text/plain"),
+        (status = 401, description = "The bearer token is missing or invalid"),
+        (status = 404, description = "The metrics are not exposed, as no token is configured")
+    )
+)]
+#[get("/metrics")]
+pub async fn metrics(
+    req: HttpRequest,
+    metrics: Data<Metrics>,
+    pool: Data<DatabaseConnectionPool>,
+    settings: Data<Settings>,
+) -> HttpResponse {
+    let token = &settings.metrics.token;
+    if token.is_empty() {
+        return HttpResponse::NotFound().finish();
+    }
+    let presented = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !presented.is_some_and(|presented| tokens_equal(presented, token)) {
+        return HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .finish();
+    }
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE))
+        .body(metrics.render(&pool))
+}
+
+/// Compares the tokens in a time independent of the position of the first difference
+fn tokens_equal(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn check_database(pool: &DatabaseConnectionPool) -> Result<(), String> {
+    let mut conn = pool
+        .get_timeout(DATABASE_CHECK_TIMEOUT)
+        .map_err(|e| e.to_string())?;
+    diesel::sql_query("SELECT 1")
+        .execute(&mut conn)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn check_assets(asset_store: &dyn AssetStore) -> Result<(), String> {
+    let probe = format!(".readiness-{}", std::process::id());
+    asset_store
+        .put(&probe, b"")
+        .and_then(|_| asset_store.delete(&probe))
+        .map_err(|e| format!("The asset store is not writable: {e}"))
+}
+
+fn check_jwt_keys(path: &Path) -> Result<(), String> {
+    api::read_key_pair(path)
+        .map(|_| ())
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn check_smtp(settings: &Settings) -> Result<(), String> {
+    let problems = settings.email.problems();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join(", "))
+    }
+}
+
+#[derive(Serialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessResponse {
+    ready: bool,
+    checks: Vec<ReadinessCheck>,
+}
+
+#[derive(Serialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessCheck {
+    name: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl ReadinessCheck {
+    fn from_result(name: &str, result: Result<(), String>) -> Self {
+        Self {
+            name: name.to_owned(),
+            ok: result.is_ok(),
+            detail: result.err(),
+        }
+    }
+}
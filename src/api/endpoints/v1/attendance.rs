@@ -0,0 +1,167 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::BackendResult;
+use crate::generic::security::ClaimRoles;
+use crate::generic::storage::session::Session;
+use crate::generic::validation::ValidatedJson;
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::commands::RegisterAttendanceCommand;
+use crate::model::interface::responses::{AttendanceReportResponse, AttendanceResponse};
+use crate::services::definitions::command::AttendanceCommandService;
+use crate::services::definitions::request::AttendanceRequestService;
+use actix_web::http::header;
+use actix_web::web::{Data, Json, Path};
+use actix_web::{get, put, HttpResponse};
+use chrono::NaiveDate;
+
+/// Returns the response of the logged in member to an occurrence of an event
+///
+/// The occurrence is identified by the event page and the day on which it starts.
+#[utoipa::path(
+    tag = "attendance",
+    responses(
+        (status = 200, description = "The response, if the member responded", body=Option<AttendanceResponse>),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    ),
+    params(
+        ("id" = i32, Path, description = "The identifier of the event page"),
+        ("date" = NaiveDate, Path, description = "The day on which the occurrence starts")
+    )
+)]
+#[get("/event/{id}/{date}")]
+pub async fn find(
+    path: Path<(i32, NaiveDate)>,
+    claims: UserClaims,
+    roles: ClaimRoles,
+    service: Data<dyn AttendanceRequestService>,
+    session: Session,
+) -> BackendResult<Json<Option<AttendanceResponse>>> {
+    let (page_id, occurrence_date) = path.into_inner();
+    Ok(Json(service.find(
+        session,
+        page_id,
+        occurrence_date,
+        &claims,
+        &roles,
+    )?))
+}
+
+/// Registers whether the logged in member attends an occurrence of an event
+///
+/// Responding again replaces the former response. Occurrences which have already taken place
+/// can no longer be responded to.
+#[utoipa::path(
+    request_body = RegisterAttendanceCommand,
+    tag = "attendance",
+    responses(
+        (status = 200, description = "The response is registered"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 409, description = "The occurrence has already taken place", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    ),
+    params(
+        ("id" = i32, Path, description = "The identifier of the event page"),
+        ("date" = NaiveDate, Path, description = "The day on which the occurrence starts")
+    )
+)]
+#[put("/event/{id}/{date}")]
+pub async fn register(
+    path: Path<(i32, NaiveDate)>,
+    claims: UserClaims,
+    roles: ClaimRoles,
+    command: ValidatedJson<RegisterAttendanceCommand>,
+    service: Data<dyn AttendanceCommandService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    let (page_id, occurrence_date) = path.into_inner();
+    service.register(session, page_id, occurrence_date, &claims, &roles, &command)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Reports the responses to an occurrence of an event by section
+///
+/// The members expected at the event who did not respond yet are included, only the committee
+/// has access to the report.
+#[utoipa::path(
+    tag = "attendance",
+    responses(
+        (status = 200, description = "The report", body=AttendanceReportResponse),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 403, description = "Forbidden", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    ),
+    params(
+        ("id" = i32, Path, description = "The identifier of the event page"),
+        ("date" = NaiveDate, Path, description = "The day on which the occurrence starts")
+    )
+)]
+#[get("/event/{id}/{date}/report")]
+pub async fn report(
+    path: Path<(i32, NaiveDate)>,
+    roles: ClaimRoles,
+    service: Data<dyn AttendanceRequestService>,
+    session: Session,
+) -> BackendResult<Json<AttendanceReportResponse>> {
+    let (page_id, occurrence_date) = path.into_inner();
+    Ok(Json(service.report(
+        session,
+        page_id,
+        occurrence_date,
+        &roles,
+    )?))
+}
+
+/// Exports the responses to an occurrence of an event as CSV
+///
+/// Contains a line for every member of the report, ordered by section.
+#[utoipa::path(
+    tag = "attendance",
+    responses(
+        (status = 200, description = "The report as CSV", content_type="text/csv"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 403, description = "Forbidden", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    ),
+    params(
+        ("id" = i32, Path, description = "The identifier of the event page"),
+        ("date" = NaiveDate, Path, description = "The day on which the occurrence starts")
+    )
+)]
+#[get("/event/{id}/{date}/report.csv")]
+pub async fn export(
+    path: Path<(i32, NaiveDate)>,
+    roles: ClaimRoles,
+    service: Data<dyn AttendanceRequestService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    let (page_id, occurrence_date) = path.into_inner();
+    let attendance_report = service.report(session, page_id, occurrence_date, &roles)?;
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, "text/csv; charset=utf-8"))
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"attendance-{page_id}-{occurrence_date}.csv\""),
+        ))
+        .body(attendance_report.to_csv()))
+}
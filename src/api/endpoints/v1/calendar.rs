@@ -0,0 +1,143 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::generic::icalendar;
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::responses::CalendarFeedResponse;
+use crate::services::definitions::command::CalendarCommandService;
+use crate::services::definitions::request::CalendarRequestService;
+use actix_web::http::header::IfNoneMatch;
+use actix_web::web::{Data, Header, Json, Path};
+use actix_web::{delete, get, post, HttpResponse, HttpResponseBuilder};
+
+/// Returns the calendar feed of the public events
+///
+/// Returns the events which are visible to the public as an iCalendar feed, to be subscribed
+/// to by calendar applications.
+#[utoipa::path(
+    tag = "events",
+    responses(
+        (status = 200, description = "The calendar feed", content_type="text/calendar"),
+        (status = 304, description = "The calendar feed is not modified"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/events.ics")]
+pub async fn public_feed(
+    service: Data<dyn CalendarRequestService>,
+    session: Session,
+    if_none_match: Option<Header<IfNoneMatch>>,
+) -> BackendResult<HttpResponse> {
+    let result = service.public_feed(session)?;
+    Ok(result.respond(if_none_match.as_deref(), calendar_body))
+}
+
+/// Returns the personal calendar feed of a member
+///
+/// Returns the events which are visible to the member owning the token as an iCalendar feed.
+/// Calendar applications can not log in, hence the token grants access instead.
+#[utoipa::path(
+    tag = "events",
+    responses(
+        (status = 200, description = "The calendar feed", content_type="text/calendar"),
+        (status = 304, description = "The calendar feed is not modified"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 403, description = "The token is unknown or revoked, or the member is deactivated", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/feed/{token}/events.ics")]
+pub async fn personal_feed(
+    token: Path<String>,
+    service: Data<dyn CalendarRequestService>,
+    session: Session,
+    if_none_match: Option<Header<IfNoneMatch>>,
+) -> BackendResult<HttpResponse> {
+    let result = service.personal_feed(session, &token)?;
+    Ok(result.respond(if_none_match.as_deref(), calendar_body))
+}
+
+/// Returns the personal calendar feed of the logged in member, if the member has one
+#[utoipa::path(
+    tag = "events",
+    responses(
+        (status = 200, description = "The calendar feed", body=Option<CalendarFeedResponse>),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/feed")]
+pub async fn find_feed(
+    claims: UserClaims,
+    service: Data<dyn CalendarRequestService>,
+    session: Session,
+) -> BackendResult<Json<Option<CalendarFeedResponse>>> {
+    Ok(Json(service.find_feed(session, &claims)?))
+}
+
+/// Creates or renews the personal calendar feed of the logged in member
+///
+/// Generates a new token for the feed, the former token no longer grants access.
+#[utoipa::path(
+    tag = "events",
+    responses(
+        (status = 200, description = "The calendar feed", body=CalendarFeedResponse),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[post("/feed")]
+pub async fn renew_feed(
+    claims: UserClaims,
+    service: Data<dyn CalendarCommandService>,
+    session: Session,
+) -> BackendResult<Json<CalendarFeedResponse>> {
+    Ok(Json(service.renew_feed(session, &claims)?))
+}
+
+/// Removes the personal calendar feed of the logged in member, revoking its token
+#[utoipa::path(
+    tag = "events",
+    responses(
+        (status = 200, description = "The calendar feed is removed"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[delete("/feed")]
+pub async fn revoke_feed(
+    claims: UserClaims,
+    service: Data<dyn CalendarCommandService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    service.revoke_feed(session, &claims)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+fn calendar_body(response: &mut HttpResponseBuilder, feed: String) -> HttpResponse {
+    response
+        .insert_header(("content-type", icalendar::CONTENT_TYPE))
+        .body(feed)
+}
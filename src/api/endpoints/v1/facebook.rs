@@ -16,13 +16,16 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::imaging::Variant;
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
+use crate::model::interface::requests::{ImageSize, ImageVariantParams};
 use crate::model::interface::responses::FacebookResponse;
 use crate::model::interface::search::{SearchParams, SearchResult};
 use crate::model::primitives::Role;
 use crate::services::definitions::request::{FacebookRequestService, MemberPictureRequestService};
-use actix_web::web::{Bytes, Data, Json, Path, Query};
+use actix_web::http::header::{Accept, IfNoneMatch, VARY};
+use actix_web::web::{Bytes, Data, Header, Json, Path, Query};
 use actix_web::{get, HttpResponse};
 use std::ops::Deref;
 
@@ -53,14 +56,22 @@ pub async fn search(
 }
 
 /// Retrieves the picture of a member, if available
+///
+/// A width-limited variant is selected using either the `w` or the `size` parameter, which is
+/// served as WebP if the `Accept` header of the client allows it.
 #[utoipa::path(
     tag = "facebook",
     responses(
-        (status = 200, description = "Successful picture retrieval", content_type="image/png"),
+        (status = 200, description = "Successful picture retrieval", content(("image/png"), ("image/webp"))),
+        (status = 304, description = "Picture not modified"),
         (status = 410, description = "Picture not available"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
+    ),
+    params(
+        ("w" = Option<u32>, Query, description = "The maximum width of the picture, rounded up to 160, 480 or 1024 pixels"),
+        ("size" = Option<ImageSize>, Query, description = "The named size of the picture"),
     )
 )]
 #[get("/{id}/picture.png")]
@@ -68,12 +79,20 @@ pub async fn picture_asset(
     session: Session,
     service: Data<dyn MemberPictureRequestService>,
     id: Path<i32>,
+    variant_params: Query<ImageVariantParams>,
+    accept: Option<Header<Accept>>,
+    if_none_match: Option<Header<IfNoneMatch>>,
 ) -> BackendResult<HttpResponse> {
-    let result = service.find_asset_by_member_id(session, id.into_inner(), &Role::Public)?;
+    let variant = Variant::negotiate(variant_params.width(), accept.as_deref());
+    let result =
+        service.find_asset_by_member_id(session, id.into_inner(), &Role::Public, &variant)?;
     match result {
         None => Ok(HttpResponse::Gone().finish()),
-        Some(data) => Ok(HttpResponse::Ok()
-            .insert_header(data.content_type)
-            .body(Bytes::from(data.bytes))),
+        Some(cached) => Ok(cached.respond(if_none_match.as_deref(), |response, data| {
+            response
+                .insert_header(data.content_type)
+                .insert_header((VARY, "Accept"))
+                .body(Bytes::from(data.bytes))
+        })),
     }
 }
@@ -16,15 +16,19 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::imaging::Variant;
 use crate::generic::result::BackendResult;
 use crate::generic::security::ClaimRoles;
 use crate::generic::storage::session::Session;
+use crate::generic::validation::{Validate, ValidatedJson};
 use crate::model::interface::commands::{ImageUploadCommand, PublishImageCommand};
+use crate::model::interface::requests::{ImageSize, ImageVariantParams};
 use crate::model::interface::responses::ImageMetaDataResponse;
 use crate::model::interface::search::{SearchParams, SearchResult};
 use crate::services::definitions::command::ImageCommandService;
 use crate::services::definitions::request::ImageRequestService;
-use actix_web::web::{Bytes, Data, Json, Path, Query};
+use actix_web::http::header::{Accept, IfNoneMatch, VARY};
+use actix_web::web::{Bytes, Data, Header, Json, Path, Query};
 use actix_web::{delete, get, post, HttpResponse};
 use serde::Deserialize;
 use std::ops::Deref;
@@ -57,7 +61,7 @@ pub async fn search(
 
 /// Creates a new image
 #[utoipa::path(
-    request_body(content(("image/png"), ("image/jpg"))),
+    request_body(content(("image/png"), ("image/jpeg"), ("image/gif"), ("image/webp"))),
     tag = "images",
     responses(
         (status = 200, description = "A new image is created"),
@@ -81,6 +85,7 @@ pub async fn upload(
         title: r.title.clone(),
         data,
     };
+    command.validate()?;
     Ok(Json(service.upload(session, &command)?))
 }
 
@@ -109,13 +114,22 @@ pub async fn find_by_id(
 }
 
 /// Returns an image asset
+///
+/// The image is served in the format it is stored in, regardless of the extension used. A
+/// width-limited variant is selected using either the `w` or the `size` parameter, which is
+/// served as WebP if the `Accept` header of the client allows it.
 #[utoipa::path(
     tag = "images",
     responses(
-        (status = 200, description = "The image metadata", content_type="image/png"),
+        (status = 200, description = "The image content", content(("image/png"), ("image/jpeg"), ("image/gif"), ("image/webp"))),
+        (status = 304, description = "The image is not modified"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
+    ),
+    params(
+        ("w" = Option<u32>, Query, description = "The maximum width of the image, rounded up to 160, 480 or 1024 pixels"),
+        ("size" = Option<ImageSize>, Query, description = "The named size of the image"),
     )
 )]
 #[get("/asset/{id}.png")]
@@ -124,15 +138,23 @@ pub async fn asset(
     id: Path<i32>,
     service: Data<dyn ImageRequestService>,
     roles: ClaimRoles,
+    variant_params: Query<ImageVariantParams>,
+    accept: Option<Header<Accept>>,
+    if_none_match: Option<Header<IfNoneMatch>>,
 ) -> BackendResult<HttpResponse> {
-    let result = service.find_content_by_id(session, id.into_inner(), &roles)?;
-    Ok(HttpResponse::Ok()
-        .insert_header(result.content_type)
-        .body(Bytes::from(result.bytes)))
+    let variant = Variant::negotiate(variant_params.width(), accept.as_deref());
+    let result = service.find_content_by_id(session, id.into_inner(), &roles, &variant)?;
+    Ok(result.respond(if_none_match.as_deref(), |response, image| {
+        response
+            .insert_header(image.content_type)
+            .insert_header((VARY, "Accept"))
+            .body(Bytes::from(image.bytes))
+    }))
 }
 
 /// Publish an existing image
 #[utoipa::path(
+    request_body = PublishImageCommand,
     tag = "images",
     responses(
         (status = 200, description = "Page is published"),
@@ -145,7 +167,7 @@ pub async fn asset(
 pub async fn publish(
     session: Session,
     id: Path<i32>,
-    command: Json<PublishImageCommand>,
+    command: ValidatedJson<PublishImageCommand>,
     service: Data<dyn ImageCommandService>,
 ) -> BackendResult<HttpResponse> {
     service.publish(session, id.into_inner(), &command)?;
@@ -0,0 +1,85 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::model::interface::responses::{JobResponse, JobRunResponse};
+use crate::services::definitions::command::JobCommandService;
+use crate::services::definitions::request::JobRequestService;
+use actix_web::web::{self, Data, Json, Path};
+use actix_web::{get, post};
+
+/// Lists all jobs, including their schedule and most recent run
+#[utoipa::path(
+    tag = "jobs",
+    responses(
+        (status = 200, description = "A list of jobs", body=Vec<JobResponse>),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/list")]
+pub async fn list(
+    service: Data<dyn JobRequestService>,
+    session: Session,
+) -> BackendResult<Json<Vec<JobResponse>>> {
+    Ok(Json(service.list(session)?))
+}
+
+/// Lists the most recent runs of a job, newest first
+#[utoipa::path(
+    tag = "jobs",
+    responses(
+        (status = 200, description = "A list of job runs", body=Vec<JobRunResponse>),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/job/{name}/runs")]
+pub async fn runs(
+    name: Path<String>,
+    service: Data<dyn JobRequestService>,
+    session: Session,
+) -> BackendResult<Json<Vec<JobRunResponse>>> {
+    Ok(Json(service.runs(session, &name)?))
+}
+
+/// Runs a job immediately, returning the outcome of the run
+#[utoipa::path(
+    tag = "jobs",
+    responses(
+        (status = 200, description = "The job has run", body=JobRunResponse),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 409, description = "The job is already running", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[post("/job/{name}/trigger")]
+pub async fn trigger(
+    name: Path<String>,
+    service: Data<dyn JobCommandService>,
+) -> BackendResult<Json<JobRunResponse>> {
+    // A job may take long, which would otherwise stall the worker handling other requests
+    let service = service.into_inner();
+    let run = web::block(move || service.trigger(&name)).await??;
+    Ok(Json(run))
+}
@@ -16,8 +16,10 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::Precondition;
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
+use crate::generic::validation::ValidatedJson;
 use crate::model::interface::commands::{CreateMailTemplateCommand, UpdateMailTemplateCommand};
 use crate::model::interface::responses::{MailTemplateNameResponse, MailTemplateResponse};
 use crate::services::definitions::command::MailTemplateCommandService;
@@ -45,6 +47,7 @@ pub async fn list(
 
 /// Creates a new email template
 #[utoipa::path(
+    request_body = CreateMailTemplateCommand,
     tag = "mail-templates",
     responses(
         (status = 200, description = "A new email template is created"),
@@ -55,7 +58,7 @@ pub async fn list(
 )]
 #[post("/template/")]
 pub async fn create(
-    command: Json<CreateMailTemplateCommand>,
+    command: ValidatedJson<CreateMailTemplateCommand>,
     service: Data<dyn MailTemplateCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
@@ -84,22 +87,25 @@ pub async fn find_by_id(
 
 /// Updates a registered email template
 #[utoipa::path(
+    request_body = UpdateMailTemplateCommand,
     tag = "mail-templates",
     responses(
         (status = 200, description = "Email template data is updated"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
     )
 )]
 #[put("/template/{id}")]
 pub async fn update(
     id: Path<i32>,
-    command: Json<UpdateMailTemplateCommand>,
+    precondition: Precondition,
+    command: ValidatedJson<UpdateMailTemplateCommand>,
     service: Data<dyn MailTemplateCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
-    service.update(session, id.into_inner(), &command)?;
+    service.update(session, id.into_inner(), &precondition, &command)?;
     Ok(HttpResponse::Ok().finish())
 }
 
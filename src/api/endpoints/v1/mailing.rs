@@ -18,13 +18,15 @@
  */
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
+use crate::generic::validation::ValidatedJson;
 use crate::model::interface::commands::SendMailCommand;
 use crate::services::definitions::command::MailingCommandService;
-use actix_web::web::{Data, Json};
+use actix_web::web::Data;
 use actix_web::{post, HttpResponse};
 
 /// Sends an email based on an email template
 #[utoipa::path(
+    request_body = SendMailCommand,
     tag = "mailing",
     responses(
         (status = 200, description = "An email is sent"),
@@ -35,7 +37,7 @@ use actix_web::{post, HttpResponse};
 )]
 #[post("/send")]
 pub async fn send(
-    command: Json<SendMailCommand>,
+    command: ValidatedJson<SendMailCommand>,
     service: Data<dyn MailingCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
@@ -20,24 +20,34 @@
 //! Members are a very core component of the backend and involve a lot of interfaces regarding
 //! member management as well as performing requests regarding members from normal website usage.
 
+use crate::generic::caching::Precondition;
+use crate::generic::imaging::Variant;
 use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::Settings;
 use crate::generic::storage::session::Session;
+use crate::generic::validation::{Validate, ValidatedJson};
 use crate::model::interface::client::UserClaims;
 use crate::model::interface::commands::{
     MemberActivationCommand, MemberImageUploadCommand, MemberRegisterCommand,
     MemberUpdateAddressCommand, MemberUpdateCommand, MemberUpdatePrivacyInfoSharingCommand,
+    RegisterAbsenceCommand, UpdateAbsenceCommand,
 };
+use crate::model::interface::requests::{ImageSize, ImageVariantParams};
 use crate::model::interface::responses::{
-    ImageAssetIdResponse, MemberAddressResponse, MemberPrivacyInfoSharingResponse, MemberResponse,
-    WorkgroupResponse,
+    AbsenceResponse, ImageAssetIdResponse, MemberAddressResponse, MemberPrivacyInfoSharingResponse,
+    MemberResponse, WorkgroupResponse,
 };
 use crate::model::interface::search::{SearchParams, SearchResult};
 use crate::services::definitions::command::{
-    MemberActivationCommandService, MemberCommandService, MemberPictureCommandService,
+    AbsenceCommandService, MemberActivationCommandService, MemberCommandService,
+    MemberPictureCommandService,
 };
-use crate::services::definitions::request::{MemberPictureRequestService, MemberRequestService};
-use actix_web::web::{Bytes, Data, Json, Path, Query};
-use actix_web::{delete, get, post, HttpResponse};
+use crate::services::definitions::request::{
+    AbsenceRequestService, MemberPictureRequestService, MemberRequestService,
+};
+use actix_web::http::header::{Accept, IfNoneMatch, VARY};
+use actix_web::web::{Bytes, Data, Header, Json, Path, Query};
+use actix_web::{delete, get, post, put, HttpResponse};
 use std::ops::Deref;
 use totp_rs::TOTP;
 
@@ -46,6 +56,7 @@ use totp_rs::TOTP;
 /// Registers a new member with the necessary details. Sends an E-Mail to the
 /// newly registered member to activate the account.
 #[utoipa::path(
+    request_body = MemberRegisterCommand,
     tag = "members",
     responses(
         (status = 200, description = "Successful registration", body=i32),
@@ -58,7 +69,7 @@ use totp_rs::TOTP;
 pub async fn register(
     session: Session,
     controller: Data<dyn MemberCommandService>,
-    command: Json<MemberRegisterCommand>,
+    command: ValidatedJson<MemberRegisterCommand>,
 ) -> BackendResult<Json<i32>> {
     Ok(Json(controller.register_inactive(session, &command)?))
 }
@@ -163,11 +174,13 @@ pub async fn find_privacy_info_sharing(
 ///
 /// Updates an existing member and primary detail record given the data.
 #[utoipa::path(
+    request_body = MemberUpdateCommand,
     tag = "members",
     responses(
         (status = 200, description = "Member is updated"),
         (status = 400, description = "Bad Request"),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal backend error", body=[String]),
     )
 )]
@@ -176,9 +189,10 @@ pub async fn update(
     session: Session,
     service: Data<dyn MemberCommandService>,
     id: Path<i32>,
-    command: Json<MemberUpdateCommand>,
+    precondition: Precondition,
+    command: ValidatedJson<MemberUpdateCommand>,
 ) -> BackendResult<HttpResponse> {
-    service.update(session, id.into_inner(), &command)?;
+    service.update(session, id.into_inner(), &precondition, &command)?;
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -186,11 +200,13 @@ pub async fn update(
 ///
 /// Given the address details of a member, saves te address details
 #[utoipa::path(
+    request_body = MemberUpdateAddressCommand,
     tag = "members",
     responses(
         (status = 200, description = "Member is updated"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal backend error", body=Option<String>),
     )
 )]
@@ -199,9 +215,10 @@ pub async fn update_address(
     session: Session,
     service: Data<dyn MemberCommandService>,
     id: Path<i32>,
-    command: Json<MemberUpdateAddressCommand>,
+    precondition: Precondition,
+    command: ValidatedJson<MemberUpdateAddressCommand>,
 ) -> BackendResult<HttpResponse> {
-    service.update_address(session, id.into_inner(), &command)?;
+    service.update_address(session, id.into_inner(), &precondition, &command)?;
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -209,11 +226,13 @@ pub async fn update_address(
 ///
 /// Given the new privacy information sharing details of a member, save the details
 #[utoipa::path(
+    request_body = MemberUpdatePrivacyInfoSharingCommand,
     tag = "members",
     responses(
         (status = 200, description = "Member is updated"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal backend error", body=Option<String>),
     )
 )]
@@ -222,9 +241,10 @@ pub async fn update_privacy_info_sharing(
     session: Session,
     service: Data<dyn MemberCommandService>,
     id: Path<i32>,
-    command: Json<MemberUpdatePrivacyInfoSharingCommand>,
+    precondition: Precondition,
+    command: ValidatedJson<MemberUpdatePrivacyInfoSharingCommand>,
 ) -> BackendResult<HttpResponse> {
-    service.update_privacy_info_sharing(session, id.into_inner(), &command)?;
+    service.update_privacy_info_sharing(session, id.into_inner(), &precondition, &command)?;
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -252,10 +272,10 @@ pub async fn find_workgroups(
 /// Upload the picture of a member
 ///
 /// Uploads the picture of a member, adjusting it to the appropriate size by cropping it and
-/// scaling it automatically. A multitude of file types are supported, but the resulting file type
-/// will always be of the PNG type.
+/// scaling it automatically. PNG, JPEG, GIF and WebP images are supported, but the resulting file
+/// type will always be of the PNG type.
 #[utoipa::path(
-    request_body(content(("image/png"), ("image/jpg"))),
+    request_body(content(("image/png"), ("image/jpeg"), ("image/gif"), ("image/webp"))),
     tag = "members",
     responses(
         (status = 200, description = "Successful upload of the picture", body=String),
@@ -271,19 +291,28 @@ pub async fn upload_picture_asset(
     id: Path<i32>,
     data: Bytes,
 ) -> BackendResult<Json<String>> {
-    let command = MemberImageUploadCommand::try_from(&data)?;
+    let command = MemberImageUploadCommand { data };
+    command.validate()?;
     Ok(Json(service.upload(session, id.into_inner(), &command)?))
 }
 
 /// Retrieves the picture of a member, if available
+///
+/// A width-limited variant is selected using either the `w` or the `size` parameter, which is
+/// served as WebP if the `Accept` header of the client allows it.
 #[utoipa::path(
     tag = "members",
     responses(
-        (status = 200, description = "Successful picture retrieval", content_type="image/png"),
+        (status = 200, description = "Successful picture retrieval", content(("image/png"), ("image/webp"))),
+        (status = 304, description = "Picture not modified"),
         (status = 410, description = "Picture not available"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
+    ),
+    params(
+        ("w" = Option<u32>, Query, description = "The maximum width of the picture, rounded up to 160, 480 or 1024 pixels"),
+        ("size" = Option<ImageSize>, Query, description = "The named size of the picture"),
     )
 )]
 #[get("/{id}/picture.png")]
@@ -292,13 +321,20 @@ pub async fn picture_asset(
     service: Data<dyn MemberPictureRequestService>,
     id: Path<i32>,
     claims: UserClaims,
+    variant_params: Query<ImageVariantParams>,
+    accept: Option<Header<Accept>>,
+    if_none_match: Option<Header<IfNoneMatch>>,
 ) -> BackendResult<HttpResponse> {
-    let result = service.find_asset_by_member_id(session, id.into_inner(), &claims)?;
+    let variant = Variant::negotiate(variant_params.width(), accept.as_deref());
+    let result = service.find_asset_by_member_id(session, id.into_inner(), &claims, &variant)?;
     match result {
         None => Ok(HttpResponse::Gone().finish()),
-        Some(data) => Ok(HttpResponse::Ok()
-            .insert_header(data.content_type)
-            .body(Bytes::from(data.bytes))),
+        Some(cached) => Ok(cached.respond(if_none_match.as_deref(), |response, data| {
+            response
+                .insert_header(data.content_type)
+                .insert_header((VARY, "Accept"))
+                .body(Bytes::from(data.bytes))
+        })),
     }
 }
 
@@ -345,9 +381,10 @@ pub async fn activation_code(
     session: Session,
     service: Data<dyn MemberRequestService>,
     activation_string: Path<String>,
+    settings: Data<Settings>,
 ) -> BackendResult<Json<String>> {
     let member_response = service.find_by_activation_string(session, &activation_string)?;
-    let totp: TOTP = member_response.try_into()?;
+    let totp: TOTP = member_response.totp(&settings.security.otp_cipher())?;
     Ok(Json(
         totp.get_qr_base64()
             .map_err(|e| BackendError::qr_code_generation(e))?,
@@ -360,6 +397,7 @@ pub async fn activation_code(
 /// returns a Bad Request. if a member is already activated by the activation string it also returns
 /// a Bad Request.
 #[utoipa::path(
+    request_body = MemberActivationCommand,
     tag = "members",
     responses(
         (status = 200, description = "Member is activated"),
@@ -371,7 +409,7 @@ pub async fn activation_code(
 pub async fn activate(
     session: Session,
     service: Data<dyn MemberActivationCommandService>,
-    command: Json<MemberActivationCommand>,
+    command: ValidatedJson<MemberActivationCommand>,
 ) -> BackendResult<HttpResponse> {
     service.activate(session, &command)?;
     Ok(HttpResponse::Ok().finish())
@@ -398,3 +436,95 @@ pub async fn unregister(
     service.unregister(session, id.into_inner())?;
     Ok(HttpResponse::Ok().finish())
 }
+
+/// Lists the absences of the logged in member
+///
+/// Absences are the periods in which the member is away, ordered by their start date.
+#[utoipa::path(
+    tag = "members",
+    responses(
+        (status = 200, description = "The absences of the member", body=[AbsenceResponse]),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/me/absences")]
+pub async fn list_absences(
+    session: Session,
+    service: Data<dyn AbsenceRequestService>,
+    claims: UserClaims,
+) -> BackendResult<Json<Vec<AbsenceResponse>>> {
+    Ok(Json(service.list(session, &claims)?))
+}
+
+/// Registers an absence of the logged in member
+///
+/// Depending on the visibility, the absence is shown to all members or only to the committee
+/// alongside the events during the absence.
+#[utoipa::path(
+    request_body = RegisterAbsenceCommand,
+    tag = "members",
+    responses(
+        (status = 200, description = "The absence is registered"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[post("/me/absences")]
+pub async fn register_absence(
+    session: Session,
+    service: Data<dyn AbsenceCommandService>,
+    claims: UserClaims,
+    command: ValidatedJson<RegisterAbsenceCommand>,
+) -> BackendResult<HttpResponse> {
+    service.register(session, &claims, &command)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Updates an absence of the logged in member
+#[utoipa::path(
+    request_body = UpdateAbsenceCommand,
+    tag = "members",
+    responses(
+        (status = 200, description = "The absence is updated"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 403, description = "The absence belongs to another member", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[put("/me/absences/{id}")]
+pub async fn update_absence(
+    session: Session,
+    service: Data<dyn AbsenceCommandService>,
+    claims: UserClaims,
+    id: Path<i32>,
+    command: ValidatedJson<UpdateAbsenceCommand>,
+) -> BackendResult<HttpResponse> {
+    service.update(session, id.into_inner(), &claims, &command)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Removes an absence of the logged in member
+#[utoipa::path(
+    tag = "members",
+    responses(
+        (status = 200, description = "The absence is removed"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 403, description = "The absence belongs to another member", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[delete("/me/absences/{id}")]
+pub async fn delete_absence(
+    session: Session,
+    service: Data<dyn AbsenceCommandService>,
+    claims: UserClaims,
+    id: Path<i32>,
+) -> BackendResult<HttpResponse> {
+    service.delete(session, id.into_inner(), &claims)?;
+    Ok(HttpResponse::Ok().finish())
+}
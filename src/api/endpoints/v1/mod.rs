@@ -16,9 +16,12 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+pub mod attendance;
 pub mod authorization;
+pub mod calendar;
 pub mod facebook;
 pub mod images;
+pub mod jobs;
 pub mod mail_templates;
 pub mod mailing;
 pub mod members;
@@ -27,4 +30,5 @@ pub mod pages;
 pub mod roles;
 pub mod setup;
 pub mod source_code;
+pub mod venues;
 pub mod workgroups;
@@ -16,8 +16,10 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::Precondition;
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
+use crate::generic::validation::ValidatedJson;
 use crate::model::interface::commands::{
     RegisterMusicalInstrumentCommand, UpdateMusicalInstrumentCommand,
 };
@@ -56,6 +58,7 @@ pub async fn search(
 
 /// Registers a new musical instrument
 #[utoipa::path(
+    request_body = RegisterMusicalInstrumentCommand,
     tag = "musical-instruments",
     responses(
         (status = 200, description = "A new musical instrument is registered"),
@@ -66,7 +69,7 @@ pub async fn search(
 )]
 #[post("/instrument/")]
 pub async fn register(
-    command: Json<RegisterMusicalInstrumentCommand>,
+    command: ValidatedJson<RegisterMusicalInstrumentCommand>,
     service: Data<dyn MusicalInstrumentCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
@@ -95,22 +98,25 @@ pub async fn find_by_id(
 
 /// Updates a registered musical instrument
 #[utoipa::path(
+    request_body = UpdateMusicalInstrumentCommand,
     tag = "musical-instruments",
     responses(
         (status = 200, description = "Musical instrument data is updated"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
     )
 )]
 #[put("/instrument/{id}")]
 pub async fn update(
     id: Path<i32>,
-    command: Json<UpdateMusicalInstrumentCommand>,
+    precondition: Precondition,
+    command: ValidatedJson<UpdateMusicalInstrumentCommand>,
     service: Data<dyn MusicalInstrumentCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
-    service.update(session, id.into_inner(), &command)?;
+    service.update(session, id.into_inner(), &precondition, &command)?;
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -16,31 +16,44 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::Precondition;
 use crate::generic::result::BackendResult;
 use crate::generic::security::ClaimRoles;
 use crate::generic::storage::session::Session;
-use crate::model::interface::commands::{CreatePageCommand, PublishPageCommand, UpdatePageCommand};
-use crate::model::interface::responses::{ExtendedPageResponse, PageResponse};
+use crate::generic::validation::ValidatedJson;
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::commands::{
+    CreatePageCommand, PublishPageCommand, SetPageEventCommand, UpdatePageCommand,
+    UpdatePageTreeCommand,
+};
+use crate::model::interface::requests::ContentParams;
+use crate::model::interface::responses::{
+    EventResponse, ExtendedPageResponse, ExtendedPageRevisionResponse, PageEventResponse,
+    PageResponse, PageRevisionResponse, PageSearchResponse, PageTreeResponse,
+};
 use crate::model::interface::search::{SearchParams, SearchResult};
+use crate::model::primitives::ContentFormat;
 use crate::services::definitions::command::PageCommandService;
-use crate::services::definitions::request::PageRequestService;
-use actix_web::web::{Data, Json, Path, Query};
+use crate::services::definitions::request::{PageLookup, PageRequestService};
+use actix_web::http::header::{self, IfNoneMatch};
+use actix_web::web::{Data, Header, Json, Path, Query};
 use actix_web::{delete, get, post, put, HttpResponse};
 use std::ops::Deref;
 
 /// Search for pages
 ///
-/// Searches on titles matching the given query.
+/// Searches on titles and published contents matching the given query, the most relevant pages
+/// first. Every page comes with a snippet of its content, in which the matching words are marked.
 #[utoipa::path(
     tag = "pages",
     responses(
-        (status = 200, description = "A list of matching pages", body=SearchResult<PageResponse>),
+        (status = 200, description = "A list of matching pages", body=SearchResult<PageSearchResponse>),
         (status = 400, description = "Bad Request"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal Server Error", body=[String])
     ),
     params(
-        ("q" = String, Query, description = "Part of the title of the page"),
+        ("q" = String, Query, description = "Words in the title or content of the page, or part of the title"),
         ("p" = Option<String>, Query, description = "The page offset to use (counting from 0)")
     )
 )]
@@ -50,7 +63,7 @@ pub async fn search(
     search_params: Query<SearchParams>,
     roles: ClaimRoles,
     session: Session,
-) -> BackendResult<Json<SearchResult<PageResponse>>> {
+) -> BackendResult<Json<SearchResult<PageSearchResponse>>> {
     Ok(Json(service.search(
         session,
         search_params.deref(),
@@ -101,8 +114,57 @@ pub async fn main_menu(
     Ok(Json(service.list_by_parent_id(session, 0, &roles)?))
 }
 
+/// Return the page tree
+///
+/// Returns all main menu pages together with their sub pages, at any depth, as far as they are
+/// visible to the caller.
+#[utoipa::path(
+    tag = "pages",
+    responses(
+        (status = 200, description = "The page tree", body=Vec<PageTreeResponse>),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/tree")]
+pub async fn tree(
+    service: Data<dyn PageRequestService>,
+    roles: ClaimRoles,
+    session: Session,
+) -> BackendResult<Json<Vec<PageTreeResponse>>> {
+    Ok(Json(service.tree(session, &roles)?))
+}
+
+/// Updates the page tree
+///
+/// Moves and reorders a batch of pages at once. Either all moves are applied, or none of them
+/// are, such as when a move would make a page a sub page of itself.
+#[utoipa::path(
+    request_body = UpdatePageTreeCommand,
+    tag = "pages",
+    responses(
+        (status = 200, description = "The page tree is updated"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 409, description = "A page would become a sub page of itself", body=Option<String>),
+        (status = 412, description = "A page was modified by someone else", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[put("/tree")]
+pub async fn update_tree(
+    command: ValidatedJson<UpdatePageTreeCommand>,
+    service: Data<dyn PageCommandService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    service.update_tree(session, &command)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
 /// Creates a new page
 #[utoipa::path(
+    request_body = CreatePageCommand,
     tag = "pages",
     responses(
         (status = 200, description = "A new page is created"),
@@ -113,7 +175,7 @@ pub async fn main_menu(
 )]
 #[post("/page/")]
 pub async fn create(
-    command: Json<CreatePageCommand>,
+    command: ValidatedJson<CreatePageCommand>,
     service: Data<dyn PageCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
@@ -122,23 +184,145 @@ pub async fn create(
 }
 
 /// Sets the content of a page
+///
+/// The content is saved as a draft, which becomes visible once the page is published. The
+/// previous content remains available as a revision of the page. The content is rendered
+/// into sanitised HTML when it is saved.
 #[utoipa::path(
     tag = "pages",
     responses(
         (status = 200, description = "Content of the given page is set"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
+    ),
+    params(
+        ("format" = Option<ContentFormat>, Query, description = "The format of the content, the format of the previously saved content if not set"),
     )
 )]
 #[put("/page/{id}/content")]
 pub async fn set_content(
     id: Path<i32>,
+    precondition: Precondition,
+    claims: UserClaims,
+    params: Query<ContentParams>,
     data: String,
     service: Data<dyn PageCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
-    service.set_content(session, id.into_inner(), &data)?;
+    service.set_content(
+        session,
+        id.into_inner(),
+        &precondition,
+        &claims,
+        params.format,
+        &data,
+    )?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Lists the content revisions of a page, newest first
+#[utoipa::path(
+    tag = "pages",
+    responses(
+        (status = 200, description = "The revisions of the page", body=Vec<PageRevisionResponse>),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 403, description = "Forbidden", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/page/{id}/revisions")]
+pub async fn revisions(
+    id: Path<i32>,
+    service: Data<dyn PageRequestService>,
+    session: Session,
+    roles: ClaimRoles,
+) -> BackendResult<Json<Vec<PageRevisionResponse>>> {
+    Ok(Json(service.list_revisions(
+        session,
+        id.into_inner(),
+        &roles,
+    )?))
+}
+
+/// Returns a content revision of a page, including the content
+#[utoipa::path(
+    tag = "pages",
+    responses(
+        (status = 200, description = "The revision", body=ExtendedPageRevisionResponse),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 403, description = "Forbidden", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/page/{id}/revisions/{revision_id}")]
+pub async fn revision(
+    path: Path<(i32, i32)>,
+    service: Data<dyn PageRequestService>,
+    session: Session,
+    roles: ClaimRoles,
+) -> BackendResult<Json<ExtendedPageRevisionResponse>> {
+    let (page_id, revision_id) = path.into_inner();
+    Ok(Json(service.find_revision(
+        session,
+        page_id,
+        revision_id,
+        &roles,
+    )?))
+}
+
+/// Returns the unified diff between the content of two revisions of a page
+#[utoipa::path(
+    tag = "pages",
+    responses(
+        (status = 200, description = "The unified diff", content_type="text/plain"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 403, description = "Forbidden", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/page/{id}/revisions/{from_revision_id}/diff/{to_revision_id}")]
+pub async fn revision_diff(
+    path: Path<(i32, i32, i32)>,
+    service: Data<dyn PageRequestService>,
+    session: Session,
+    roles: ClaimRoles,
+) -> BackendResult<HttpResponse> {
+    let (page_id, from_revision_id, to_revision_id) = path.into_inner();
+    let diff =
+        service.diff_revisions(session, page_id, from_revision_id, to_revision_id, &roles)?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("content-type", "text/plain"))
+        .body(diff))
+}
+
+/// Restores a content revision of a page
+///
+/// The content of the revision becomes the content of the page, as a new revision.
+#[utoipa::path(
+    tag = "pages",
+    responses(
+        (status = 200, description = "The revision is restored"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[post("/page/{id}/revisions/{revision_id}/restoration")]
+pub async fn restore_revision(
+    path: Path<(i32, i32)>,
+    precondition: Precondition,
+    claims: UserClaims,
+    service: Data<dyn PageCommandService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    let (page_id, revision_id) = path.into_inner();
+    service.restore_revision(session, page_id, &precondition, &claims, revision_id)?;
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -147,6 +331,7 @@ pub async fn set_content(
     tag = "pages",
     responses(
         (status = 200, description = "The page", body=ExtendedPageResponse),
+        (status = 304, description = "The page is not modified"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
@@ -158,19 +343,59 @@ pub async fn find_by_id(
     service: Data<dyn PageRequestService>,
     roles: ClaimRoles,
     session: Session,
-) -> BackendResult<Json<ExtendedPageResponse>> {
-    Ok(Json(service.find_by_id(
-        session,
-        id.into_inner(),
-        &roles,
-    )?))
+    if_none_match: Option<Header<IfNoneMatch>>,
+) -> BackendResult<HttpResponse> {
+    let result = service.find_by_id(session, id.into_inner(), &roles)?;
+    Ok(result.respond(if_none_match.as_deref(), |response, page| {
+        response.json(page)
+    }))
+}
+
+/// Returns an existing page using its path
+///
+/// The path consists of the slugs of the page and its ancestors, e.g. `concerts/spring-2025`.
+/// Paths which belonged to a page before it was renamed or moved redirect to its current path.
+#[utoipa::path(
+    tag = "pages",
+    responses(
+        (status = 200, description = "The page", body=ExtendedPageResponse),
+        (status = 304, description = "The page is not modified"),
+        (status = 308, description = "The page has moved, the current path is in the Location header"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 404, description = "No page has the path", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/by-path/{path:.*}")]
+pub async fn find_by_path(
+    path: Path<String>,
+    service: Data<dyn PageRequestService>,
+    roles: ClaimRoles,
+    session: Session,
+    if_none_match: Option<Header<IfNoneMatch>>,
+) -> BackendResult<HttpResponse> {
+    match service.find_by_path(session, &path, &roles)? {
+        PageLookup::Found(result) => Ok(result
+            .respond(if_none_match.as_deref(), |response, page| {
+                response.json(page)
+            })),
+        PageLookup::Moved(current_path) => Ok(HttpResponse::PermanentRedirect()
+            .insert_header((
+                header::LOCATION,
+                format!("/api/pages/v1/by-path/{current_path}"),
+            ))
+            .finish()),
+    }
 }
 
 /// Finds the events for the upcoming months
+///
+/// Recurring events are returned once for every time they take place within the period.
 #[utoipa::path(
     tag = "events",
     responses(
-        (status = 200, description = "The events", body=[PageResponse]),
+        (status = 200, description = "The event occurrences", body=[EventResponse]),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
@@ -181,15 +406,90 @@ pub async fn events(
     service: Data<dyn PageRequestService>,
     roles: ClaimRoles,
     session: Session,
-) -> BackendResult<Json<Vec<PageResponse>>> {
+) -> BackendResult<Json<Vec<EventResponse>>> {
     Ok(Json(service.events(session, &roles)?))
 }
 
+/// Finds the times, venue and recurrence of an event page
+#[utoipa::path(
+    tag = "events",
+    responses(
+        (status = 200, description = "The event, if the page has event times", body=Option<PageEventResponse>),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/page/{id}/event")]
+pub async fn find_event(
+    id: Path<i32>,
+    service: Data<dyn PageRequestService>,
+    roles: ClaimRoles,
+    session: Session,
+) -> BackendResult<Json<Option<PageEventResponse>>> {
+    Ok(Json(service.find_event(
+        session,
+        id.into_inner(),
+        &roles,
+    )?))
+}
+
+/// Sets the times, venue and recurrence of an event page
+///
+/// The event dates of the page are set to the days of the first occurrence.
+#[utoipa::path(
+    request_body = SetPageEventCommand,
+    tag = "events",
+    responses(
+        (status = 200, description = "The event is set"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[put("/page/{id}/event")]
+pub async fn set_event(
+    id: Path<i32>,
+    precondition: Precondition,
+    command: ValidatedJson<SetPageEventCommand>,
+    service: Data<dyn PageCommandService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    service.set_event(session, id.into_inner(), &precondition, &command)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Removes the times, venue and recurrence of an event page
+///
+/// The page remains an event lasting the whole days of its event dates.
+#[utoipa::path(
+    tag = "events",
+    responses(
+        (status = 200, description = "The event times are removed"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[delete("/page/{id}/event")]
+pub async fn delete_event(
+    id: Path<i32>,
+    precondition: Precondition,
+    service: Data<dyn PageCommandService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    service.delete_event(session, id.into_inner(), &precondition)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
 /// Returns the default page if set
 #[utoipa::path(
     tag = "pages",
     responses(
         (status = 200, description = "The page", body=ExtendedPageResponse),
+        (status = 304, description = "The page is not modified"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
@@ -224,11 +524,12 @@ pub async fn put_default(
     Ok(HttpResponse::Ok().finish())
 }
 
-/// Returns page content
+/// Returns the published page content, rendered as sanitised HTML
 #[utoipa::path(
     tag = "pages",
     responses(
-        (status = 200, description = "The page", content_type="text/plain"),
+        (status = 200, description = "The page", content_type="text/html"),
+        (status = 304, description = "The page content is not modified"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
@@ -240,31 +541,70 @@ pub async fn content(
     service: Data<dyn PageRequestService>,
     roles: ClaimRoles,
     session: Session,
+    if_none_match: Option<Header<IfNoneMatch>>,
 ) -> BackendResult<HttpResponse> {
     let result = service.find_content_by_id(session, id.into_inner(), &roles)?;
-    Ok(HttpResponse::Ok()
-        .insert_header(("content-type", "text/plain"))
-        .body(result))
+    Ok(
+        result.respond(if_none_match.as_deref(), |response, content| {
+            response
+                .insert_header(("content-type", "text/html; charset=utf-8"))
+                .body(content)
+        }),
+    )
+}
+
+/// Returns the latest saved page content, including changes which are not published yet, as it
+/// was written
+#[utoipa::path(
+    tag = "pages",
+    responses(
+        (status = 200, description = "The draft content of the page", content_type="text/plain"),
+        (status = 304, description = "The draft content is not modified"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 403, description = "Forbidden", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/page/{id}/draft")]
+pub async fn draft(
+    id: Path<i32>,
+    service: Data<dyn PageRequestService>,
+    session: Session,
+    roles: ClaimRoles,
+    if_none_match: Option<Header<IfNoneMatch>>,
+) -> BackendResult<HttpResponse> {
+    let result = service.find_draft_content_by_id(session, id.into_inner(), &roles)?;
+    Ok(
+        result.respond(if_none_match.as_deref(), |response, draft_content| {
+            response
+                .insert_header(("content-type", "text/plain"))
+                .body(draft_content)
+        }),
+    )
 }
 
 /// Updates an existing page
 #[utoipa::path(
+    request_body = UpdatePageCommand,
     tag = "pages",
     responses(
         (status = 200, description = "Page is updated"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
     )
 )]
 #[put("/page/{id}")]
 pub async fn update(
     id: Path<i32>,
-    command: Json<UpdatePageCommand>,
+    precondition: Precondition,
+    command: ValidatedJson<UpdatePageCommand>,
     service: Data<dyn PageCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
-    service.update(session, id.into_inner(), &command)?;
+    service.update(session, id.into_inner(), &precondition, &command)?;
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -275,39 +615,48 @@ pub async fn update(
         (status = 200, description = "Page order is updated"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
     )
 )]
 #[put("/page/{id}/order")]
 pub async fn set_order(
     id: Path<i32>,
+    precondition: Precondition,
     number: Json<i32>,
     service: Data<dyn PageCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
-    service.set_order(session, id.into_inner(), number.into_inner())?;
+    service.set_order(session, id.into_inner(), &precondition, number.into_inner())?;
     Ok(HttpResponse::Ok().finish())
 }
 
-/// Sets the parent page for a page, a parent page can only be set if the parent page
-/// does not have a parent.
+/// Sets the parent page for a page, as long as the page does not become a sub page of itself.
 #[utoipa::path(
     tag = "pages",
     responses(
         (status = 200, description = "Parent page is set"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 409, description = "The page would become a sub page of itself", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
     )
 )]
 #[put("/page/{id}/parent")]
 pub async fn set_parent(
     id: Path<i32>,
+    precondition: Precondition,
     parent_id: Json<i32>,
     service: Data<dyn PageCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
-    service.set_or_unset_parent_id(session, id.into_inner(), Some(parent_id.into_inner()))?;
+    service.set_or_unset_parent_id(
+        session,
+        id.into_inner(),
+        &precondition,
+        Some(parent_id.into_inner()),
+    )?;
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -318,57 +667,69 @@ pub async fn set_parent(
         (status = 200, description = "Parent page is unset"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
     )
 )]
 #[delete("/page/{id}/parent")]
 pub async fn unset_parent(
     id: Path<i32>,
+    precondition: Precondition,
     service: Data<dyn PageCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
-    service.set_or_unset_parent_id(session, id.into_inner(), None)?;
+    service.set_or_unset_parent_id(session, id.into_inner(), &precondition, None)?;
     Ok(HttpResponse::Ok().finish())
 }
 
 /// Publish an existing page
+///
+/// Makes the draft content visible to the given roles, optionally limited to a publication
+/// window.
 #[utoipa::path(
+    request_body = PublishPageCommand,
     tag = "pages",
     responses(
         (status = 200, description = "Page is published"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
     )
 )]
 #[post("/page/{id}/publication")]
 pub async fn publish(
     id: Path<i32>,
-    command: Json<PublishPageCommand>,
+    precondition: Precondition,
+    command: ValidatedJson<PublishPageCommand>,
     service: Data<dyn PageCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
-    service.publish(session, id.into_inner(), &command)?;
+    service.publish(session, id.into_inner(), &precondition, &command)?;
     Ok(HttpResponse::Ok().finish())
 }
 
 /// Unpublish an existing page
+///
+/// The page is only available to operators, any scheduled publication is cancelled.
 #[utoipa::path(
     tag = "pages",
     responses(
         (status = 200, description = "Page is unpublished"),
         (status = 400, description = "Bad Request", body=Option<String>),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal Server Error", body=Option<String>)
     )
 )]
 #[delete("/page/{id}/publication")]
 pub async fn unpublish(
     id: Path<i32>,
+    precondition: Precondition,
     service: Data<dyn PageCommandService>,
     session: Session,
 ) -> BackendResult<HttpResponse> {
-    service.unpublish(session, id.into_inner())?;
+    service.unpublish(session, id.into_inner(), &precondition)?;
     Ok(HttpResponse::Ok().finish())
 }
 
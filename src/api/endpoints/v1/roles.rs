@@ -18,6 +18,7 @@
  */
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
+use crate::generic::validation::ValidatedJson;
 use crate::model::interface::commands::{AssociateRoleCommand, DissociateRoleCommand};
 use crate::model::primitives::{Role, RoleClass};
 use crate::services::definitions::command::RoleCommandService;
@@ -30,6 +31,7 @@ use actix_web::{get, post, HttpResponse};
 /// Member association is used to allow members to act on specific roles
 /// Work group association is used to allow groups of members to act on specific roles
 #[utoipa::path(
+    request_body = AssociateRoleCommand,
     tag = "roles",
     responses(
         (status = 200, description = "Successful association of a role"),
@@ -42,7 +44,7 @@ use actix_web::{get, post, HttpResponse};
 pub async fn associate(
     session: Session,
     service: Data<dyn RoleCommandService>,
-    command: Json<AssociateRoleCommand>,
+    command: ValidatedJson<AssociateRoleCommand>,
 ) -> BackendResult<HttpResponse> {
     service.associate_role(session, &command)?;
     Ok(HttpResponse::Ok().finish())
@@ -53,6 +55,7 @@ pub async fn associate(
 /// Member association is used to allow members to act on specific roles
 /// Work group association is used to allow groups of members to act on specific roles
 #[utoipa::path(
+    request_body = DissociateRoleCommand,
     tag = "roles",
     responses(
         (status = 200, description = "Successful dissociation of a role"),
@@ -65,7 +68,7 @@ pub async fn associate(
 pub async fn dissociate(
     session: Session,
     service: Data<dyn RoleCommandService>,
-    command: Json<DissociateRoleCommand>,
+    command: ValidatedJson<DissociateRoleCommand>,
 ) -> BackendResult<HttpResponse> {
     service.dissociate_role(session, &command)?;
     Ok(HttpResponse::Ok().finish())
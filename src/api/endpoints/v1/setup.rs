@@ -19,6 +19,7 @@
 
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
+use crate::generic::validation::ValidatedJson;
 use crate::model::interface::commands::FirstOperatorRegisterCommand;
 use crate::services::definitions::command::SetupCommandService;
 use crate::services::definitions::request::SetupRequestService;
@@ -54,6 +55,7 @@ pub async fn should_setup(
 ///
 /// ⚠️ If an operator already exists, this API call (for obvious reasons) becomes invalid.
 #[utoipa::path(
+    request_body = FirstOperatorRegisterCommand,
     tag = "setup",
     responses(
         (status = 200, description = "Created a new first operator", body=String),
@@ -64,7 +66,7 @@ pub async fn should_setup(
 #[post("/setup_first_operator")]
 pub async fn setup_first_operator(
     session: Session,
-    command: Json<FirstOperatorRegisterCommand>,
+    command: ValidatedJson<FirstOperatorRegisterCommand>,
     service: Data<dyn SetupCommandService>,
 ) -> BackendResult<Json<String>> {
     Ok(Json(service.register_first_operator(session, &command)?))
@@ -17,11 +17,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::{BACKEND_SOURCE_CODE_URL, FRONTEND_SOURCE_CODE_URL};
+use crate::generic::settings::Settings;
 use actix_web::get;
-use actix_web::web::Json;
+use actix_web::web::{Data, Json};
 use serde::Serialize;
-use std::sync::LazyLock;
 use utoipa::ToSchema;
 
 /// Shows the source code details of the frontend and backend
@@ -31,15 +30,13 @@ use utoipa::ToSchema;
         )
     )]
 #[get("/")]
-pub async fn details() -> Json<SourceCodeDetails> {
-    Json(SOURCE_CODE_DETAILS.clone())
+pub async fn details(settings: Data<Settings>) -> Json<SourceCodeDetails> {
+    Json(SourceCodeDetails {
+        frontend_url: settings.source_code.frontend_url.clone(),
+        backend_url: settings.source_code.backend_url.clone(),
+    })
 }
 
-static SOURCE_CODE_DETAILS: LazyLock<SourceCodeDetails> = LazyLock::new(|| SourceCodeDetails {
-    frontend_url: FRONTEND_SOURCE_CODE_URL.clone(),
-    backend_url: BACKEND_SOURCE_CODE_URL.clone(),
-});
-
 #[derive(Serialize, ToSchema, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceCodeDetails {
@@ -0,0 +1,113 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::caching::Precondition;
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::validation::ValidatedJson;
+use crate::model::interface::commands::{RegisterVenueCommand, UpdateVenueCommand};
+use crate::model::interface::responses::VenueResponse;
+use crate::services::definitions::command::VenueCommandService;
+use crate::services::definitions::request::VenueRequestService;
+use actix_web::web::{Data, Json, Path};
+use actix_web::{delete, get, post, put, HttpResponse};
+
+/// Lists all venues
+#[utoipa::path(
+    tag = "venues",
+    responses(
+        (status = 200, description = "The venues ordered by name", body=[VenueResponse]),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[get("/list")]
+pub async fn list(
+    service: Data<dyn VenueRequestService>,
+    session: Session,
+) -> BackendResult<Json<Vec<VenueResponse>>> {
+    Ok(Json(service.list(session)?))
+}
+
+/// Registers a new venue
+#[utoipa::path(
+    request_body = RegisterVenueCommand,
+    tag = "venues",
+    responses(
+        (status = 200, description = "A new venue is registered"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[post("/venue")]
+pub async fn register(
+    command: ValidatedJson<RegisterVenueCommand>,
+    service: Data<dyn VenueCommandService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    service.register(session, &command)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Updates a registered venue
+#[utoipa::path(
+    request_body = UpdateVenueCommand,
+    tag = "venues",
+    responses(
+        (status = 200, description = "The venue is updated"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[put("/venue/{id}")]
+pub async fn update(
+    id: Path<i32>,
+    precondition: Precondition,
+    command: ValidatedJson<UpdateVenueCommand>,
+    service: Data<dyn VenueCommandService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    service.update(session, id.into_inner(), &precondition, &command)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Deletes a registered venue
+///
+/// Events taking place at the venue no longer refer to a venue.
+#[utoipa::path(
+    tag = "venues",
+    responses(
+        (status = 200, description = "The venue is removed from the database"),
+        (status = 400, description = "Bad Request", body=Option<String>),
+        (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 500, description = "Internal Server Error", body=Option<String>)
+    )
+)]
+#[delete("/venue/{id}")]
+pub async fn delete(
+    id: Path<i32>,
+    service: Data<dyn VenueCommandService>,
+    session: Session,
+) -> BackendResult<HttpResponse> {
+    service.delete(session, id.into_inner())?;
+    Ok(HttpResponse::Ok().finish())
+}
@@ -19,8 +19,10 @@
 
 //! Work groups are collections of members, allowing for additional roles.
 
+use crate::generic::caching::Precondition;
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
+use crate::generic::validation::ValidatedJson;
 use crate::model::interface::commands::{
     AssociateMemberToWorkgroupCommand, DissociateMemberFromWorkgroupCommand,
     WorkgroupRegisterCommand, WorkgroupUpdateCommand,
@@ -39,6 +41,7 @@ use std::ops::Deref;
 /// perform within the orchestra. Further, the members of a work group can have additional
 /// functionality enabled through the role they have within the work group.
 #[utoipa::path(
+    request_body = WorkgroupRegisterCommand,
     tag = "workgroups",
     responses(
         (status = 200, description = "Successful registration"),
@@ -51,7 +54,7 @@ use std::ops::Deref;
 pub async fn register(
     session: Session,
     service: Data<dyn WorkgroupCommandService>,
-    command: Json<WorkgroupRegisterCommand>,
+    command: ValidatedJson<WorkgroupRegisterCommand>,
 ) -> BackendResult<Json<i32>> {
     Ok(Json(service.register(session, &command)?))
 }
@@ -107,11 +110,13 @@ pub async fn find(
 ///
 /// Updates an existing work group record given the data.
 #[utoipa::path(
+    request_body = WorkgroupUpdateCommand,
     tag = "workgroups",
     responses(
         (status = 200, description = "Work group is updated"),
         (status = 400, description = "Bad Request"),
         (status = 401, description = "Unauthorized", body=Option<String>),
+        (status = 412, description = "The version in If-Match is outdated", body=Option<String>),
         (status = 500, description = "Internal backend error", body=[String]),
     )
 )]
@@ -120,9 +125,10 @@ pub async fn update(
     session: Session,
     service: Data<dyn WorkgroupCommandService>,
     id: Path<i32>,
-    command: Json<WorkgroupUpdateCommand>,
+    precondition: Precondition,
+    command: ValidatedJson<WorkgroupUpdateCommand>,
 ) -> BackendResult<HttpResponse> {
-    service.update(session, id.into_inner(), &command)?;
+    service.update(session, id.into_inner(), &precondition, &command)?;
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -195,6 +201,7 @@ pub async fn available_members_search(
 
 /// Associate a member to a work group
 #[utoipa::path(
+    request_body = AssociateMemberToWorkgroupCommand,
     tag = "workgroups",
     responses(
         (status = 200, description = "Successful association of a member to a work group"),
@@ -207,7 +214,7 @@ pub async fn available_members_search(
 pub async fn associate(
     session: Session,
     service: Data<dyn WorkgroupCommandService>,
-    command: Json<AssociateMemberToWorkgroupCommand>,
+    command: ValidatedJson<AssociateMemberToWorkgroupCommand>,
 ) -> BackendResult<HttpResponse> {
     service.associate_member_to_workgroup(session, &command)?;
     Ok(HttpResponse::Ok().finish())
@@ -215,6 +222,7 @@ pub async fn associate(
 
 /// Dissociate a member from a work group
 #[utoipa::path(
+    request_body = DissociateMemberFromWorkgroupCommand,
     tag = "workgroups",
     responses(
         (status = 200, description = "Successful dissociation of a member from a work group"),
@@ -227,7 +235,7 @@ pub async fn associate(
 pub async fn dissociate(
     session: Session,
     service: Data<dyn WorkgroupCommandService>,
-    command: Json<DissociateMemberFromWorkgroupCommand>,
+    command: ValidatedJson<DissociateMemberFromWorkgroupCommand>,
 ) -> BackendResult<HttpResponse> {
     service.dissociate_member_from_workgroup(session, &command)?;
     Ok(HttpResponse::Ok().finish())
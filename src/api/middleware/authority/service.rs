@@ -19,6 +19,7 @@
 use crate::api::middleware::authority::config::AuthorityConfig;
 use crate::api::middleware::authority::Allowance;
 use crate::generic::http::Method;
+use crate::generic::logging;
 use crate::generic::security::ClaimRoles;
 use crate::model::interface::client::UserClaims;
 use crate::model::traits::RoleContainer;
@@ -98,6 +99,9 @@ where
                 let extensions = req.extensions();
                 extensions.get::<UserClaims>().cloned()
             };
+            if let Some(user_claims) = &user_claims {
+                logging::set_email_address(&user_claims.email_address);
+            }
 
             let roles = ClaimRoles::from(&user_claims);
             req.extensions_mut().insert(roles);
@@ -27,13 +27,15 @@ use std::rc::Rc;
 
 pub struct DatabaseService<S> {
     service: Rc<S>,
+    excluded_paths: Rc<Vec<&'static str>>,
 }
 
 impl<S> DatabaseService<S> {
-    pub fn new(service: S) -> Self {
+    pub fn new(service: S, excluded_paths: Rc<Vec<&'static str>>) -> Self {
         let service = Rc::new(service);
         Self {
             service: service.clone(),
+            excluded_paths,
         }
     }
 }
@@ -52,18 +54,34 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = self.service.clone();
+        let excluded = self
+            .excluded_paths
+            .iter()
+            .any(|prefix| req.path().starts_with(prefix));
 
         Box::pin(async move {
+            if excluded {
+                return service.call(req).await;
+            }
             let manager = req.app_data::<Data<dyn SessionManager>>();
             if let Some(manager) = manager {
                 let mut session = manager.prepare()?;
                 req.extensions_mut().insert(session.clone());
                 let response = service.call(req).await;
                 match response {
-                    Ok(response) => {
+                    // Errors of handlers and extractors are turned into responses, these must
+                    // not commit the partial changes made before the error occurred
+                    Ok(response)
+                        if response.response().error().is_none()
+                            && response.status().is_success() =>
+                    {
                         session.commit()?;
                         Ok(response)
                     }
+                    Ok(response) => {
+                        session.rollback()?;
+                        Ok(response)
+                    }
                     Err(e) => {
                         session.rollback()?;
                         Err(e)
@@ -79,3 +97,55 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::middleware::database::DatabaseMiddleware;
+    use crate::generic::result::{BackendError, BackendResult};
+    use crate::generic::storage::session::{FauxSessionManagerImplementation, Session};
+    use crate::generic::Injectable;
+    use actix_web::http::StatusCode;
+    use actix_web::web::Data;
+    use actix_web::{test, web, App, HttpResponse};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    async fn call(path: &str) -> (StatusCode, bool) {
+        let committed = Data::new(AtomicBool::new(false));
+        let app = test::init_service(
+            App::new()
+                .app_data(FauxSessionManagerImplementation::make(&()))
+                .app_data(committed.clone())
+                .wrap(DatabaseMiddleware::new())
+                .route("/succeed", web::get().to(succeed))
+                .route("/fail", web::get().to(fail)),
+        )
+        .await;
+        let response =
+            test::call_service(&app, test::TestRequest::get().uri(path).to_request()).await;
+        (response.status(), committed.load(Ordering::SeqCst))
+    }
+
+    fn defer(mut session: Session, committed: Data<AtomicBool>) {
+        session.after_commit(move || committed.store(true, Ordering::SeqCst));
+    }
+
+    async fn succeed(session: Session, committed: Data<AtomicBool>) -> HttpResponse {
+        defer(session, committed);
+        HttpResponse::Ok().finish()
+    }
+
+    async fn fail(session: Session, committed: Data<AtomicBool>) -> BackendResult<HttpResponse> {
+        defer(session, committed);
+        Err(BackendError::bad())
+    }
+
+    #[actix_web::test]
+    async fn commits_successful_requests() {
+        assert_eq!(call("/succeed").await, (StatusCode::OK, true));
+    }
+
+    #[actix_web::test]
+    async fn rolls_back_failed_requests() {
+        assert_eq!(call("/fail").await, (StatusCode::BAD_REQUEST, false));
+    }
+}
@@ -21,12 +21,24 @@ use actix_web::body::MessageBody;
 use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
 use actix_web::Error;
 use std::future::{ready, Ready};
+use std::rc::Rc;
 
-pub struct DatabaseMiddleware {}
+pub struct DatabaseMiddleware {
+    excluded_paths: Rc<Vec<&'static str>>,
+}
 
 impl DatabaseMiddleware {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            excluded_paths: Rc::new(vec![]),
+        }
+    }
+
+    /// Requests of which the path starts with the given prefix are handled without a session,
+    /// such that these keep working when the database is not available.
+    pub fn exclude(mut self, prefix: &'static str) -> Self {
+        Rc::make_mut(&mut self.excluded_paths).push(prefix);
+        self
     }
 }
 impl<S, B> Transform<S, ServiceRequest> for DatabaseMiddleware
@@ -42,6 +54,9 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(DatabaseService::new(service)))
+        ready(Ok(DatabaseService::new(
+            service,
+            self.excluded_paths.clone(),
+        )))
     }
 }
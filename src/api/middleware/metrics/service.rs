@@ -0,0 +1,84 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::generic::metrics::Metrics;
+use crate::generic::result::BackendError;
+use actix_web::body::MessageBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse};
+use actix_web::web::Data;
+use actix_web::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Route label used for requests which did not match any resource
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+pub struct MetricsService<S> {
+    service: Rc<S>,
+}
+
+impl<S> MetricsService<S> {
+    pub fn new(service: S) -> Self {
+        Self {
+            service: Rc::new(service),
+        }
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let Some(metrics) = req.app_data::<Data<Metrics>>().cloned() else {
+                return service.call(req).await;
+            };
+            let started = Instant::now();
+            let method = req.method().to_string();
+            let route = req
+                .match_pattern()
+                .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+            let response = service.call(req).await;
+
+            let (status, error) = match &response {
+                Ok(response) => (response.status(), response.response().error()),
+                Err(e) => (e.as_response_error().status_code(), Some(e)),
+            };
+            if let Some(e) = error.and_then(|e| e.as_error::<BackendError>()) {
+                metrics.record_error(&e.kind);
+            }
+            metrics.record_request(&method, &route, status.as_u16(), started.elapsed());
+            response
+        })
+    }
+}
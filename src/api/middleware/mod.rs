@@ -18,3 +18,5 @@
  */
 pub mod authority;
 pub mod database;
+pub mod metrics;
+pub mod request_id;
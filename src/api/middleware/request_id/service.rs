@@ -0,0 +1,98 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::generic::logging;
+use crate::generic::logging::REQUEST_ID_HEADER;
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+/// Longest request id accepted from a client, longer ids are replaced
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+pub struct RequestIdService<S> {
+    service: Rc<S>,
+}
+
+impl<S> RequestIdService<S> {
+    pub fn new(service: S) -> Self {
+        Self {
+            service: Rc::new(service),
+        }
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| is_valid_request_id(value))
+            .map(str::to_owned)
+            .unwrap_or_else(generate_request_id);
+        let header_value =
+            HeaderValue::from_str(&request_id).expect("Request id should be a valid header");
+        // Also replace the request header, such that the access log shows the effective id
+        req.headers_mut().insert(
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            header_value.clone(),
+        );
+        let http_request = req.request().clone();
+
+        Box::pin(logging::scope(request_id, async move {
+            let mut response = match service.call(req).await {
+                Ok(response) => response.map_into_boxed_body(),
+                Err(e) => ServiceResponse::new(http_request, e.error_response()),
+            };
+            response
+                .headers_mut()
+                .insert(HeaderName::from_static(REQUEST_ID_HEADER), header_value);
+            Ok(response)
+        }))
+    }
+}
+
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LENGTH
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn generate_request_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
@@ -19,29 +19,28 @@
 
 use ed25519_compact::{KeyPair, PublicKey, SecretKey};
 use log::info;
-use std::env;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Error, ErrorKind, Read};
 use std::path::Path;
 
 pub mod config;
 pub mod endpoints;
 pub mod middleware;
 pub mod server;
+pub mod tls;
 
-fn load_key_pair() -> (SecretKey, PublicKey) {
-    let keys_location = env::var("JWT_KEYS").expect("JWT_KEYS should be set");
-    info!("Loading JWT keys from {}", keys_location);
-    let path = Path::new(&keys_location);
+fn load_key_pair(path: &Path) -> (SecretKey, PublicKey) {
+    info!("Loading JWT keys from {}", path.display());
+    read_key_pair(path).expect("Key pair should be created with the specified file in JWT_KEYS")
+}
+
+/// Reads the JWT key pair from the PEM file
+pub fn read_key_pair(path: &Path) -> std::io::Result<(SecretKey, PublicKey)> {
     let mut pem = String::new();
-    let _ = File::open(path)
-        .expect("JWT_KEYS should exist")
-        .read_to_string(&mut pem)
-        .expect("JWT_KEYS should be readable");
+    File::open(path)?.read_to_string(&mut pem)?;
     let KeyPair {
         sk: secret_key,
         pk: public_key,
-    } = KeyPair::from_pem(&pem)
-        .expect("Key pair should be created with the specified file in JWT_KEYS");
-    (secret_key, public_key)
+    } = KeyPair::from_pem(&pem).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    Ok((secret_key, public_key))
 }
@@ -18,36 +18,60 @@
  */
 
 use crate::api;
-use crate::api::config;
+use crate::api::endpoints::health;
 use crate::api::endpoints::v1::*;
 use crate::api::middleware::authority::AuthorityMiddleware;
 use crate::api::middleware::database::DatabaseMiddleware;
-use crate::generic::storage::database;
+use crate::api::middleware::metrics::MetricsMiddleware;
+use crate::api::middleware::request_id::RequestIdMiddleware;
+use crate::api::{config, tls};
+use crate::commands::jobs::registry::JobRegistry;
+use crate::commands::jobs::scheduler;
+use crate::generic::metrics::Metrics;
+use crate::generic::settings::{ServerSettings, Settings, TlsSettings};
+use crate::generic::storage::{database, migrations};
 use crate::model::interface::client::UserClaims;
 use actix_jwt_auth_middleware::{Authority, TokenSigner};
+use actix_web::dev::Server;
 use actix_web::middleware::Logger;
 use actix_web::web::Data;
-use actix_web::{App, HttpServer};
+use actix_web::{web, App, HttpRequest, HttpServer};
 use jwt_compact::alg::Ed25519;
-use std::net::Ipv4Addr;
-use std::time::Duration;
+use log::info;
 use utoipa_actix_web::{scope, AppExt};
 use utoipa_scalar::{Scalar, Servable};
 
-pub async fn launch() -> std::io::Result<()> {
-    let (secret_key, public_key) = api::load_key_pair();
+/// The default access log format, extended with the request id
+const ACCESS_LOG_FORMAT: &str =
+    r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{x-request-id}i"#;
 
-    let pool = database::initialize_database_connection_pool();
+pub async fn launch(settings: Settings) -> std::io::Result<()> {
+    let (secret_key, public_key) = api::load_key_pair(&settings.security.jwt_keys);
+
+    let pool = database::initialize_database_connection_pool(&settings.database);
+    let mut conn = pool.get().map_err(std::io::Error::other)?;
+    migrations::ensure_current(&mut conn, settings.database.auto_migrate)
+        .map_err(std::io::Error::other)?;
+    drop(conn);
 
     let token_signer = TokenSigner::new()
         .signing_key(secret_key.clone())
         .algorithm(Ed25519)
-        .access_token_lifetime(Duration::from_secs(3 * 60))
-        .refresh_token_lifetime(Duration::from_secs(10 * 60))
+        .access_token_lifetime(settings.security.access_token_lifetime())
+        .refresh_token_lifetime(settings.security.refresh_token_lifetime())
         .build()
         .expect("Token Signer should be initialized");
 
-    Ok(HttpServer::new(move || {
+    let server_settings = settings.server.clone();
+    let tls_settings = settings.tls().cloned();
+    let settings = Data::new(settings);
+    let metrics = Data::new(Metrics::new());
+    let job_registry = Data::new(JobRegistry::new(&pool, &settings));
+    if settings.jobs.enabled {
+        scheduler::spawn(job_registry.clone());
+    }
+
+    let mut server = HttpServer::new(move || {
         let authority = Authority::<UserClaims, Ed25519, _, _>::new()
             .refresh_authorizer(|| async move { Ok(()) })
             .token_signer(Some(token_signer.clone()))
@@ -58,16 +82,33 @@ pub async fn launch() -> std::io::Result<()> {
         let authority_middleware =
             AuthorityMiddleware::new(authority, config::configure_authority());
 
-        let database_middleware = DatabaseMiddleware::new();
+        let database_middleware = DatabaseMiddleware::new()
+            .exclude("/health/")
+            .exclude("/metrics");
 
-        let app = crate::injection::inject(&pool, &Data::new(token_signer.clone()), App::new());
+        let app = crate::injection::inject(
+            &pool,
+            &settings,
+            &metrics,
+            &job_registry,
+            &Data::new(token_signer.clone()),
+            App::new(),
+        );
         let (app, api) = app
             .into_utoipa_app()
             .map(|app| {
-                app.wrap(Logger::default())
+                app.wrap(Logger::new(ACCESS_LOG_FORMAT))
                     .wrap(authority_middleware)
                     .wrap(database_middleware)
+                    .wrap(MetricsMiddleware::new())
+                    .wrap(RequestIdMiddleware::new())
             })
+            .service(
+                scope("/health")
+                    .service(health::live)
+                    .service(health::ready),
+            )
+            .service(health::metrics)
             .service(
                 scope("/api/authorization/v1")
                     .service(authorization::login)
@@ -80,6 +121,10 @@ pub async fn launch() -> std::io::Result<()> {
                     .service(members::activate)
                     .service(members::picture_asset)
                     .service(members::picture)
+                    .service(members::list_absences)
+                    .service(members::register_absence)
+                    .service(members::update_absence)
+                    .service(members::delete_absence)
                     .service(members::search)
                     .service(members::find)
                     .service(members::find_address)
@@ -125,10 +170,18 @@ pub async fn launch() -> std::io::Result<()> {
                     .service(pages::search)
                     .service(pages::create)
                     .service(pages::find_by_id)
+                    .service(pages::find_by_path)
                     .service(pages::main_menu)
                     .service(pages::sub_menu)
+                    .service(pages::tree)
+                    .service(pages::update_tree)
                     .service(pages::set_content)
+                    .service(pages::revisions)
+                    .service(pages::revision)
+                    .service(pages::revision_diff)
+                    .service(pages::restore_revision)
                     .service(pages::content)
+                    .service(pages::draft)
                     .service(pages::put_default)
                     .service(pages::get_default)
                     .service(pages::update)
@@ -138,7 +191,25 @@ pub async fn launch() -> std::io::Result<()> {
                     .service(pages::publish)
                     .service(pages::unpublish)
                     .service(pages::delete)
-                    .service(pages::events),
+                    .service(pages::events)
+                    .service(pages::find_event)
+                    .service(pages::set_event)
+                    .service(pages::delete_event),
+            )
+            .service(
+                scope("/api/attendance/v1")
+                    .service(attendance::export)
+                    .service(attendance::report)
+                    .service(attendance::find)
+                    .service(attendance::register),
+            )
+            .service(
+                scope("/api/calendar/v1")
+                    .service(calendar::public_feed)
+                    .service(calendar::personal_feed)
+                    .service(calendar::find_feed)
+                    .service(calendar::renew_feed)
+                    .service(calendar::revoke_feed),
             )
             .service(
                 scope("/api/images/v1")
@@ -158,6 +229,13 @@ pub async fn launch() -> std::io::Result<()> {
                     .service(musical_instruments::update)
                     .service(musical_instruments::delete),
             )
+            .service(
+                scope("/api/venues/v1")
+                    .service(venues::list)
+                    .service(venues::register)
+                    .service(venues::update)
+                    .service(venues::delete),
+            )
             .service(
                 scope("/api/mail-templates/v1")
                     .service(mail_templates::list)
@@ -167,12 +245,67 @@ pub async fn launch() -> std::io::Result<()> {
                     .service(mail_templates::delete),
             )
             .service(scope("/api/mailing/v1").service(mailing::send))
+            .service(
+                scope("/api/jobs/v1")
+                    .service(jobs::list)
+                    .service(jobs::runs)
+                    .service(jobs::trigger),
+            )
             .service(scope("/api/source_code_details/v1").service(source_code::details))
             .split_for_parts();
 
         app.service(Scalar::with_url("/docs", api))
     })
-    .bind((Ipv4Addr::UNSPECIFIED, 8080))?
-    .run()
-    .await?)
+    .shutdown_timeout(server_settings.shutdown_timeout_seconds)
+    .client_request_timeout(server_settings.client_request_timeout())
+    .client_disconnect_timeout(server_settings.client_disconnect_timeout());
+
+    let Some(tls_settings) = tls_settings else {
+        for address in &server_settings.bind {
+            info!("Listening on http://{address}");
+            server = server.bind(address)?;
+        }
+        return server.run().await;
+    };
+
+    let resolver = tls::CertificateResolver::load(&tls_settings)?;
+    tls::reload_on_hangup(resolver.clone())?;
+    let tls_config = tls::server_config(resolver);
+    for address in &tls_settings.bind {
+        info!("Listening on https://{address}");
+        server = server.bind_rustls_0_23(address, tls_config.clone())?;
+    }
+    let server = server.run();
+
+    if tls_settings.redirect_http && !server_settings.bind.is_empty() {
+        let redirect_server = launch_redirect(&server_settings, &tls_settings)?;
+        let redirect_handle = redirect_server.handle();
+        actix_web::rt::spawn(redirect_server);
+        let result = server.await;
+        redirect_handle.stop(true).await;
+        result
+    } else {
+        server.await
+    }
+}
+
+/// Launches a server on the plain HTTP addresses, redirecting all requests to HTTPS
+fn launch_redirect(settings: &ServerSettings, tls: &TlsSettings) -> std::io::Result<Server> {
+    let host = tls.redirect_host.clone();
+    let port = tls.redirect_port();
+    let mut server = HttpServer::new(move || {
+        let host = host.clone();
+        App::new()
+            .wrap(Logger::default())
+            .default_service(web::to(move |req: HttpRequest| {
+                tls::redirect_to_https(req, host.clone(), port)
+            }))
+    })
+    .workers(1)
+    .shutdown_timeout(settings.shutdown_timeout_seconds);
+    for address in &settings.bind {
+        info!("Redirecting http://{address} to HTTPS");
+        server = server.bind(address)?;
+    }
+    Ok(server.run())
 }
@@ -0,0 +1,206 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Native TLS support. The certificate and private key are read from PEM files, and are
+//! swapped in place when the process receives a SIGHUP, so renewed certificates can be
+//! activated without restarting the backend.
+
+use crate::generic::settings::TlsSettings;
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse};
+use log::{error, info};
+use rustls::crypto::ring;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::ServerConfig;
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Resolves the certificate for every TLS handshake, the certificate can be reloaded at runtime
+#[derive(Debug)]
+pub struct CertificateResolver {
+    certificate: PathBuf,
+    private_key: PathBuf,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl CertificateResolver {
+    pub fn load(settings: &TlsSettings) -> std::io::Result<Arc<Self>> {
+        let current = Self::read_certified_key(&settings.certificate, &settings.private_key)?;
+        Ok(Arc::new(Self {
+            certificate: settings.certificate.clone(),
+            private_key: settings.private_key.clone(),
+            current: RwLock::new(Arc::new(current)),
+        }))
+    }
+
+    /// Reads the certificate and private key again, the current certificate remains active if
+    /// reading fails.
+    pub fn reload(&self) -> std::io::Result<()> {
+        let certified_key = Self::read_certified_key(&self.certificate, &self.private_key)?;
+        let mut current = self
+            .current
+            .write()
+            .map_err(|_| Error::other("certificate lock is poisoned"))?;
+        *current = Arc::new(certified_key);
+        info!(
+            "Reloaded TLS certificate from {}",
+            self.certificate.display()
+        );
+        Ok(())
+    }
+
+    fn read_certified_key(certificate: &Path, private_key: &Path) -> std::io::Result<CertifiedKey> {
+        let mut reader = BufReader::new(File::open(certificate)?);
+        let certificates = rustls_pemfile::certs(&mut reader).collect::<Result<Vec<_>, _>>()?;
+        if certificates.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("no certificates found in {}", certificate.display()),
+            ));
+        }
+
+        let mut reader = BufReader::new(File::open(private_key)?);
+        let key = rustls_pemfile::private_key(&mut reader)?.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("no private key found in {}", private_key.display()),
+            )
+        })?;
+        let signing_key = ring::sign::any_supported_type(&key)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        Ok(CertifiedKey::new(certificates, signing_key))
+    }
+}
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _: ClientHello) -> Option<Arc<CertifiedKey>> {
+        self.current.read().ok().map(|current| current.clone())
+    }
+}
+
+/// Creates the server configuration, using the resolver for every handshake
+pub fn server_config(resolver: Arc<CertificateResolver>) -> ServerConfig {
+    ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("Default protocol versions should be supported")
+        .with_no_client_auth()
+        .with_cert_resolver(resolver)
+}
+
+/// Reloads the certificate each time the process receives a SIGHUP
+#[cfg(unix)]
+pub fn reload_on_hangup(resolver: Arc<CertificateResolver>) -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut hangup = signal(SignalKind::hangup())?;
+    actix_web::rt::spawn(async move {
+        while hangup.recv().await.is_some() {
+            if let Err(e) = resolver.reload() {
+                error!("Unable to reload TLS certificate: {e}");
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn reload_on_hangup(_: Arc<CertificateResolver>) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Redirects any plain HTTP request permanently to the same location using HTTPS. The host
+/// redirected to is the configured one, or otherwise the one from the `Host` header, the
+/// forwarding headers are not trusted, as they would turn this into an open redirect.
+pub async fn redirect_to_https(req: HttpRequest, host: Option<String>, port: u16) -> HttpResponse {
+    let host = host.or_else(|| {
+        req.headers()
+            .get(header::HOST)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    });
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str())
+        .unwrap_or("/");
+    match host.and_then(|host| redirect_location(&host, port, path)) {
+        Some(location) => HttpResponse::PermanentRedirect()
+            .insert_header((header::LOCATION, location))
+            .finish(),
+        None => HttpResponse::BadRequest().finish(),
+    }
+}
+
+/// Returns the HTTPS location for the host and path, or nothing if the host is not a valid
+/// host name or address, optionally followed by a port
+fn redirect_location(host: &str, port: u16, path: &str) -> Option<String> {
+    let name = match host.rsplit_once(':') {
+        // Strip the port, but leave IPv6 addresses intact
+        Some((name, port)) if !port.contains(']') => name,
+        _ => host,
+    };
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'));
+    if !valid {
+        return None;
+    }
+    let authority = if port == 443 {
+        name.to_owned()
+    } else {
+        format!("{name}:{port}")
+    };
+    Some(format!("https://{authority}{path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirects_to_the_requested_host() {
+        assert_eq!(
+            redirect_location("example.org", 443, "/api?q=1").as_deref(),
+            Some("https://example.org/api?q=1")
+        );
+        assert_eq!(
+            redirect_location("example.org:8080", 8443, "/").as_deref(),
+            Some("https://example.org:8443/")
+        );
+        assert_eq!(
+            redirect_location("[::1]:8080", 8443, "/").as_deref(),
+            Some("https://[::1]:8443/")
+        );
+        assert_eq!(
+            redirect_location("[::1]", 443, "/").as_deref(),
+            Some("https://[::1]/")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_hosts() {
+        assert_eq!(redirect_location("", 443, "/"), None);
+        assert_eq!(redirect_location("evil.org/path", 443, "/"), None);
+        assert_eq!(redirect_location("user@evil.org", 443, "/"), None);
+    }
+}
@@ -0,0 +1,85 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use dotenv::dotenv;
+use onvp_backend::commands::assets;
+use onvp_backend::generic::logging;
+use onvp_backend::generic::settings::Settings;
+use onvp_backend::generic::storage::assets::asset_store;
+use onvp_backend::generic::storage::database;
+use std::error::Error;
+use std::process::exit;
+
+const USAGE: &str = "Usage: onvp-fsck [--fix]";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    dotenv().ok();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let fix = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => false,
+        ["--fix"] => true,
+        _ => {
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    };
+    let settings = Settings::load_storage()?;
+    logging::init(&settings.logging);
+    let pool = database::initialize_database_connection_pool(&settings.database);
+
+    let asset_store = asset_store(&settings.assets);
+    let report = assets::check(&pool, asset_store.get_ref())?;
+    for asset_id in &report.missing {
+        println!("missing  {asset_id}");
+    }
+    for orphan in &report.orphaned {
+        println!(
+            "orphaned {} (last modified {} hour(s) ago)",
+            orphan.asset_id,
+            orphan.age.as_secs() / 3600
+        );
+    }
+    if report.missing.is_empty() && report.orphaned.is_empty() {
+        println!("All assets are consistent");
+    }
+
+    if fix {
+        let quarantined = assets::quarantine(
+            asset_store.get_ref(),
+            settings.assets.orphan_grace_period(),
+            &report,
+        )?;
+        println!(
+            "Quarantined {} orphaned asset(s), {} remain within the grace period of {} hour(s)",
+            quarantined.len(),
+            report.orphaned.len() - quarantined.len(),
+            settings.assets.orphan_grace_hours
+        );
+    }
+
+    if !report.missing.is_empty() {
+        exit(1);
+    }
+    Ok(())
+}
@@ -19,11 +19,39 @@
 
 use dotenv::dotenv;
 use onvp_backend::api::server;
+use onvp_backend::generic::logging;
+use onvp_backend::generic::settings::Settings;
 use std::error::Error;
+use std::process::exit;
 
 #[actix_web::main]
 async fn main() -> Result<(), impl Error> {
-    env_logger::init();
     dotenv().ok();
-    server::launch().await
+
+    let check_config = match std::env::args().nth(1).as_deref() {
+        None => false,
+        Some("check-config") => true,
+        Some(other) => {
+            eprintln!(
+                "Unknown subcommand: {other}, the only supported subcommand is: check-config"
+            );
+            exit(2);
+        }
+    };
+
+    let settings = match Settings::load() {
+        Ok(settings) => settings,
+        Err(report) => {
+            eprint!("{report}");
+            exit(1);
+        }
+    };
+    logging::init(&settings.logging);
+
+    if check_config {
+        println!("Configuration is valid");
+        return Ok(());
+    }
+
+    server::launch(settings).await
 }
@@ -0,0 +1,74 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use dotenv::dotenv;
+use onvp_backend::commands::backup;
+use onvp_backend::generic::logging;
+use onvp_backend::generic::settings::Settings;
+use onvp_backend::generic::storage::assets::asset_store;
+use onvp_backend::generic::storage::database;
+use std::error::Error;
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "Usage: onvp-backup <create <archive> | restore <archive> [--force]>";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    dotenv().ok();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let settings = Settings::load_storage()?;
+    logging::init(&settings.logging);
+    let pool = database::initialize_database_connection_pool(&settings.database);
+    let asset_store = asset_store(&settings.assets);
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["create", archive] => {
+            let manifest = backup::create(&pool, asset_store.get_ref(), Path::new(archive))?;
+            println!(
+                "Created {archive} containing {} file(s)",
+                manifest.files.len()
+            );
+            if !manifest.missing_assets.is_empty() {
+                eprintln!(
+                    "The following referenced assets are missing: {}",
+                    manifest.missing_assets.join(", ")
+                );
+            }
+        }
+        ["restore", archive] => {
+            let manifest =
+                backup::restore(&pool, asset_store.get_ref(), Path::new(archive), false)?;
+            println!("Restored the backup created at {}", manifest.created_at);
+        }
+        ["restore", archive, "--force"] | ["restore", "--force", archive] => {
+            let manifest = backup::restore(&pool, asset_store.get_ref(), Path::new(archive), true)?;
+            println!("Restored the backup created at {}", manifest.created_at);
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use dotenv::dotenv;
+use onvp_backend::generic::logging;
+use onvp_backend::generic::settings::Settings;
+use onvp_backend::generic::storage::database;
+use onvp_backend::generic::storage::migrations;
+use std::error::Error;
+use std::process::exit;
+
+const USAGE: &str = "Usage: onvp-migrate <status | up | down --to <version> | verify>";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    dotenv().ok();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let settings = Settings::load_database()?;
+    logging::init(&settings.logging);
+    let pool = database::initialize_database_connection_pool(&settings.database);
+    let mut conn = pool.get()?;
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["status"] => {
+            for migration in migrations::status(&mut conn)? {
+                let state = if migration.applied {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!("{state:<8} {}", migration.name);
+            }
+        }
+        ["up"] => {
+            let applied = migrations::run_pending(&mut conn)?;
+            if applied.is_empty() {
+                println!("No pending migrations");
+            }
+            for version in applied {
+                println!("Applied {version}");
+            }
+        }
+        ["down", "--to", version] => {
+            for version in migrations::revert_to(&mut conn, version)? {
+                println!("Reverted {version}");
+            }
+        }
+        ["verify"] => {
+            let problems = migrations::verify(&mut conn)?;
+            if problems.is_empty() {
+                println!("The database schema is up to date");
+            } else {
+                for problem in problems {
+                    eprintln!("{problem}");
+                }
+                exit(1);
+            }
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Commands working on the assets in the asset store, such as the consistency check
+//! cross-referencing the assets known to the database with the assets in the store.
+
+use crate::generic::imaging;
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::Settings;
+use crate::generic::storage::assets::{self, AssetStore};
+use crate::generic::storage::database::{DatabaseConnection, DatabaseConnectionPool};
+use crate::schema::{images, members, pages};
+use diesel::prelude::*;
+use log::{info, warn};
+use std::collections::BTreeSet;
+use std::time::{Duration, SystemTime};
+
+/// The outcome of cross-referencing the database with the asset store
+pub struct AssetReport {
+    /// Assets referenced from the database, which are missing from the store
+    pub missing: Vec<String>,
+    /// Assets in the store, which are not referenced from the database
+    pub orphaned: Vec<OrphanedAsset>,
+}
+
+pub struct OrphanedAsset {
+    pub asset_id: String,
+    /// The time since the asset was last modified
+    pub age: Duration,
+}
+
+/// Collects the identifiers of all assets referenced from the database
+pub fn referenced_assets(conn: &mut DatabaseConnection) -> BackendResult<BTreeSet<String>> {
+    let mut assets = BTreeSet::new();
+    assets.extend(images::table.select(images::asset).load::<String>(conn)?);
+    assets.extend(
+        pages::table
+            .select(pages::content_asset)
+            .load::<String>(conn)?,
+    );
+    assets.extend(
+        pages::table
+            .select(pages::draft_content_asset)
+            .load::<Option<String>>(conn)?
+            .into_iter()
+            .flatten(),
+    );
+    assets.extend(
+        pages::table
+            .select(pages::icon_asset)
+            .load::<Option<String>>(conn)?
+            .into_iter()
+            .flatten(),
+    );
+    assets.extend(
+        members::table
+            .select(members::picture_asset_id)
+            .load::<Option<String>>(conn)?
+            .into_iter()
+            .flatten(),
+    );
+    Ok(assets)
+}
+
+/// Cross-references the assets known to the database with the assets in the store. The
+/// database is read first, such that assets written by an upload which is not yet committed
+/// show up as (recent) orphans, rather than being missed.
+pub fn check(
+    pool: &DatabaseConnectionPool,
+    asset_store: &dyn AssetStore,
+) -> BackendResult<AssetReport> {
+    let referenced = referenced_assets(&mut *pool.get()?)?;
+    let now = SystemTime::now();
+    let mut stored: Vec<(String, Duration)> = asset_store
+        .list()?
+        .into_iter()
+        .map(|asset| {
+            let age = now.duration_since(asset.modified).unwrap_or_default();
+            (asset.asset_id, age)
+        })
+        .collect();
+    stored.sort();
+
+    let names: BTreeSet<&String> = stored.iter().map(|(asset_id, _)| asset_id).collect();
+    let missing = referenced
+        .iter()
+        .filter(|asset_id| !names.contains(asset_id))
+        .cloned()
+        .collect();
+    // Cached variants and renderings belong to the asset they are derived from
+    let is_referenced = |asset_id: &String| {
+        referenced.contains(asset_id)
+            || imaging::base_asset_id(asset_id).is_some_and(|base| referenced.contains(base))
+    };
+    let orphaned = stored
+        .into_iter()
+        .filter(|(asset_id, _)| !is_referenced(asset_id))
+        .map(|(asset_id, age)| OrphanedAsset { asset_id, age })
+        .collect();
+    Ok(AssetReport { missing, orphaned })
+}
+
+/// Quarantines the orphaned assets older than the grace period, from where they can be
+/// recovered or removed manually. Returns the quarantined assets.
+pub fn quarantine(
+    asset_store: &dyn AssetStore,
+    grace_period: Duration,
+    report: &AssetReport,
+) -> BackendResult<Vec<String>> {
+    let mut quarantined = Vec::new();
+    for orphan in report
+        .orphaned
+        .iter()
+        .filter(|orphan| orphan.age >= grace_period)
+    {
+        asset_store.quarantine(&orphan.asset_id)?;
+        info!("Quarantined orphaned asset {}", orphan.asset_id);
+        quarantined.push(orphan.asset_id.clone());
+    }
+    Ok(quarantined)
+}
+
+/// Scheduled job reporting the inconsistencies between the database and the asset store,
+/// fixing them is left to an operator, using onvp-fsck
+pub fn check_assets(pool: &DatabaseConnectionPool, settings: &Settings) -> BackendResult<()> {
+    let asset_store = assets::asset_store(&settings.assets);
+    let report = check(pool, asset_store.get_ref())?;
+    for asset_id in &report.missing {
+        warn!("Asset {asset_id} is referenced, but missing from the asset store");
+    }
+    if !report.orphaned.is_empty() {
+        warn!(
+            "Found {} orphaned asset(s), run onvp-fsck to inspect them",
+            report.orphaned.len()
+        );
+    }
+    if report.missing.is_empty() {
+        Ok(())
+    } else {
+        Err(BackendError::asset(format!(
+            "{} referenced asset(s) are missing",
+            report.missing.len()
+        )))
+    }
+}
@@ -0,0 +1,644 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Creates and restores backups of the backend. The assets are stored in the asset store,
+//! while their metadata is stored in the database, therefore a backup contains both,
+//! taken from a single database snapshot, together with a manifest holding the checksums of
+//! all files in the archive.
+
+use crate::commands::assets::referenced_assets;
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::storage::assets::AssetStore;
+use crate::generic::storage::database::{DatabaseConnection, DatabaseConnectionPool};
+use crate::generic::storage::migrations;
+use crate::schema;
+use chrono::{DateTime, Utc};
+use diesel::dsl::{exists, sql};
+use diesel::pg::{CopyFormat, CopyHeader};
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::Integer;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path};
+
+/// The version of the archive layout, increased when the layout changes incompatibly
+const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+const DATA_DIRECTORY: &str = "data";
+const ASSETS_DIRECTORY: &str = "assets";
+
+/// Generates the list of backed up tables, together with the functions to export, import and
+/// inspect them by name, because COPY requires the concrete table types.
+macro_rules! backup_tables {
+    ($($table:ident),+ $(,)?) => {
+        /// The backed up tables, in an order satisfying the foreign keys
+        const TABLES: &[&str] = &[$(stringify!($table)),+];
+
+        fn export_table(conn: &mut DatabaseConnection, name: &str) -> BackendResult<Vec<u8>> {
+            $(
+                if name == stringify!($table) {
+                    let mut data = Vec::new();
+                    diesel::copy_to(schema::$table::table)
+                        .with_format(CopyFormat::Csv)
+                        .with_header(true)
+                        .load_raw(conn)?
+                        .read_to_end(&mut data)
+                        .map_err(|e| BackendError::backup(format!("Unable to export {name}: {e}")))?;
+                    return Ok(data);
+                }
+            )+
+            Err(BackendError::backup(format!("Unknown table: {name}")))
+        }
+
+        fn import_table(
+            conn: &mut DatabaseConnection,
+            name: &str,
+            data: &[u8],
+        ) -> BackendResult<usize> {
+            $(
+                if name == stringify!($table) {
+                    let rows = diesel::copy_from(schema::$table::table)
+                        .from_raw_data(schema::$table::table, |copy| -> BackendResult<()> {
+                            copy.write_all(data).map_err(|e| {
+                                BackendError::backup(format!("Unable to import {name}: {e}"))
+                            })
+                        })
+                        .with_format(CopyFormat::Csv)
+                        .with_header(CopyHeader::Set(true))
+                        .execute(conn)?;
+                    return Ok(rows);
+                }
+            )+
+            Err(BackendError::backup(format!("Unknown table: {name}")))
+        }
+
+        fn table_has_rows(conn: &mut DatabaseConnection, name: &str) -> BackendResult<bool> {
+            $(
+                if name == stringify!($table) {
+                    let rows = schema::$table::table.select(sql::<Integer>("1"));
+                    return Ok(diesel::select(exists(rows)).get_result(conn)?);
+                }
+            )+
+            Err(BackendError::backup(format!("Unknown table: {name}")))
+        }
+    };
+}
+
+backup_tables!(
+    musical_instruments,
+    member_address_details,
+    member_details,
+    members,
+    member_role_associations,
+    member_absences,
+    workgroups,
+    workgroup_member_relationships,
+    workgroup_role_associations,
+    calendar_feeds,
+    pages,
+    page_access_policies,
+    page_redirects,
+    page_revisions,
+    venues,
+    page_events,
+    page_event_exceptions,
+    page_event_references,
+    page_image_references,
+    event_attendances,
+    event_attendance_reminders,
+    images,
+    image_access_policies,
+    properties,
+    mail_templates,
+    job_runs,
+);
+
+/// Describes the contents of a backup archive
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    /// The applied migrations of the backed up database
+    pub migrations: Vec<String>,
+    /// The SHA-256 checksum of every file in the archive, by path
+    pub files: BTreeMap<String, String>,
+    /// Assets referenced from the database, which were not found in the assets directory
+    pub missing_assets: Vec<String>,
+}
+
+/// Writes a backup of the database and all referenced assets to the archive, a gzipped tar
+/// file. The archive is written next to its destination first, and only moved in place when
+/// complete.
+pub fn create(
+    pool: &DatabaseConnectionPool,
+    asset_store: &dyn AssetStore,
+    archive: &Path,
+) -> BackendResult<Manifest> {
+    let partial = archive.with_extension("partial");
+    let result = write_archive(pool, asset_store, &partial).and_then(|manifest| {
+        std::fs::rename(&partial, archive).map_err(|e| io_error(archive, e))?;
+        Ok(manifest)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result
+}
+
+/// Restores a backup into the database and the asset store. The archive is verified
+/// against its manifest before anything is changed. A database which already contains data is
+/// only overwritten when forced.
+pub fn restore(
+    pool: &DatabaseConnectionPool,
+    asset_store: &dyn AssetStore,
+    archive: &Path,
+    force: bool,
+) -> BackendResult<Manifest> {
+    let staging = std::env::temp_dir().join(format!("onvp-restore-{}", std::process::id()));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging).map_err(|e| io_error(&staging, e))?;
+    }
+    let result = unpack(archive, &staging)
+        .and_then(|manifest| verify(&staging, &manifest).map(|_| manifest))
+        .and_then(|manifest| {
+            let mut conn = pool.get()?;
+            import(&mut conn, asset_store, &staging, &manifest, force)?;
+            Ok(manifest)
+        });
+    if let Err(e) = std::fs::remove_dir_all(&staging) {
+        warn!("Unable to remove {}: {e}", staging.display());
+    }
+    result
+}
+
+fn write_archive(
+    pool: &DatabaseConnectionPool,
+    asset_store: &dyn AssetStore,
+    path: &Path,
+) -> BackendResult<Manifest> {
+    let file = File::create(path).map_err(|e| io_error(path, e))?;
+    let mut archive = ArchiveWriter::new(BufWriter::new(file), Utc::now());
+    let mut conn = pool.get()?;
+
+    // A single snapshot is used, such that the exported tables are consistent with each other
+    // and with the referenced assets, which are therefore read while the snapshot is open. An
+    // asset removed before it is read is recorded as missing.
+    let (migrations, missing_assets) =
+        conn.build_transaction()
+            .repeatable_read()
+            .read_only()
+            .run(|conn| -> BackendResult<_> {
+                let migrations = migrations::applied(conn)?;
+                for table in TABLES {
+                    let data = export_table(conn, table)?;
+                    archive.add(&format!("{DATA_DIRECTORY}/{table}.csv"), &data)?;
+                }
+                let mut missing_assets = Vec::new();
+                for asset in referenced_assets(conn)? {
+                    match asset_store.get(&asset) {
+                        Ok(data) => archive.add(&format!("{ASSETS_DIRECTORY}/{asset}"), &data)?,
+                        Err(e) => {
+                            warn!("Asset {asset} is referenced, but can not be read: {e}");
+                            missing_assets.push(asset);
+                        }
+                    }
+                }
+                Ok((migrations, missing_assets))
+            })?;
+
+    archive.finish(migrations, missing_assets)
+}
+
+/// Writes the files of a backup to a gzipped tar stream, recording their checksums for the
+/// manifest, which is written last
+struct ArchiveWriter<W: Write> {
+    builder: tar::Builder<GzEncoder<W>>,
+    files: BTreeMap<String, String>,
+    created_at: DateTime<Utc>,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    fn new(writer: W, created_at: DateTime<Utc>) -> Self {
+        Self {
+            builder: tar::Builder::new(GzEncoder::new(writer, Compression::default())),
+            files: BTreeMap::new(),
+            created_at,
+        }
+    }
+
+    fn add(&mut self, name: &str, data: &[u8]) -> BackendResult<()> {
+        self.append(name, data)?;
+        self.files.insert(name.to_owned(), checksum(data));
+        Ok(())
+    }
+
+    fn append(&mut self, name: &str, data: &[u8]) -> BackendResult<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o640);
+        header.set_mtime(self.created_at.timestamp().max(0) as u64);
+        header.set_cksum();
+        self.builder
+            .append_data(&mut header, name, data)
+            .map_err(|e| BackendError::backup(format!("Unable to add {name} to the archive: {e}")))
+    }
+
+    fn finish(
+        mut self,
+        migrations: Vec<String>,
+        missing_assets: Vec<String>,
+    ) -> BackendResult<Manifest> {
+        let manifest = Manifest {
+            format_version: FORMAT_VERSION,
+            created_at: self.created_at,
+            migrations,
+            files: std::mem::take(&mut self.files),
+            missing_assets,
+        };
+        let data = serde_json::to_vec_pretty(&manifest).map_err(BackendError::backup)?;
+        self.append(MANIFEST, &data)?;
+        self.builder
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .and_then(|mut writer| writer.flush())
+            .map_err(|e| BackendError::backup(format!("Unable to write the archive: {e}")))?;
+        Ok(manifest)
+    }
+}
+
+/// Extracts the archive into the staging directory, refusing any file which does not belong
+/// in a backup
+fn unpack(archive: &Path, staging: &Path) -> BackendResult<Manifest> {
+    let file = File::open(archive).map_err(|e| io_error(archive, e))?;
+    let mut entries = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+    std::fs::create_dir_all(staging.join(DATA_DIRECTORY)).map_err(|e| io_error(staging, e))?;
+    std::fs::create_dir_all(staging.join(ASSETS_DIRECTORY)).map_err(|e| io_error(staging, e))?;
+
+    for entry in entries.entries().map_err(|e| io_error(archive, e))? {
+        let mut entry = entry.map_err(|e| io_error(archive, e))?;
+        let path = entry.path().map_err(|e| io_error(archive, e))?.into_owned();
+        let name = archive_name(&path).ok_or_else(|| {
+            BackendError::backup(format!("Unexpected file in archive: {}", path.display()))
+        })?;
+        let destination = staging.join(&name);
+        let mut output = File::create(&destination).map_err(|e| io_error(&destination, e))?;
+        std::io::copy(&mut entry, &mut output).map_err(|e| io_error(&destination, e))?;
+    }
+
+    let path = staging.join(MANIFEST);
+    let data = std::fs::read(&path)
+        .map_err(|_| BackendError::backup("The archive does not contain a manifest"))?;
+    let manifest: Manifest = serde_json::from_slice(&data).map_err(BackendError::backup)?;
+    if manifest.format_version != FORMAT_VERSION {
+        return Err(BackendError::backup(format!(
+            "Unsupported backup format version {}, expected {FORMAT_VERSION}",
+            manifest.format_version
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Validates the path of an archive entry, returning the normalized name if it is the manifest,
+/// the export of a known table, or an asset
+fn archive_name(path: &Path) -> Option<String> {
+    let components: Vec<&str> = path
+        .components()
+        .map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    match components.as_slice() {
+        [MANIFEST] => Some(MANIFEST.to_owned()),
+        [DATA_DIRECTORY, file] => {
+            let table = file.strip_suffix(".csv")?;
+            TABLES
+                .contains(&table)
+                .then(|| format!("{DATA_DIRECTORY}/{file}"))
+        }
+        [ASSETS_DIRECTORY, asset] if asset.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            Some(format!("{ASSETS_DIRECTORY}/{asset}"))
+        }
+        _ => None,
+    }
+}
+
+/// Checks that the staged files match the manifest exactly
+fn verify(staging: &Path, manifest: &Manifest) -> BackendResult<()> {
+    for (name, expected) in &manifest.files {
+        let path = staging.join(name);
+        let data = std::fs::read(&path)
+            .map_err(|_| BackendError::backup(format!("The archive is missing {name}")))?;
+        if &checksum(&data) != expected {
+            return Err(BackendError::backup(format!(
+                "Checksum mismatch for {name}"
+            )));
+        }
+    }
+    for directory in [DATA_DIRECTORY, ASSETS_DIRECTORY] {
+        for file in staged_files(&staging.join(directory))? {
+            let name = format!("{directory}/{file}");
+            if !manifest.files.contains_key(&name) {
+                return Err(BackendError::backup(format!(
+                    "The archive contains {name}, which is not in the manifest"
+                )));
+            }
+        }
+    }
+    for table in TABLES {
+        let name = format!("{DATA_DIRECTORY}/{table}.csv");
+        if !manifest.files.contains_key(&name) {
+            return Err(BackendError::backup(format!(
+                "The archive is missing {name}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn import(
+    conn: &mut DatabaseConnection,
+    asset_store: &dyn AssetStore,
+    staging: &Path,
+    manifest: &Manifest,
+    force: bool,
+) -> BackendResult<()> {
+    conn.transaction(|conn| {
+        migrations::apply_versions(conn, &manifest.migrations)?;
+        if migrations::applied(conn)? != manifest.migrations {
+            return Err(BackendError::backup(
+                "The migrations of the database do not match the migrations of the backup",
+            ));
+        }
+
+        let mut populated = Vec::new();
+        for table in TABLES {
+            if table_has_rows(conn, table)? {
+                populated.push(*table);
+            }
+        }
+        if !populated.is_empty() {
+            if !force {
+                return Err(BackendError::backup(format!(
+                    "The database is not empty (tables: {}), use --force to overwrite it",
+                    populated.join(", ")
+                )));
+            }
+            warn!("Overwriting the existing data");
+            sql_query(format!(
+                "TRUNCATE {} RESTART IDENTITY CASCADE",
+                TABLES.join(", ")
+            ))
+            .execute(conn)?;
+        }
+
+        for table in TABLES {
+            let path = staging.join(format!("{DATA_DIRECTORY}/{table}.csv"));
+            let data = std::fs::read(&path).map_err(|e| io_error(&path, e))?;
+            let rows = import_table(conn, table, &data)?;
+            info!("Restored {rows} row(s) into {table}");
+        }
+        for table in TABLES {
+            reset_sequence(conn, table)?;
+        }
+
+        // The assets are stored last, such that any failure before rolls back the database
+        let assets = staging.join(ASSETS_DIRECTORY);
+        for asset in staged_files(&assets)? {
+            let path = assets.join(&asset);
+            let data = std::fs::read(&path).map_err(|e| io_error(&path, e))?;
+            asset_store.put(&asset, &data)?;
+        }
+        Ok(())
+    })
+}
+
+/// Moves the sequence of the identifier of the table, if any, past the restored identifiers
+fn reset_sequence(conn: &mut DatabaseConnection, table: &str) -> BackendResult<()> {
+    if table_has_id(conn, table)? {
+        sql_query(format!(
+            "SELECT setval(pg_get_serial_sequence('{table}', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM {table}"
+        ))
+        .execute(conn)?;
+    }
+    Ok(())
+}
+
+fn table_has_id(conn: &mut DatabaseConnection, table: &str) -> BackendResult<bool> {
+    use diesel::sql_types::Text;
+
+    #[derive(QueryableByName)]
+    struct Count {
+        #[diesel(sql_type = diesel::sql_types::BigInt)]
+        count: i64,
+    }
+
+    let result: Count = sql_query(
+        "SELECT COUNT(*) AS count FROM information_schema.columns \
+         WHERE table_schema = current_schema() AND table_name = $1 AND column_name = 'id'",
+    )
+    .bind::<Text, _>(table)
+    .get_result(conn)?;
+    Ok(result.count > 0)
+}
+
+fn staged_files(directory: &Path) -> BackendResult<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(directory).map_err(|e| io_error(directory, e))? {
+        let entry = entry.map_err(|e| io_error(directory, e))?;
+        files.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    Ok(files)
+}
+
+fn checksum(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> BackendError {
+    BackendError::backup(format!("{}: {error}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A directory which is removed when the test finishes
+    struct TestDirectory(PathBuf);
+
+    impl TestDirectory {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("onvp-backup-test-{}-{name}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TestDirectory {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_backup(path: &Path, extra: &[(&str, &[u8])]) -> Manifest {
+        let file = File::create(path).unwrap();
+        let mut archive = ArchiveWriter::new(file, Utc::now());
+        for table in TABLES {
+            let data = format!("id\n{}\n", table.len());
+            archive
+                .add(&format!("{DATA_DIRECTORY}/{table}.csv"), data.as_bytes())
+                .unwrap();
+        }
+        archive.add("assets/abc123", b"asset contents").unwrap();
+        for (name, data) in extra {
+            archive.append(name, data).unwrap();
+        }
+        archive
+            .finish(
+                vec!["20250101000000".to_owned()],
+                vec!["missing".to_owned()],
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let directory = TestDirectory::new("round-trip");
+        let archive = directory.0.join("backup.tar.gz");
+        let staging = directory.0.join("staging");
+        let written = write_backup(&archive, &[]);
+        assert_eq!(written.files.len(), TABLES.len() + 1);
+
+        let read = unpack(&archive, &staging).unwrap();
+        assert_eq!(read.files, written.files);
+        assert_eq!(read.migrations, ["20250101000000"]);
+        assert_eq!(read.missing_assets, ["missing"]);
+        assert!(verify(&staging, &read).is_ok());
+        assert_eq!(
+            std::fs::read(staging.join("assets/abc123")).unwrap(),
+            b"asset contents"
+        );
+    }
+
+    #[test]
+    fn verify_detects_modified_files() {
+        let directory = TestDirectory::new("modified");
+        let archive = directory.0.join("backup.tar.gz");
+        let staging = directory.0.join("staging");
+        write_backup(&archive, &[]);
+        let manifest = unpack(&archive, &staging).unwrap();
+        std::fs::write(staging.join("assets/abc123"), b"tampered").unwrap();
+        let error = verify(&staging, &manifest).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Checksum mismatch for assets/abc123"));
+    }
+
+    #[test]
+    fn verify_detects_files_missing_from_the_manifest() {
+        let directory = TestDirectory::new("unlisted");
+        let archive = directory.0.join("backup.tar.gz");
+        let staging = directory.0.join("staging");
+        write_backup(&archive, &[("assets/def456", b"unlisted")]);
+        let manifest = unpack(&archive, &staging).unwrap();
+        let error = verify(&staging, &manifest).unwrap_err();
+        assert!(error.to_string().contains("assets/def456"));
+    }
+
+    #[test]
+    fn verify_detects_missing_tables() {
+        let directory = TestDirectory::new("missing-table");
+        let archive = directory.0.join("backup.tar.gz");
+        let staging = directory.0.join("staging");
+        write_backup(&archive, &[]);
+        let mut manifest = unpack(&archive, &staging).unwrap();
+        let name = format!("{DATA_DIRECTORY}/{}.csv", TABLES[0]);
+        manifest.files.remove(&name);
+        std::fs::remove_file(staging.join(&name)).unwrap();
+        let error = verify(&staging, &manifest).unwrap_err();
+        assert!(error.to_string().contains(&name));
+    }
+
+    #[test]
+    fn unpack_refuses_unexpected_files() {
+        let directory = TestDirectory::new("unexpected");
+        let archive = directory.0.join("backup.tar.gz");
+        let staging = directory.0.join("staging");
+        write_backup(&archive, &[("data/unknown.csv", b"id\n")]);
+        let error = unpack(&archive, &staging).unwrap_err();
+        assert!(error.to_string().contains("Unexpected file in archive"));
+    }
+
+    #[test]
+    fn unpack_refuses_other_format_versions() {
+        let directory = TestDirectory::new("version");
+        let archive = directory.0.join("backup.tar.gz");
+        let staging = directory.0.join("staging");
+        let mut writer = ArchiveWriter::new(File::create(&archive).unwrap(), Utc::now());
+        let manifest = serde_json::json!({
+            "formatVersion": FORMAT_VERSION + 1,
+            "createdAt": Utc::now(),
+            "migrations": [],
+            "files": {},
+            "missingAssets": [],
+        });
+        writer
+            .append(MANIFEST, manifest.to_string().as_bytes())
+            .unwrap();
+        writer.builder.into_inner().unwrap().finish().unwrap();
+        let error = unpack(&archive, &staging).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Unsupported backup format version"));
+    }
+
+    #[test]
+    fn archive_names() {
+        assert_eq!(
+            archive_name(Path::new("manifest.json")).as_deref(),
+            Some(MANIFEST)
+        );
+        assert_eq!(
+            archive_name(Path::new("data/pages.csv")).as_deref(),
+            Some("data/pages.csv")
+        );
+        assert_eq!(
+            archive_name(Path::new("assets/abc123")).as_deref(),
+            Some("assets/abc123")
+        );
+        assert_eq!(archive_name(Path::new("data/unknown.csv")), None);
+        assert_eq!(archive_name(Path::new("assets/abc.123")), None);
+        assert_eq!(archive_name(Path::new("assets/../manifest.json")), None);
+        assert_eq!(archive_name(Path::new("/manifest.json")), None);
+        assert_eq!(archive_name(Path::new("other/file")), None);
+    }
+}
@@ -17,15 +17,35 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod registry;
+pub mod schedule;
+pub mod scheduler;
+
+use crate::generic::rendering::{self, Shortcode};
 use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::security::ClaimRoles;
+use crate::generic::settings::{SendEmailConfig, Settings};
+use crate::generic::storage::assets::{self, renderings};
 use crate::generic::storage::database::{DatabaseConnection, DatabaseConnectionPool};
+use crate::generic::storage::session::DefaultSessionManagerImplementation;
+use crate::generic::Injectable;
+use crate::model::primitives::{ContentFormat, Role};
 use crate::model::storage::entities::{Member, MemberAddressDetail, MemberDetail};
+use crate::repositories::implementation::{absence, attendance, page};
 use crate::schema;
-use crate::schema::{member_address_details, member_details, members};
+use crate::schema::{member_address_details, member_details, members, page_access_policies, pages};
+use chrono::{Days, NaiveDateTime};
 use diesel::prelude::*;
-use log::info;
+use handlebars::{no_escape, Handlebars};
+use lettre::message::header::ContentType;
+use lettre::{Message, SmtpTransport, Transport};
+use log::{info, warn};
+use serde_json::json;
 
-pub fn clean_late_non_activated_members(pool: DatabaseConnectionPool) -> BackendResult<()> {
+pub fn clean_late_non_activated_members(
+    pool: &DatabaseConnectionPool,
+    _: &Settings,
+) -> BackendResult<()> {
     let mut conn = pool.get()?;
     conn.transaction(|conn| {
         let activated_filter = schema::members::activated.eq(false);
@@ -64,6 +84,242 @@ pub fn clean_late_non_activated_members(pool: DatabaseConnectionPool) -> Backend
     Ok(())
 }
 
+/// Records the transitions of pages which reached their scheduled publication moments. Until
+/// then, the pages are hidden by their publication window. Published pages lose their
+/// publication moment, while unpublished pages are only available to operators again.
+pub fn apply_scheduled_publications(
+    pool: &DatabaseConnectionPool,
+    settings: &Settings,
+) -> BackendResult<()> {
+    let mut conn = pool.get()?;
+    let changed: Vec<Shortcode> = conn.transaction(|conn| {
+        let now = chrono::Utc::now().naive_utc();
+
+        let unpublished: Vec<(i32, String)> =
+            diesel::update(pages::table.filter(pages::unpublish_at.le(now)))
+                .set((
+                    pages::publish_at.eq(None::<NaiveDateTime>),
+                    pages::unpublish_at.eq(None::<NaiveDateTime>),
+                    pages::etag.eq(crate::generate_asset_id()),
+                    pages::last_modified.eq(now),
+                ))
+                .returning((pages::id, pages::title))
+                .get_results(conn)?;
+        for (page_id, title) in &unpublished {
+            diesel::delete(page_access_policies::table)
+                .filter(page_access_policies::page_id.eq(page_id))
+                .filter(page_access_policies::system_role.ne(Role::Operator))
+                .execute(conn)?;
+            info!("Unpublished page: {page_id} with title: {title}");
+        }
+
+        let published: Vec<(i32, String)> =
+            diesel::update(pages::table.filter(pages::publish_at.le(now)))
+                .set((
+                    pages::publish_at.eq(None::<NaiveDateTime>),
+                    pages::etag.eq(crate::generate_asset_id()),
+                    pages::last_modified.eq(now),
+                ))
+                .returning((pages::id, pages::title))
+                .get_results(conn)?;
+        for (page_id, title) in &published {
+            info!("Published page: {page_id} with title: {title}");
+        }
+
+        info!(
+            "Published {} and unpublished {} pages",
+            published.len(),
+            unpublished.len()
+        );
+        Ok::<_, BackendError>(
+            unpublished
+                .iter()
+                .chain(&published)
+                .map(|(page_id, _)| Shortcode::Event(*page_id))
+                .collect(),
+        )
+    })?;
+    if changed.is_empty() {
+        return Ok(());
+    }
+
+    // Links to events in the content of other pages depend on the publication of the events
+    let page_repository = page::Implementation::make(settings);
+    let mut session = DefaultSessionManagerImplementation::make(pool).prepare()?;
+    let asset_ids = page_repository.find_referring_content_assets(&mut session, &changed)?;
+    session.commit()?;
+    let asset_store = assets::asset_store(&settings.assets);
+    for asset_id in &asset_ids {
+        if let Err(e) = renderings::delete(asset_store.as_ref(), asset_id) {
+            warn!("Unable to discard the rendering of content {asset_id}: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Indexes the published content of the pages which are not indexed yet, such as pages which
+/// were published before their content was indexed. Newly published content is indexed when it
+/// is published.
+pub fn index_page_content(pool: &DatabaseConnectionPool, settings: &Settings) -> BackendResult<()> {
+    let asset_store = assets::asset_store(&settings.assets);
+    let mut conn = pool.get()?;
+    let unindexed: Vec<(i32, String, ContentFormat)> = pages::table
+        .filter(pages::search_text.is_null())
+        .select((pages::id, pages::content_asset, pages::content_format))
+        .load(&mut conn)?;
+
+    for (page_id, asset_id, format) in &unindexed {
+        let rendering_id = renderings::rendering_asset_id(asset_id);
+        let html = if asset_store.exists(&rendering_id)? {
+            String::from_utf8(asset_store.get(&rendering_id)?)
+                .map_err(BackendError::byte_conversion)?
+        } else if asset_store.exists(asset_id)? {
+            // Shortcodes remain as written, the rendering is stored when the page is published
+            let source = String::from_utf8(asset_store.get(asset_id)?)
+                .map_err(BackendError::byte_conversion)?;
+            rendering::render(&source, *format, |_| None)
+        } else {
+            String::new()
+        };
+        diesel::update(pages::table.filter(pages::id.eq(page_id)))
+            .set(pages::search_text.eq(rendering::plain_text(&html)))
+            .execute(&mut conn)?;
+    }
+
+    info!("Indexed the content of {} pages", unindexed.len());
+    Ok(())
+}
+
+/// Reminds the members who did not respond to an upcoming occurrence of an event yet, once the
+/// occurrence starts within the configured number of days. Every member is reminded once per
+/// occurrence, members whose reminder could not be sent are tried again on the next run. Members
+/// who registered to be away on the day of the occurrence are not reminded, as for mailings.
+pub fn remind_event_attendance(
+    pool: &DatabaseConnectionPool,
+    settings: &Settings,
+) -> BackendResult<()> {
+    let event_settings = &settings.events;
+    if event_settings.attendance_reminder_days == 0 {
+        return Ok(());
+    }
+    let page_repository = page::Implementation::make(settings);
+    let attendance_repository = attendance::Implementation::make(&());
+    let absence_repository = absence::Implementation::make(&());
+    let session_manager = DefaultSessionManagerImplementation::make(pool);
+    let mut session = session_manager.prepare()?;
+
+    let mut templates = Handlebars::new();
+    templates.register_escape_fn(no_escape);
+    templates.register_template_string("subject", &event_settings.attendance_reminder_subject)?;
+    templates.register_template_string("body", &event_settings.attendance_reminder_body)?;
+    let email_config = settings.send_email_config();
+    let transport = email_config.transport()?;
+
+    let today = chrono::Utc::now().date_naive();
+    let end_date = today
+        .checked_add_days(Days::new(event_settings.attendance_reminder_days as u64))
+        .ok_or_else(BackendError::bad)?;
+    // Events for operators only are not published, hence not awaiting any responses
+    let roles =
+        ClaimRoles::from([Role::Member, Role::OrchestraCommittee, Role::Director].as_slice());
+    let occurrences = page_repository.find_events(&mut session, &roles, &today, &end_date)?;
+
+    let mut pending = Vec::new();
+    for occurrence in &occurrences {
+        let page = &occurrence.page;
+        let Some(occurrence_date) = page.event_date.filter(|date| *date >= today) else {
+            continue;
+        };
+        let page_roles = page_repository.find_associated_roles_by_id(&mut session, page.id)?;
+        let attendees = attendance_repository.list_attendees(
+            &mut session,
+            page.id,
+            occurrence_date,
+            &page_roles,
+        )?;
+        let already_reminded =
+            attendance_repository.list_reminded(&mut session, page.id, occurrence_date)?;
+        let absent_member_ids =
+            absence_repository.list_absent_member_ids(&mut session, &occurrence_date)?;
+
+        for attendee in attendees.into_iter().filter(|attendee| {
+            attendee.attendance.is_none()
+                && !already_reminded.contains(&attendee.member_id)
+                && !absent_member_ids.contains(&attendee.member_id)
+        }) {
+            let data = json!({
+                "first_name": attendee.first_name,
+                "last_name": attendee.last_name,
+                "title": page.title,
+                "date": occurrence_date.format("%Y-%m-%d").to_string(),
+                "link": settings.content.page_link(page.id),
+            });
+            pending.push((page.id, occurrence_date, attendee, data));
+        }
+    }
+    session.commit()?;
+
+    let mut reminded = 0;
+    for (page_id, occurrence_date, attendee, data) in &pending {
+        // Every reminder is recorded before it is sent, such that a failing run never sends a
+        // reminder twice
+        let mut session = session_manager.prepare()?;
+        attendance_repository.add_reminder(
+            &mut session,
+            *page_id,
+            *occurrence_date,
+            attendee.member_id,
+        )?;
+        session.commit()?;
+
+        let sent = send_reminder(
+            &transport,
+            &email_config,
+            &templates,
+            &attendee.email_address,
+            data,
+        );
+        match sent {
+            Ok(()) => reminded += 1,
+            Err(e) => {
+                warn!(
+                    "Failed to remind member: {} of page: {page_id} on {occurrence_date}: {e}",
+                    attendee.member_id
+                );
+                let mut session = session_manager.prepare()?;
+                attendance_repository.remove_reminder(
+                    &mut session,
+                    *page_id,
+                    *occurrence_date,
+                    attendee.member_id,
+                )?;
+                session.commit()?;
+            }
+        }
+    }
+
+    info!("Reminded {reminded} members to respond to upcoming events");
+    Ok(())
+}
+
+/// Renders a reminder for a member and sends it
+fn send_reminder(
+    transport: &SmtpTransport,
+    email_config: &SendEmailConfig,
+    templates: &Handlebars,
+    email_address: &str,
+    data: &serde_json::Value,
+) -> BackendResult<()> {
+    let email = Message::builder()
+        .from(email_config.email_from.clone())
+        .to(email_address.parse()?)
+        .subject(templates.render("subject", data)?)
+        .header(ContentType::TEXT_PLAIN)
+        .body(templates.render("body", data)?)?;
+    transport.send(&email)?;
+    Ok(())
+}
+
 pub fn find_detail_by_detail_id(
     conn: &mut DatabaseConnection,
     detail_id: &i32,
@@ -94,7 +350,7 @@ pub fn delete_member_address_detail_by_id(
         })
         .filter(|r| r.is_err())
         .map(|r| r.unwrap_err())
-        .nth(0);
+        .next();
     match maybe_first_error {
         Some(first_error) => Err(first_error.into()),
         None => Ok(()),
@@ -117,7 +373,7 @@ pub fn delete_member_detail_by_id(
         })
         .filter(|r| r.is_err())
         .map(|r| r.unwrap_err())
-        .nth(0);
+        .next();
     match maybe_first_error {
         Some(first_error) => Err(first_error.into()),
         None => Ok(()),
@@ -0,0 +1,188 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The registry of all jobs known to the backend. Running a job takes a Postgres advisory lock,
+//! such that a job runs at most once at a time, even if multiple instances of the backend are
+//! running. Every run is recorded in the `job_runs` table.
+
+use crate::commands::jobs::schedule::Schedule;
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::Settings;
+use crate::generic::storage::database::{DatabaseConnection, DatabaseConnectionPool};
+use crate::model::storage::entities::JobRun;
+use crate::schema::job_runs;
+use actix_web::web::Data;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use log::{error, info};
+
+diesel::define_sql_function! {
+    fn pg_try_advisory_xact_lock(key: BigInt) -> Bool;
+}
+
+/// Describes a job which can be scheduled
+pub struct JobDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The schedule used, unless overridden in the settings
+    pub default_schedule: &'static str,
+    run: fn(&DatabaseConnectionPool, &Settings) -> BackendResult<()>,
+}
+
+/// All jobs known to the backend
+pub const DEFINITIONS: &[JobDefinition] = &[
+    JobDefinition {
+        name: "clean_late_non_activated_members",
+        description: "Removes registered members who did not activate their account in time",
+        default_schedule: "*/15 * * * *",
+        run: super::clean_late_non_activated_members,
+    },
+    JobDefinition {
+        name: "apply_scheduled_publications",
+        description: "Publishes and unpublishes pages which reached their scheduled moment",
+        default_schedule: "* * * * *",
+        run: super::apply_scheduled_publications,
+    },
+    JobDefinition {
+        name: "index_page_content",
+        description: "Indexes the published content of pages which are not indexed yet",
+        default_schedule: "@hourly",
+        run: super::index_page_content,
+    },
+    JobDefinition {
+        name: "check_assets",
+        description: "Reports missing and orphaned asset files",
+        default_schedule: "@daily",
+        run: crate::commands::assets::check_assets,
+    },
+    JobDefinition {
+        name: "remind_event_attendance",
+        description: "Reminds members to respond to upcoming events",
+        default_schedule: "@hourly",
+        run: super::remind_event_attendance,
+    },
+];
+
+/// A job together with its effective schedule
+pub struct ScheduledJob {
+    pub definition: &'static JobDefinition,
+    pub schedule: Schedule,
+}
+
+pub struct JobRegistry {
+    pool: DatabaseConnectionPool,
+    settings: Data<Settings>,
+    jobs: Vec<ScheduledJob>,
+}
+
+impl JobRegistry {
+    pub fn new(pool: &DatabaseConnectionPool, settings: &Data<Settings>) -> Self {
+        let jobs = DEFINITIONS
+            .iter()
+            .map(|definition| ScheduledJob {
+                definition,
+                schedule: settings
+                    .jobs
+                    .schedule(definition)
+                    .expect("Job schedules should be validated"),
+            })
+            .collect();
+        Self {
+            pool: pool.clone(),
+            settings: settings.clone(),
+            jobs,
+        }
+    }
+
+    pub fn jobs(&self) -> &[ScheduledJob] {
+        &self.jobs
+    }
+
+    pub fn find(&self, name: &str) -> BackendResult<&ScheduledJob> {
+        self.jobs
+            .iter()
+            .find(|job| job.definition.name == name)
+            .ok_or_else(BackendError::bad)
+    }
+
+    /// Runs the job, unless it is already running elsewhere, in which case nothing is returned.
+    ///
+    /// The lock is bound to a transaction which stays open while the job runs, so it is released
+    /// however the run ends. If the job panics, the pool discards the connection, ending the
+    /// transaction. The run itself is recorded using another connection, so that it is visible
+    /// while the job is running.
+    pub fn run(&self, name: &str, manual: bool) -> BackendResult<Option<JobRun>> {
+        let job = self.find(name)?;
+        let key = lock_key(name);
+        let mut lock_conn = self.pool.get()?;
+        lock_conn.transaction(|lock_conn| {
+            if !diesel::select(pg_try_advisory_xact_lock(key)).get_result::<bool>(lock_conn)? {
+                info!("Job {name} is already running, skipping");
+                return Ok(None);
+            }
+            let mut conn = self.pool.get()?;
+            self.run_locked(&mut conn, job, manual).map(Some)
+        })
+    }
+
+    fn run_locked(
+        &self,
+        conn: &mut DatabaseConnection,
+        job: &ScheduledJob,
+        manual: bool,
+    ) -> BackendResult<JobRun> {
+        let name = job.definition.name;
+        let mut run = JobRun::started(name, manual);
+        run.id = diesel::insert_into(job_runs::table)
+            .values(&run)
+            .returning(job_runs::id)
+            .get_result(conn)?;
+
+        info!("Running job {name}");
+        let outcome = (job.definition.run)(&self.pool, &self.settings);
+        run.finished_at = Some(chrono::Utc::now().naive_utc());
+        run.succeeded = Some(outcome.is_ok());
+        run.error = outcome.err().map(|e| e.to_string());
+        match &run.error {
+            Some(e) => error!("Job {name} failed: {e}"),
+            None => info!("Job {name} finished"),
+        }
+
+        diesel::update(job_runs::table)
+            .filter(job_runs::id.eq(run.id))
+            .set((
+                job_runs::finished_at.eq(run.finished_at),
+                job_runs::succeeded.eq(run.succeeded),
+                job_runs::error.eq(&run.error),
+            ))
+            .execute(conn)?;
+        Ok(run)
+    }
+}
+
+/// Derives the advisory lock key from the job name (64-bit FNV-1a), such that it is equal for
+/// all instances of the backend
+fn lock_key(name: &str) -> i64 {
+    let hash = format!("onvp-job:{name}")
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    hash as i64
+}
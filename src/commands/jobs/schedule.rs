@@ -0,0 +1,305 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Cron-like schedules, consisting of five fields: minute, hour, day of the month, month and
+//! day of the week. Each field is either `*`, a value, a range (`1-5`) or a list of these
+//! (`1,3,5`), optionally with a step (`*/15`, `0-30/10`). The macros `@hourly`, `@daily`,
+//! `@weekly` and `@monthly` are also understood. Schedules are evaluated in UTC.
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Upper bound of the minutes searched for the next occurrence, a little over four years, to
+/// cover schedules which only occur on leap days
+const SEARCH_LIMIT_MINUTES: i64 = 4 * 366 * 24 * 60;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl Schedule {
+    /// Returns the first moment strictly after the given moment matching the schedule
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut candidate = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+        let limit = candidate + Duration::minutes(SEARCH_LIMIT_MINUTES);
+        while candidate < limit {
+            if !contains(self.months, candidate.month()) {
+                candidate = start_of_next_month(candidate)?;
+            } else if !self.matches_day(candidate) {
+                candidate = candidate.with_hour(0)?.with_minute(0)? + Duration::days(1);
+            } else if !contains(self.hours, candidate.hour()) {
+                candidate = candidate.with_minute(0)? + Duration::hours(1);
+            } else if !contains(self.minutes, candidate.minute()) {
+                candidate += Duration::minutes(1);
+            } else {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    /// Following cron, if both the day of the month and the day of the week are restricted,
+    /// either one needs to match
+    fn matches_day(&self, moment: DateTime<Utc>) -> bool {
+        let day_of_month = contains(self.days_of_month, moment.day());
+        let day_of_week = contains(self.days_of_week, moment.weekday().num_days_from_sunday());
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expression = s.trim();
+        let expanded = match expression {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(format!(
+                "schedule '{expression}' should consist of five fields"
+            ));
+        };
+        // Sunday is both 0 and 7
+        let mut days_of_week_mask = parse_field(days_of_week, 0, 7)?;
+        if contains(days_of_week_mask, 7) {
+            days_of_week_mask |= 1;
+        }
+        Ok(Self {
+            expression: expression.to_owned(),
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days_of_month: parse_field(days_of_month, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            days_of_week: days_of_week_mask,
+            any_day_of_month: days_of_month == "*",
+            any_day_of_week: days_of_week == "*",
+        })
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+fn contains(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+fn start_of_next_month(moment: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (year, month) = match moment.month() {
+        12 => (moment.year() + 1, 1),
+        month => (moment.year(), month + 1),
+    };
+    moment
+        .with_day(1)?
+        .with_hour(0)?
+        .with_minute(0)?
+        .with_month(month)?
+        .with_year(year)
+}
+
+/// Parses a single field into a bit mask of the allowed values
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, parse_value(step, 1, max)?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max)?, parse_value(end, min, max)?)
+        } else {
+            let start = parse_value(range, min, max)?;
+            // A single value with a step continues to the end of the range, as in cron
+            (start, if part.contains('/') { max } else { start })
+        };
+        if start > end {
+            return Err(format!("range '{range}' is descending"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(parsed) if (min..=max).contains(&parsed) => Ok(parsed),
+        _ => Err(format!("'{value}' should be a number from {min} to {max}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        expression.parse::<Schedule>().unwrap().next_after(after)
+    }
+
+    #[test]
+    fn parses_fields() {
+        assert_eq!(parse_field("*", 0, 5), Ok(0b111111));
+        assert_eq!(parse_field("3", 0, 59), Ok(1 << 3));
+        assert_eq!(parse_field("1-3", 0, 59), Ok(0b1110));
+        assert_eq!(parse_field("1,4", 0, 59), Ok(0b10010));
+        assert_eq!(parse_field("*/2", 0, 5), Ok(0b010101));
+        assert_eq!(parse_field("1-5/2", 0, 59), Ok(0b101010));
+        assert_eq!(parse_field("2/2", 0, 5), Ok(0b010100));
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        assert!(parse_field("60", 0, 59).is_err());
+        assert!(parse_field("0", 1, 31).is_err());
+        assert!(parse_field("5-1", 0, 59).is_err());
+        assert!(parse_field("*/0", 0, 59).is_err());
+        assert!(parse_field("a", 0, 59).is_err());
+        assert!(parse_field("", 0, 59).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert!("* * * *".parse::<Schedule>().is_err());
+        assert!("* * * * * *".parse::<Schedule>().is_err());
+        assert!("@yearly".parse::<Schedule>().is_err());
+        assert!("* 24 * * *".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn expands_macros() {
+        let daily = "@daily".parse::<Schedule>().unwrap();
+        let expanded = "0 0 * * *".parse::<Schedule>().unwrap();
+        assert_eq!(
+            daily.next_after(at(2025, 8, 1, 12, 0)),
+            expanded.next_after(at(2025, 8, 1, 12, 0))
+        );
+        assert_eq!(daily.to_string(), "@daily");
+    }
+
+    #[test]
+    fn next_minute() {
+        let after = Utc.with_ymd_and_hms(2025, 8, 1, 12, 0, 30).unwrap();
+        assert_eq!(next("* * * * *", after), Some(at(2025, 8, 1, 12, 1)));
+    }
+
+    #[test]
+    fn next_is_strictly_after() {
+        assert_eq!(
+            next("*/15 * * * *", at(2025, 8, 1, 12, 15)),
+            Some(at(2025, 8, 1, 12, 30))
+        );
+    }
+
+    #[test]
+    fn next_rolls_over_hour_day_month_and_year() {
+        assert_eq!(
+            next("@hourly", at(2025, 8, 1, 12, 5)),
+            Some(at(2025, 8, 1, 13, 0))
+        );
+        assert_eq!(
+            next("30 2 * * *", at(2025, 8, 1, 3, 0)),
+            Some(at(2025, 8, 2, 2, 30))
+        );
+        assert_eq!(
+            next("@monthly", at(2025, 8, 31, 23, 59)),
+            Some(at(2025, 9, 1, 0, 0))
+        );
+        assert_eq!(
+            next("0 0 1 1 *", at(2025, 8, 1, 0, 0)),
+            Some(at(2026, 1, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn next_day_of_week() {
+        // 1 August 2025 is a Friday
+        assert_eq!(
+            next("0 9 * * 1", at(2025, 8, 1, 12, 0)),
+            Some(at(2025, 8, 4, 9, 0))
+        );
+        assert_eq!(
+            next("@weekly", at(2025, 8, 1, 12, 0)),
+            Some(at(2025, 8, 3, 0, 0))
+        );
+        assert_eq!(
+            next("0 0 * * 7", at(2025, 8, 1, 12, 0)),
+            Some(at(2025, 8, 3, 0, 0))
+        );
+    }
+
+    #[test]
+    fn next_matches_either_day_if_both_restricted() {
+        // The 15th or any Monday, whichever comes first
+        assert_eq!(
+            next("0 0 15 * 1", at(2025, 8, 1, 12, 0)),
+            Some(at(2025, 8, 4, 0, 0))
+        );
+        assert_eq!(
+            next("0 0 5 * 1", at(2025, 8, 1, 12, 0)),
+            Some(at(2025, 8, 4, 0, 0))
+        );
+        assert_eq!(
+            next("0 0 2 * 1", at(2025, 8, 1, 12, 0)),
+            Some(at(2025, 8, 2, 0, 0))
+        );
+    }
+
+    #[test]
+    fn next_leap_day() {
+        assert_eq!(
+            next("0 0 29 2 *", at(2025, 3, 1, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+    }
+
+    #[test]
+    fn next_never() {
+        assert_eq!(next("0 0 31 2 *", at(2025, 3, 1, 0, 0)), None);
+    }
+}
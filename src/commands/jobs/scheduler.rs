@@ -0,0 +1,62 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Runs the registered jobs according to their schedules, in the background of the backend
+
+use crate::commands::jobs::registry::JobRegistry;
+use actix_web::rt::task::spawn_blocking;
+use actix_web::rt::time::sleep;
+use actix_web::web::Data;
+use chrono::Utc;
+use log::{error, info};
+
+/// Spawns the scheduler on the current runtime, this should only be done once per process
+pub fn spawn(registry: Data<JobRegistry>) {
+    actix_web::rt::spawn(async move {
+        let now = Utc::now();
+        let mut next_runs: Vec<_> = registry
+            .jobs()
+            .iter()
+            .map(|job| job.schedule.next_after(now))
+            .collect();
+        for (job, next_run) in registry.jobs().iter().zip(&next_runs) {
+            if let Some(next_run) = next_run {
+                info!("Scheduled job {} at {next_run}", job.definition.name);
+            }
+        }
+
+        while let Some(earliest) = next_runs.iter().flatten().min().cloned() {
+            sleep((earliest - Utc::now()).to_std().unwrap_or_default()).await;
+            let now = Utc::now();
+            for (index, job) in registry.jobs().iter().enumerate() {
+                if next_runs[index].is_none_or(|next_run| next_run > now) {
+                    continue;
+                }
+                let name = job.definition.name;
+                let runner = registry.clone();
+                match spawn_blocking(move || runner.run(name, false)).await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => error!("Unable to run job {name}: {e}"),
+                    Err(e) => error!("Job {name} panicked: {e}"),
+                }
+                next_runs[index] = job.schedule.next_after(Utc::now());
+            }
+        }
+    });
+}
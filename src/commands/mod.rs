@@ -17,4 +17,6 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod assets;
+pub mod backup;
 pub mod jobs;
@@ -0,0 +1,253 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! HTTP caching of pages and assets. Resources are always revalidated, either by the browser
+//! (private resources) or by any shared cache in between (public resources), using their
+//! entity tag, such that a change in content or visibility takes effect immediately. The same
+//! entity tags are used as version tokens, guarding edits against concurrent modifications.
+
+use crate::generic::result::{BackendError, BackendResult};
+use actix_web::dev::Payload;
+use actix_web::http::header::{
+    CacheControl, CacheDirective, ETag, EntityTag, Header, HttpDate, IfMatch, IfNoneMatch,
+    LastModified, IF_MATCH,
+};
+use actix_web::{Error, FromRequest, HttpRequest, HttpResponse, HttpResponseBuilder};
+use chrono::NaiveDateTime;
+use std::future::{ready, Ready};
+use std::time::SystemTime;
+
+/// Determines which caches are allowed to store a resource
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    /// The resource is published to the public, it may be stored by shared caches
+    Public,
+    /// The resource is restricted to roles, it may only be stored by the browser
+    Private,
+}
+
+/// A resource, together with its entity tag and the caches allowed to store it
+#[derive(Clone, Debug)]
+pub struct Cached<T> {
+    pub value: T,
+    pub etag: String,
+    pub last_modified: Option<NaiveDateTime>,
+    pub visibility: Visibility,
+}
+
+impl<T> Cached<T> {
+    pub fn new(value: T, etag: String, visibility: Visibility) -> Self {
+        Self {
+            value,
+            etag,
+            last_modified: None,
+            visibility,
+        }
+    }
+
+    pub fn with_last_modified(mut self, last_modified: NaiveDateTime) -> Self {
+        self.last_modified = Some(last_modified);
+        self
+    }
+
+    /// Responds with 304 Not Modified if the client holds the current version of the resource,
+    /// otherwise the body is added to the response
+    pub fn respond<F>(self, if_none_match: Option<&IfNoneMatch>, body: F) -> HttpResponse
+    where
+        F: FnOnce(&mut HttpResponseBuilder, T) -> HttpResponse,
+    {
+        let etag = EntityTag::new_strong(self.etag);
+        let not_modified = match if_none_match {
+            Some(IfNoneMatch::Any) => true,
+            Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+            None => false,
+        };
+
+        let mut response = if not_modified {
+            HttpResponse::NotModified()
+        } else {
+            HttpResponse::Ok()
+        };
+        let visibility = match self.visibility {
+            Visibility::Public => CacheDirective::Public,
+            Visibility::Private => CacheDirective::Private,
+        };
+        response
+            .insert_header(ETag(etag))
+            .insert_header(CacheControl(vec![visibility, CacheDirective::NoCache]));
+        if let Some(last_modified) = self.last_modified {
+            let last_modified = SystemTime::from(last_modified.and_utc());
+            response.insert_header(LastModified(HttpDate::from(last_modified)));
+        }
+
+        if not_modified {
+            response.finish()
+        } else {
+            body(&mut response, self.value)
+        }
+    }
+}
+
+/// The version of a resource the client expects to modify, taken from the `If-Match` header.
+/// Without the header any version is modified, such that existing clients keep working.
+#[derive(Clone, Debug, Default)]
+pub struct Precondition(Option<IfMatch>);
+
+impl Precondition {
+    /// Verifies that the resource is still at the version the client expects, otherwise the
+    /// resource has been modified by someone else in the meantime
+    pub fn check(&self, etag: &str) -> BackendResult<()> {
+        let matches = match &self.0 {
+            None | Some(IfMatch::Any) => true,
+            Some(IfMatch::Items(tags)) => {
+                let current = EntityTag::new_strong(etag.to_owned());
+                tags.iter().any(|tag| tag.strong_eq(&current))
+            }
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(BackendError::precondition_failed())
+        }
+    }
+}
+
+impl FromRequest for Precondition {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if !req.headers().contains_key(IF_MATCH) {
+            return ready(Ok(Self(None)));
+        }
+        ready(
+            IfMatch::parse(req)
+                .map(|if_match| Self(Some(if_match)))
+                .map_err(|_| BackendError::bad().into()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic::result::ErrorKind;
+    use actix_web::http::header::{CACHE_CONTROL, ETAG, LAST_MODIFIED};
+    use actix_web::http::StatusCode;
+    use chrono::NaiveDate;
+
+    fn if_match(tags: &[EntityTag]) -> Precondition {
+        Precondition(Some(IfMatch::Items(tags.to_vec())))
+    }
+
+    fn if_none_match(tags: &[EntityTag]) -> IfNoneMatch {
+        IfNoneMatch::Items(tags.to_vec())
+    }
+
+    fn respond(cached: Cached<&'static str>, if_none_match: Option<&IfNoneMatch>) -> HttpResponse {
+        cached.respond(if_none_match, |response, value| response.body(value))
+    }
+
+    fn header(response: &HttpResponse, name: actix_web::http::header::HeaderName) -> &str {
+        response.headers().get(name).unwrap().to_str().unwrap()
+    }
+
+    #[test]
+    fn precondition_without_if_match() {
+        assert!(Precondition::default().check("abc").is_ok());
+        assert!(Precondition(Some(IfMatch::Any)).check("abc").is_ok());
+    }
+
+    #[test]
+    fn precondition_matching_etag() {
+        let precondition = if_match(&[
+            EntityTag::new_strong("old".to_owned()),
+            EntityTag::new_strong("abc".to_owned()),
+        ]);
+        assert!(precondition.check("abc").is_ok());
+    }
+
+    #[test]
+    fn precondition_modified_etag() {
+        let result = if_match(&[EntityTag::new_strong("old".to_owned())]).check("abc");
+        assert!(matches!(
+            result,
+            Err(BackendError {
+                kind: ErrorKind::PreconditionFailed
+            })
+        ));
+    }
+
+    #[test]
+    fn precondition_requires_strong_etag() {
+        let precondition = if_match(&[EntityTag::new_weak("abc".to_owned())]);
+        assert!(precondition.check("abc").is_err());
+    }
+
+    #[test]
+    fn respond_with_body() {
+        let last_modified = NaiveDate::from_ymd_opt(2025, 8, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let cached = Cached::new("body", "abc".to_owned(), Visibility::Public)
+            .with_last_modified(last_modified);
+        let response = respond(cached, None);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, ETAG), "\"abc\"");
+        assert_eq!(header(&response, CACHE_CONTROL), "public, no-cache");
+        assert_eq!(
+            header(&response, LAST_MODIFIED),
+            "Fri, 01 Aug 2025 12:00:00 GMT"
+        );
+    }
+
+    #[test]
+    fn respond_private() {
+        let cached = Cached::new("body", "abc".to_owned(), Visibility::Private);
+        let response = respond(cached, None);
+        assert_eq!(header(&response, CACHE_CONTROL), "private, no-cache");
+        assert!(response.headers().get(LAST_MODIFIED).is_none());
+    }
+
+    #[test]
+    fn respond_not_modified() {
+        let cached = Cached::new("body", "abc".to_owned(), Visibility::Public);
+        let tags = if_none_match(&[EntityTag::new_weak("abc".to_owned())]);
+        let response = cached.respond(Some(&tags), |_, _| panic!("The body is not needed"));
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(header(&response, ETAG), "\"abc\"");
+        assert_eq!(header(&response, CACHE_CONTROL), "public, no-cache");
+    }
+
+    #[test]
+    fn respond_not_modified_for_any() {
+        let cached = Cached::new("body", "abc".to_owned(), Visibility::Public);
+        let response = respond(cached, Some(&IfNoneMatch::Any));
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn respond_modified() {
+        let cached = Cached::new("body", "abc".to_owned(), Visibility::Public);
+        let tags = if_none_match(&[EntityTag::new_strong("old".to_owned())]);
+        let response = respond(cached, Some(&tags));
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
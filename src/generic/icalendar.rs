@@ -0,0 +1,231 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Rendering of events into iCalendar feeds (RFC 5545), which calendar applications subscribe
+//! to. Calendar applications recognise an event by its UID and replace their copy once the
+//! sequence of the event increases.
+
+use chrono::{Days, NaiveDate, NaiveDateTime};
+
+/// The media type of an iCalendar feed
+pub const CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+
+/// Identifies the product which created the feed
+const PRODUCT_ID: &str = "-//ONVP//ONVP Backend//EN";
+
+/// The format of moments in UTC
+const UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// The maximum length of a content line in octets, excluding the line break
+const MAX_LINE_LENGTH: usize = 75;
+
+/// An event in the feed
+#[derive(Clone, Debug)]
+pub struct Event {
+    /// Identifies the event globally, it must not change during the lifetime of the event
+    pub uid: String,
+    /// Increases whenever the event changes
+    pub sequence: i32,
+    pub summary: String,
+    pub time: EventTime,
+    /// Where the event takes place, e.g. the name and address of the venue
+    pub location: Option<String>,
+    pub last_modified: NaiveDateTime,
+    /// Links to the event on the website, only absolute links are allowed
+    pub url: Option<String>,
+}
+
+/// When an event takes place
+#[derive(Clone, Debug)]
+pub enum EventTime {
+    /// The event lasts one or more whole days, the end date is the start date for single day
+    /// events
+    Days {
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    },
+    /// The event starts and ends at the given moments in UTC
+    Moments {
+        starts_at: NaiveDateTime,
+        ends_at: NaiveDateTime,
+    },
+}
+
+/// Renders the events into a feed with the given name
+pub fn render(name: &str, events: &[Event]) -> String {
+    let mut feed = String::new();
+    push_line(&mut feed, "BEGIN:VCALENDAR");
+    push_line(&mut feed, "VERSION:2.0");
+    push_line(&mut feed, &format!("PRODID:{PRODUCT_ID}"));
+    push_line(&mut feed, "CALSCALE:GREGORIAN");
+    push_line(&mut feed, "METHOD:PUBLISH");
+    push_line(&mut feed, &format!("X-WR-CALNAME:{}", escape(name)));
+    for event in events {
+        push_event(&mut feed, event);
+    }
+    push_line(&mut feed, "END:VCALENDAR");
+    feed
+}
+
+fn push_event(feed: &mut String, event: &Event) {
+    let last_modified = event.last_modified.format(UTC_FORMAT);
+
+    push_line(feed, "BEGIN:VEVENT");
+    push_line(feed, &format!("UID:{}", escape(&event.uid)));
+    push_line(feed, &format!("SEQUENCE:{}", event.sequence));
+    push_line(feed, &format!("DTSTAMP:{last_modified}"));
+    push_line(feed, &format!("LAST-MODIFIED:{last_modified}"));
+    match &event.time {
+        EventTime::Days {
+            start_date,
+            end_date,
+        } => {
+            // The end date is exclusive, hence the event ends at the start of the day after
+            let end_date = end_date
+                .max(start_date)
+                .checked_add_days(Days::new(1))
+                .unwrap_or(NaiveDate::MAX);
+            push_line(
+                feed,
+                &format!("DTSTART;VALUE=DATE:{}", start_date.format("%Y%m%d")),
+            );
+            push_line(
+                feed,
+                &format!("DTEND;VALUE=DATE:{}", end_date.format("%Y%m%d")),
+            );
+        }
+        EventTime::Moments { starts_at, ends_at } => {
+            push_line(feed, &format!("DTSTART:{}", starts_at.format(UTC_FORMAT)));
+            push_line(feed, &format!("DTEND:{}", ends_at.format(UTC_FORMAT)));
+        }
+    }
+    push_line(feed, &format!("SUMMARY:{}", escape(&event.summary)));
+    if let Some(location) = &event.location {
+        push_line(feed, &format!("LOCATION:{}", escape(location)));
+    }
+    if let Some(url) = &event.url {
+        push_line(feed, &format!("URL:{url}"));
+    }
+    push_line(feed, "END:VEVENT");
+}
+
+/// Adds a content line, folding it into multiple lines if it is too long
+fn push_line(feed: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            // The continuation starts with a space, which counts towards its length
+            feed.push_str("\r\n ");
+            length = 1;
+        }
+        feed.push(c);
+        length += c.len_utf8();
+    }
+    feed.push_str("\r\n");
+}
+
+/// Escapes a text value
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(summary: &str, start_date: NaiveDate, end_date: NaiveDate) -> Event {
+        Event {
+            uid: "page-12@example.org".to_owned(),
+            sequence: 3,
+            summary: summary.to_owned(),
+            time: EventTime::Days {
+                start_date,
+                end_date,
+            },
+            location: None,
+            last_modified: start_date.and_hms_opt(12, 30, 0).unwrap(),
+            url: None,
+        }
+    }
+
+    #[test]
+    fn render_uses_exclusive_end_dates() {
+        let start_date = NaiveDate::from_ymd_opt(2025, 5, 30).unwrap();
+        let end_date = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let feed = render("Concerts", &[event("Tour", start_date, end_date)]);
+        assert!(
+            feed.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"),
+            "{feed}"
+        );
+        assert!(feed.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"), "{feed}");
+        assert!(feed.contains("UID:page-12@example.org\r\n"), "{feed}");
+        assert!(feed.contains("SEQUENCE:3\r\n"), "{feed}");
+        assert!(feed.contains("DTSTAMP:20250530T123000Z\r\n"), "{feed}");
+        assert!(feed.contains("DTSTART;VALUE=DATE:20250530\r\n"), "{feed}");
+        assert!(feed.contains("DTEND;VALUE=DATE:20250602\r\n"), "{feed}");
+    }
+
+    #[test]
+    fn render_escapes_and_folds_text() {
+        let date = NaiveDate::from_ymd_opt(2025, 5, 12).unwrap();
+        let summary = "Spring concert; rehearsal, dress code \\ black\n".repeat(3);
+        let feed = render("Concerts", &[event(&summary, date, date)]);
+        assert!(
+            feed.contains("SUMMARY:Spring concert\\; rehearsal\\, dress code \\\\ black\\n"),
+            "{feed}"
+        );
+        for line in feed.split("\r\n") {
+            assert!(line.len() <= MAX_LINE_LENGTH, "{line}");
+        }
+        let unfolded = feed.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!(
+            "SUMMARY:{}\r\n",
+            "Spring concert\\; rehearsal\\, dress code \\\\ black\\n".repeat(3)
+        )));
+    }
+
+    #[test]
+    fn render_uses_utc_moments_and_locations() {
+        let date = NaiveDate::from_ymd_opt(2025, 9, 2).unwrap();
+        let mut event = event("Rehearsal", date, date);
+        event.time = EventTime::Moments {
+            starts_at: date.and_hms_opt(17, 30, 0).unwrap(),
+            ends_at: date.and_hms_opt(20, 0, 0).unwrap(),
+        };
+        event.location = Some("De Doelen, Schouwburgplein 50".to_owned());
+        let feed = render("Concerts", &[event]);
+        assert!(feed.contains("DTSTART:20250902T173000Z\r\n"), "{feed}");
+        assert!(feed.contains("DTEND:20250902T200000Z\r\n"), "{feed}");
+        assert!(
+            feed.contains("LOCATION:De Doelen\\, Schouwburgplein 50\r\n"),
+            "{feed}"
+        );
+    }
+}
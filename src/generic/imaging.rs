@@ -0,0 +1,490 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Decoding and encoding of uploaded images. Uploads are always decoded before they are
+//! stored, such that only actual images of a supported format and size end up in the asset
+//! store, and such that the format they are served in is known. Smaller variants of the
+//! stored images are derived on request, see [`crate::generic::storage::assets::variants`].
+
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::NormalizedImageFormat;
+use crate::generic::validation::FieldError;
+use actix_web::http::header::{Accept, ContentType, Quality};
+use image::codecs::gif::GifDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::error::LimitErrorKind;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{
+    AnimationDecoder, DynamicImage, ImageDecoder, ImageError, ImageFormat, ImageReader,
+    ImageResult, Limits,
+};
+use std::io::Cursor;
+
+/// The formats accepted for uploaded images
+pub const ACCEPTED_FORMATS: [ImageFormat; 4] = [
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+];
+
+/// The widths of the image variants, requested widths are rounded up to one of these, such that
+/// the amount of derived assets per image is bounded
+pub const VARIANT_WIDTHS: [u32; 3] = [160, 480, 1024];
+
+/// An uploaded image, together with the format it was uploaded in
+pub struct DecodedImage {
+    pub image: DynamicImage,
+    pub format: ImageFormat,
+    /// Whether the EXIF orientation changed the image, in which case the uploaded data can not
+    /// be stored without encoding the image again
+    pub reoriented: bool,
+}
+
+/// Decodes the image, rejecting anything which is not an image of one of the accepted formats,
+/// or which is wider or higher than the maximum dimension. Animated images are rejected, as
+/// only their first frame would be kept. The EXIF orientation is applied to the decoded image,
+/// while all other metadata is discarded, such that encoding the decoded image results in an
+/// image without metadata.
+pub fn decode(data: &[u8], max_dimension: u32) -> BackendResult<DecodedImage> {
+    let mut reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+    let format = reader
+        .format()
+        .filter(|format| ACCEPTED_FORMATS.contains(format))
+        .ok_or_else(|| rejected("Only PNG, JPEG, GIF and WebP images are accepted"))?;
+    if is_animated(data, format) {
+        return Err(rejected("Animated images are not accepted"));
+    }
+
+    reader.limits(limits(max_dimension));
+
+    let (image, reoriented) = decode_oriented(reader).map_err(|e| match e {
+        ImageError::Limits(ref limit) if limit.kind() == LimitErrorKind::DimensionError => {
+            rejected(&format!(
+                "Images must not exceed {max_dimension} pixels in width or height"
+            ))
+        }
+        ImageError::Limits(_) => rejected("The image is too large to be processed"),
+        _ => rejected("The image could not be decoded"),
+    })?;
+    Ok(DecodedImage {
+        image,
+        format,
+        reoriented,
+    })
+}
+
+/// The limits applied when decoding an image, bounding its dimensions as well as the memory
+/// used to decode it
+fn limits(max_dimension: u32) -> Limits {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_dimension);
+    limits.max_image_height = Some(max_dimension);
+    limits
+}
+
+fn decode_oriented(reader: ImageReader<Cursor<&[u8]>>) -> ImageResult<(DynamicImage, bool)> {
+    let mut decoder = reader.into_decoder()?;
+    // Verifies the decoded image fits within the default allocation limit
+    Limits::default().reserve(decoder.total_bytes())?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok((image, orientation != Orientation::NoTransforms))
+}
+
+fn is_animated(data: &[u8], format: ImageFormat) -> bool {
+    match format {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(data))
+            .is_ok_and(|decoder| decoder.into_frames().take(2).count() > 1),
+        ImageFormat::WebP => {
+            WebPDecoder::new(Cursor::new(data)).is_ok_and(|decoder| decoder.has_animation())
+        }
+        _ => false,
+    }
+}
+
+/// Returns the uploaded image without its metadata. JPEG images are not compressed again,
+/// only their metadata segments are removed, unless their orientation had to be applied. The
+/// other formats are encoded again, which does not reduce their quality.
+pub fn without_metadata(data: &[u8], decoded: &DecodedImage) -> BackendResult<Vec<u8>> {
+    match decoded.format {
+        ImageFormat::Jpeg if !decoded.reoriented => {
+            strip_jpeg_metadata(data).ok_or_else(|| rejected("The image could not be decoded"))
+        }
+        format => encode(&decoded.image, format),
+    }
+}
+
+/// Removes the metadata segments preceding the compressed data of a JPEG image, without
+/// decoding it. The JFIF header, the ICC color profile and the Adobe segment describing the
+/// color transform are kept, as they affect how the image is displayed.
+fn strip_jpeg_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(data.len());
+    output.extend_from_slice(&[0xFF, 0xD8]);
+    let data = data.strip_prefix(&[0xFF, 0xD8])?;
+    let mut position = 0;
+    loop {
+        if *data.get(position)? != 0xFF {
+            return None;
+        }
+        // Markers may be preceded by any amount of fill bytes
+        while *data.get(position)? == 0xFF {
+            position += 1;
+        }
+        let marker = *data.get(position)?;
+        position += 1;
+        match marker {
+            0x00 | 0xD8 | 0xD9 => return None,
+            0x01 | 0xD0..=0xD7 => {
+                output.extend_from_slice(&[0xFF, marker]);
+                continue;
+            }
+            _ => {}
+        }
+        let length = u16::from_be_bytes([*data.get(position)?, *data.get(position + 1)?]) as usize;
+        let segment = data
+            .get(position..position + length)
+            .filter(|_| length >= 2)?;
+        position += length;
+        let payload = &segment[2..];
+        let keep = match marker {
+            0xE0 => payload.starts_with(b"JFIF\0"),
+            0xE2 => payload.starts_with(b"ICC_PROFILE\0"),
+            0xEE => payload.starts_with(b"Adobe"),
+            0xE1..=0xEF | 0xFE => false,
+            _ => true,
+        };
+        if keep {
+            output.extend_from_slice(&[0xFF, marker]);
+            output.extend_from_slice(segment);
+        }
+        if marker == 0xDA {
+            // The start of the first scan is followed by the compressed data, which is copied
+            // as is
+            output.extend_from_slice(&data[position..]);
+            return Some(output);
+        }
+    }
+}
+
+/// Encodes the image in the given format
+pub fn encode(image: &DynamicImage, format: ImageFormat) -> BackendResult<Vec<u8>> {
+    // Not every encoder accepts every color type, JPEG has no alpha channel and the WebP and
+    // GIF encoders only accept 8-bit samples
+    let image = match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8()),
+        ImageFormat::WebP | ImageFormat::Gif => DynamicImage::ImageRgba8(image.to_rgba8()),
+        _ => image.clone(),
+    };
+    let mut data = Vec::new();
+    image.write_to(&mut Cursor::new(&mut data), format)?;
+    Ok(data)
+}
+
+/// A variant of a stored image, limited in width and possibly converted to another format
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Variant {
+    pub width: Option<u32>,
+    pub format: Option<ImageFormat>,
+}
+
+impl Variant {
+    /// Selects the variant of the given width, which is served as WebP if the client accepts it
+    pub fn negotiate(width: Option<u32>, accept: Option<&Accept>) -> Self {
+        let accepts_webp = accept.is_some_and(|accept| {
+            accept
+                .iter()
+                .any(|item| item.item.essence_str() == "image/webp" && item.quality > Quality::ZERO)
+        });
+        Self {
+            width,
+            format: width.and(accepts_webp.then_some(ImageFormat::WebP)),
+        }
+    }
+}
+
+/// Returns the identifier under which the variant of the asset is cached in the asset store
+pub fn variant_asset_id(asset_id: &str, width: u32, format: ImageFormat) -> String {
+    format!("{asset_id}.w{width}.{}", format_name(format))
+}
+
+/// Returns the entity tag of the variant of the asset, assets are never changed after they are
+/// stored, so the tag only depends on the variant
+pub fn variant_etag(asset_id: &str, width: Option<u32>, format: ImageFormat) -> String {
+    match width {
+        Some(width) => variant_asset_id(asset_id, width, format),
+        None => format!("{asset_id}.{}", format_name(format)),
+    }
+}
+
+/// Returns the identifier of the asset a variant is derived from, if the asset is a variant
+pub fn base_asset_id(asset_id: &str) -> Option<&str> {
+    asset_id.split_once('.').map(|(base, _)| base)
+}
+
+/// Derives the variant from the stored image, returning the format and data of the variant.
+/// The same limits apply as when decoding uploads, as images stored before these limits were
+/// introduced may be arbitrarily large.
+pub fn derive(
+    data: &[u8],
+    format: ImageFormat,
+    variant: &Variant,
+    max_dimension: u32,
+) -> BackendResult<(ImageFormat, Vec<u8>)> {
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    reader.limits(limits(max_dimension));
+    let mut image = reader.decode()?;
+    if let Some(width) = variant.width.filter(|width| *width < image.width()) {
+        image = image.resize(width, image.height(), FilterType::Lanczos3);
+    }
+    let format = variant.format.unwrap_or(format);
+    Ok((format, encode(&image, format)?))
+}
+
+/// Returns the format images are normalized to, if any
+pub fn normalized_format(format: NormalizedImageFormat) -> Option<ImageFormat> {
+    match format {
+        NormalizedImageFormat::Original => None,
+        NormalizedImageFormat::Png => Some(ImageFormat::Png),
+        NormalizedImageFormat::Jpeg => Some(ImageFormat::Jpeg),
+        NormalizedImageFormat::Webp => Some(ImageFormat::WebP),
+    }
+}
+
+/// Returns the name under which the format is stored in the database
+pub fn format_name(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "png",
+        ImageFormat::Jpeg => "jpeg",
+        ImageFormat::Gif => "gif",
+        ImageFormat::WebP => "webp",
+        _ => format.extensions_str().first().copied().unwrap_or_default(),
+    }
+}
+
+/// Parses the name under which the format is stored in the database
+pub fn parse_format_name(name: &str) -> Option<ImageFormat> {
+    ImageFormat::from_extension(name)
+}
+
+/// Determines the format of stored image data, used for images stored before the format
+/// was recorded
+pub fn guess_format(data: &[u8]) -> Option<ImageFormat> {
+    image::guess_format(data).ok()
+}
+
+pub fn content_type(format: ImageFormat) -> ContentType {
+    ContentType(
+        format
+            .to_mime_type()
+            .parse()
+            .unwrap_or(mime::APPLICATION_OCTET_STREAM),
+    )
+}
+
+fn rejected(message: &str) -> BackendError {
+    BackendError::validation(vec![FieldError {
+        field: "data".to_string(),
+        message: message.to_string(),
+    }])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgba};
+
+    /// A 32x16 JPEG, red on the left half and blue on the right half, carrying a camera make
+    /// and GPS coordinates
+    const GPS: &[u8] = include_bytes!("../../tests/fixtures/exif-gps.jpg");
+
+    /// The same JPEG, carrying an EXIF orientation of 6, i.e. to be rotated 90 degrees clockwise
+    const GPS_ROTATED: &[u8] = include_bytes!("../../tests/fixtures/exif-gps-rotated.jpg");
+
+    fn is_red(pixel: Rgba<u8>) -> bool {
+        pixel[0] > 200 && pixel[2] < 60
+    }
+
+    fn is_blue(pixel: Rgba<u8>) -> bool {
+        pixel[2] > 200 && pixel[0] < 60
+    }
+
+    fn contains(data: &[u8], needle: &[u8]) -> bool {
+        data.windows(needle.len()).any(|window| window == needle)
+    }
+
+    #[test]
+    fn decode_keeps_unrotated_image() {
+        let decoded = decode(GPS, 8192).unwrap();
+        assert_eq!(decoded.format, ImageFormat::Jpeg);
+        assert_eq!(decoded.image.dimensions(), (32, 16));
+        assert!(is_red(decoded.image.get_pixel(4, 8)));
+        assert!(is_blue(decoded.image.get_pixel(28, 8)));
+    }
+
+    #[test]
+    fn decode_applies_orientation() {
+        let decoded = decode(GPS_ROTATED, 8192).unwrap();
+        assert_eq!(decoded.image.dimensions(), (16, 32));
+        // Rotating clockwise moves the left half to the top
+        assert!(is_red(decoded.image.get_pixel(8, 4)));
+        assert!(is_blue(decoded.image.get_pixel(8, 28)));
+    }
+
+    #[test]
+    fn encode_strips_metadata() {
+        assert!(contains(GPS_ROTATED, b"Exif"));
+        assert!(contains(GPS_ROTATED, b"ONVPCam"));
+        for format in ACCEPTED_FORMATS {
+            let decoded = decode(GPS_ROTATED, 8192).unwrap();
+            let data = encode(&decoded.image, format).unwrap();
+            assert!(!contains(&data, b"Exif"), "{format:?} contains EXIF");
+            assert!(
+                !contains(&data, b"ONVPCam"),
+                "{format:?} contains the camera make"
+            );
+
+            // The orientation is applied once, it must not be applied again when decoding
+            let decoded = decode(&data, 8192).unwrap();
+            assert_eq!(decoded.image.dimensions(), (16, 32));
+        }
+    }
+
+    #[test]
+    fn jpeg_metadata_is_stripped_without_encoding_again() {
+        let decoded = decode(GPS, 8192).unwrap();
+        assert!(!decoded.reoriented);
+        let data = without_metadata(GPS, &decoded).unwrap();
+        assert!(!contains(&data, b"Exif"));
+        assert!(!contains(&data, b"ONVPCam"));
+        assert!(data.len() < GPS.len());
+        // The compressed data is kept as is, so the pixels are identical
+        let stripped = decode(&data, 8192).unwrap();
+        assert_eq!(stripped.image.to_rgb8(), decoded.image.to_rgb8());
+        assert!(GPS.ends_with(&data[data.len() - 64..]));
+    }
+
+    #[test]
+    fn reoriented_jpeg_is_encoded_again() {
+        let decoded = decode(GPS_ROTATED, 8192).unwrap();
+        assert!(decoded.reoriented);
+        let data = without_metadata(GPS_ROTATED, &decoded).unwrap();
+        assert!(!contains(&data, b"Exif"));
+        assert_eq!(decode(&data, 8192).unwrap().image.dimensions(), (16, 32));
+    }
+
+    #[test]
+    fn strip_jpeg_metadata_rejects_malformed_data() {
+        assert!(strip_jpeg_metadata(b"").is_none());
+        assert!(strip_jpeg_metadata(&GPS[..GPS.len() / 8]).is_none());
+        assert!(strip_jpeg_metadata(&[0xFF, 0xD8, 0x00]).is_none());
+    }
+
+    fn gif(frames: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut data);
+        for index in 0..frames {
+            let mut buffer = image::RgbaImage::new(4, 4);
+            buffer.put_pixel(0, 0, Rgba([255, index as u8, 0, 255]));
+            encoder.encode_frame(image::Frame::new(buffer)).unwrap();
+        }
+        drop(encoder);
+        data
+    }
+
+    #[test]
+    fn decode_rejects_animated_images() {
+        assert!(decode(&gif(1), 8192).is_ok());
+        assert!(decode(&gif(2), 8192).is_err());
+    }
+
+    #[test]
+    fn derive_limits_the_width() {
+        let variant = Variant {
+            width: Some(16),
+            format: Some(ImageFormat::WebP),
+        };
+        let (format, data) = derive(GPS, ImageFormat::Jpeg, &variant, 8192).unwrap();
+        assert_eq!(format, ImageFormat::WebP);
+        let derived = image::load_from_memory_with_format(&data, format).unwrap();
+        assert_eq!(derived.dimensions(), (16, 8));
+    }
+
+    #[test]
+    fn derive_keeps_narrower_images() {
+        let variant = Variant {
+            width: Some(160),
+            format: None,
+        };
+        let (format, data) = derive(GPS, ImageFormat::Jpeg, &variant, 8192).unwrap();
+        assert_eq!(format, ImageFormat::Jpeg);
+        let derived = image::load_from_memory_with_format(&data, format).unwrap();
+        assert_eq!(derived.dimensions(), (32, 16));
+    }
+
+    #[test]
+    fn derive_applies_the_decoding_limits() {
+        let variant = Variant {
+            width: Some(16),
+            format: None,
+        };
+        assert!(derive(GPS, ImageFormat::Jpeg, &variant, 31).is_err());
+    }
+
+    fn accept(value: &str) -> Accept {
+        Accept(value.split(',').map(|item| item.parse().unwrap()).collect())
+    }
+
+    #[test]
+    fn negotiate_serves_webp_if_accepted() {
+        let variant = Variant::negotiate(Some(480), Some(&accept("image/webp,image/*;q=0.8")));
+        assert_eq!(variant.width, Some(480));
+        assert_eq!(variant.format, Some(ImageFormat::WebP));
+    }
+
+    #[test]
+    fn negotiate_keeps_the_format_otherwise() {
+        let variant = Variant::negotiate(Some(480), Some(&accept("image/png,image/*;q=0.8")));
+        assert_eq!(variant.format, None);
+        let variant = Variant::negotiate(Some(480), Some(&accept("image/webp;q=0")));
+        assert_eq!(variant.format, None);
+        let variant = Variant::negotiate(Some(480), None);
+        assert_eq!(variant.format, None);
+    }
+
+    #[test]
+    fn negotiate_serves_the_original_without_width() {
+        let variant = Variant::negotiate(None, Some(&accept("image/webp")));
+        assert_eq!(variant, Variant::default());
+    }
+
+    #[test]
+    fn decode_rejects_oversized_images() {
+        assert!(decode(GPS_ROTATED, 31).is_err());
+        assert!(decode(GPS_ROTATED, 32).is_ok());
+    }
+
+    #[test]
+    fn decode_rejects_non_images() {
+        assert!(decode(b"%PDF-1.7", 8192).is_err());
+        assert!(decode(&GPS[..GPS.len() / 2], 8192).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Logging of the backend. Every line logged while handling a request carries the request id
+//! and, if known, the email address of the authenticated member. The context is kept in a task
+//! local, so it follows the request across all middleware, services and the [`Session`].
+//!
+//! [`Session`]: crate::generic::storage::session::Session
+
+use crate::generic::settings::{LogFormat, LoggingSettings};
+use chrono::{SecondsFormat, Utc};
+use env_logger::fmt::Formatter;
+use log::Record;
+use serde_json::json;
+use std::cell::RefCell;
+use std::future::Future;
+use std::io::Write;
+
+/// The header used to receive and return the request id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: RequestContext;
+}
+
+struct RequestContext {
+    request_id: String,
+    email_address: RefCell<Option<String>>,
+}
+
+/// Initializes the logger, the log level is configured using the `RUST_LOG` environment variable
+pub fn init(settings: &LoggingSettings) {
+    let mut builder = env_logger::Builder::from_default_env();
+    match settings.format {
+        LogFormat::Text => builder.format(format_text),
+        LogFormat::Json => builder.format(format_json),
+    };
+    builder.init();
+}
+
+/// Runs the future, attaching the request id to all lines logged while running it
+pub fn scope<F: Future>(request_id: String, future: F) -> impl Future<Output = F::Output> {
+    let context = RequestContext {
+        request_id,
+        email_address: RefCell::new(None),
+    };
+    REQUEST_CONTEXT.scope(context, future)
+}
+
+/// Attaches the email address of the authenticated member to the current request
+pub fn set_email_address(email_address: &str) {
+    let _ = REQUEST_CONTEXT.try_with(|context| {
+        *context.email_address.borrow_mut() = Some(email_address.to_owned());
+    });
+}
+
+/// Returns the id of the request currently being handled, if any
+pub fn current_request_id() -> Option<String> {
+    REQUEST_CONTEXT
+        .try_with(|context| context.request_id.clone())
+        .ok()
+}
+
+fn current_context() -> (Option<String>, Option<String>) {
+    REQUEST_CONTEXT
+        .try_with(|context| {
+            (
+                Some(context.request_id.clone()),
+                context.email_address.borrow().clone(),
+            )
+        })
+        .unwrap_or_default()
+}
+
+fn timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn format_text(buf: &mut Formatter, record: &Record) -> std::io::Result<()> {
+    write!(
+        buf,
+        "[{} {} {}",
+        timestamp(),
+        record.level(),
+        record.target()
+    )?;
+    let (request_id, email_address) = current_context();
+    if let Some(request_id) = request_id {
+        write!(buf, " request_id={request_id}")?;
+    }
+    if let Some(email_address) = email_address {
+        write!(buf, " email={email_address}")?;
+    }
+    writeln!(buf, "] {}", record.args())
+}
+
+fn format_json(buf: &mut Formatter, record: &Record) -> std::io::Result<()> {
+    let (request_id, email_address) = current_context();
+    let line = json!({
+        "timestamp": timestamp(),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+        "requestId": request_id,
+        "email": email_address,
+    });
+    writeln!(buf, "{line}")
+}
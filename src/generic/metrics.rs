@@ -0,0 +1,254 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Collects the operational metrics of the backend, which are exposed in the Prometheus text
+//! exposition format.
+
+use crate::generic::result::ErrorKind;
+use crate::generic::storage::database::DatabaseConnectionPool;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (in seconds) of the request latency histogram buckets
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The purpose of a sent email
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MailPurpose {
+    Activation,
+    Mailing,
+}
+
+impl MailPurpose {
+    fn label(&self) -> &'static str {
+        match self {
+            MailPurpose::Activation => "activation",
+            MailPurpose::Mailing => "mailing",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct RequestKey {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Default)]
+struct RequestStatistics {
+    count: u64,
+    duration_sum: f64,
+    buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<RequestKey, RequestStatistics>>,
+    errors: Mutex<BTreeMap<&'static str, u64>>,
+    mails: Mutex<BTreeMap<(MailPurpose, bool), u64>>,
+    login_successes: AtomicU64,
+    login_failures: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let key = RequestKey {
+            method: method.to_owned(),
+            route: route.to_owned(),
+            status,
+        };
+        if let Ok(mut requests) = self.requests.lock() {
+            let statistics = requests.entry(key).or_default();
+            statistics.count += 1;
+            statistics.duration_sum += seconds;
+            for (bucket, bound) in statistics.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+                if seconds <= bound {
+                    *bucket += 1;
+                }
+            }
+        }
+    }
+
+    pub fn record_error(&self, kind: &ErrorKind) {
+        if let Ok(mut errors) = self.errors.lock() {
+            *errors.entry(kind.simplified_string()).or_default() += 1;
+        }
+    }
+
+    pub fn record_mail(&self, purpose: MailPurpose, success: bool) {
+        if let Ok(mut mails) = self.mails.lock() {
+            *mails.entry((purpose, success)).or_default() += 1;
+        }
+    }
+
+    pub fn record_login(&self, success: bool) {
+        let counter = if success {
+            &self.login_successes
+        } else {
+            &self.login_failures
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format
+    pub fn render(&self, pool: &DatabaseConnectionPool) -> String {
+        let mut out = String::new();
+        self.render_requests(&mut out);
+        self.render_errors(&mut out);
+        self.render_mails(&mut out);
+        self.render_logins(&mut out);
+        Self::render_pool(&mut out, pool);
+        out
+    }
+
+    fn render_requests(&self, out: &mut String) {
+        let Ok(requests) = self.requests.lock() else {
+            return;
+        };
+        let _ = writeln!(out, "# HELP onvp_http_requests_total Handled HTTP requests");
+        let _ = writeln!(out, "# TYPE onvp_http_requests_total counter");
+        for (key, statistics) in requests.iter() {
+            let _ = writeln!(
+                out,
+                "onvp_http_requests_total{{{}}} {}",
+                key.labels(),
+                statistics.count
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP onvp_http_request_duration_seconds HTTP request latency"
+        );
+        let _ = writeln!(out, "# TYPE onvp_http_request_duration_seconds histogram");
+        for (key, statistics) in requests.iter() {
+            let labels = key.labels();
+            for (bucket, bound) in statistics.buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "onvp_http_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {bucket}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "onvp_http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                statistics.count
+            );
+            let _ = writeln!(
+                out,
+                "onvp_http_request_duration_seconds_sum{{{labels}}} {}",
+                statistics.duration_sum
+            );
+            let _ = writeln!(
+                out,
+                "onvp_http_request_duration_seconds_count{{{labels}}} {}",
+                statistics.count
+            );
+        }
+    }
+
+    fn render_errors(&self, out: &mut String) {
+        let Ok(errors) = self.errors.lock() else {
+            return;
+        };
+        let _ = writeln!(out, "# HELP onvp_errors_total Errors by kind");
+        let _ = writeln!(out, "# TYPE onvp_errors_total counter");
+        for (kind, count) in errors.iter() {
+            let _ = writeln!(out, "onvp_errors_total{{kind=\"{kind}\"}} {count}");
+        }
+    }
+
+    fn render_mails(&self, out: &mut String) {
+        let Ok(mails) = self.mails.lock() else {
+            return;
+        };
+        let _ = writeln!(out, "# HELP onvp_mails_total Emails sent by outcome");
+        let _ = writeln!(out, "# TYPE onvp_mails_total counter");
+        for ((purpose, success), count) in mails.iter() {
+            let outcome = if *success { "success" } else { "failure" };
+            let _ = writeln!(
+                out,
+                "onvp_mails_total{{purpose=\"{}\",outcome=\"{outcome}\"}} {count}",
+                purpose.label()
+            );
+        }
+    }
+
+    fn render_logins(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP onvp_logins_total Login attempts by outcome");
+        let _ = writeln!(out, "# TYPE onvp_logins_total counter");
+        let _ = writeln!(
+            out,
+            "onvp_logins_total{{outcome=\"success\"}} {}",
+            self.login_successes.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            out,
+            "onvp_logins_total{{outcome=\"failure\"}} {}",
+            self.login_failures.load(Ordering::Relaxed)
+        );
+    }
+
+    fn render_pool(out: &mut String, pool: &DatabaseConnectionPool) {
+        let state = pool.state();
+        let _ = writeln!(
+            out,
+            "# HELP onvp_db_pool_connections Database pool connections by state"
+        );
+        let _ = writeln!(out, "# TYPE onvp_db_pool_connections gauge");
+        let _ = writeln!(
+            out,
+            "onvp_db_pool_connections{{state=\"idle\"}} {}",
+            state.idle_connections
+        );
+        let _ = writeln!(
+            out,
+            "onvp_db_pool_connections{{state=\"in_use\"}} {}",
+            state.connections - state.idle_connections
+        );
+        let _ = writeln!(
+            out,
+            "# HELP onvp_db_pool_max_connections Maximum database pool size"
+        );
+        let _ = writeln!(out, "# TYPE onvp_db_pool_max_connections gauge");
+        let _ = writeln!(out, "onvp_db_pool_max_connections {}", pool.max_size());
+    }
+}
+
+impl RequestKey {
+    fn labels(&self) -> String {
+        format!(
+            "method=\"{}\",route=\"{}\",status=\"{}\"",
+            self.method,
+            self.route.replace('\\', "\\\\").replace('"', "\\\""),
+            self.status
+        )
+    }
+}
@@ -18,12 +18,21 @@
  */
 use actix_web::web::Data;
 
+pub mod caching;
 pub mod http;
-pub mod lazy;
+pub mod icalendar;
+pub mod imaging;
+pub mod logging;
+pub mod metrics;
+pub mod recurrence;
+pub mod rendering;
 pub mod result;
 pub mod search_helpers;
 pub mod security;
+pub mod settings;
+pub mod slug;
 pub mod storage;
+pub mod validation;
 
 /// This trait is implemented by all injectables
 pub trait Injectable<U, T: ?Sized> {
@@ -0,0 +1,455 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Recurrence of events, following the RRULE property of iCalendar (RFC 5545). The frequency,
+//! interval, count and end of a rule are supported, together with the week days of weekly
+//! rules. Occurrences are expanded in the local time of the event, such that an event keeps
+//! its time of day when summer time starts or ends.
+
+use chrono::{Datelike, Days, LocalResult, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Weekday};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// The maximum amount of periods which are expanded, guarding against rules which hardly ever
+/// produce an occurrence
+const MAX_PERIODS: u32 = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The end of a recurrence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Until {
+    /// The last day on which an occurrence may start, in the time zone of the event
+    Date(NaiveDate),
+    /// The last moment at which an occurrence may start, in UTC
+    Moment(NaiveDateTime),
+}
+
+/// A recurrence rule, e.g. `FREQ=WEEKLY;BYDAY=TU,TH;UNTIL=20251231`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<Until>,
+    /// The week days of a weekly rule, the week day of the first occurrence is used if empty
+    pub by_day: Vec<Weekday>,
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.strip_prefix("RRULE:").unwrap_or(value);
+        let mut frequency = None;
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+        };
+        for part in value.split(';') {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("{part} is not a rule part"))?;
+            match key {
+                "FREQ" => frequency = Some(parse_frequency(value)?),
+                "INTERVAL" => rule.interval = parse_positive(key, value)?,
+                "COUNT" => rule.count = Some(parse_positive(key, value)?),
+                "UNTIL" => rule.until = Some(parse_until(value)?),
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(parse_weekday)
+                        .collect::<Result<_, _>>()?;
+                }
+                // Weeks always start on monday
+                "WKST" if value == "MO" => {}
+                _ => return Err(format!("{key} is not supported")),
+            }
+        }
+        rule.frequency = frequency.ok_or("FREQ is required")?;
+        if rule.count.is_some() && rule.until.is_some() {
+            return Err("COUNT and UNTIL can not be combined".to_owned());
+        }
+        if !rule.by_day.is_empty() && rule.frequency != Frequency::Weekly {
+            return Err("BYDAY is only supported for weekly rules".to_owned());
+        }
+        rule.by_day.sort_by_key(Weekday::num_days_from_monday);
+        rule.by_day.dedup();
+        Ok(rule)
+    }
+}
+
+impl Rule {
+    /// Verifies whether the rule produces an occurrence on the day of the first occurrence, as
+    /// the first occurrence always counts as an occurrence
+    pub fn includes_start(&self, start: NaiveDate) -> bool {
+        self.by_day.is_empty() || self.by_day.contains(&start.weekday())
+    }
+
+    /// The first day of the period with the given index
+    fn period_start(&self, start: NaiveDate, period: u32) -> Option<NaiveDate> {
+        let steps = period.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => start.checked_add_days(Days::new(steps as u64)),
+            Frequency::Weekly => start
+                .checked_sub_days(Days::new(start.weekday().num_days_from_monday() as u64))?
+                .checked_add_days(Days::new(steps as u64 * 7)),
+            Frequency::Monthly => {
+                let month = start.month0() as i64 + steps as i64;
+                let year = start.year() as i64 + month / 12;
+                NaiveDate::from_ymd_opt(year.try_into().ok()?, (month % 12) as u32 + 1, 1)
+            }
+            Frequency::Yearly => {
+                NaiveDate::from_ymd_opt(start.year().checked_add(steps.try_into().ok()?)?, 1, 1)
+            }
+        }
+    }
+
+    /// The days within the period on which the event occurs. Months without the day of the
+    /// month of the first occurrence are skipped, like the 31st of April.
+    fn dates(&self, start: NaiveDate, period_start: NaiveDate) -> Vec<NaiveDate> {
+        match self.frequency {
+            Frequency::Daily => vec![period_start],
+            Frequency::Weekly if self.by_day.is_empty() => period_start
+                .checked_add_days(Days::new(start.weekday().num_days_from_monday() as u64))
+                .into_iter()
+                .collect(),
+            Frequency::Weekly => self
+                .by_day
+                .iter()
+                .filter_map(|day| {
+                    period_start.checked_add_days(Days::new(day.num_days_from_monday() as u64))
+                })
+                .filter(|date| *date >= start)
+                .collect(),
+            Frequency::Monthly => {
+                NaiveDate::from_ymd_opt(period_start.year(), period_start.month(), start.day())
+                    .into_iter()
+                    .collect()
+            }
+            Frequency::Yearly => {
+                NaiveDate::from_ymd_opt(period_start.year(), start.month(), start.day())
+                    .into_iter()
+                    .collect()
+            }
+        }
+    }
+
+    /// The periods which certainly end before the first day, which can be skipped if the
+    /// occurrences are not counted
+    fn skippable_periods(
+        &self,
+        start: NaiveDate,
+        duration: TimeDelta,
+        first_date: NaiveDate,
+    ) -> u32 {
+        let days = (first_date - start).num_days() - duration.num_days() - 1;
+        let period_days = match self.frequency {
+            Frequency::Daily => self.interval as i64,
+            Frequency::Weekly => self.interval as i64 * 7,
+            Frequency::Monthly | Frequency::Yearly => return 0,
+        };
+        if days <= 0 || self.count.is_some() {
+            0
+        } else {
+            (days / period_days).try_into().unwrap_or(0)
+        }
+    }
+}
+
+impl Until {
+    /// Verifies whether an occurrence starting at the local moment lies beyond the end
+    fn excludes(&self, local: NaiveDateTime, time_zone: Tz) -> bool {
+        match self {
+            Until::Date(date) => local.date() > *date,
+            Until::Moment(moment) => to_utc(time_zone, local) > *moment,
+        }
+    }
+}
+
+/// Expands the starts of the occurrences of an event which take place between the first and
+/// last day, both inclusive. The event first starts at `start` and lasts `duration`, in the
+/// local time of the time zone. Occurrences starting on one of the exceptions are left out.
+pub fn occurrences(
+    rule: Option<&Rule>,
+    start: NaiveDateTime,
+    duration: TimeDelta,
+    time_zone: Tz,
+    exceptions: &[NaiveDate],
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+) -> Vec<NaiveDateTime> {
+    let takes_place = |moment: NaiveDateTime| {
+        !exceptions.contains(&moment.date())
+            && moment.date() <= last_date
+            && (moment + duration).date() >= first_date
+    };
+    let Some(rule) = rule else {
+        return [start].into_iter().filter(|s| takes_place(*s)).collect();
+    };
+
+    let mut result = Vec::new();
+    let mut count = 0;
+    let skipped = rule.skippable_periods(start.date(), duration, first_date);
+    for period in skipped..skipped.saturating_add(MAX_PERIODS) {
+        let Some(period_start) = rule.period_start(start.date(), period) else {
+            break;
+        };
+        if period_start > last_date {
+            break;
+        }
+        for date in rule.dates(start.date(), period_start) {
+            let moment = date.and_time(start.time());
+            if rule.count.is_some_and(|max_count| count >= max_count)
+                || rule
+                    .until
+                    .is_some_and(|until| until.excludes(moment, time_zone))
+                || date > last_date
+            {
+                return result;
+            }
+            count += 1;
+            if takes_place(moment) {
+                result.push(moment);
+            }
+        }
+    }
+    result
+}
+
+/// Determines the last day of the last occurrence, if the recurrence ends
+pub fn last_date(
+    rule: Option<&Rule>,
+    start: NaiveDateTime,
+    duration: TimeDelta,
+    time_zone: Tz,
+) -> Option<NaiveDate> {
+    match rule {
+        Some(rule) if rule.count.is_none() && rule.until.is_none() => None,
+        _ => occurrences(
+            rule,
+            start,
+            duration,
+            time_zone,
+            &[],
+            start.date(),
+            NaiveDate::MAX,
+        )
+        .last()
+        .map(|last| (*last + duration).date()),
+    }
+}
+
+/// Converts a local moment in the time zone into UTC. Moments skipped when summer time starts
+/// are moved forward by an hour, for moments occurring twice the first one is taken.
+pub fn to_utc(time_zone: Tz, local: NaiveDateTime) -> NaiveDateTime {
+    match time_zone.from_local_datetime(&local) {
+        LocalResult::Single(moment) | LocalResult::Ambiguous(moment, _) => moment.naive_utc(),
+        LocalResult::None => time_zone
+            .from_local_datetime(&(local + TimeDelta::hours(1)))
+            .earliest()
+            .map(|moment| moment.naive_utc())
+            .unwrap_or(local),
+    }
+}
+
+fn parse_frequency(value: &str) -> Result<Frequency, String> {
+    match value {
+        "DAILY" => Ok(Frequency::Daily),
+        "WEEKLY" => Ok(Frequency::Weekly),
+        "MONTHLY" => Ok(Frequency::Monthly),
+        "YEARLY" => Ok(Frequency::Yearly),
+        _ => Err(format!("FREQ={value} is not supported")),
+    }
+}
+
+fn parse_positive(key: &str, value: &str) -> Result<u32, String> {
+    match value.parse() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!("{key} must be a positive number")),
+    }
+}
+
+fn parse_until(value: &str) -> Result<Until, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return Ok(Until::Date(date));
+    }
+    // Local moments are ambiguous, hence the end is only accepted as a date or in UTC
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .map(Until::Moment)
+        .map_err(|_| "UNTIL must be a date or a moment in UTC".to_owned())
+}
+
+fn parse_weekday(value: &str) -> Result<Weekday, String> {
+    match value {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(format!("BYDAY={value} is not supported")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Amsterdam;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn moment(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        date(year, month, day).and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn rule_parses_supported_parts() {
+        let rule: Rule = "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TH,TU;UNTIL=20251231T230000Z"
+            .parse()
+            .unwrap();
+        assert_eq!(rule.frequency, Frequency::Weekly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.by_day, vec![Weekday::Tue, Weekday::Thu]);
+        assert_eq!(rule.until, Some(Until::Moment(moment(2025, 12, 31, 23, 0))));
+    }
+
+    #[test]
+    fn rule_rejects_unsupported_parts() {
+        for rule in [
+            "",
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;COUNT=0",
+            "FREQ=DAILY;COUNT=3;UNTIL=20251231",
+            "FREQ=DAILY;UNTIL=20251231T230000",
+            "FREQ=MONTHLY;BYDAY=1MO",
+            "FREQ=MONTHLY;BYMONTHDAY=1",
+        ] {
+            assert!(rule.parse::<Rule>().is_err(), "{rule}");
+        }
+    }
+
+    #[test]
+    fn occurrences_expand_weekly_rules_within_the_period() {
+        let rule: Rule = "FREQ=WEEKLY;BYDAY=TU,TH".parse().unwrap();
+        let result = occurrences(
+            Some(&rule),
+            moment(2025, 9, 2, 19, 30),
+            TimeDelta::hours(2),
+            Amsterdam,
+            &[date(2025, 9, 11)],
+            date(2025, 9, 8),
+            date(2025, 9, 18),
+        );
+        assert_eq!(
+            result,
+            vec![
+                moment(2025, 9, 9, 19, 30),
+                moment(2025, 9, 16, 19, 30),
+                moment(2025, 9, 18, 19, 30),
+            ]
+        );
+    }
+
+    #[test]
+    fn occurrences_skip_months_without_the_day() {
+        let rule: Rule = "FREQ=MONTHLY;COUNT=3".parse().unwrap();
+        let start = moment(2025, 1, 31, 20, 0);
+        let result = occurrences(
+            Some(&rule),
+            start,
+            TimeDelta::hours(1),
+            Amsterdam,
+            &[],
+            date(2025, 1, 1),
+            date(2025, 12, 31),
+        );
+        assert_eq!(
+            result,
+            vec![
+                start,
+                moment(2025, 3, 31, 20, 0),
+                moment(2025, 5, 31, 20, 0)
+            ]
+        );
+        assert_eq!(
+            last_date(Some(&rule), start, TimeDelta::hours(1), Amsterdam),
+            Some(date(2025, 5, 31))
+        );
+    }
+
+    #[test]
+    fn occurrences_end_at_the_until_moment() {
+        let rule: Rule = "FREQ=DAILY;UNTIL=20250103T180000Z".parse().unwrap();
+        let start = moment(2025, 1, 1, 19, 0);
+        let result = occurrences(
+            Some(&rule),
+            start,
+            TimeDelta::hours(1),
+            Amsterdam,
+            &[],
+            date(2025, 1, 1),
+            date(2025, 1, 31),
+        );
+        // 19:00 in Amsterdam is 18:00 in UTC during winter
+        assert_eq!(
+            result,
+            vec![start, moment(2025, 1, 2, 19, 0), moment(2025, 1, 3, 19, 0)]
+        );
+        assert_eq!(
+            last_date(
+                Some(&"FREQ=DAILY".parse().unwrap()),
+                start,
+                TimeDelta::hours(1),
+                Amsterdam
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn to_utc_follows_summer_time() {
+        assert_eq!(
+            to_utc(Amsterdam, moment(2025, 1, 14, 19, 30)),
+            moment(2025, 1, 14, 18, 30)
+        );
+        assert_eq!(
+            to_utc(Amsterdam, moment(2025, 7, 14, 19, 30)),
+            moment(2025, 7, 14, 17, 30)
+        );
+        // 02:30 is skipped when summer time starts
+        assert_eq!(
+            to_utc(Amsterdam, moment(2025, 3, 30, 2, 30)),
+            moment(2025, 3, 30, 1, 30)
+        );
+    }
+}
@@ -0,0 +1,365 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Rendering of page content into HTML which is safe to serve to every visitor. Content is
+//! written in Markdown or in HTML, after which shortcodes are expanded and the result is
+//! sanitised, keeping only the elements, attributes and URL schemes on the allow-list.
+
+use crate::model::primitives::ContentFormat;
+use ammonia::{Builder, UrlRelative};
+use chrono::NaiveDate;
+use pulldown_cmark::{html, Options, Parser};
+use std::collections::{HashMap, HashSet};
+
+/// The elements which are kept by the sanitiser
+const ALLOWED_TAGS: [&str; 38] = [
+    "a",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "code",
+    "del",
+    "div",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "tr",
+    "ul",
+];
+
+/// The URL schemes which are allowed in links and image sources, relative URLs are allowed too
+const ALLOWED_URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// A reference in the content to another entity, written as `[[image:12]]` or `[[event:34]]`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shortcode {
+    /// Shows the image with the given identifier
+    Image(i32),
+    /// Links to the event page with the given identifier
+    Event(i32),
+}
+
+/// The entity a shortcode is resolved into
+#[derive(Clone, Debug)]
+pub enum Link {
+    /// An image, which is shown inline
+    Image { id: i32, title: String },
+    /// A page, which is linked to
+    Page {
+        href: String,
+        title: String,
+        event_date: Option<NaiveDate>,
+    },
+}
+
+/// Renders the content into sanitised HTML. Shortcodes which can not be resolved are left in
+/// the content as they are written.
+pub fn render(
+    source: &str,
+    format: ContentFormat,
+    resolve: impl FnMut(Shortcode) -> Option<Link>,
+) -> String {
+    let html = match format {
+        ContentFormat::Html => source.to_owned(),
+        ContentFormat::Markdown => {
+            let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+            let mut html = String::with_capacity(source.len() * 3 / 2);
+            html::push_html(&mut html, Parser::new_ext(source, options));
+            html
+        }
+    };
+    // Expanded shortcodes are sanitised as well, such that titles can not inject markup
+    sanitizer()
+        .clean(&expand_shortcodes(&html, resolve))
+        .to_string()
+}
+
+/// Marks the start of a highlighted term in search snippets, see the `page_search_snippet`
+/// database function
+pub const HIGHLIGHT_START: char = '\u{2}';
+
+/// Marks the end of a highlighted term in search snippets
+pub const HIGHLIGHT_STOP: char = '\u{3}';
+
+/// Extracts the text from rendered content, as indexed for searching. Elements are replaced by
+/// white space, such that words in adjacent elements are kept apart.
+pub fn plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '<' => {
+                let end = rest.find('>').map(|end| end + 1).unwrap_or(rest.len());
+                text.push(' ');
+                rest = &rest[end..];
+                continue;
+            }
+            '&' => {
+                let entity = ENTITIES.iter().find(|(entity, _)| rest.starts_with(entity));
+                if let Some((entity, decoded)) = entity {
+                    text.push(*decoded);
+                    rest = &rest[entity.len()..];
+                    continue;
+                }
+                text.push(c);
+            }
+            c if c.is_control() => text.push(' '),
+            c => text.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Converts a search snippet into HTML, marking the highlighted terms with `<mark>`
+pub fn highlight(snippet: &str) -> String {
+    escape(snippet)
+        .replace(HIGHLIGHT_START, "<mark>")
+        .replace(HIGHLIGHT_STOP, "</mark>")
+}
+
+/// The entities produced by the sanitiser when serialising text
+const ENTITIES: [(&str, char); 6] = [
+    ("&amp;", '&'),
+    ("&lt;", '<'),
+    ("&gt;", '>'),
+    ("&quot;", '"'),
+    ("&#39;", '\''),
+    ("&nbsp;", ' '),
+];
+
+fn sanitizer() -> Builder<'static> {
+    let tag_attributes = HashMap::from([
+        ("a", HashSet::from(["href"])),
+        ("img", HashSet::from(["src", "alt", "width", "height"])),
+        ("ol", HashSet::from(["start"])),
+        ("td", HashSet::from(["colspan", "rowspan"])),
+        ("th", HashSet::from(["colspan", "rowspan"])),
+        ("time", HashSet::from(["datetime"])),
+    ]);
+    let mut builder = Builder::default();
+    builder
+        .tags(HashSet::from(ALLOWED_TAGS))
+        .tag_attributes(tag_attributes)
+        .generic_attributes(HashSet::from(["title"]))
+        .url_schemes(HashSet::from(ALLOWED_URL_SCHEMES))
+        .url_relative(UrlRelative::PassThrough)
+        .link_rel(Some("noopener noreferrer"));
+    builder
+}
+
+fn expand_shortcodes(html: &str, mut resolve: impl FnMut(Shortcode) -> Option<Link>) -> String {
+    let mut expanded = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("[[") {
+        let (before, candidate) = rest.split_at(start);
+        expanded.push_str(before);
+        let parsed = candidate
+            .find("]]")
+            .and_then(|end| Some((parse_shortcode(&candidate[2..end])?, end)));
+        match parsed.and_then(|(shortcode, end)| Some((resolve(shortcode)?, end))) {
+            Some((link, end)) => {
+                push_link(&mut expanded, &link);
+                rest = &candidate[end + 2..];
+            }
+            None => {
+                expanded.push_str("[[");
+                rest = &candidate[2..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+fn parse_shortcode(code: &str) -> Option<Shortcode> {
+    let (name, id) = code.trim().split_once(':')?;
+    let id = id.trim().parse().ok()?;
+    match name.trim() {
+        "image" => Some(Shortcode::Image(id)),
+        "event" => Some(Shortcode::Event(id)),
+        _ => None,
+    }
+}
+
+fn push_link(html: &mut String, link: &Link) {
+    match link {
+        Link::Image { id, title } => {
+            let title = escape(title);
+            html.push_str(&format!(
+                r#"<img src="/api/images/v1/asset/{id}.png" alt="{title}" title="{title}">"#
+            ));
+        }
+        Link::Page {
+            href,
+            title,
+            event_date,
+        } => {
+            html.push_str(&format!(r#"<a href="{}">{}"#, escape(href), escape(title)));
+            if let Some(date) = event_date {
+                html.push_str(&format!(r#" <time datetime="{date}">{date}</time>"#));
+            }
+            html.push_str("</a>");
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unresolved(_: Shortcode) -> Option<Link> {
+        None
+    }
+
+    #[test]
+    fn render_converts_markdown() {
+        let html = render(
+            "# Concert\n\n*Tickets* at the door",
+            ContentFormat::Markdown,
+            unresolved,
+        );
+        assert_eq!(
+            html,
+            "<h1>Concert</h1>\n<p><em>Tickets</em> at the door</p>\n"
+        );
+    }
+
+    #[test]
+    fn render_removes_scripts_and_event_handlers() {
+        let source = r#"<p onclick="steal()">Hello<script>steal()</script></p><img src="x.png" onerror="steal()">"#;
+        for format in [ContentFormat::Html, ContentFormat::Markdown] {
+            let html = render(source, format, unresolved);
+            assert!(!html.contains("steal"), "{html}");
+            assert!(html.contains("Hello"), "{html}");
+        }
+    }
+
+    #[test]
+    fn render_removes_unsafe_urls() {
+        let html = render(
+            "[click](javascript:steal()) <a href=\"/page/1\">page</a>",
+            ContentFormat::Markdown,
+            unresolved,
+        );
+        assert!(!html.contains("javascript"), "{html}");
+        assert!(html.contains(r#"href="/page/1""#), "{html}");
+    }
+
+    #[test]
+    fn render_expands_shortcodes() {
+        let html = render(
+            "[[image:12]] and [[event: 34]]",
+            ContentFormat::Markdown,
+            |shortcode| match shortcode {
+                Shortcode::Image(id) => Some(Link::Image {
+                    id,
+                    title: "Stage <b>".to_owned(),
+                }),
+                Shortcode::Event(id) => Some(Link::Page {
+                    href: format!("/page/{id}"),
+                    title: "Spring concert".to_owned(),
+                    event_date: NaiveDate::from_ymd_opt(2025, 5, 12),
+                }),
+            },
+        );
+        assert!(
+            html.contains(r#"<img src="/api/images/v1/asset/12.png" alt="Stage &lt;b&gt;""#),
+            "{html}"
+        );
+        assert!(html.contains(r#"href="/page/34""#), "{html}");
+        assert!(
+            html.contains(r#"<time datetime="2025-05-12">2025-05-12</time>"#),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn render_keeps_unresolved_shortcodes() {
+        let html = render(
+            "[[image:12]] [[unknown:1]] [[",
+            ContentFormat::Html,
+            unresolved,
+        );
+        assert_eq!(html, "[[image:12]] [[unknown:1]] [[");
+    }
+
+    #[test]
+    fn plain_text_strips_elements_and_entities() {
+        let html = render(
+            "# Dress code\n\nBlack &amp; white,<br>no *jeans* & no sneakers",
+            ContentFormat::Markdown,
+            unresolved,
+        );
+        assert_eq!(
+            plain_text(&html),
+            "Dress code Black & white, no jeans & no sneakers"
+        );
+    }
+
+    #[test]
+    fn highlight_escapes_the_snippet() {
+        let snippet = format!("<b>concert</b> {HIGHLIGHT_START}dress{HIGHLIGHT_STOP} code");
+        assert_eq!(
+            highlight(&snippet),
+            "&lt;b&gt;concert&lt;/b&gt; <mark>dress</mark> code"
+        );
+    }
+}
@@ -17,8 +17,11 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::generic::logging;
+use crate::generic::validation::FieldError;
 use actix_jwt_auth_middleware::AuthError;
 use actix_web::body::BoxBody;
+use actix_web::error::BlockingError;
 use actix_web::http::{header, StatusCode};
 use actix_web::web::BytesMut;
 use actix_web::{HttpResponse, ResponseError};
@@ -55,11 +58,26 @@ impl BackendError {
             kind: ErrorKind::InsufficientBytes(reason.to_string()),
         }
     }
+    pub(crate) fn not_found() -> Self {
+        Self {
+            kind: ErrorKind::NotFound,
+        }
+    }
     pub(crate) fn forbidden() -> Self {
         Self {
             kind: ErrorKind::Forbidden,
         }
     }
+    pub(crate) fn precondition_failed() -> Self {
+        Self {
+            kind: ErrorKind::PreconditionFailed,
+        }
+    }
+    pub(crate) fn validation(errors: Vec<FieldError>) -> Self {
+        Self {
+            kind: ErrorKind::Validation(errors),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -78,7 +96,15 @@ pub enum ErrorKind {
     ConfigError(String),
     EmailError(String),
     TemplateError(String),
+    Migration(String),
+    Conflict(String),
+    Backup(String),
+    Asset(String),
+    Blocking(String),
+    NotFound,
     Forbidden,
+    PreconditionFailed,
+    Validation(Vec<FieldError>),
 }
 
 impl ErrorKind {
@@ -98,14 +124,26 @@ impl ErrorKind {
             ErrorKind::ConfigError(_) => "CONFIG_ERROR",
             ErrorKind::EmailError(_) => "EMAIL_ERROR",
             ErrorKind::TemplateError(_) => "TEMPLATE_ERROR",
+            ErrorKind::Migration(_) => "MIGRATION",
+            ErrorKind::Conflict(_) => "CONFLICT",
+            ErrorKind::Backup(_) => "BACKUP",
+            ErrorKind::Asset(_) => "ASSET",
+            ErrorKind::Blocking(_) => "BLOCKING",
+            ErrorKind::NotFound => "NOT_FOUND",
             ErrorKind::Forbidden => "FORBIDDEN",
+            ErrorKind::PreconditionFailed => "PRECONDITION_FAILED",
+            ErrorKind::Validation(_) => "VALIDATION",
         }
     }
 
     pub fn status_code(&self) -> StatusCode {
         match &self {
             ErrorKind::BadRequest => StatusCode::BAD_REQUEST,
+            ErrorKind::Validation(_) => StatusCode::BAD_REQUEST,
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
             ErrorKind::Forbidden => StatusCode::FORBIDDEN,
+            ErrorKind::Conflict(_) => StatusCode::CONFLICT,
+            ErrorKind::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -126,7 +164,17 @@ impl ErrorKind {
             ErrorKind::ConfigError(s) => s.to_string(),
             ErrorKind::EmailError(s) => s.to_string(),
             ErrorKind::TemplateError(s) => s.to_string(),
+            ErrorKind::Migration(s) => s.to_string(),
+            ErrorKind::Conflict(s) => s.to_string(),
+            ErrorKind::Backup(s) => s.to_string(),
+            ErrorKind::Asset(s) => s.to_string(),
+            ErrorKind::Blocking(s) => s.to_string(),
+            ErrorKind::NotFound => "Not Found".to_string(),
             ErrorKind::Forbidden => "Access Denied".to_string(),
+            ErrorKind::PreconditionFailed => {
+                "The resource was modified by someone else".to_string()
+            }
+            ErrorKind::Validation(_) => "Validation Failed".to_string(),
         }
     }
 }
@@ -135,6 +183,10 @@ impl ErrorKind {
 struct PreparedError {
     kind: String,
     message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<FieldError>,
+    #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 impl BackendError {
@@ -144,6 +196,30 @@ impl BackendError {
         }
     }
 
+    pub fn migration<T: ToString>(reason: T) -> BackendError {
+        Self {
+            kind: ErrorKind::Migration(reason.to_string()),
+        }
+    }
+
+    pub fn conflict<T: ToString>(reason: T) -> BackendError {
+        Self {
+            kind: ErrorKind::Conflict(reason.to_string()),
+        }
+    }
+
+    pub fn backup<T: ToString>(reason: T) -> BackendError {
+        Self {
+            kind: ErrorKind::Backup(reason.to_string()),
+        }
+    }
+
+    pub fn asset<T: ToString>(reason: T) -> BackendError {
+        Self {
+            kind: ErrorKind::Asset(reason.to_string()),
+        }
+    }
+
     pub fn qr_code_generation(reason: String) -> BackendError {
         Self {
             kind: ErrorKind::QrCodeGeneration(reason),
@@ -154,6 +230,11 @@ impl BackendError {
         let pre = PreparedError {
             kind: self.kind.simplified_string().to_string(),
             message: self.kind.message(),
+            fields: match &self.kind {
+                ErrorKind::Validation(fields) => fields.clone(),
+                _ => vec![],
+            },
+            request_id: logging::current_request_id(),
         };
         serde_json::to_string_pretty(&pre).unwrap_or_default()
     }
@@ -163,7 +244,7 @@ impl Display for BackendError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let kind = self.kind.simplified_string();
         let explanation = self.kind.message();
-        if explanation.len() > 0 {
+        if !explanation.is_empty() {
             write!(f, "{}: {}", kind, explanation)
         } else {
             write!(f, "{}", kind)
@@ -279,6 +360,14 @@ impl From<ImageError> for BackendError {
     }
 }
 
+impl From<BlockingError> for BackendError {
+    fn from(value: BlockingError) -> Self {
+        Self {
+            kind: ErrorKind::Blocking(value.to_string()),
+        }
+    }
+}
+
 impl From<VarError> for BackendError {
     fn from(value: VarError) -> Self {
         Self {
@@ -354,9 +443,7 @@ impl From<diesel::result::Error> for BackendError {
                 commit_error,
             } => Self {
                 kind: ErrorKind::Database(format!(
-                    "Rollback Error: {} on Commit Error: {}",
-                    rollback_error.to_string(),
-                    commit_error.to_string()
+                    "Rollback Error: {rollback_error} on Commit Error: {commit_error}"
                 )),
             },
             diesel::result::Error::RollbackTransaction => Self {
@@ -35,6 +35,7 @@ use diesel::{
 
 use rand::distr::{Alphanumeric, SampleString};
 use rand::rng;
+use sha2::{Digest, Sha256};
 use std::collections::HashSet;
 pub use totp_rs::TOTP;
 
@@ -43,6 +44,11 @@ pub fn generate_activation_string() -> String {
     validation_string
 }
 
+/// Hashes a token which grants access by itself, such that only the hash needs to be stored
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
 #[non_exhaustive]
 #[derive(Clone, FromRequest)]
 pub struct ClaimRoles(HashSet<Role>);
@@ -103,3 +109,11 @@ impl From<&Option<UserClaims>> for ClaimRoles {
         Self(result)
     }
 }
+
+impl From<&[Role]> for ClaimRoles {
+    fn from(value: &[Role]) -> Self {
+        let mut result = HashSet::from_iter(value.iter().copied());
+        result.insert(Role::Public);
+        Self(result)
+    }
+}
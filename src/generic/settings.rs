@@ -0,0 +1,1304 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Typed configuration of the backend. The settings are read from a TOML file, after which
+//! the environment variables (also read from the `.env` file) override individual settings.
+//! The settings are validated eagerly, all problems are reported at once, so that the backend
+//! never starts with a partial configuration.
+
+use crate::commands::jobs::registry::{JobDefinition, DEFINITIONS};
+use crate::commands::jobs::schedule::Schedule;
+use crate::generic::result::BackendResult;
+use aes_gcm::{Aes256Gcm, Key, KeyInit};
+use base64::engine::general_purpose;
+use base64::Engine;
+use chrono::TimeDelta;
+use handlebars::Handlebars;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::client::Tls;
+use lettre::SmtpTransport;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env::var;
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Environment variable pointing to the configuration file
+pub const CONFIG_FILE_VARIABLE: &str = "ONVP_CONFIG";
+
+/// Configuration file used if [`CONFIG_FILE_VARIABLE`] is not set, it is allowed to be absent
+pub const DEFAULT_CONFIG_FILE: &str = "onvp-backend.toml";
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub server: ServerSettings,
+    pub tls: Option<TlsSettings>,
+    pub logging: LoggingSettings,
+    pub metrics: MetricsSettings,
+    pub database: DatabaseSettings,
+    pub security: SecuritySettings,
+    pub activation: ActivationSettings,
+    pub search: SearchSettings,
+    pub events: EventSettings,
+    pub revisions: RevisionSettings,
+    pub content: ContentSettings,
+    pub assets: AssetSettings,
+    pub images: ImageSettings,
+    pub email: EmailSettings,
+    pub source_code: SourceCodeSettings,
+    pub jobs: JobSettings,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    /// The addresses the HTTP server binds to, if TLS is enabled, these addresses either redirect
+    /// to HTTPS or are not bound at all.
+    pub bind: Vec<SocketAddr>,
+    /// The amount of seconds workers are given to finish their requests when shutting down
+    pub shutdown_timeout_seconds: u64,
+    /// The amount of milliseconds a client is given to send the request headers
+    pub client_request_timeout_ms: u64,
+    /// The amount of milliseconds a client is given to acknowledge the closing of a connection
+    pub client_disconnect_timeout_ms: u64,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            bind: vec![SocketAddr::from(([0, 0, 0, 0], 8080))],
+            shutdown_timeout_seconds: 30,
+            client_request_timeout_ms: 5000,
+            client_disconnect_timeout_ms: 1000,
+        }
+    }
+}
+
+impl ServerSettings {
+    pub fn client_request_timeout(&self) -> Duration {
+        Duration::from_millis(self.client_request_timeout_ms)
+    }
+
+    pub fn client_disconnect_timeout(&self) -> Duration {
+        Duration::from_millis(self.client_disconnect_timeout_ms)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsSettings {
+    /// Serves the API using HTTPS, the other TLS settings are ignored unless this is set
+    pub enabled: bool,
+    /// The addresses the HTTPS server binds to
+    pub bind: Vec<SocketAddr>,
+    /// PEM file containing the certificate chain, reloaded on SIGHUP
+    pub certificate: PathBuf,
+    /// PEM file containing the private key, reloaded on SIGHUP
+    pub private_key: PathBuf,
+    /// If set, the HTTP addresses redirect all requests to HTTPS
+    pub redirect_http: bool,
+    /// The host name to redirect to, defaults to the host from the Host header of the request
+    pub redirect_host: Option<String>,
+    /// The port to redirect to, defaults to the port of the first HTTPS address. This is useful
+    /// if the backend is reachable through a port different from the one it binds to.
+    pub redirect_port: Option<u16>,
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: vec![SocketAddr::from(([0, 0, 0, 0], 8443))],
+            certificate: PathBuf::new(),
+            private_key: PathBuf::new(),
+            redirect_http: true,
+            redirect_host: None,
+            redirect_port: None,
+        }
+    }
+}
+
+impl TlsSettings {
+    pub fn redirect_port(&self) -> u16 {
+        self.redirect_port
+            .or_else(|| self.bind.first().map(SocketAddr::port))
+            .unwrap_or(443)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSettings {
+    /// The format of each log line
+    pub format: LogFormat,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsSettings {
+    /// The bearer token required to read the metrics, the metrics are not exposed if empty
+    pub token: String,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, suitable for log aggregation
+    Json,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseSettings {
+    /// The PostgreSQL connection URL
+    pub url: String,
+    /// Applies pending migrations when the backend starts, instead of refusing to start
+    pub auto_migrate: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecuritySettings {
+    /// Base64 encoded AES-256 key, generated using onvp-otp-keygen
+    pub otp_key: String,
+    /// Location of the PEM file containing the key pair, generated using onvp-jwt-keygen
+    pub jwt_keys: PathBuf,
+    pub access_token_minutes: u64,
+    pub refresh_token_minutes: u64,
+    /// The amount of seconds before token expiry after which the tokens are refreshed
+    pub token_expiry_high_water_mark: u64,
+}
+
+impl Default for SecuritySettings {
+    fn default() -> Self {
+        Self {
+            otp_key: String::new(),
+            jwt_keys: PathBuf::new(),
+            access_token_minutes: 3,
+            refresh_token_minutes: 10,
+            token_expiry_high_water_mark: 120,
+        }
+    }
+}
+
+impl SecuritySettings {
+    /// Returns the Cipher used for one-time password validation
+    pub fn otp_cipher(&self) -> Aes256Gcm {
+        let buffer = general_purpose::STANDARD
+            .decode(&self.otp_key)
+            .expect("OTP key should be validated");
+        let key = Key::<Aes256Gcm>::from_slice(&buffer);
+        Aes256Gcm::new(key)
+    }
+
+    pub fn access_token_lifetime(&self) -> Duration {
+        Duration::from_secs(self.access_token_minutes * 60)
+    }
+
+    pub fn refresh_token_lifetime(&self) -> Duration {
+        Duration::from_secs(self.refresh_token_minutes * 60)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ActivationSettings {
+    pub member_minutes: u32,
+    pub first_operator_minutes: u32,
+}
+
+impl Default for ActivationSettings {
+    fn default() -> Self {
+        Self {
+            member_minutes: 2880,
+            first_operator_minutes: 30,
+        }
+    }
+}
+
+impl ActivationSettings {
+    pub fn member_delta(&self) -> TimeDelta {
+        TimeDelta::minutes(self.member_minutes as i64)
+    }
+
+    pub fn first_operator_delta(&self) -> TimeDelta {
+        TimeDelta::minutes(self.first_operator_minutes as i64)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchSettings {
+    /// The page size of each page for a search
+    pub page_size: usize,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self { page_size: 10 }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventSettings {
+    /// The maximum days past the current date from which events are to be fetched
+    pub max_days: u32,
+    /// The days before the current date from which events are included in calendar feeds
+    pub calendar_past_days: u32,
+    /// The days after the current date until which events are included in calendar feeds
+    pub calendar_future_days: u32,
+    /// The domain which makes the identifiers of events in calendar feeds globally unique,
+    /// usually the domain of the website
+    pub calendar_domain: String,
+    /// The name of the calendar feeds, as shown in calendar applications
+    pub calendar_name: String,
+    /// The days before an event on which members who did not respond yet are reminded, 0
+    /// disables the reminders
+    pub attendance_reminder_days: u32,
+    /// The Handlebars template of the subject of the reminders
+    pub attendance_reminder_subject: String,
+    /// The Handlebars template of the body of the reminders, with first_name, last_name, title,
+    /// date and link as variables
+    pub attendance_reminder_body: String,
+}
+
+impl Default for EventSettings {
+    fn default() -> Self {
+        Self {
+            max_days: 90,
+            calendar_past_days: 90,
+            calendar_future_days: 365,
+            calendar_domain: "localhost".to_owned(),
+            calendar_name: "ONVP".to_owned(),
+            attendance_reminder_days: 3,
+            attendance_reminder_subject: "Will you attend {{title}}?".to_owned(),
+            attendance_reminder_body: "Dear {{first_name}},\n\nPlease let us know whether you \
+                attend {{title}} on {{date}}: {{link}}\n"
+                .to_owned(),
+        }
+    }
+}
+
+impl EventSettings {
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut registry = Handlebars::new();
+        if let Err(e) =
+            registry.register_template_string("subject", &self.attendance_reminder_subject)
+        {
+            problems.push(format!(
+                "events.attendance_reminder_subject (ATTENDANCE_REMINDER_SUBJECT) is invalid: {e}"
+            ));
+        }
+        if let Err(e) = registry.register_template_string("body", &self.attendance_reminder_body) {
+            problems.push(format!(
+                "events.attendance_reminder_body (ATTENDANCE_REMINDER_BODY) is invalid: {e}"
+            ));
+        }
+        problems
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct RevisionSettings {
+    /// The maximum amount of revisions kept for every page, 0 keeps all revisions
+    pub max_per_page: u32,
+    /// The maximum age in days of a revision, 0 keeps revisions regardless of their age
+    pub max_age_days: u32,
+}
+
+impl Default for RevisionSettings {
+    fn default() -> Self {
+        Self {
+            max_per_page: 50,
+            max_age_days: 0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContentSettings {
+    /// The link to a page on the website, used when rendering shortcodes referring to pages,
+    /// where {id} is replaced by the identifier of the page
+    pub page_link: String,
+}
+
+impl Default for ContentSettings {
+    fn default() -> Self {
+        Self {
+            page_link: "/page/{id}".to_owned(),
+        }
+    }
+}
+
+impl ContentSettings {
+    /// Returns the link to the page with the given identifier
+    pub fn page_link(&self, page_id: i32) -> String {
+        self.page_link.replace("{id}", &page_id.to_string())
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.page_link.contains("{id}") {
+            problems.push("content.page_link (CONTENT_PAGE_LINK) must contain {id}".to_owned());
+        }
+        problems
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetBackend {
+    /// Assets are stored as files in a local directory
+    #[default]
+    Filesystem,
+    /// Assets are stored as objects in an S3-compatible bucket
+    S3,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct AssetSettings {
+    /// Where the assets are stored
+    pub backend: AssetBackend,
+    /// The directory in which the assets are stored, when using the filesystem backend
+    pub path: PathBuf,
+    /// The minimum age of an orphaned asset before it is quarantined
+    pub orphan_grace_hours: u32,
+    /// The URL of the S3-compatible endpoint, e.g. https://s3.eu-west-1.amazonaws.com
+    pub s3_endpoint: String,
+    pub s3_bucket: String,
+    pub s3_region: String,
+    pub s3_access_key: String,
+    pub s3_secret_key: String,
+    /// Prepended to the asset identifier to form the object key
+    pub s3_prefix: String,
+}
+
+impl Default for AssetSettings {
+    fn default() -> Self {
+        Self {
+            backend: AssetBackend::Filesystem,
+            path: PathBuf::new(),
+            orphan_grace_hours: 24,
+            s3_endpoint: String::new(),
+            s3_bucket: String::new(),
+            s3_region: "us-east-1".to_owned(),
+            s3_access_key: String::new(),
+            s3_secret_key: String::new(),
+            s3_prefix: String::new(),
+        }
+    }
+}
+
+impl AssetSettings {
+    pub fn orphan_grace_period(&self) -> Duration {
+        Duration::from_secs(self.orphan_grace_hours as u64 * 3600)
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |condition: bool, problem: &str| {
+            if !condition {
+                problems.push(problem.to_owned());
+            }
+        };
+        match self.backend {
+            AssetBackend::Filesystem => check(
+                self.path.is_dir(),
+                "assets.path (ASSETS_PATH) must point to an existing directory",
+            ),
+            AssetBackend::S3 => {
+                check(
+                    self.s3_endpoint.starts_with("http://")
+                        || self.s3_endpoint.starts_with("https://"),
+                    "assets.s3_endpoint (ASSETS_S3_ENDPOINT) must be an http(s) URL",
+                );
+                check(
+                    !self.s3_bucket.is_empty(),
+                    "assets.s3_bucket (ASSETS_S3_BUCKET) must be set",
+                );
+                check(
+                    !self.s3_access_key.is_empty() && !self.s3_secret_key.is_empty(),
+                    "assets.s3_access_key and assets.s3_secret_key (ASSETS_S3_ACCESS_KEY, ASSETS_S3_SECRET_KEY) must be set",
+                );
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizedImageFormat {
+    /// Uploaded images keep the format they were uploaded in
+    #[default]
+    Original,
+    Png,
+    Jpeg,
+    Webp,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImageSettings {
+    /// The maximum width and height of an uploaded image, larger images are rejected
+    pub max_dimension: u32,
+    /// The format uploaded images are converted to
+    pub normalize_format: NormalizedImageFormat,
+    /// Uploaded images exceeding this width or height are scaled down, 0 disables scaling
+    pub normalize_max_dimension: u32,
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        Self {
+            max_dimension: 8192,
+            normalize_format: NormalizedImageFormat::Original,
+            normalize_max_dimension: 0,
+        }
+    }
+}
+
+impl ImageSettings {
+    /// Returns whether uploaded images are converted or scaled before they are stored
+    pub fn normalizes(&self) -> bool {
+        self.normalize_format != NormalizedImageFormat::Original || self.normalize_max_dimension > 0
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |condition: bool, problem: &str| {
+            if !condition {
+                problems.push(problem.to_owned());
+            }
+        };
+        check(
+            self.max_dimension > 0,
+            "images.max_dimension (IMAGES_MAX_DIMENSION) must be positive",
+        );
+        check(
+            self.normalize_max_dimension <= self.max_dimension,
+            "images.normalize_max_dimension (IMAGES_NORMALIZE_MAX_DIMENSION) must not exceed images.max_dimension",
+        );
+        problems
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailSettings {
+    /// Development mode, bypasses TLS and authentication towards the SMTP relay
+    pub dev_mode: bool,
+    pub from: String,
+    pub registration_subject: String,
+    /// Registration body, with {} as substitution for the activation string
+    pub registration_body: String,
+    pub smtp_user: String,
+    pub smtp_password: String,
+    pub smtp_relay: String,
+    pub smtp_port: u16,
+}
+
+impl Default for EmailSettings {
+    fn default() -> Self {
+        Self {
+            dev_mode: false,
+            from: String::new(),
+            registration_subject: String::new(),
+            registration_body: String::new(),
+            smtp_user: String::new(),
+            smtp_password: String::new(),
+            smtp_relay: String::new(),
+            smtp_port: 587,
+        }
+    }
+}
+
+impl EmailSettings {
+    /// Validates the email settings, returning a human-readable description of each problem
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |condition: bool, problem: &str| {
+            if !condition {
+                problems.push(problem.to_owned());
+            }
+        };
+
+        check(
+            self.from.parse::<Mailbox>().is_ok(),
+            "email.from (EMAIL_FROM) must be a valid mailbox",
+        );
+        check(
+            !self.registration_subject.is_empty(),
+            "email.registration_subject (EMAIL_REGISTRATION_SUBJECT) must be set",
+        );
+        check(
+            self.registration_body.contains("{}"),
+            "email.registration_body (EMAIL_REGISTRATION_BODY) must contain {} for the activation string",
+        );
+        check(
+            !self.smtp_relay.is_empty(),
+            "email.smtp_relay (EMAIL_SMTP_RELAY) must be set",
+        );
+        check(
+            self.dev_mode || !self.smtp_user.is_empty(),
+            "email.smtp_user (EMAIL_SMTP_USER) must be set unless in development mode",
+        );
+        check(
+            self.dev_mode || !self.smtp_password.is_empty(),
+            "email.smtp_password (EMAIL_SMTP_PASSWORD) must be set unless in development mode",
+        );
+        problems
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourceCodeSettings {
+    /// The source code URL of the backend, useful if for example a fork is used
+    pub backend_url: String,
+    /// The source code URL of the frontend, useful if for example a fork is used
+    pub frontend_url: String,
+}
+
+impl Default for SourceCodeSettings {
+    fn default() -> Self {
+        Self {
+            backend_url: "https://github.com/santidhammo/onvp-backend".to_owned(),
+            frontend_url: "https://github.com/santidhammo/onvp-frontend".to_owned(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobSettings {
+    /// Runs the scheduled jobs within this instance of the backend
+    pub enabled: bool,
+    /// Overrides the schedule of a job, by job name
+    pub schedules: BTreeMap<String, String>,
+}
+
+impl Default for JobSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            schedules: BTreeMap::new(),
+        }
+    }
+}
+
+impl JobSettings {
+    /// Returns the effective schedule of the job
+    pub fn schedule(&self, definition: &JobDefinition) -> Result<Schedule, String> {
+        self.schedules
+            .get(definition.name)
+            .map(String::as_str)
+            .unwrap_or(definition.default_schedule)
+            .parse()
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, schedule) in &self.schedules {
+            if !DEFINITIONS.iter().any(|definition| definition.name == name) {
+                problems.push(format!("jobs.schedules.{name} refers to an unknown job"));
+            } else if let Err(e) = schedule.parse::<Schedule>() {
+                problems.push(format!("jobs.schedules.{name} is invalid: {e}"));
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Clone)]
+pub struct SendEmailConfig {
+    pub email_dev_mode: bool,
+    pub email_from: Mailbox,
+    pub email_registration_subject: String,
+    pub email_registration_body_template: String,
+    pub email_smtp_user: String,
+    pub email_smtp_password: String,
+    pub email_smtp_relay: String,
+    pub email_smtp_port: u16,
+}
+
+impl SendEmailConfig {
+    /// Builds the transport relaying emails to the SMTP server
+    pub fn transport(&self) -> BackendResult<SmtpTransport> {
+        let mut builder = SmtpTransport::relay(&self.email_smtp_relay)?.port(self.email_smtp_port);
+        if !self.email_dev_mode {
+            let smtp_relay_credentials = lettre::transport::smtp::authentication::Credentials::new(
+                self.email_smtp_user.clone(),
+                self.email_smtp_password.clone(),
+            );
+            builder = builder.credentials(smtp_relay_credentials)
+        } else {
+            builder = builder.tls(Tls::None)
+        }
+        Ok(builder.build())
+    }
+}
+
+impl From<&EmailSettings> for SendEmailConfig {
+    fn from(value: &EmailSettings) -> Self {
+        Self {
+            email_dev_mode: value.dev_mode,
+            email_from: value.from.parse().expect("EMAIL_FROM should be validated"),
+            email_registration_subject: value.registration_subject.clone(),
+            email_registration_body_template: value.registration_body.clone(),
+            email_smtp_user: value.smtp_user.clone(),
+            email_smtp_password: value.smtp_password.clone(),
+            email_smtp_relay: value.smtp_relay.clone(),
+            email_smtp_port: value.smtp_port,
+        }
+    }
+}
+
+enum ValueKind {
+    Text,
+    TextList,
+    Integer,
+    Boolean,
+}
+
+/// Environment variables overriding the settings from the configuration file. The names are
+/// kept identical to the ones used before the configuration file was introduced, so existing
+/// `.env` files keep working.
+const ENVIRONMENT_OVERRIDES: &[(&str, &str, &str, ValueKind)] = &[
+    ("BIND_ADDRESS", "server", "bind", ValueKind::TextList),
+    (
+        "SHUTDOWN_TIMEOUT_SECONDS",
+        "server",
+        "shutdown_timeout_seconds",
+        ValueKind::Integer,
+    ),
+    ("TLS_ENABLED", "tls", "enabled", ValueKind::Boolean),
+    ("TLS_BIND_ADDRESS", "tls", "bind", ValueKind::TextList),
+    ("TLS_CERTIFICATE", "tls", "certificate", ValueKind::Text),
+    ("TLS_PRIVATE_KEY", "tls", "private_key", ValueKind::Text),
+    (
+        "TLS_REDIRECT_HTTP",
+        "tls",
+        "redirect_http",
+        ValueKind::Boolean,
+    ),
+    ("TLS_REDIRECT_HOST", "tls", "redirect_host", ValueKind::Text),
+    ("LOG_FORMAT", "logging", "format", ValueKind::Text),
+    ("METRICS_TOKEN", "metrics", "token", ValueKind::Text),
+    ("JOBS_ENABLED", "jobs", "enabled", ValueKind::Boolean),
+    ("DATABASE_URL", "database", "url", ValueKind::Text),
+    (
+        "DATABASE_AUTO_MIGRATE",
+        "database",
+        "auto_migrate",
+        ValueKind::Boolean,
+    ),
+    ("OTP_KEY", "security", "otp_key", ValueKind::Text),
+    ("JWT_KEYS", "security", "jwt_keys", ValueKind::Text),
+    (
+        "ACCESS_TOKEN_MINUTES",
+        "security",
+        "access_token_minutes",
+        ValueKind::Integer,
+    ),
+    (
+        "REFRESH_TOKEN_MINUTES",
+        "security",
+        "refresh_token_minutes",
+        ValueKind::Integer,
+    ),
+    (
+        "TOKEN_EXPIRY_HIGH_WATER_MARK",
+        "security",
+        "token_expiry_high_water_mark",
+        ValueKind::Integer,
+    ),
+    (
+        "MEMBER_ACTIVATION_MINUTES",
+        "activation",
+        "member_minutes",
+        ValueKind::Integer,
+    ),
+    (
+        "FIRST_OPERATOR_ACTIVATION_MINUTES",
+        "activation",
+        "first_operator_minutes",
+        ValueKind::Integer,
+    ),
+    (
+        "SEARCH_PAGE_SIZE",
+        "search",
+        "page_size",
+        ValueKind::Integer,
+    ),
+    ("MAX_EVENT_DAYS", "events", "max_days", ValueKind::Integer),
+    (
+        "CALENDAR_PAST_DAYS",
+        "events",
+        "calendar_past_days",
+        ValueKind::Integer,
+    ),
+    (
+        "CALENDAR_FUTURE_DAYS",
+        "events",
+        "calendar_future_days",
+        ValueKind::Integer,
+    ),
+    (
+        "CALENDAR_DOMAIN",
+        "events",
+        "calendar_domain",
+        ValueKind::Text,
+    ),
+    ("CALENDAR_NAME", "events", "calendar_name", ValueKind::Text),
+    (
+        "ATTENDANCE_REMINDER_DAYS",
+        "events",
+        "attendance_reminder_days",
+        ValueKind::Integer,
+    ),
+    (
+        "ATTENDANCE_REMINDER_SUBJECT",
+        "events",
+        "attendance_reminder_subject",
+        ValueKind::Text,
+    ),
+    (
+        "ATTENDANCE_REMINDER_BODY",
+        "events",
+        "attendance_reminder_body",
+        ValueKind::Text,
+    ),
+    (
+        "REVISIONS_MAX_PER_PAGE",
+        "revisions",
+        "max_per_page",
+        ValueKind::Integer,
+    ),
+    (
+        "REVISIONS_MAX_AGE_DAYS",
+        "revisions",
+        "max_age_days",
+        ValueKind::Integer,
+    ),
+    ("CONTENT_PAGE_LINK", "content", "page_link", ValueKind::Text),
+    ("ASSETS_BACKEND", "assets", "backend", ValueKind::Text),
+    ("ASSETS_PATH", "assets", "path", ValueKind::Text),
+    (
+        "ASSETS_ORPHAN_GRACE_HOURS",
+        "assets",
+        "orphan_grace_hours",
+        ValueKind::Integer,
+    ),
+    (
+        "ASSETS_S3_ENDPOINT",
+        "assets",
+        "s3_endpoint",
+        ValueKind::Text,
+    ),
+    ("ASSETS_S3_BUCKET", "assets", "s3_bucket", ValueKind::Text),
+    ("ASSETS_S3_REGION", "assets", "s3_region", ValueKind::Text),
+    (
+        "ASSETS_S3_ACCESS_KEY",
+        "assets",
+        "s3_access_key",
+        ValueKind::Text,
+    ),
+    (
+        "ASSETS_S3_SECRET_KEY",
+        "assets",
+        "s3_secret_key",
+        ValueKind::Text,
+    ),
+    ("ASSETS_S3_PREFIX", "assets", "s3_prefix", ValueKind::Text),
+    (
+        "IMAGES_MAX_DIMENSION",
+        "images",
+        "max_dimension",
+        ValueKind::Integer,
+    ),
+    (
+        "IMAGES_NORMALIZE_FORMAT",
+        "images",
+        "normalize_format",
+        ValueKind::Text,
+    ),
+    (
+        "IMAGES_NORMALIZE_MAX_DIMENSION",
+        "images",
+        "normalize_max_dimension",
+        ValueKind::Integer,
+    ),
+    ("EMAIL_DEV_MODE", "email", "dev_mode", ValueKind::Boolean),
+    ("EMAIL_FROM", "email", "from", ValueKind::Text),
+    (
+        "EMAIL_REGISTRATION_SUBJECT",
+        "email",
+        "registration_subject",
+        ValueKind::Text,
+    ),
+    (
+        "EMAIL_REGISTRATION_BODY",
+        "email",
+        "registration_body",
+        ValueKind::Text,
+    ),
+    ("EMAIL_SMTP_USER", "email", "smtp_user", ValueKind::Text),
+    (
+        "EMAIL_SMTP_PASSWORD",
+        "email",
+        "smtp_password",
+        ValueKind::Text,
+    ),
+    ("EMAIL_SMTP_RELAY", "email", "smtp_relay", ValueKind::Text),
+    ("EMAIL_SMTP_PORT", "email", "smtp_port", ValueKind::Integer),
+    (
+        "BACKEND_SOURCE_CODE_URL",
+        "source_code",
+        "backend_url",
+        ValueKind::Text,
+    ),
+    (
+        "FRONTEND_SOURCE_CODE_URL",
+        "source_code",
+        "frontend_url",
+        ValueKind::Text,
+    ),
+];
+
+/// Lists all problems found while loading and validating the configuration
+#[derive(Debug, Clone)]
+pub struct ConfigurationReport {
+    pub source: String,
+    pub problems: Vec<String>,
+}
+
+impl Display for ConfigurationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Configuration ({}) contains {} problem(s):",
+            self.source,
+            self.problems.len()
+        )?;
+        for problem in &self.problems {
+            writeln!(f, "  - {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigurationReport {}
+
+impl Settings {
+    /// Loads the settings from the configuration file and the environment, and validates them.
+    pub fn load() -> Result<Settings, ConfigurationReport> {
+        Self::load_validated(Settings::problems)
+    }
+
+    /// Loads the settings like [`Settings::load`], but only validates the database settings,
+    /// so that tools working on the database alone do not require the rest of the configuration
+    pub fn load_database() -> Result<Settings, ConfigurationReport> {
+        Self::load_validated(Settings::database_problems)
+    }
+
+    /// Loads the settings like [`Settings::load`], but only validates the database and asset
+    /// settings, for tools working on the stored data
+    pub fn load_storage() -> Result<Settings, ConfigurationReport> {
+        Self::load_validated(|settings| {
+            let mut problems = settings.database_problems();
+            problems.extend(settings.assets.problems());
+            problems
+        })
+    }
+
+    fn load_validated(
+        validate: impl Fn(&Settings) -> Vec<String>,
+    ) -> Result<Settings, ConfigurationReport> {
+        let (source, required) = match var(CONFIG_FILE_VARIABLE) {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_CONFIG_FILE.to_owned(), false),
+        };
+        let report = |problems: Vec<String>| ConfigurationReport {
+            source: source.clone(),
+            problems,
+        };
+
+        let table = match std::fs::read_to_string(&source) {
+            Ok(contents) => contents
+                .parse::<toml::Table>()
+                .map_err(|e| report(vec![format!("unable to parse {source}: {e}")]))?,
+            Err(e) if required => {
+                return Err(report(vec![format!("unable to read {source}: {e}")]));
+            }
+            Err(_) => toml::Table::new(),
+        };
+
+        Self::from_table(table, |variable| var(variable).ok(), validate).map_err(report)
+    }
+
+    /// Applies the environment overrides, obtained using the lookup, to the table and
+    /// deserializes and validates the result
+    fn from_table(
+        mut table: toml::Table,
+        lookup: impl Fn(&str) -> Option<String>,
+        validate: impl Fn(&Settings) -> Vec<String>,
+    ) -> Result<Settings, Vec<String>> {
+        let problems = Self::apply_environment_overrides(&mut table, lookup);
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        let settings: Settings = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| vec![e.to_string().trim().to_owned()])?;
+
+        let problems = validate(&settings);
+        if problems.is_empty() {
+            Ok(settings)
+        } else {
+            Err(problems)
+        }
+    }
+
+    fn apply_environment_overrides(
+        table: &mut toml::Table,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (variable, section, key, kind) in ENVIRONMENT_OVERRIDES {
+            let Some(raw) = lookup(variable) else {
+                continue;
+            };
+            let value = match kind {
+                ValueKind::Text => Some(toml::Value::String(raw.clone())),
+                ValueKind::TextList => Some(toml::Value::Array(
+                    raw.split(',')
+                        .map(|item| toml::Value::String(item.trim().to_owned()))
+                        .collect(),
+                )),
+                ValueKind::Integer => raw.trim().parse().ok().map(toml::Value::Integer),
+                ValueKind::Boolean => raw.trim().parse().ok().map(toml::Value::Boolean),
+            };
+            let Some(value) = value else {
+                problems.push(format!("{variable} has an invalid value: {raw}"));
+                continue;
+            };
+            let section = table
+                .entry(section.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            match section.as_table_mut() {
+                Some(section) => {
+                    section.insert(key.to_string(), value);
+                }
+                None => problems.push(format!("{variable} overrides a key in a non-table")),
+            }
+        }
+        problems
+    }
+
+    /// Validates the settings, returning a human-readable description of each problem
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |condition: bool, problem: &str| {
+            if !condition {
+                problems.push(problem.to_owned());
+            }
+        };
+
+        match self.tls() {
+            Some(tls) => {
+                check(
+                    !tls.bind.is_empty(),
+                    "tls.bind (TLS_BIND_ADDRESS) must contain at least one address",
+                );
+                check(
+                    tls.certificate.is_file(),
+                    "tls.certificate (TLS_CERTIFICATE) must point to an existing PEM file",
+                );
+                check(
+                    tls.private_key.is_file(),
+                    "tls.private_key (TLS_PRIVATE_KEY) must point to an existing PEM file",
+                );
+            }
+            None => check(
+                !self.server.bind.is_empty(),
+                "server.bind (BIND_ADDRESS) must contain at least one address",
+            ),
+        }
+
+        let otp_key = general_purpose::STANDARD.decode(&self.security.otp_key);
+        check(
+            otp_key.is_ok_and(|key| key.len() == 32),
+            "security.otp_key (OTP_KEY) must be a base64 encoded 256 bit key",
+        );
+        check(
+            self.security.jwt_keys.is_file(),
+            "security.jwt_keys (JWT_KEYS) must point to an existing key pair file",
+        );
+        check(
+            self.security.access_token_minutes > 0,
+            "security.access_token_minutes must be positive",
+        );
+        check(
+            self.security.refresh_token_minutes > self.security.access_token_minutes,
+            "security.refresh_token_minutes must exceed security.access_token_minutes",
+        );
+        match self.security.access_token_minutes.checked_mul(60) {
+            Some(lifetime) => check(
+                self.security.token_expiry_high_water_mark < lifetime,
+                "security.token_expiry_high_water_mark must be shorter than the access token lifetime",
+            ),
+            None => check(false, "security.access_token_minutes is too large"),
+        }
+        check(
+            self.security
+                .refresh_token_minutes
+                .checked_mul(60)
+                .is_some(),
+            "security.refresh_token_minutes is too large",
+        );
+
+        check(
+            self.activation.member_minutes > 0,
+            "activation.member_minutes (MEMBER_ACTIVATION_MINUTES) must be positive",
+        );
+        check(
+            self.activation.first_operator_minutes > 0,
+            "activation.first_operator_minutes (FIRST_OPERATOR_ACTIVATION_MINUTES) must be positive",
+        );
+        check(
+            self.search.page_size > 0,
+            "search.page_size (SEARCH_PAGE_SIZE) must be positive",
+        );
+
+        problems.extend(self.database_problems());
+        problems.extend(self.events.problems());
+        problems.extend(self.content.problems());
+        problems.extend(self.assets.problems());
+        problems.extend(self.images.problems());
+        problems.extend(self.email.problems());
+        problems.extend(self.jobs.problems());
+        problems
+    }
+
+    fn database_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.database.url.is_empty() {
+            problems.push("database.url (DATABASE_URL) must be set".to_owned());
+        }
+        problems
+    }
+
+    /// Returns the TLS settings if TLS is enabled
+    pub fn tls(&self) -> Option<&TlsSettings> {
+        self.tls.as_ref().filter(|tls| tls.enabled)
+    }
+
+    pub fn send_email_config(&self) -> SendEmailConfig {
+        SendEmailConfig::from(&self.email)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+    fn valid_table() -> toml::Table {
+        format!(
+            r#"
+            [database]
+            url = "postgres://onvp@localhost/onvp"
+
+            [security]
+            otp_key = "{otp_key}"
+            jwt_keys = "{manifest_dir}/Cargo.toml"
+
+            [assets]
+            path = "{manifest_dir}"
+
+            [email]
+            dev_mode = true
+            from = "ONVP <noreply@example.org>"
+            registration_subject = "Welcome"
+            registration_body = "Activate using {{}}"
+            smtp_relay = "localhost"
+            "#,
+            otp_key = general_purpose::STANDARD.encode([7u8; 32]),
+            manifest_dir = MANIFEST_DIR,
+        )
+        .parse()
+        .unwrap()
+    }
+
+    fn environment(variables: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let variables: BTreeMap<String, String> = variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        move |name| variables.get(name).cloned()
+    }
+
+    fn problems(table: toml::Table, variables: &[(&str, &str)]) -> Vec<String> {
+        Settings::from_table(table, environment(variables), Settings::problems).unwrap_err()
+    }
+
+    #[test]
+    fn parses_toml_and_applies_defaults() {
+        let settings =
+            Settings::from_table(valid_table(), environment(&[]), Settings::problems).unwrap();
+        assert_eq!(settings.database.url, "postgres://onvp@localhost/onvp");
+        assert!(settings.email.dev_mode);
+        assert_eq!(settings.server.bind, ServerSettings::default().bind);
+        assert_eq!(settings.search.page_size, 10);
+        assert_eq!(settings.logging.format, LogFormat::Text);
+        assert!(settings.tls().is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let mut table = valid_table();
+        table.insert("unknown".to_owned(), toml::Value::Boolean(true));
+        assert_eq!(problems(table, &[]).len(), 1);
+    }
+
+    #[test]
+    fn environment_overrides_the_configuration_file() {
+        let settings = Settings::from_table(
+            valid_table(),
+            environment(&[
+                ("BIND_ADDRESS", "127.0.0.1:8000, [::1]:8000"),
+                ("DATABASE_URL", "postgres://other@localhost/onvp"),
+                ("SEARCH_PAGE_SIZE", "25"),
+                ("EMAIL_DEV_MODE", "false"),
+                ("EMAIL_SMTP_USER", "user"),
+                ("EMAIL_SMTP_PASSWORD", "secret"),
+                ("LOG_FORMAT", "json"),
+            ]),
+            Settings::problems,
+        )
+        .unwrap();
+        let bind: Vec<String> = settings.server.bind.iter().map(|a| a.to_string()).collect();
+        assert_eq!(bind, ["127.0.0.1:8000", "[::1]:8000"]);
+        assert_eq!(settings.database.url, "postgres://other@localhost/onvp");
+        assert_eq!(settings.search.page_size, 25);
+        assert!(!settings.email.dev_mode);
+        assert_eq!(settings.logging.format, LogFormat::Json);
+    }
+
+    #[test]
+    fn reports_all_invalid_environment_values() {
+        let problems = problems(
+            valid_table(),
+            &[("SEARCH_PAGE_SIZE", "many"), ("EMAIL_DEV_MODE", "maybe")],
+        );
+        assert_eq!(
+            problems,
+            [
+                "SEARCH_PAGE_SIZE has an invalid value: many",
+                "EMAIL_DEV_MODE has an invalid value: maybe",
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregates_all_problems() {
+        let problems = problems(toml::Table::new(), &[]);
+        for expected in [
+            "database.url (DATABASE_URL) must be set",
+            "security.otp_key (OTP_KEY) must be a base64 encoded 256 bit key",
+            "security.jwt_keys (JWT_KEYS) must point to an existing key pair file",
+            "assets.path (ASSETS_PATH) must point to an existing directory",
+            "email.from (EMAIL_FROM) must be a valid mailbox",
+            "email.smtp_relay (EMAIL_SMTP_RELAY) must be set",
+        ] {
+            assert!(problems.iter().any(|p| p == expected), "{expected}");
+        }
+    }
+
+    #[test]
+    fn database_tools_only_require_the_database() {
+        let table: toml::Table = "[database]\nurl = \"postgres://onvp@localhost/onvp\""
+            .parse()
+            .unwrap();
+        assert!(
+            Settings::from_table(table.clone(), environment(&[]), Settings::database_problems)
+                .is_ok()
+        );
+        let problems = Settings::from_table(
+            toml::Table::new(),
+            environment(&[]),
+            Settings::database_problems,
+        )
+        .unwrap_err();
+        assert_eq!(problems, ["database.url (DATABASE_URL) must be set"]);
+    }
+
+    #[test]
+    fn tls_is_only_enabled_explicitly() {
+        let settings = Settings::from_table(
+            valid_table(),
+            environment(&[("TLS_CERTIFICATE", "/nonexistent/certificate.pem")]),
+            Settings::problems,
+        )
+        .unwrap();
+        assert!(settings.tls().is_none());
+
+        let problems = problems(
+            valid_table(),
+            &[
+                ("TLS_ENABLED", "true"),
+                ("TLS_CERTIFICATE", "/nonexistent/certificate.pem"),
+            ],
+        );
+        assert_eq!(
+            problems,
+            [
+                "tls.certificate (TLS_CERTIFICATE) must point to an existing PEM file",
+                "tls.private_key (TLS_PRIVATE_KEY) must point to an existing PEM file",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_overflowing_token_lifetimes() {
+        let problems = problems(
+            valid_table(),
+            &[
+                ("ACCESS_TOKEN_MINUTES", &i64::MAX.to_string()),
+                ("REFRESH_TOKEN_MINUTES", &i64::MAX.to_string()),
+            ],
+        );
+        assert!(problems.contains(&"security.access_token_minutes is too large".to_owned()));
+        assert!(problems.contains(&"security.refresh_token_minutes is too large".to_owned()));
+    }
+}
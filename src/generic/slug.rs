@@ -0,0 +1,113 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Slugs identify a page among its sibling pages in a human-readable way. The path of a page
+//! consists of the slugs of its ancestors and of the page itself, e.g. `concerts/spring-2025`.
+
+/// The maximum length of a slug, longer slugs are cut off when derived from a title
+pub const MAX_LENGTH: usize = 80;
+
+/// Derives a slug from the title, keeping lower case letters and digits, replacing accented
+/// letters by their plain counterparts and anything else by a single dash
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.to_lowercase().chars() {
+        match c {
+            'a'..='z' | '0'..='9' => slug.push(c),
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => slug.push('a'),
+            'è' | 'é' | 'ê' | 'ë' => slug.push('e'),
+            'ì' | 'í' | 'î' | 'ï' => slug.push('i'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => slug.push('o'),
+            'ù' | 'ú' | 'û' | 'ü' => slug.push('u'),
+            'ý' | 'ÿ' => slug.push('y'),
+            'ñ' => slug.push('n'),
+            'ç' => slug.push('c'),
+            _ if !slug.is_empty() && !slug.ends_with('-') => slug.push('-'),
+            _ => {}
+        }
+        if slug.len() >= MAX_LENGTH {
+            break;
+        }
+    }
+    slug.trim_end_matches('-').to_owned()
+}
+
+/// Verifies that the value is a slug as derived by [slugify]
+pub fn is_slug(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_LENGTH
+        && !value.starts_with('-')
+        && !value.ends_with('-')
+        && !value.contains("--")
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Appends the number to the slug, cutting off the slug such that the result does not exceed
+/// the maximum length
+pub fn numbered(slug: &str, number: u32) -> String {
+    let suffix = format!("-{number}");
+    let length = slug.len().min(MAX_LENGTH.saturating_sub(suffix.len()));
+    format!("{}{suffix}", slug[..length].trim_end_matches('-'))
+}
+
+/// Splits a path into its slugs, ignoring leading, trailing and repeated slashes
+pub fn segments(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_derives_readable_slugs() {
+        assert_eq!(slugify("Concert dress code"), "concert-dress-code");
+        assert_eq!(slugify("  Café & Crème brûlée! "), "cafe-creme-brulee");
+        assert_eq!(slugify("Voorjaarsconcert 2025"), "voorjaarsconcert-2025");
+        assert_eq!(slugify("!!!"), "");
+        assert!(slugify(&"a ".repeat(100)).len() <= MAX_LENGTH);
+    }
+
+    #[test]
+    fn is_slug_accepts_derived_slugs_only() {
+        assert!(is_slug("concert-dress-code"));
+        assert!(is_slug(&slugify(&"a ".repeat(100))));
+        assert!(!is_slug(""));
+        assert!(!is_slug("-concert"));
+        assert!(!is_slug("concert--code"));
+        assert!(!is_slug("Concert"));
+        assert!(!is_slug("concert/code"));
+    }
+
+    #[test]
+    fn numbered_slugs_stay_within_the_maximum_length() {
+        assert_eq!(numbered("concert", 2), "concert-2");
+        let long = slugify(&"ab ".repeat(40));
+        assert_eq!(long.len(), MAX_LENGTH);
+        // The slug is cut off right after a dash, which is left out
+        let slug = numbered(&long, 7);
+        assert_eq!(slug.len(), MAX_LENGTH - 1);
+        assert!(slug.ends_with("ab-7"));
+        assert!(is_slug(&slug), "{slug}");
+    }
+}
@@ -0,0 +1,112 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Stores the assets as files in a local directory
+
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::AssetSettings;
+use crate::generic::storage::assets::{AssetStore, StoredAsset};
+use crate::generic::Injectable;
+use actix_web::web::Data;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// The directory, within the assets directory, to which quarantined assets are moved
+const QUARANTINE_DIRECTORY: &str = ".quarantine";
+
+pub struct Implementation {
+    assets_path: PathBuf,
+}
+
+impl AssetStore for Implementation {
+    fn put(&self, asset_id: &str, data: &[u8]) -> BackendResult<()> {
+        // Written next to the asset first, such that readers never see a partial asset
+        let partial = self.assets_path.join(format!(".{asset_id}.partial"));
+        std::fs::write(&partial, data).map_err(|e| Self::error(asset_id, e))?;
+        std::fs::rename(&partial, self.path(asset_id)).map_err(|e| {
+            let _ = std::fs::remove_file(&partial);
+            Self::error(asset_id, e)
+        })
+    }
+
+    fn stream(&self, asset_id: &str) -> BackendResult<Box<dyn Read + Send>> {
+        let file = File::open(self.path(asset_id)).map_err(|e| Self::error(asset_id, e))?;
+        Ok(Box::new(file))
+    }
+
+    fn delete(&self, asset_id: &str) -> BackendResult<()> {
+        match std::fs::remove_file(self.path(asset_id)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(Self::error(asset_id, e)),
+            _ => Ok(()),
+        }
+    }
+
+    fn exists(&self, asset_id: &str) -> BackendResult<bool> {
+        Ok(self.path(asset_id).is_file())
+    }
+
+    fn list(&self) -> BackendResult<Vec<StoredAsset>> {
+        let unreadable =
+            |e: std::io::Error| BackendError::asset(format!("{}: {e}", self.assets_path.display()));
+        let mut assets = Vec::new();
+        for entry in std::fs::read_dir(&self.assets_path).map_err(unreadable)? {
+            let entry = entry.map_err(unreadable)?;
+            let asset_id = entry.file_name().to_string_lossy().into_owned();
+            let metadata = entry.metadata().map_err(unreadable)?;
+            // Hidden entries are partially written or quarantined assets
+            if asset_id.starts_with('.') || !metadata.is_file() {
+                continue;
+            }
+            assets.push(StoredAsset {
+                asset_id,
+                modified: metadata.modified().map_err(unreadable)?,
+            });
+        }
+        Ok(assets)
+    }
+
+    fn quarantine(&self, asset_id: &str) -> BackendResult<()> {
+        let directory = self.assets_path.join(QUARANTINE_DIRECTORY);
+        std::fs::create_dir_all(&directory).map_err(|e| Self::error(asset_id, e))?;
+        std::fs::rename(self.path(asset_id), directory.join(asset_id))
+            .map_err(|e| Self::error(asset_id, e))
+    }
+}
+
+impl Implementation {
+    fn path(&self, asset_id: &str) -> PathBuf {
+        crate::path_for_asset(&self.assets_path, asset_id)
+    }
+
+    fn error(asset_id: &str, error: std::io::Error) -> BackendError {
+        BackendError::asset(format!("Asset {asset_id}: {error}"))
+    }
+}
+
+impl Injectable<AssetSettings, dyn AssetStore> for Implementation {
+    fn make(settings: &AssetSettings) -> Data<dyn AssetStore> {
+        let implementation = Self {
+            assets_path: settings.path.clone(),
+        };
+        let arc: Arc<dyn AssetStore> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Storage of the assets, such as page contents, images and member pictures. The metadata of
+//! the assets is stored in the database, while their contents are stored in an asset store,
+//! identified by the asset identifier.
+
+pub mod filesystem;
+pub mod renderings;
+pub mod s3;
+pub mod variants;
+
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::{AssetBackend, AssetSettings};
+use crate::generic::Injectable;
+use actix_web::web::Data;
+use std::io::Read;
+use std::time::SystemTime;
+
+/// An asset present in the asset store
+pub struct StoredAsset {
+    pub asset_id: String,
+    pub modified: SystemTime,
+}
+
+pub trait AssetStore: Send + Sync {
+    /// Stores the asset, replacing the asset if it already exists
+    fn put(&self, asset_id: &str, data: &[u8]) -> BackendResult<()>;
+
+    /// Opens the asset for reading
+    fn stream(&self, asset_id: &str) -> BackendResult<Box<dyn Read + Send>>;
+
+    /// Reads the complete asset
+    fn get(&self, asset_id: &str) -> BackendResult<Vec<u8>> {
+        let mut data = Vec::new();
+        self.stream(asset_id)?
+            .read_to_end(&mut data)
+            .map_err(|e| BackendError::asset(format!("Asset {asset_id}: {e}")))?;
+        Ok(data)
+    }
+
+    /// Deletes the asset, deleting an asset which does not exist succeeds
+    fn delete(&self, asset_id: &str) -> BackendResult<()>;
+
+    fn exists(&self, asset_id: &str) -> BackendResult<bool>;
+
+    /// Lists all assets in the store
+    fn list(&self) -> BackendResult<Vec<StoredAsset>>;
+
+    /// Moves the asset out of the way, such that it is no longer listed or served, while it
+    /// can still be recovered manually
+    fn quarantine(&self, asset_id: &str) -> BackendResult<()>;
+}
+
+/// Creates the asset store for the configured backend
+pub fn asset_store(settings: &AssetSettings) -> Data<dyn AssetStore> {
+    match settings.backend {
+        AssetBackend::Filesystem => filesystem::Implementation::make(settings),
+        AssetBackend::S3 => s3::Implementation::make(settings),
+    }
+}
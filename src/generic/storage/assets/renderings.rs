@@ -0,0 +1,67 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Renderings of page contents, i.e. the sanitised HTML served to visitors. Renderings are
+//! stored when the content is saved, under an identifier starting with the identifier of the
+//! content asset, such that they can be cleaned up together with it.
+
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::storage::assets::AssetStore;
+use log::warn;
+
+/// Returns the identifier under which the rendering of the content asset is stored
+pub fn rendering_asset_id(asset_id: &str) -> String {
+    format!("{asset_id}.html")
+}
+
+/// Stores the rendering of the content asset
+pub fn store(asset_store: &dyn AssetStore, asset_id: &str, html: &str) -> BackendResult<()> {
+    asset_store.put(&rendering_asset_id(asset_id), html.as_bytes())
+}
+
+/// Returns the rendering of the content asset, rendering and storing it if it is missing, such
+/// as for content saved before renderings were stored
+pub fn load(
+    asset_store: &dyn AssetStore,
+    asset_id: &str,
+    render: impl FnOnce(&str) -> BackendResult<String>,
+) -> BackendResult<String> {
+    let rendering_id = rendering_asset_id(asset_id);
+    if asset_store.exists(&rendering_id)? {
+        let data = asset_store.get(&rendering_id)?;
+        return String::from_utf8(data).map_err(BackendError::byte_conversion);
+    }
+    // Content which was never saved has no rendering either
+    if !asset_store.exists(asset_id)? {
+        return Ok(String::new());
+    }
+
+    let data = asset_store.get(asset_id)?;
+    let html = render(&String::from_utf8(data).map_err(BackendError::byte_conversion)?)?;
+    // The content is rendered again on the next request if storing the rendering fails
+    if let Err(e) = asset_store.put(&rendering_id, html.as_bytes()) {
+        warn!("Unable to store rendering {rendering_id}: {e}");
+    }
+    Ok(html)
+}
+
+/// Deletes the rendering of the content asset
+pub fn delete(asset_store: &dyn AssetStore, asset_id: &str) -> BackendResult<()> {
+    asset_store.delete(&rendering_asset_id(asset_id))
+}
@@ -0,0 +1,66 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Variants of the stored images, such as thumbnails. Variants are derived on their first
+//! request and cached in the asset store, under an identifier starting with the identifier of
+//! the asset they are derived from, such that they can be cleaned up together with it.
+
+use crate::generic::imaging::{self, Variant, ACCEPTED_FORMATS, VARIANT_WIDTHS};
+use crate::generic::result::BackendResult;
+use crate::generic::storage::assets::AssetStore;
+use image::ImageFormat;
+use log::warn;
+
+/// Returns the requested variant of the asset, deriving and caching it if necessary. Images
+/// exceeding the maximum dimension are not decoded to derive a variant.
+pub fn load(
+    asset_store: &dyn AssetStore,
+    asset_id: &str,
+    format: ImageFormat,
+    variant: &Variant,
+    max_dimension: u32,
+) -> BackendResult<(ImageFormat, Vec<u8>)> {
+    let Some(width) = variant.width else {
+        return Ok((format, asset_store.get(asset_id)?));
+    };
+
+    let target_format = variant.format.unwrap_or(format);
+    let variant_id = imaging::variant_asset_id(asset_id, width, target_format);
+    if asset_store.exists(&variant_id)? {
+        return Ok((target_format, asset_store.get(&variant_id)?));
+    }
+
+    let (format, data) =
+        imaging::derive(&asset_store.get(asset_id)?, format, variant, max_dimension)?;
+    // The variant is derived again on the next request if caching it fails
+    if let Err(e) = asset_store.put(&variant_id, &data) {
+        warn!("Unable to cache variant {variant_id}: {e}");
+    }
+    Ok((format, data))
+}
+
+/// Deletes all cached variants of the asset
+pub fn delete(asset_store: &dyn AssetStore, asset_id: &str) -> BackendResult<()> {
+    for width in VARIANT_WIDTHS {
+        for format in ACCEPTED_FORMATS {
+            asset_store.delete(&imaging::variant_asset_id(asset_id, width, format))?;
+        }
+    }
+    Ok(())
+}
@@ -17,6 +17,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::generic::settings::DatabaseSettings;
 use diesel::pg::{Pg, TransactionBuilder};
 use diesel::r2d2::ConnectionManager;
 use diesel::*;
@@ -27,9 +28,8 @@ pub type DatabaseBackend = Pg;
 pub type DatabaseTransactionBuilder<'a> = TransactionBuilder<'a, DatabaseConnection>;
 
 //noinspection SpellCheckingInspection
-pub fn initialize_database_connection_pool() -> DatabaseConnectionPool {
-    let conn_spec = std::env::var("DATABASE_URL").expect("DATABASE_URL should be set");
-    let manager = ConnectionManager::<DatabaseConnection>::new(conn_spec);
+pub fn initialize_database_connection_pool(settings: &DatabaseSettings) -> DatabaseConnectionPool {
+    let manager = ConnectionManager::<DatabaseConnection>::new(&settings.url);
     r2d2::Pool::builder()
         .build(manager)
         .expect("storage URL should be a valid URL towards PostgreSQL storage")
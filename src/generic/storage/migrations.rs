@@ -0,0 +1,191 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The database migrations, embedded in the binaries, such that deployments do not require
+//! the diesel CLI. The backend refuses to start against a database with pending migrations,
+//! unless it is configured to apply them automatically.
+
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::storage::database::{DatabaseBackend, DatabaseConnection};
+use diesel::migration::MigrationSource;
+use diesel::Connection;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use log::info;
+use std::collections::HashSet;
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/postgres");
+
+/// Describes an embedded migration and whether it is applied to the database
+pub struct MigrationStatus {
+    pub version: String,
+    pub name: String,
+    pub applied: bool,
+}
+
+/// Lists all embedded migrations, in order, together with their state
+pub fn status(conn: &mut DatabaseConnection) -> BackendResult<Vec<MigrationStatus>> {
+    let applied = applied_versions(conn)?;
+    Ok(embedded()?
+        .into_iter()
+        .map(|(version, name)| MigrationStatus {
+            applied: applied.contains(&version),
+            version,
+            name,
+        })
+        .collect())
+}
+
+/// Lists the names of the migrations which are not yet applied
+pub fn pending(conn: &mut DatabaseConnection) -> BackendResult<Vec<String>> {
+    Ok(conn
+        .pending_migrations(MIGRATIONS)
+        .map_err(BackendError::migration)?
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect())
+}
+
+/// Applies all pending migrations, returning the applied versions
+pub fn run_pending(conn: &mut DatabaseConnection) -> BackendResult<Vec<String>> {
+    let versions = conn
+        .run_pending_migrations(MIGRATIONS)
+        .map_err(BackendError::migration)?;
+    Ok(versions.iter().map(|version| version.to_string()).collect())
+}
+
+/// Applies the given (embedded) migrations which are not yet applied, in order, such that the
+/// database schema matches a known set of migrations
+pub fn apply_versions(conn: &mut DatabaseConnection, versions: &[String]) -> BackendResult<()> {
+    let applied = applied_versions(conn)?;
+    let mut migrations = MigrationSource::<DatabaseBackend>::migrations(&MIGRATIONS)
+        .map_err(BackendError::migration)?;
+    migrations.sort_by_key(|migration| migration.name().version().to_string());
+    for migration in migrations {
+        let version = migration.name().version().to_string();
+        if versions.contains(&version) && !applied.contains(&version) {
+            conn.run_migration(migration.as_ref())
+                .map_err(BackendError::migration)?;
+            info!("Applied migration {version}");
+        }
+    }
+    Ok(())
+}
+
+/// Lists the versions of all applied migrations, in order
+pub fn applied(conn: &mut DatabaseConnection) -> BackendResult<Vec<String>> {
+    let mut applied: Vec<String> = applied_versions(conn)?.into_iter().collect();
+    applied.sort();
+    Ok(applied)
+}
+
+/// Reverts the applied migrations newer than the target version, the target version itself
+/// remains applied. All migrations are reverted in a single transaction.
+pub fn revert_to(conn: &mut DatabaseConnection, target: &str) -> BackendResult<Vec<String>> {
+    let target = target.replace('-', "");
+    if !applied_versions(conn)?.contains(&target) {
+        return Err(BackendError::migration(format!(
+            "Migration {target} is not applied"
+        )));
+    }
+    conn.transaction(|conn| {
+        let mut reverted = Vec::new();
+        while let Some(latest) = applied_versions(conn)?.into_iter().max() {
+            if latest == target {
+                break;
+            }
+            let version = conn
+                .revert_last_migration(MIGRATIONS)
+                .map_err(BackendError::migration)?;
+            info!("Reverted migration {version}");
+            reverted.push(version.to_string());
+        }
+        Ok(reverted)
+    })
+}
+
+/// Checks that the database schema matches the embedded migrations exactly, returning a
+/// description of each problem found
+pub fn verify(conn: &mut DatabaseConnection) -> BackendResult<Vec<String>> {
+    let applied = applied_versions(conn)?;
+    let embedded = embedded()?;
+    let known: HashSet<&String> = embedded.iter().map(|(version, _)| version).collect();
+    let latest_applied = applied.iter().max().cloned();
+
+    let mut problems: Vec<String> = applied
+        .iter()
+        .filter(|version| !known.contains(version))
+        .map(|version| format!("Migration {version} is applied, but unknown to this release"))
+        .collect();
+    for (version, name) in &embedded {
+        if applied.contains(version) {
+            continue;
+        }
+        match &latest_applied {
+            Some(latest) if version < latest => problems.push(format!(
+                "Migration {name} is pending, but older than the applied migration {latest}"
+            )),
+            _ => problems.push(format!("Migration {name} is pending")),
+        }
+    }
+    Ok(problems)
+}
+
+/// Ensures no migrations are pending before the backend starts, either by applying them, or by
+/// refusing to continue
+pub fn ensure_current(conn: &mut DatabaseConnection, auto_migrate: bool) -> BackendResult<()> {
+    let pending = pending(conn)?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    if auto_migrate {
+        for version in run_pending(conn)? {
+            info!("Applied migration {version}");
+        }
+        Ok(())
+    } else {
+        Err(BackendError::migration(format!(
+            "The database has {} pending migration(s): {}, run onvp-migrate up or enable database.auto_migrate",
+            pending.len(),
+            pending.join(", ")
+        )))
+    }
+}
+
+fn applied_versions(conn: &mut DatabaseConnection) -> BackendResult<HashSet<String>> {
+    Ok(conn
+        .applied_migrations()
+        .map_err(BackendError::migration)?
+        .iter()
+        .map(|version| version.to_string())
+        .collect())
+}
+
+fn embedded() -> BackendResult<Vec<(String, String)>> {
+    let migrations = MigrationSource::<DatabaseBackend>::migrations(&MIGRATIONS)
+        .map_err(BackendError::migration)?;
+    let mut embedded: Vec<(String, String)> = migrations
+        .iter()
+        .map(|migration| {
+            let name = migration.name();
+            (name.version().to_string(), name.to_string())
+        })
+        .collect();
+    embedded.sort();
+    Ok(embedded)
+}
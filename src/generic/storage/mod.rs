@@ -17,5 +17,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod assets;
 pub mod database;
+pub mod migrations;
 pub mod session;
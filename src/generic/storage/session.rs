@@ -33,10 +33,14 @@ use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
+/// An action which runs once the session transaction is committed
+type CommitAction = Box<dyn FnOnce()>;
+
 #[derive(Clone, FromRequest)]
 pub struct Session {
     first_run: Rc<Mutex<AtomicBool>>,
     conn: Rc<Mutex<Option<PooledConnection<ConnectionManager<DatabaseConnection>>>>>,
+    after_commit: Rc<Mutex<Vec<CommitAction>>>,
 }
 
 impl Session {
@@ -66,6 +70,18 @@ impl Session {
         }
     }
 
+    /// Defers an action until the session transaction is committed, the action is dropped when
+    /// the transaction is rolled back. Assets which are no longer referenced are deleted this
+    /// way, as the database would otherwise refer to deleted assets after a rollback.
+    pub fn after_commit<F>(&mut self, action: F)
+    where
+        F: FnOnce() + 'static,
+    {
+        if let Ok(mut actions) = self.after_commit.lock() {
+            actions.push(Box::new(action));
+        }
+    }
+
     fn build_start_transaction_query(conn: &mut DatabaseConnection) -> BackendResult<String> {
         let mut query_builder = <DatabaseBackend as Backend>::QueryBuilder::default();
         let builder: DatabaseTransactionBuilder = conn.build_transaction().deferrable();
@@ -86,6 +102,7 @@ impl Session {
                     AnsiTransactionManager::rollback_transaction(conn)?;
                 }
             }
+            self.take_commit_actions();
             Ok(())
         } else {
             Err(BackendError::bad())
@@ -93,9 +110,12 @@ impl Session {
     }
 
     pub fn commit(&mut self) -> BackendResult<()> {
-        let conn_lock = self.conn.lock();
-        let atomic_first_run_lock = self.first_run.lock();
-        if let (Ok(mut conn), Ok(atomic_first_run)) = (conn_lock, atomic_first_run_lock) {
+        {
+            let conn_lock = self.conn.lock();
+            let atomic_first_run_lock = self.first_run.lock();
+            let (Ok(mut conn), Ok(atomic_first_run)) = (conn_lock, atomic_first_run_lock) else {
+                return Err(BackendError::bad());
+            };
             if let Some(mut conn) = conn.take() {
                 let first_run = atomic_first_run.load(Ordering::Relaxed);
                 if !first_run {
@@ -104,10 +124,18 @@ impl Session {
                     AnsiTransactionManager::commit_transaction(conn)?;
                 }
             }
-            Ok(())
-        } else {
-            Err(BackendError::bad())
         }
+        for action in self.take_commit_actions() {
+            action();
+        }
+        Ok(())
+    }
+
+    fn take_commit_actions(&self) -> Vec<CommitAction> {
+        self.after_commit
+            .lock()
+            .map(|mut actions| std::mem::take(&mut *actions))
+            .unwrap_or_default()
     }
 }
 pub trait SessionManager {
@@ -125,6 +153,7 @@ impl SessionManager for DefaultSessionManagerImplementation {
         Ok(Session {
             first_run: Rc::new(Mutex::new(AtomicBool::new(true))),
             conn: Rc::new(Mutex::new(Some(conn))),
+            after_commit: Rc::new(Mutex::new(Vec::new())),
         })
     }
 }
@@ -148,6 +177,7 @@ impl SessionManager for FauxSessionManagerImplementation {
         Ok(Session {
             first_run: Rc::new(Mutex::new(AtomicBool::new(true))),
             conn: Rc::new(Mutex::new(None)),
+            after_commit: Rc::new(Mutex::new(Vec::new())),
         })
     }
 }
@@ -160,3 +190,31 @@ impl Injectable<(), dyn SessionManager> for FauxSessionManagerImplementation {
         Data::from(arc)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn commit_runs_deferred_actions() {
+        let mut session = FauxSessionManagerImplementation.prepare().unwrap();
+        let ran = Rc::new(Cell::new(false));
+        let action_ran = ran.clone();
+        session.after_commit(move || action_ran.set(true));
+        assert!(!ran.get());
+        session.commit().unwrap();
+        assert!(ran.get());
+    }
+
+    #[test]
+    fn rollback_drops_deferred_actions() {
+        let mut session = FauxSessionManagerImplementation.prepare().unwrap();
+        let ran = Rc::new(Cell::new(false));
+        let action_ran = ran.clone();
+        session.after_commit(move || action_ran.set(true));
+        session.rollback().unwrap();
+        session.commit().unwrap();
+        assert!(!ran.get());
+    }
+}
@@ -0,0 +1,391 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Validation of incoming commands. Every command implements [`Validate`], usually by
+//! composing the rules found in [`rules`] using a [`Validator`]. The [`ValidatedJson`]
+//! extractor deserializes and validates a command in one step, returning all failing
+//! fields at once to the client.
+
+use crate::generic::result::{BackendError, BackendResult, ErrorKind};
+use actix_web::dev::Payload;
+use actix_web::web::Json;
+use actix_web::{Error, FromRequest, HttpRequest};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::ops::Deref;
+use std::pin::Pin;
+
+/// Describes a single field which did not pass validation
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Implemented by all commands which can be validated before being handed to a service
+pub trait Validate {
+    fn validate(&self) -> BackendResult<()>;
+}
+
+/// Collects field errors, after which the validation can be finished, resulting in either
+/// success or a validation error containing all the collected field errors.
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an error for the field if the condition does not hold
+    pub fn check(mut self, field: &str, condition: bool, message: &str) -> Self {
+        if !condition {
+            self.errors.push(FieldError {
+                field: field.to_string(),
+                message: message.to_string(),
+            });
+        }
+        self
+    }
+
+    /// Validates a nested (sub) command, prefixing the field names of the nested errors
+    pub fn nested<V: Validate>(mut self, field: &str, value: &V) -> Self {
+        if let Err(BackendError {
+            kind: ErrorKind::Validation(errors),
+        }) = value.validate()
+        {
+            self.errors.extend(errors.into_iter().map(|e| FieldError {
+                field: format!("{field}.{}", e.field),
+                message: e.message,
+            }));
+        }
+        self
+    }
+
+    pub fn finish(self) -> BackendResult<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(BackendError::validation(self.errors))
+        }
+    }
+}
+
+/// Reusable validation rules
+pub mod rules {
+    use crate::model::primitives::EventDate;
+    use lettre::Address;
+    use std::str::FromStr;
+
+    pub fn is_not_blank(value: &str) -> bool {
+        !value.trim().is_empty()
+    }
+
+    pub fn is_email_address(value: &str) -> bool {
+        Address::from_str(value).is_ok()
+    }
+
+    /// Phone numbers in national or international notation, e.g. 0612345678 or +31612345678,
+    /// optionally grouped using spaces, dashes, dots or parentheses
+    pub fn is_phone_number(value: &str) -> bool {
+        let value = value.trim();
+        let digits = value.strip_prefix('+').unwrap_or(value);
+        let mut count = 0;
+        for c in digits.chars() {
+            match c {
+                '0'..='9' => count += 1,
+                ' ' | '-' | '.' | '(' | ')' => {}
+                _ => return false,
+            }
+        }
+        (7..=15).contains(&count)
+    }
+
+    /// Dutch postal codes, consisting of four digits and two letters, e.g. 1234AB, 1234 AB or
+    /// 1234ab
+    pub fn is_postal_code(value: &str) -> bool {
+        let normalized: Vec<u8> = value
+            .bytes()
+            .filter(|b| !b.is_ascii_whitespace())
+            .map(|b| b.to_ascii_uppercase())
+            .collect();
+        normalized.len() == 6
+            && normalized[..4].iter().all(|b| b.is_ascii_digit())
+            && normalized[4..].iter().all(|b| b.is_ascii_uppercase())
+    }
+
+    /// Only links to an article on (a language variant of) Wikipedia are accepted
+    pub fn is_wikipedia_url(value: &str) -> bool {
+        let Some(rest) = value.strip_prefix("https://") else {
+            return false;
+        };
+        let (host, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => return false,
+        };
+        let host_valid = host == "wikipedia.org"
+            || host
+                .strip_suffix(".wikipedia.org")
+                .is_some_and(|sub| sub.split('.').all(is_host_label));
+        let article_valid = path.strip_prefix("/wiki/").is_some_and(|article| {
+            !article.is_empty() && !article.chars().any(char::is_whitespace)
+        });
+        host_valid && article_valid
+    }
+
+    fn is_host_label(label: &str) -> bool {
+        !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    }
+
+    pub fn is_valid_date(value: &Option<EventDate>) -> bool {
+        match value {
+            Some(date) => date.as_validated().is_ok(),
+            None => true,
+        }
+    }
+
+    /// A date range is valid if the end date is not before the start date, and an end date
+    /// is only given together with a start date.
+    pub fn is_date_range(start: &Option<EventDate>, end: &Option<EventDate>) -> bool {
+        match (start, end) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(start), Some(end)) => match (start.as_validated(), end.as_validated()) {
+                (Ok(start), Ok(end)) => end >= start,
+                // Invalid dates are reported separately
+                _ => true,
+            },
+        }
+    }
+}
+
+/// Normalization of values which are accepted in several spellings, so that they are stored
+/// consistently. The values are expected to have passed the corresponding rule in [`rules`].
+pub mod normalize {
+    /// Removes the grouping from a phone number, e.g. 06-12345678 becomes 0612345678. The
+    /// national trunk prefix written as (0) in international numbers is dropped as well.
+    pub fn phone_number(value: &str) -> String {
+        let value = value.trim();
+        let international = value.starts_with('+');
+        let value = if international {
+            value.replace("(0)", "")
+        } else {
+            value.to_owned()
+        };
+        let digits = value.chars().filter(char::is_ascii_digit);
+        if international {
+            std::iter::once('+').chain(digits).collect()
+        } else {
+            digits.collect()
+        }
+    }
+
+    /// Removes whitespace and uppercases the letters, e.g. 1234 ab becomes 1234AB
+    pub fn postal_code(value: &str) -> String {
+        value
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    }
+}
+
+/// Extracts a JSON payload, similar to [`Json`], and validates it before handing it to the
+/// endpoint. If validation fails, a bad request is returned, enumerating the failing fields.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> ValidatedJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidatedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate + 'static,
+{
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = Json::<T>::from_request(req, payload);
+        Box::pin(async move {
+            let value = json.await?.into_inner();
+            value.validate()?;
+            Ok(ValidatedJson(value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rules::*;
+    use super::*;
+    use crate::model::primitives::EventDate;
+
+    struct Nested {
+        name: String,
+    }
+
+    impl Validate for Nested {
+        fn validate(&self) -> BackendResult<()> {
+            Validator::new()
+                .check("name", is_not_blank(&self.name), "Name is required")
+                .finish()
+        }
+    }
+
+    fn date(day: u32, month: u32, year: i32) -> Option<EventDate> {
+        Some(EventDate { day, month, year })
+    }
+
+    #[test]
+    fn validator_collects_all_errors() {
+        let nested = Nested {
+            name: " ".to_owned(),
+        };
+        let result = Validator::new()
+            .check("firstName", false, "First name is required")
+            .check("lastName", true, "Last name is required")
+            .check("emailAddress", false, "Invalid email address")
+            .nested("address", &nested)
+            .finish();
+        let Err(BackendError {
+            kind: ErrorKind::Validation(errors),
+        }) = result
+        else {
+            panic!("Expected a validation error");
+        };
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["firstName", "emailAddress", "address.name"]);
+        assert_eq!(errors[2].message, "Name is required");
+    }
+
+    #[test]
+    fn validator_succeeds_without_errors() {
+        let nested = Nested {
+            name: "Home".to_owned(),
+        };
+        let result = Validator::new()
+            .check("firstName", true, "First name is required")
+            .nested("address", &nested)
+            .finish();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn not_blank() {
+        assert!(is_not_blank("John"));
+        assert!(!is_not_blank(""));
+        assert!(!is_not_blank(" \t\n"));
+    }
+
+    #[test]
+    fn email_address() {
+        assert!(is_email_address("john.doe@example.org"));
+        assert!(!is_email_address("john.doe"));
+        assert!(!is_email_address("john doe@example.org"));
+    }
+
+    #[test]
+    fn phone_number() {
+        assert!(is_phone_number("+31612345678"));
+        assert!(is_phone_number("0612345678"));
+        assert!(is_phone_number("06-12345678"));
+        assert!(is_phone_number("010 123 45 67"));
+        assert!(is_phone_number("+31 (0)6 1234 5678"));
+        assert!(!is_phone_number(""));
+        assert!(!is_phone_number("12345"));
+        assert!(!is_phone_number("+1234567890123456"));
+        assert!(!is_phone_number("06-1234567a"));
+        assert!(!is_phone_number("06+12345678"));
+    }
+
+    #[test]
+    fn postal_code() {
+        assert!(is_postal_code("1234AB"));
+        assert!(is_postal_code("1234 AB"));
+        assert!(is_postal_code("1234ab"));
+        assert!(is_postal_code(" 1234 ab "));
+        assert!(!is_postal_code("1234"));
+        assert!(!is_postal_code("123AB"));
+        assert!(!is_postal_code("12345A"));
+        assert!(!is_postal_code("1234ABC"));
+    }
+
+    #[test]
+    fn normalized_phone_number() {
+        assert_eq!(normalize::phone_number("+31612345678"), "+31612345678");
+        assert_eq!(normalize::phone_number("06-12345678"), "0612345678");
+        assert_eq!(normalize::phone_number(" 010 123 45 67 "), "0101234567");
+        assert_eq!(
+            normalize::phone_number("+31 (0)6 1234 5678"),
+            "+31612345678"
+        );
+        assert_eq!(normalize::phone_number("(010) 123.45.67"), "0101234567");
+    }
+
+    #[test]
+    fn normalized_postal_code() {
+        assert_eq!(normalize::postal_code("1234AB"), "1234AB");
+        assert_eq!(normalize::postal_code(" 1234 ab "), "1234AB");
+    }
+
+    #[test]
+    fn wikipedia_url() {
+        assert!(is_wikipedia_url("https://en.wikipedia.org/wiki/Violin"));
+        assert!(is_wikipedia_url("https://wikipedia.org/wiki/Viool"));
+        assert!(!is_wikipedia_url("http://en.wikipedia.org/wiki/Violin"));
+        assert!(!is_wikipedia_url(
+            "https://en.wikipedia.org.example.org/wiki/Violin"
+        ));
+        assert!(!is_wikipedia_url("https://en.wikipedia.org/wiki/"));
+        assert!(!is_wikipedia_url("https://en.wikipedia.org/w/Violin"));
+    }
+
+    #[test]
+    fn valid_date() {
+        assert!(is_valid_date(&None));
+        assert!(is_valid_date(&date(29, 2, 2024)));
+        assert!(!is_valid_date(&date(29, 2, 2025)));
+        assert!(!is_valid_date(&date(1, 13, 2025)));
+    }
+
+    #[test]
+    fn date_range() {
+        assert!(is_date_range(&None, &None));
+        assert!(is_date_range(&date(1, 8, 2025), &None));
+        assert!(is_date_range(&date(1, 8, 2025), &date(1, 8, 2025)));
+        assert!(is_date_range(&date(1, 8, 2025), &date(21, 8, 2025)));
+        assert!(!is_date_range(&date(21, 8, 2025), &date(1, 8, 2025)));
+        assert!(!is_date_range(&None, &date(1, 8, 2025)));
+    }
+}
@@ -16,14 +16,20 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::commands::jobs::registry::JobRegistry;
+use crate::generic::metrics::Metrics;
+use crate::generic::settings::Settings;
+use crate::generic::storage::assets::{self, AssetStore};
 use crate::generic::storage::database::DatabaseConnectionPool;
 use crate::generic::storage::session::DefaultSessionManagerImplementation;
 use crate::generic::Injectable;
 use crate::model::interface::client::UserClaims;
 use crate::repositories::definitions::{
-    AuthorizationRepository, FacebookRepository, ImageRepository, MailTemplateRepository,
+    AbsenceRepository, AttendanceRepository, AuthorizationRepository, CalendarFeedRepository,
+    FacebookRepository, ImageRepository, JobRunRepository, MailTemplateRepository,
     MemberPictureRepository, MemberRepository, MemberRoleRepository, MusicalInstrumentRepository,
-    PageRepository, PropertiesRepository, WorkgroupRepository, WorkgroupRoleRepository,
+    PageRepository, PageRevisionRepository, PropertiesRepository, VenueRepository,
+    WorkgroupRepository, WorkgroupRoleRepository,
 };
 use crate::{repositories, services};
 use actix_jwt_auth_middleware::TokenSigner;
@@ -34,16 +40,25 @@ use jwt_compact::alg::Ed25519;
 
 pub(crate) fn inject<T>(
     pool: &DatabaseConnectionPool,
+    settings: &Data<Settings>,
+    metrics: &Data<Metrics>,
+    job_registry: &Data<JobRegistry>,
     token_signer: &Data<TokenSigner<UserClaims, Ed25519>>,
     app: App<T>,
 ) -> App<T>
 where
     T: ServiceFactory<ServiceRequest, Config = (), Error = Error, InitError = ()>,
 {
-    let repositories = ServiceDependencies::dependencies(token_signer);
+    let repositories =
+        ServiceDependencies::dependencies(settings, metrics, job_registry, token_signer);
     let session_manager = DefaultSessionManagerImplementation::make(pool);
 
-    let app = app.app_data(session_manager);
+    let app = app
+        .app_data(session_manager)
+        .app_data(Data::new(pool.clone()))
+        .app_data(settings.clone())
+        .app_data(metrics.clone())
+        .app_data(repositories.asset_store.clone());
     let app = inject_command_services(app, &repositories);
     inject_request_services(app, &repositories)
 }
@@ -64,6 +79,11 @@ where
         .app_data(musical_instrument::Implementation::make(service_deps))
         .app_data(mail_template::Implementation::make(service_deps))
         .app_data(mailing::Implementation::make(service_deps))
+        .app_data(job::Implementation::make(service_deps))
+        .app_data(calendar::Implementation::make(service_deps))
+        .app_data(venue::Implementation::make(service_deps))
+        .app_data(attendance::Implementation::make(service_deps))
+        .app_data(absence::Implementation::make(service_deps))
 }
 
 fn inject_request_services<T>(app: App<T>, service_deps: &ServiceDependencies) -> App<T>
@@ -82,6 +102,11 @@ where
         .app_data(image::Implementation::make(service_deps))
         .app_data(musical_instrument::Implementation::make(service_deps))
         .app_data(mail_template::Implementation::make(service_deps))
+        .app_data(job::Implementation::make(service_deps))
+        .app_data(calendar::Implementation::make(service_deps))
+        .app_data(venue::Implementation::make(service_deps))
+        .app_data(attendance::Implementation::make(service_deps))
+        .app_data(absence::Implementation::make(service_deps))
 }
 
 pub struct ServiceDependencies {
@@ -94,29 +119,56 @@ pub struct ServiceDependencies {
     pub authorization_repository: Data<dyn AuthorizationRepository>,
     pub facebook_repository: Data<dyn FacebookRepository>,
     pub page_repository: Data<dyn PageRepository>,
+    pub page_revision_repository: Data<dyn PageRevisionRepository>,
     pub image_repository: Data<dyn ImageRepository>,
     pub musical_instrument_repository: Data<dyn MusicalInstrumentRepository>,
     pub mail_template_repository: Data<dyn MailTemplateRepository>,
+    pub job_run_repository: Data<dyn JobRunRepository>,
+    pub calendar_feed_repository: Data<dyn CalendarFeedRepository>,
+    pub venue_repository: Data<dyn VenueRepository>,
+    pub attendance_repository: Data<dyn AttendanceRepository>,
+    pub absence_repository: Data<dyn AbsenceRepository>,
+    pub asset_store: Data<dyn AssetStore>,
     pub token_signer: Data<TokenSigner<UserClaims, Ed25519>>,
+    pub settings: Data<Settings>,
+    pub metrics: Data<Metrics>,
+    pub job_registry: Data<JobRegistry>,
 }
 
 impl ServiceDependencies {
-    fn dependencies(token_signer: &Data<TokenSigner<UserClaims, Ed25519>>) -> ServiceDependencies {
+    fn dependencies(
+        settings: &Data<Settings>,
+        metrics: &Data<Metrics>,
+        job_registry: &Data<JobRegistry>,
+        token_signer: &Data<TokenSigner<UserClaims, Ed25519>>,
+    ) -> ServiceDependencies {
         use repositories::implementation::*;
         let repositories = ServiceDependencies {
             properties_repository: properties::Implementation::make(&()),
-            member_repository: member::Implementation::make(&()),
-            workgroup_repository: workgroup::Implementation::make(&()),
+            member_repository: member::Implementation::make(settings.get_ref()),
+            workgroup_repository: workgroup::Implementation::make(settings.get_ref()),
             member_role_repository: member_role::Implementation::make(&()),
             member_picture_repository: member_picture::Implementation::make(&()),
             workgroup_role_repository: workgroup_role::Implementation::make(&()),
             authorization_repository: authorization::Implementation::make(&()),
-            facebook_repository: facebook::Implementation::make(&()),
-            page_repository: page::Implementation::make(&()),
-            image_repository: image::Implementation::make(&()),
-            musical_instrument_repository: musical_instrument::Implementation::make(&()),
+            facebook_repository: facebook::Implementation::make(settings.get_ref()),
+            page_repository: page::Implementation::make(settings.get_ref()),
+            page_revision_repository: page_revision::Implementation::make(&()),
+            image_repository: image::Implementation::make(settings.get_ref()),
+            musical_instrument_repository: musical_instrument::Implementation::make(
+                settings.get_ref(),
+            ),
             mail_template_repository: mail_template::Implementation::make(&()),
+            job_run_repository: job_run::Implementation::make(&()),
+            calendar_feed_repository: calendar_feed::Implementation::make(&()),
+            venue_repository: venue::Implementation::make(&()),
+            attendance_repository: attendance::Implementation::make(&()),
+            absence_repository: absence::Implementation::make(&()),
+            asset_store: assets::asset_store(&settings.assets),
             token_signer: token_signer.clone(),
+            settings: settings.clone(),
+            metrics: metrics.clone(),
+            job_registry: job_registry.clone(),
         };
         repositories
     }
@@ -27,34 +27,14 @@ pub mod repositories;
 pub mod schema;
 pub mod services;
 
-use crate::generic::result::BackendResult;
 use rand::distr::{Alphanumeric, SampleString};
 use rand::rng;
-use std::env::var;
-use std::path::PathBuf;
-use std::sync::LazyLock;
+use std::path::{Path, PathBuf};
 
 fn generate_asset_id() -> String {
     Alphanumeric.sample_string(&mut rng(), 16)
 }
 
-fn path_for_asset(asset_id: &str) -> BackendResult<PathBuf> {
-    let mut pb = PathBuf::new();
-    pb.push(var("ASSETS_PATH")?);
-    pb.push(asset_id);
-    Ok(pb)
+fn path_for_asset(assets_path: &Path, asset_id: &str) -> PathBuf {
+    assets_path.join(asset_id)
 }
-
-/// Returns the source code URL of the backend, this can be overwritten using the
-/// BACKEND_SOURCE_CODE_URL environment variable. This is useful if for example a fork is used
-pub static BACKEND_SOURCE_CODE_URL: LazyLock<String> = LazyLock::new(|| {
-    var("BACKEND_SOURCE_CODE_URL")
-        .unwrap_or("https://github.com/santidhammo/onvp-backend".to_owned())
-});
-
-/// Returns the source code URL of the frontend, this can be overwritten using the
-/// FRONTEND_SOURCE_CODE_URL environment variable. This is useful if for example a fork is used
-pub static FRONTEND_SOURCE_CODE_URL: LazyLock<String> = LazyLock::new(|| {
-    var("FRONTEND_SOURCE_CODE_URL")
-        .unwrap_or("https://github.com/santidhammo/onvp-frontend".to_owned())
-});
@@ -17,6 +17,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 use crate::generic::result::BackendResult;
+use crate::generic::settings::Settings;
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
 use crate::model::interface::commands::MemberRegisterCommand;
@@ -27,15 +28,16 @@ use chrono::TimeDelta;
 //use rand::distributions::{Alphanumeric, DistString};
 use rand::distr::{Alphanumeric, SampleString};
 use rand::{rng, Rng};
-use std::ops::Add;
 
 pub fn create(
     mut session: Session,
+    settings: &Settings,
     count: i32,
     activation_delta: TimeDelta,
     role: Role,
 ) -> BackendResult<()> {
-    let member_repository = crate::repositories::implementation::member::Implementation::make(&());
+    let member_repository =
+        crate::repositories::implementation::member::Implementation::make(settings);
     let member_role_repository =
         crate::repositories::implementation::member_role::Implementation::make(&());
 
@@ -61,8 +63,7 @@ pub fn create(
             },
         };
 
-        let mut extended_member = ExtendedMember::from(&command);
-        extended_member.activation_time = extended_member.creation_time.add(activation_delta);
+        let extended_member = ExtendedMember::from((&command, activation_delta));
 
         let member_id = member_repository.create_inactive(&mut session, &extended_member)?;
         member_role_repository.associate(&mut session, member_id, role)?;
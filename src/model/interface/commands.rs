@@ -16,14 +16,24 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::recurrence::Rule;
+use crate::generic::result::BackendResult;
+use crate::generic::slug;
+use crate::generic::validation::rules::{
+    is_date_range, is_email_address, is_not_blank, is_phone_number, is_postal_code, is_valid_date,
+    is_wikipedia_url,
+};
+use crate::generic::validation::{Validate, Validator};
 use crate::model::interface::commands::send_mail::MailRecipientType;
-use crate::model::interface::sub_commands::{AddressRegisterSubCommand, DetailRegisterSubCommand};
-use crate::model::primitives::{EventDate, Role, RoleClass};
+use crate::model::interface::sub_commands::{
+    AddressRegisterSubCommand, DetailRegisterSubCommand, PageMoveSubCommand,
+};
+use crate::model::primitives::{AbsenceVisibility, AttendanceStatus, EventDate, Role, RoleClass};
 use actix_web::web::Bytes;
-use image::{DynamicImage, ImageReader};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
 use serde::Deserialize;
-use std::io::Cursor;
+use std::collections::HashSet;
 use utoipa::ToSchema;
 
 /// Command to register a new work group
@@ -34,6 +44,14 @@ pub struct WorkgroupRegisterCommand {
     pub name: String,
 }
 
+impl Validate for WorkgroupRegisterCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("name", is_not_blank(&self.name), "Name is required")
+            .finish()
+    }
+}
+
 /// Command to update an existing work group
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -42,6 +60,14 @@ pub struct WorkgroupUpdateCommand {
     pub name: String,
 }
 
+impl Validate for WorkgroupUpdateCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("name", is_not_blank(&self.name), "Name is required")
+            .finish()
+    }
+}
+
 /// To register a new member, registration data is necessary. The registration data consists
 /// of the data necessary to create the member itself, alongside the member details and member
 /// address details.
@@ -52,6 +78,21 @@ pub struct MemberRegisterCommand {
     pub address_register_sub_command: AddressRegisterSubCommand,
 }
 
+impl Validate for MemberRegisterCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .nested(
+                "detailRegisterSubCommand",
+                &self.detail_register_sub_command,
+            )
+            .nested(
+                "addressRegisterSubCommand",
+                &self.address_register_sub_command,
+            )
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct FirstOperatorRegisterCommand {
@@ -59,6 +100,21 @@ pub struct FirstOperatorRegisterCommand {
     pub address_register_sub_command: AddressRegisterSubCommand,
 }
 
+impl Validate for FirstOperatorRegisterCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .nested(
+                "detailRegisterSubCommand",
+                &self.detail_register_sub_command,
+            )
+            .nested(
+                "addressRegisterSubCommand",
+                &self.address_register_sub_command,
+            )
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MemberUpdateCommand {
@@ -81,6 +137,33 @@ pub struct MemberUpdateCommand {
     pub description: Option<String>,
 }
 
+impl Validate for MemberUpdateCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check(
+                "firstName",
+                is_not_blank(&self.first_name),
+                "First name is required",
+            )
+            .check(
+                "lastName",
+                is_not_blank(&self.last_name),
+                "Last name is required",
+            )
+            .check(
+                "emailAddress",
+                is_email_address(&self.email_address),
+                "Not a valid email address",
+            )
+            .check(
+                "phoneNumber",
+                is_phone_number(&self.phone_number),
+                "Not a valid phone number",
+            )
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MemberUpdatePrivacyInfoSharingCommand {
@@ -88,6 +171,12 @@ pub struct MemberUpdatePrivacyInfoSharingCommand {
     pub allow: bool,
 }
 
+impl Validate for MemberUpdatePrivacyInfoSharingCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MemberUpdateAddressCommand {
@@ -107,6 +196,29 @@ pub struct MemberUpdateAddressCommand {
     pub domicile: String,
 }
 
+impl Validate for MemberUpdateAddressCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("street", is_not_blank(&self.street), "Street is required")
+            .check(
+                "houseNumber",
+                self.house_number > 0,
+                "House number must be positive",
+            )
+            .check(
+                "postalCode",
+                is_postal_code(&self.postal_code),
+                "Not a valid postal code",
+            )
+            .check(
+                "domicile",
+                is_not_blank(&self.domicile),
+                "Domicile is required",
+            )
+            .finish()
+    }
+}
+
 /// Command to associate a member to a work group
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -118,6 +230,12 @@ pub struct AssociateMemberToWorkgroupCommand {
     pub workgroup_id: i32,
 }
 
+impl Validate for AssociateMemberToWorkgroupCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Ok(())
+    }
+}
+
 /// Command to dissociate a member from a work group
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -129,6 +247,12 @@ pub struct DissociateMemberFromWorkgroupCommand {
     pub workgroup_id: i32,
 }
 
+impl Validate for DissociateMemberFromWorkgroupCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Ok(())
+    }
+}
+
 /// Associates a class with a given identifier to a given role
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -144,6 +268,12 @@ pub struct AssociateRoleCommand {
     pub class: RoleClass,
 }
 
+impl Validate for AssociateRoleCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Ok(())
+    }
+}
+
 /// Dissociates a class with a given identifier from a given role
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -159,6 +289,12 @@ pub struct DissociateRoleCommand {
     pub class: RoleClass,
 }
 
+impl Validate for DissociateRoleCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MemberActivationCommand {
@@ -168,21 +304,29 @@ pub struct MemberActivationCommand {
     pub token: String,
 }
 
+impl Validate for MemberActivationCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check(
+                "activationString",
+                is_not_blank(&self.activation_string),
+                "Activation string is required",
+            )
+            .check("token", is_not_blank(&self.token), "Token is required")
+            .finish()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct MemberImageUploadCommand {
-    pub dynamic_image: DynamicImage,
+    pub data: Bytes,
 }
 
-impl TryFrom<&Bytes> for MemberImageUploadCommand {
-    type Error = BackendError;
-
-    /// Attempts to convert the data to an image
-    fn try_from(value: &Bytes) -> BackendResult<Self> {
-        Ok(Self {
-            dynamic_image: ImageReader::new(Cursor::new(value))
-                .with_guessed_format()?
-                .decode()?,
-        })
+impl Validate for MemberImageUploadCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("data", !self.data.is_empty(), "Image data is required")
+            .finish()
     }
 }
 
@@ -197,6 +341,29 @@ pub struct CreatePageCommand {
     pub end_event_date: Option<EventDate>,
 }
 
+impl Validate for CreatePageCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("title", is_not_blank(&self.title), "Title is required")
+            .check(
+                "eventDate",
+                is_valid_date(&self.event_date),
+                "Not a valid date",
+            )
+            .check(
+                "endEventDate",
+                is_valid_date(&self.end_event_date),
+                "Not a valid date",
+            )
+            .check(
+                "endEventDate",
+                is_date_range(&self.event_date, &self.end_event_date),
+                "End date must not be before the event date",
+            )
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdatePageCommand {
@@ -206,12 +373,263 @@ pub struct UpdatePageCommand {
     pub event_date: Option<EventDate>,
 
     pub end_event_date: Option<EventDate>,
+
+    /// Identifies the page in its path, derived from the title if the title changes and no
+    /// slug is given
+    #[serde(default)]
+    #[schema(example = "foo")]
+    pub slug: Option<String>,
+}
+
+impl Validate for UpdatePageCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("title", is_not_blank(&self.title), "Title is required")
+            .check(
+                "slug",
+                self.slug.as_deref().is_none_or(slug::is_slug),
+                "Only lower case letters, digits and single dashes are allowed",
+            )
+            .check(
+                "eventDate",
+                is_valid_date(&self.event_date),
+                "Not a valid date",
+            )
+            .check(
+                "endEventDate",
+                is_valid_date(&self.end_event_date),
+                "Not a valid date",
+            )
+            .check(
+                "endEventDate",
+                is_date_range(&self.event_date, &self.end_event_date),
+                "End date must not be before the event date",
+            )
+            .finish()
+    }
+}
+
+/// Command to set the times, venue and recurrence of an event page
+#[derive(Deserialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SetPageEventCommand {
+    /// The start of the first occurrence, in the local time of the time zone
+    #[schema(example = "2025-09-02T19:30:00")]
+    pub starts_at: NaiveDateTime,
+
+    /// The end of the first occurrence, in the local time of the time zone
+    #[schema(example = "2025-09-02T22:00:00")]
+    pub ends_at: NaiveDateTime,
+
+    #[schema(example = "Europe/Amsterdam")]
+    pub time_zone: String,
+
+    #[serde(default)]
+    pub venue_id: Option<i32>,
+
+    /// Makes the event recur, as an RRULE of iCalendar (RFC 5545). The frequency, interval,
+    /// count, end and the week days of weekly rules are supported
+    #[serde(default)]
+    #[schema(example = "FREQ=WEEKLY;BYDAY=TU;UNTIL=20251223")]
+    pub recurrence_rule: Option<String>,
+
+    /// The days on which a recurring event does not take place
+    #[serde(default)]
+    pub exceptions: Vec<NaiveDate>,
+}
+
+impl Validate for SetPageEventCommand {
+    fn validate(&self) -> BackendResult<()> {
+        let rule = self.recurrence_rule.as_deref().map(str::parse::<Rule>);
+        let rule_problem = match &rule {
+            Some(Err(problem)) => Some(problem.as_str()),
+            _ => None,
+        };
+        Validator::new()
+            .check(
+                "endsAt",
+                self.ends_at > self.starts_at,
+                "The event must end after it starts",
+            )
+            .check(
+                "timeZone",
+                self.time_zone.parse::<Tz>().is_ok(),
+                "Not a known time zone",
+            )
+            .check(
+                "recurrenceRule",
+                rule_problem.is_none(),
+                rule_problem.unwrap_or_default(),
+            )
+            .check(
+                "recurrenceRule",
+                match &rule {
+                    Some(Ok(rule)) => rule.includes_start(self.starts_at.date()),
+                    _ => true,
+                },
+                "The week days must include the day on which the event starts",
+            )
+            .finish()
+    }
+}
+
+/// The response of a member to an occurrence of an event
+#[derive(Deserialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterAttendanceCommand {
+    pub status: AttendanceStatus,
+
+    /// Explains the response, e.g. arriving late
+    #[serde(default)]
+    #[schema(example = "Arriving half an hour late")]
+    pub note: Option<String>,
+}
+
+impl Validate for RegisterAttendanceCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check(
+                "note",
+                self.note
+                    .as_ref()
+                    .is_none_or(|note| note.chars().count() <= MAX_ATTENDANCE_NOTE_LENGTH),
+                "The note is too long",
+            )
+            .finish()
+    }
+}
+
+/// The maximum amount of characters of the note of an attendance
+const MAX_ATTENDANCE_NOTE_LENGTH: usize = 500;
+
+/// Registers a period in which the member is away, both dates are included
+#[derive(Deserialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterAbsenceCommand {
+    #[schema(example = "2025-08-01")]
+    pub start_date: NaiveDate,
+
+    #[schema(example = "2025-08-21")]
+    pub end_date: NaiveDate,
+
+    #[serde(default)]
+    #[schema(example = "Holiday")]
+    pub reason: Option<String>,
+
+    pub visibility: AbsenceVisibility,
+}
+
+impl Validate for RegisterAbsenceCommand {
+    fn validate(&self) -> BackendResult<()> {
+        validate_absence(self.start_date, self.end_date, &self.reason)
+    }
 }
 
+#[derive(Deserialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAbsenceCommand {
+    #[schema(example = "2025-08-01")]
+    pub start_date: NaiveDate,
+
+    #[schema(example = "2025-08-21")]
+    pub end_date: NaiveDate,
+
+    #[serde(default)]
+    #[schema(example = "Holiday")]
+    pub reason: Option<String>,
+
+    pub visibility: AbsenceVisibility,
+}
+
+impl Validate for UpdateAbsenceCommand {
+    fn validate(&self) -> BackendResult<()> {
+        validate_absence(self.start_date, self.end_date, &self.reason)
+    }
+}
+
+fn validate_absence(
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    reason: &Option<String>,
+) -> BackendResult<()> {
+    Validator::new()
+        .check(
+            "endDate",
+            start_date <= end_date,
+            "End date must not be before the start date",
+        )
+        .check(
+            "reason",
+            reason
+                .as_ref()
+                .is_none_or(|reason| reason.chars().count() <= MAX_ABSENCE_REASON_LENGTH),
+            "The reason is too long",
+        )
+        .finish()
+}
+
+/// The maximum amount of characters of the reason of an absence
+const MAX_ABSENCE_REASON_LENGTH: usize = 500;
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct PublishPageCommand {
     pub roles: Vec<Role>,
+
+    /// The moment from which the page is visible, immediately if not set
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>,
+
+    /// The moment from which the page is no longer visible, never if not set
+    #[serde(default)]
+    pub unpublish_at: Option<DateTime<Utc>>,
+}
+
+impl Validate for PublishPageCommand {
+    fn validate(&self) -> BackendResult<()> {
+        let window_is_valid = match (self.publish_at, self.unpublish_at) {
+            (Some(publish_at), Some(unpublish_at)) => publish_at < unpublish_at,
+            _ => true,
+        };
+        Validator::new()
+            .check(
+                "unpublishAt",
+                window_is_valid,
+                "The page must be unpublished after it is published",
+            )
+            .finish()
+    }
+}
+
+/// Applies a batch of moves to the page tree at once, either all moves are applied or none
+#[derive(Deserialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePageTreeCommand {
+    pub moves: Vec<PageMoveSubCommand>,
+}
+
+impl Validate for UpdatePageTreeCommand {
+    fn validate(&self) -> BackendResult<()> {
+        let mut page_ids = HashSet::new();
+        let unique = self
+            .moves
+            .iter()
+            .all(|page_move| page_ids.insert(page_move.page_id));
+        let validator = Validator::new()
+            .check(
+                "moves",
+                !self.moves.is_empty(),
+                "At least one move is required",
+            )
+            .check("moves", unique, "A page can only be moved once");
+        self.moves
+            .iter()
+            .enumerate()
+            .fold(validator, |validator, (index, page_move)| {
+                validator.nested(&format!("moves[{index}]"), page_move)
+            })
+            .finish()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -220,12 +638,27 @@ pub struct ImageUploadCommand {
     pub data: Bytes,
 }
 
+impl Validate for ImageUploadCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("title", is_not_blank(&self.title), "Title is required")
+            .check("data", !self.data.is_empty(), "Image data is required")
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct PublishImageCommand {
     pub roles: Vec<Role>,
 }
 
+impl Validate for PublishImageCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RegisterMusicalInstrumentCommand {
@@ -235,6 +668,22 @@ pub struct RegisterMusicalInstrumentCommand {
     pub wikipedia_url: Option<String>,
 }
 
+impl Validate for RegisterMusicalInstrumentCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("name", is_not_blank(&self.name), "Name is required")
+            .check(
+                "wikipediaUrl",
+                self.wikipedia_url
+                    .as_deref()
+                    .map(is_wikipedia_url)
+                    .unwrap_or(true),
+                "Not a valid Wikipedia article URL",
+            )
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateMusicalInstrumentCommand {
@@ -244,6 +693,58 @@ pub struct UpdateMusicalInstrumentCommand {
     pub wikipedia_url: Option<String>,
 }
 
+impl Validate for UpdateMusicalInstrumentCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("name", is_not_blank(&self.name), "Name is required")
+            .check(
+                "wikipediaUrl",
+                self.wikipedia_url
+                    .as_deref()
+                    .map(is_wikipedia_url)
+                    .unwrap_or(true),
+                "Not a valid Wikipedia article URL",
+            )
+            .finish()
+    }
+}
+
+#[derive(Deserialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterVenueCommand {
+    #[schema(example = "Concert hall")]
+    pub name: String,
+
+    #[schema(example = "Main street 1, Amsterdam")]
+    pub address: Option<String>,
+}
+
+impl Validate for RegisterVenueCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("name", is_not_blank(&self.name), "Name is required")
+            .finish()
+    }
+}
+
+#[derive(Deserialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateVenueCommand {
+    #[schema(example = "Concert hall")]
+    pub name: String,
+
+    #[schema(example = "Main street 1, Amsterdam")]
+    pub address: Option<String>,
+}
+
+impl Validate for UpdateVenueCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("name", is_not_blank(&self.name), "Name is required")
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateMailTemplateCommand {
@@ -254,6 +755,15 @@ pub struct CreateMailTemplateCommand {
     pub body: String,
 }
 
+impl Validate for CreateMailTemplateCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("name", is_not_blank(&self.name), "Name is required")
+            .check("body", is_not_blank(&self.body), "Body is required")
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateMailTemplateCommand {
@@ -261,6 +771,14 @@ pub struct UpdateMailTemplateCommand {
     pub body: String,
 }
 
+impl Validate for UpdateMailTemplateCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("body", is_not_blank(&self.body), "Body is required")
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SendMailCommand {
@@ -275,6 +793,23 @@ pub struct SendMailCommand {
 
     #[schema(example = 1)]
     pub recipient_id: i32,
+
+    /// Leaves out the recipients who registered an absence including this day
+    #[serde(default)]
+    #[schema(example = "2025-08-12")]
+    pub skip_absent_on: Option<NaiveDate>,
+}
+
+impl Validate for SendMailCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check(
+                "subject",
+                is_not_blank(&self.subject),
+                "Subject is required",
+            )
+            .finish()
+    }
 }
 
 pub mod send_mail {
@@ -289,3 +824,43 @@ pub mod send_mail {
         MusicalInstrument,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic::result::{BackendError, ErrorKind};
+
+    fn invalid_fields(result: BackendResult<()>) -> Vec<String> {
+        match result {
+            Ok(()) => vec![],
+            Err(BackendError {
+                kind: ErrorKind::Validation(errors),
+            }) => errors.into_iter().map(|error| error.field).collect(),
+            Err(e) => panic!("Expected a validation error, got {e}"),
+        }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn absences_end_on_or_after_their_start() {
+        let single_day = validate_absence(date("2025-08-01"), date("2025-08-01"), &None);
+        assert!(invalid_fields(single_day).is_empty());
+        let period = validate_absence(date("2025-08-01"), date("2025-08-21"), &None);
+        assert!(invalid_fields(period).is_empty());
+        let reversed = validate_absence(date("2025-08-21"), date("2025-08-01"), &None);
+        assert_eq!(invalid_fields(reversed), ["endDate"]);
+    }
+
+    #[test]
+    fn absence_reasons_are_limited_in_characters() {
+        let longest = Some("é".repeat(MAX_ABSENCE_REASON_LENGTH));
+        let result = validate_absence(date("2025-08-01"), date("2025-08-01"), &longest);
+        assert!(invalid_fields(result).is_empty());
+        let too_long = Some("a".repeat(MAX_ABSENCE_REASON_LENGTH + 1));
+        let result = validate_absence(date("2025-08-01"), date("2025-08-01"), &too_long);
+        assert_eq!(invalid_fields(result), ["reason"]);
+    }
+}
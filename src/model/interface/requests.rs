@@ -16,6 +16,8 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::imaging::VARIANT_WIDTHS;
+use crate::model::primitives::ContentFormat;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -27,3 +29,85 @@ pub struct AuthorizationRequest {
     #[schema(example = "123456")]
     pub token: String,
 }
+
+/// The named sizes of the image variants
+#[derive(Deserialize, ToSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageSize {
+    /// 160 pixels wide, e.g. for menus and the facebook
+    Thumbnail,
+    /// 480 pixels wide
+    Small,
+    /// 1024 pixels wide
+    Medium,
+}
+
+impl ImageSize {
+    pub fn width(self) -> u32 {
+        match self {
+            ImageSize::Thumbnail => VARIANT_WIDTHS[0],
+            ImageSize::Small => VARIANT_WIDTHS[1],
+            ImageSize::Medium => VARIANT_WIDTHS[2],
+        }
+    }
+}
+
+/// Selects a width-limited variant of an image, either using the maximum width, which is
+/// rounded up to the nearest variant width, or using a named size. Without either, or if the
+/// width exceeds the widest variant, the original image is selected.
+#[derive(Deserialize, ToSchema, Clone, Debug, Default)]
+pub struct ImageVariantParams {
+    /// The maximum width of the image in pixels
+    pub w: Option<u32>,
+
+    /// The named size of the image
+    pub size: Option<ImageSize>,
+}
+
+impl ImageVariantParams {
+    /// Returns the width of the selected variant, if any
+    pub fn width(&self) -> Option<u32> {
+        match (self.size, self.w) {
+            (Some(size), _) => Some(size.width()),
+            (None, Some(w)) => VARIANT_WIDTHS.into_iter().find(|width| *width >= w),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Selects the format in which saved page content is written
+#[derive(Deserialize, ToSchema, Clone, Debug, Default)]
+pub struct ContentParams {
+    /// The format of the content, the format of the previously saved content if not set
+    pub format: Option<ContentFormat>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn width(w: Option<u32>, size: Option<ImageSize>) -> Option<u32> {
+        ImageVariantParams { w, size }.width()
+    }
+
+    #[test]
+    fn width_is_rounded_up_to_a_variant_width() {
+        assert_eq!(width(Some(1), None), Some(160));
+        assert_eq!(width(Some(160), None), Some(160));
+        assert_eq!(width(Some(161), None), Some(480));
+        assert_eq!(width(Some(1024), None), Some(1024));
+    }
+
+    #[test]
+    fn width_beyond_the_widest_variant_selects_the_original() {
+        assert_eq!(width(Some(1025), None), None);
+        assert_eq!(width(None, None), None);
+    }
+
+    #[test]
+    fn named_size_takes_precedence() {
+        assert_eq!(width(None, Some(ImageSize::Thumbnail)), Some(160));
+        assert_eq!(width(Some(2000), Some(ImageSize::Small)), Some(480));
+        assert_eq!(width(Some(1), Some(ImageSize::Medium)), Some(1024));
+    }
+}
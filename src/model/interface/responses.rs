@@ -16,20 +16,29 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::OTP_CIPHER;
+use crate::commands::jobs::registry::ScheduledJob;
+use crate::generic::rendering;
 use crate::generic::result::{BackendError, BackendResult};
-use crate::model::primitives::{EventDate, Role};
-use crate::model::storage::entities::{Image, MailTemplate, MusicalInstrument, Page, Workgroup};
-use crate::model::storage::extended_entities::{ExtendedMember, FacebookMember};
+use crate::model::primitives::{
+    AbsenceVisibility, AttendanceStatus, ContentFormat, EventDate, Role,
+};
+use crate::model::storage::entities::{
+    CalendarFeed, EventAttendance, Image, JobRun, MailTemplate, MemberAbsence, MemberDetail,
+    MusicalInstrument, Page, PageEvent, PageRevision, Venue, Workgroup,
+};
+use crate::model::storage::extended_entities::{
+    AbsentMember, Attendee, EventOccurrence, ExtendedMember, FacebookMember,
+};
 use actix_web::cookie::Cookie;
 use actix_web::http::header::ContentType;
 use aes_gcm::aead::consts::U12;
 use aes_gcm::aead::generic_array::GenericArray;
 use aes_gcm::aead::Aead;
+use aes_gcm::Aes256Gcm;
 use base64::engine::general_purpose;
 use base64::Engine;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use serde::Serialize;
-use std::ops::Deref;
 use totp_rs::{Algorithm, Secret, TOTP};
 use utoipa::ToSchema;
 
@@ -71,6 +80,10 @@ pub struct MemberResponse {
 
     #[schema(example = "Description of this member")]
     pub description: Option<String>,
+
+    /// The version token of the member, to be sent as `If-Match` when updating the member
+    #[schema(example = "FOOBAR")]
+    pub etag: String,
 }
 
 /// Converts an Extended Member into a Member Response used by the associated services
@@ -94,24 +107,20 @@ impl From<&ExtendedMember> for MemberResponse {
             nonce: value.nonce.clone(),
             activation_string: value.activation_string.clone(),
             description: value.description.clone(),
+            etag: value.etag.clone(),
         }
     }
 }
 
-/// Attempts to generate a TOTP (one-time password)
-impl TryInto<TOTP> for MemberResponse {
-    type Error = BackendError;
-
-    fn try_into(self) -> BackendResult<TOTP> {
+impl MemberResponse {
+    /// Attempts to generate a TOTP (one-time password)
+    pub fn totp(&self, otp_cipher: &Aes256Gcm) -> BackendResult<TOTP> {
         let nonce = self.decoded_nonce()?;
         let activation_bytes = self.activation_string.as_bytes();
-        let otp_cipher = OTP_CIPHER.deref();
         let cipher_text = otp_cipher.encrypt(&nonce, activation_bytes)?;
         self.generate_totp(cipher_text)
     }
-}
 
-impl MemberResponse {
     pub fn full_name(&self) -> String {
         format!("{} {}", self.first_name, self.last_name)
             .trim()
@@ -213,6 +222,10 @@ pub struct WorkgroupResponse {
 
     #[schema(example = "Orchestra Committee")]
     pub name: String,
+
+    /// The version token of the workgroup, to be sent as `If-Match` when updating the workgroup
+    #[schema(example = "FOOBAR")]
+    pub etag: String,
 }
 
 impl From<&Workgroup> for WorkgroupResponse {
@@ -220,6 +233,7 @@ impl From<&Workgroup> for WorkgroupResponse {
         Self {
             id: value.id,
             name: value.name.to_string(),
+            etag: value.etag.clone(),
         }
     }
 }
@@ -318,6 +332,10 @@ pub struct ExtendedPageResponse {
     #[schema(example = "Foo")]
     title: String,
 
+    /// Identifies the page among its sibling pages, as used in the path of the page
+    #[schema(example = "foo")]
+    slug: String,
+
     #[schema(example = 0)]
     order_number: i32,
 
@@ -328,6 +346,22 @@ pub struct ExtendedPageResponse {
     roles: Vec<Role>,
 
     end_event_date: Option<EventDate>,
+
+    /// The version token of the page, to be sent as `If-Match` when updating the page
+    #[schema(example = "FOOBAR")]
+    etag: String,
+
+    /// Set if the content has changes which are not published yet
+    draft: bool,
+
+    /// The moment from which the page is visible, immediately if not set
+    publish_at: Option<DateTime<Utc>>,
+
+    /// The moment from which the page is no longer visible, never if not set
+    unpublish_at: Option<DateTime<Utc>>,
+
+    /// The format of the latest saved content, as served by the draft content
+    content_format: ContentFormat,
 }
 
 impl From<(&Page, &Vec<Role>)> for ExtendedPageResponse {
@@ -335,11 +369,17 @@ impl From<(&Page, &Vec<Role>)> for ExtendedPageResponse {
         Self {
             id: page.id,
             title: page.title.clone(),
+            slug: page.slug.clone(),
             event_date: page.event_date.map(|e| EventDate::from(&e)),
             roles: roles.clone(),
             parent_id: page.parent_id,
             order_number: page.order_number,
             end_event_date: page.end_event_date.map(|e| EventDate::from(&e)),
+            etag: page.etag.clone(),
+            draft: page.draft_content_asset.is_some(),
+            publish_at: page.publish_at.map(|moment| moment.and_utc()),
+            unpublish_at: page.unpublish_at.map(|moment| moment.and_utc()),
+            content_format: page.latest_content_format(),
         }
     }
 }
@@ -353,6 +393,10 @@ pub struct PageResponse {
     #[schema(example = "Foo")]
     title: String,
 
+    /// Identifies the page among its sibling pages, as used in the path of the page
+    #[schema(example = "foo")]
+    slug: String,
+
     #[schema(example = 0)]
     order_number: i32,
 
@@ -369,6 +413,7 @@ impl From<&Page> for PageResponse {
             id: value.id,
             parent_id: value.parent_id,
             title: value.title.clone(),
+            slug: value.slug.clone(),
             order_number: value.order_number,
             event_date: value.event_date.map(|e| EventDate::from(&e)),
             end_event_date: value.end_event_date.map(|e| EventDate::from(&e)),
@@ -376,6 +421,215 @@ impl From<&Page> for PageResponse {
     }
 }
 
+/// A page within the page tree, together with its sub pages
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PageTreeResponse {
+    #[serde(flatten)]
+    page: PageResponse,
+
+    /// The sub pages, ordered by their order number
+    #[schema(no_recursion)]
+    children: Vec<PageTreeResponse>,
+}
+
+impl PageTreeResponse {
+    /// Builds the trees below the parent page, or the main menu pages if there is no parent.
+    /// Pages of which the parent page is not among the given pages are left out, together with
+    /// their sub pages.
+    pub fn build(pages: &[Page], parent_id: Option<i32>) -> Vec<Self> {
+        pages
+            .iter()
+            .filter(|page| page.parent_id.filter(|id| *id != 0) == parent_id)
+            .map(|page| Self {
+                page: PageResponse::from(page),
+                children: Self::build(pages, Some(page.id)),
+            })
+            .collect()
+    }
+}
+
+/// An occurrence of an event, recurring events occur once for each time they take place
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EventResponse {
+    /// The page of the event, of which the event dates are the days of this occurrence
+    #[serde(flatten)]
+    page: PageResponse,
+
+    /// The start of the occurrence, not set for events lasting whole days
+    starts_at: Option<DateTime<Utc>>,
+
+    /// The end of the occurrence, not set for events lasting whole days
+    ends_at: Option<DateTime<Utc>>,
+
+    /// The time zone in which the event takes place
+    #[schema(example = "Europe/Amsterdam")]
+    time_zone: Option<String>,
+
+    venue: Option<VenueResponse>,
+
+    /// Whether the occurrence is one of many of a recurring event
+    recurring: bool,
+
+    /// The members who are away during the occurrence, as far as the caller may see them
+    absent_members: Vec<AbsentMemberResponse>,
+}
+
+impl From<(&EventOccurrence, &[AbsentMember])> for EventResponse {
+    /// Reports the absences which include one of the days of the occurrence
+    fn from((value, absences): (&EventOccurrence, &[AbsentMember])) -> Self {
+        let times = value.times.as_ref();
+        let absent_members = match value.page.event_date {
+            Some(start_date) => {
+                let end_date = value.page.end_event_date.unwrap_or(start_date);
+                absences
+                    .iter()
+                    .filter(|absent| absent.absence.overlaps(start_date, end_date))
+                    .map(AbsentMemberResponse::from)
+                    .collect()
+            }
+            None => vec![],
+        };
+        Self {
+            page: PageResponse::from(&value.page),
+            starts_at: times.map(|times| times.starts_at.and_utc()),
+            ends_at: times.map(|times| times.ends_at.and_utc()),
+            time_zone: times.map(|times| times.time_zone.clone()),
+            venue: times
+                .and_then(|times| times.venue.as_ref())
+                .map(VenueResponse::from),
+            recurring: times.is_some_and(|times| times.recurring),
+            absent_members,
+        }
+    }
+}
+
+/// The times, venue and recurrence of an event page
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PageEventResponse {
+    /// The start of the first occurrence, in the local time of the time zone
+    #[schema(example = "2025-09-02T19:30:00")]
+    starts_at: NaiveDateTime,
+
+    /// The end of the first occurrence, in the local time of the time zone
+    #[schema(example = "2025-09-02T22:00:00")]
+    ends_at: NaiveDateTime,
+
+    #[schema(example = "Europe/Amsterdam")]
+    time_zone: String,
+
+    venue_id: Option<i32>,
+
+    #[schema(example = "FREQ=WEEKLY;BYDAY=TU;UNTIL=20251223")]
+    recurrence_rule: Option<String>,
+
+    /// The days on which the recurring event does not take place
+    exceptions: Vec<NaiveDate>,
+}
+
+impl From<(&PageEvent, &Vec<NaiveDate>)> for PageEventResponse {
+    fn from((event, exceptions): (&PageEvent, &Vec<NaiveDate>)) -> Self {
+        Self {
+            starts_at: event.starts_at,
+            ends_at: event.ends_at,
+            time_zone: event.time_zone.clone(),
+            venue_id: event.venue_id,
+            recurrence_rule: event.recurrence_rule.clone(),
+            exceptions: exceptions.clone(),
+        }
+    }
+}
+
+/// A page found by searching, ranked by its relevance to the search term
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PageSearchResponse {
+    #[serde(flatten)]
+    page: PageResponse,
+
+    /// The relevance of the page, higher ranked pages are returned first
+    #[schema(example = 0.6)]
+    rank: f32,
+
+    /// Fragments of the content matching the search term, as HTML in which the matching words
+    /// are marked using `<mark>`
+    #[schema(example = "The <mark>dress</mark> <mark>code</mark> for the concert is black")]
+    snippet: String,
+}
+
+impl From<&(Page, f32, String)> for PageSearchResponse {
+    fn from((page, rank, snippet): &(Page, f32, String)) -> Self {
+        Self {
+            page: PageResponse::from(page),
+            rank: *rank,
+            snippet: rendering::highlight(snippet),
+        }
+    }
+}
+
+/// A revision of the content of a page
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PageRevisionResponse {
+    #[schema(example = 1)]
+    id: i32,
+
+    #[schema(example = 1)]
+    page_id: i32,
+
+    /// The full name of the member who saved the revision, unset if the member is no longer
+    /// registered
+    #[schema(example = "John Doe")]
+    author: Option<String>,
+
+    creation_time: DateTime<Utc>,
+
+    /// The title of the page at the moment the revision was saved
+    #[schema(example = "Foo")]
+    title: String,
+}
+
+impl From<(&PageRevision, &Option<MemberDetail>)> for PageRevisionResponse {
+    fn from((revision, author): (&PageRevision, &Option<MemberDetail>)) -> Self {
+        Self {
+            id: revision.id,
+            page_id: revision.page_id,
+            author: author.as_ref().map(|author| {
+                format!("{} {}", author.first_name, author.last_name)
+                    .trim()
+                    .to_string()
+            }),
+            creation_time: revision.creation_time.and_utc(),
+            title: revision.title.clone(),
+        }
+    }
+}
+
+/// A revision of the content of a page, including the content itself
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtendedPageRevisionResponse {
+    #[serde(flatten)]
+    revision: PageRevisionResponse,
+
+    #[schema(example = "Lorem ipsum dolor sit amet")]
+    content: String,
+
+    content_format: ContentFormat,
+}
+
+impl From<(&PageRevision, &Option<MemberDetail>)> for ExtendedPageRevisionResponse {
+    fn from((revision, author): (&PageRevision, &Option<MemberDetail>)) -> Self {
+        Self {
+            revision: PageRevisionResponse::from((revision, author)),
+            content: revision.content.clone(),
+            content_format: revision.content_format,
+        }
+    }
+}
+
 /// Image response containing extended metadata of the image
 #[derive(Serialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -389,6 +643,9 @@ pub struct ImageMetaDataResponse {
     /// The asset name of the image
     pub asset: String,
 
+    /// The format of the image, e.g. png or jpeg
+    pub format: Option<String>,
+
     /// The roles belonging to the image
     pub roles: Vec<Role>,
 }
@@ -399,6 +656,7 @@ impl From<(&Image, &Vec<Role>)> for ImageMetaDataResponse {
             id: image.id,
             title: image.title.clone(),
             asset: image.asset.clone(),
+            format: image.format.clone(),
             roles: roles.clone(),
         }
     }
@@ -414,6 +672,10 @@ pub struct MusicalInstrumentResponse {
     name: String,
 
     wikipedia_url: Option<String>,
+
+    /// The version token of the musical instrument, to be sent as `If-Match` when updating it
+    #[schema(example = "FOOBAR")]
+    etag: String,
 }
 
 impl From<&MusicalInstrument> for MusicalInstrumentResponse {
@@ -422,6 +684,36 @@ impl From<&MusicalInstrument> for MusicalInstrumentResponse {
             id: value.id,
             name: value.name.clone(),
             wikipedia_url: value.wikipedia_url.clone(),
+            etag: value.etag.clone(),
+        }
+    }
+}
+
+/// A venue at which events take place
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VenueResponse {
+    #[schema(example = 1)]
+    id: i32,
+
+    #[schema(example = "Concert hall")]
+    name: String,
+
+    #[schema(example = "Main street 1, Amsterdam")]
+    address: Option<String>,
+
+    /// The version token of the venue, to be sent as `If-Match` when updating it
+    #[schema(example = "FOOBAR")]
+    etag: String,
+}
+
+impl From<&Venue> for VenueResponse {
+    fn from(value: &Venue) -> Self {
+        Self {
+            id: value.id,
+            name: value.name.clone(),
+            address: value.address.clone(),
+            etag: value.etag.clone(),
         }
     }
 }
@@ -441,6 +733,10 @@ pub struct MailTemplateResponse {
     /// The body (content) of the email template
     #[schema(example = "Lorem ipsum dolor sit amet")]
     body: String,
+
+    /// The version token of the email template, to be sent as `If-Match` when updating it
+    #[schema(example = "FOOBAR")]
+    etag: String,
 }
 
 impl From<&MailTemplate> for MailTemplateResponse {
@@ -449,6 +745,7 @@ impl From<&MailTemplate> for MailTemplateResponse {
             id: value.id,
             name: value.name.clone(),
             body: value.body.clone(),
+            etag: value.etag.clone(),
         }
     }
 }
@@ -474,3 +771,543 @@ impl From<(i32, &str)> for MailTemplateNameResponse {
         }
     }
 }
+
+/// A job known to the backend, together with its schedule and last run
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JobResponse {
+    /// The (unique) name of the job
+    #[schema(example = "clean_late_non_activated_members")]
+    name: String,
+
+    /// Describes what the job does
+    description: String,
+
+    /// The schedule of the job in cron notation (UTC)
+    #[schema(example = "*/15 * * * *")]
+    schedule: String,
+
+    /// The next moment the job is scheduled
+    next_run: Option<DateTime<Utc>>,
+
+    /// The most recent run of the job, if any
+    last_run: Option<JobRunResponse>,
+}
+
+impl From<(&ScheduledJob, Option<&JobRun>)> for JobResponse {
+    fn from((job, last_run): (&ScheduledJob, Option<&JobRun>)) -> Self {
+        Self {
+            name: job.definition.name.to_string(),
+            description: job.definition.description.to_string(),
+            schedule: job.schedule.to_string(),
+            next_run: job.schedule.next_after(Utc::now()),
+            last_run: last_run.map(JobRunResponse::from),
+        }
+    }
+}
+
+/// A single run of a job
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRunResponse {
+    /// The identifier of the run
+    #[schema(example = 1)]
+    id: i32,
+
+    /// Set if the run was triggered manually instead of by the schedule
+    manual: bool,
+
+    started_at: DateTime<Utc>,
+
+    /// Not set while the job is still running
+    finished_at: Option<DateTime<Utc>>,
+
+    /// Not set while the job is still running
+    succeeded: Option<bool>,
+
+    /// The error of a failed run
+    error: Option<String>,
+}
+
+impl From<&JobRun> for JobRunResponse {
+    fn from(value: &JobRun) -> Self {
+        Self {
+            id: value.id,
+            manual: value.manual,
+            started_at: value.started_at.and_utc(),
+            finished_at: value.finished_at.map(|t| t.and_utc()),
+            succeeded: value.succeeded,
+            error: value.error.clone(),
+        }
+    }
+}
+
+/// The personal calendar feed of a member
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarFeedResponse {
+    /// Grants access to the feed, anyone knowing the token can read the events of the member.
+    /// Only returned when the feed is created or renewed, as the token itself is not stored
+    #[schema(example = "FOOBAR")]
+    token: Option<String>,
+
+    /// The path of the feed, to be subscribed to by calendar applications, returned along with
+    /// the token
+    #[schema(example = "/api/calendar/v1/feed/FOOBAR/events.ics")]
+    path: Option<String>,
+
+    created_at: DateTime<Utc>,
+}
+
+impl From<&CalendarFeed> for CalendarFeedResponse {
+    fn from(value: &CalendarFeed) -> Self {
+        Self {
+            token: None,
+            path: None,
+            created_at: value.creation_time.and_utc(),
+        }
+    }
+}
+
+impl CalendarFeedResponse {
+    /// Includes the token of a newly generated feed, and the path containing it
+    pub fn with_token(self, token: &str) -> Self {
+        Self {
+            token: Some(token.to_owned()),
+            path: Some(format!("/api/calendar/v1/feed/{token}/events.ics")),
+            ..self
+        }
+    }
+}
+
+/// The response of a member to an occurrence of an event
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceResponse {
+    status: AttendanceStatus,
+
+    #[schema(example = "Arriving half an hour late")]
+    note: Option<String>,
+
+    responded_at: DateTime<Utc>,
+}
+
+impl From<&EventAttendance> for AttendanceResponse {
+    fn from(value: &EventAttendance) -> Self {
+        Self {
+            status: value.status,
+            note: value.note.clone(),
+            responded_at: value.response_time.and_utc(),
+        }
+    }
+}
+
+/// The responses to an occurrence of an event, grouped by section
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceReportResponse {
+    #[schema(example = 1)]
+    page_id: i32,
+
+    #[schema(example = "Rehearsal")]
+    title: String,
+
+    occurrence_date: NaiveDate,
+
+    /// The sections ordered by musical instrument, members without a musical instrument last
+    sections: Vec<AttendanceSectionResponse>,
+}
+
+/// The responses of the members playing the same musical instrument
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceSectionResponse {
+    musical_instrument_id: Option<i32>,
+
+    #[schema(example = "Violin")]
+    musical_instrument: Option<String>,
+
+    #[schema(example = 8)]
+    attending: usize,
+
+    #[schema(example = 1)]
+    absent: usize,
+
+    #[schema(example = 1)]
+    maybe: usize,
+
+    /// The amount of members who did not respond yet
+    #[schema(example = 2)]
+    unanswered: usize,
+
+    attendees: Vec<AttendeeResponse>,
+}
+
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendeeResponse {
+    #[schema(example = 1)]
+    member_id: i32,
+
+    #[schema(example = "John")]
+    first_name: String,
+
+    #[schema(example = "Doe")]
+    last_name: String,
+
+    /// The response of the member, not set if the member did not respond yet
+    status: Option<AttendanceStatus>,
+
+    note: Option<String>,
+}
+
+impl AttendanceReportResponse {
+    /// Groups the attendees by musical instrument, keeping the order of the attendees
+    pub fn build(page: &Page, occurrence_date: NaiveDate, attendees: &[Attendee]) -> Self {
+        let mut sections: Vec<AttendanceSectionResponse> = Vec::new();
+        for attendee in attendees {
+            let index = match sections
+                .iter()
+                .position(|section| section.musical_instrument_id == attendee.musical_instrument_id)
+            {
+                Some(index) => index,
+                None => {
+                    sections.push(AttendanceSectionResponse {
+                        musical_instrument_id: attendee.musical_instrument_id,
+                        musical_instrument: attendee.musical_instrument.clone(),
+                        attending: 0,
+                        absent: 0,
+                        maybe: 0,
+                        unanswered: 0,
+                        attendees: Vec::new(),
+                    });
+                    sections.len() - 1
+                }
+            };
+            let section = &mut sections[index];
+            let attendance = attendee.attendance.as_ref();
+            match attendance.map(|attendance| attendance.status) {
+                Some(AttendanceStatus::Attending) => section.attending += 1,
+                Some(AttendanceStatus::Absent) => section.absent += 1,
+                Some(AttendanceStatus::Maybe) => section.maybe += 1,
+                None => section.unanswered += 1,
+            }
+            section.attendees.push(AttendeeResponse {
+                member_id: attendee.member_id,
+                first_name: attendee.first_name.clone(),
+                last_name: attendee.last_name.clone(),
+                status: attendance.map(|attendance| attendance.status),
+                note: attendance.and_then(|attendance| attendance.note.clone()),
+            });
+        }
+        Self {
+            page_id: page.id,
+            title: page.title.clone(),
+            occurrence_date,
+            sections,
+        }
+    }
+
+    /// Renders the report as CSV, with a line for every attendee
+    pub fn to_csv(&self) -> String {
+        let mut csv = csv_line(&["Section", "First name", "Last name", "Status", "Note"]);
+        for section in &self.sections {
+            for attendee in &section.attendees {
+                csv.push_str(&csv_line(&[
+                    section.musical_instrument.as_deref().unwrap_or_default(),
+                    &attendee.first_name,
+                    &attendee.last_name,
+                    attendee
+                        .status
+                        .map(AttendanceStatus::as_str)
+                        .unwrap_or_default(),
+                    attendee.note.as_deref().unwrap_or_default(),
+                ]));
+            }
+        }
+        csv
+    }
+}
+
+/// Renders a line of CSV (RFC 4180), quoting the fields which need quoting
+fn csv_line(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+/// A period in which the member is away
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AbsenceResponse {
+    #[schema(example = 1)]
+    id: i32,
+
+    start_date: NaiveDate,
+
+    end_date: NaiveDate,
+
+    #[schema(example = "Holiday")]
+    reason: Option<String>,
+
+    visibility: AbsenceVisibility,
+}
+
+impl From<&MemberAbsence> for AbsenceResponse {
+    fn from(value: &MemberAbsence) -> Self {
+        Self {
+            id: value.id,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            reason: value.reason.clone(),
+            visibility: value.visibility,
+        }
+    }
+}
+
+/// A member who is away during an occurrence of an event
+#[derive(Serialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AbsentMemberResponse {
+    #[schema(example = 1)]
+    member_id: i32,
+
+    #[schema(example = "John")]
+    first_name: String,
+
+    #[schema(example = "Doe")]
+    last_name: String,
+
+    start_date: NaiveDate,
+
+    end_date: NaiveDate,
+
+    #[schema(example = "Holiday")]
+    reason: Option<String>,
+}
+
+impl From<&AbsentMember> for AbsentMemberResponse {
+    fn from(value: &AbsentMember) -> Self {
+        Self {
+            member_id: value.absence.member_id,
+            first_name: value.first_name.clone(),
+            last_name: value.last_name.clone(),
+            start_date: value.absence.start_date,
+            end_date: value.absence.end_date,
+            reason: value.absence.reason.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::interface::commands::CreatePageCommand;
+    use crate::model::primitives::AbsenceVisibility;
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn page(event_date: &str, end_event_date: &str) -> Page {
+        let mut page = Page::from(&CreatePageCommand {
+            title: "Rehearsal".to_owned(),
+            event_date: None,
+            end_event_date: None,
+        });
+        page.id = 1;
+        page.event_date = Some(date(event_date));
+        page.end_event_date = Some(date(end_event_date));
+        page
+    }
+
+    fn attendee(
+        member_id: i32,
+        first_name: &str,
+        section: Option<(i32, &str)>,
+        response: Option<(AttendanceStatus, Option<&str>)>,
+    ) -> Attendee {
+        Attendee {
+            member_id,
+            first_name: first_name.to_owned(),
+            last_name: "Doe".to_owned(),
+            email_address: format!("{first_name}@example.com"),
+            musical_instrument_id: section.map(|(id, _)| id),
+            musical_instrument: section.map(|(_, name)| name.to_owned()),
+            attendance: response.map(|(status, note)| EventAttendance {
+                page_id: 1,
+                occurrence_date: date("2025-09-02"),
+                member_id,
+                status,
+                note: note.map(str::to_owned),
+                response_time: date("2025-08-30").and_hms_opt(12, 0, 0).unwrap(),
+            }),
+        }
+    }
+
+    #[test]
+    fn attendance_report_groups_attendees_by_section() {
+        let attendees = [
+            attendee(
+                1,
+                "Anna",
+                Some((2, "Violin")),
+                Some((AttendanceStatus::Attending, None)),
+            ),
+            attendee(2, "Bram", None, None),
+            attendee(
+                3,
+                "Cas",
+                Some((1, "Cello")),
+                Some((AttendanceStatus::Maybe, None)),
+            ),
+            attendee(4, "Dirk", Some((2, "Violin")), None),
+            attendee(
+                5,
+                "Eva",
+                Some((2, "Violin")),
+                Some((AttendanceStatus::Absent, Some("Ill"))),
+            ),
+        ];
+        let report = AttendanceReportResponse::build(
+            &page("2025-09-02", "2025-09-02"),
+            date("2025-09-02"),
+            &attendees,
+        );
+
+        let sections: Vec<Option<&str>> = report
+            .sections
+            .iter()
+            .map(|section| section.musical_instrument.as_deref())
+            .collect();
+        assert_eq!(sections, [Some("Violin"), None, Some("Cello")]);
+
+        let violins = &report.sections[0];
+        assert_eq!(
+            (
+                violins.attending,
+                violins.absent,
+                violins.maybe,
+                violins.unanswered
+            ),
+            (1, 1, 0, 1)
+        );
+        let member_ids: Vec<i32> = violins.attendees.iter().map(|a| a.member_id).collect();
+        assert_eq!(member_ids, [1, 4, 5]);
+        assert_eq!(violins.attendees[2].note.as_deref(), Some("Ill"));
+        assert_eq!(report.sections[1].unanswered, 1);
+        assert_eq!(report.sections[2].maybe, 1);
+    }
+
+    #[test]
+    fn attendance_report_renders_csv_with_quoted_fields() {
+        let attendees = [
+            attendee(
+                1,
+                "Anna",
+                Some((2, "Violin")),
+                Some((AttendanceStatus::Attending, None)),
+            ),
+            attendee(
+                2,
+                "Bram",
+                None,
+                Some((AttendanceStatus::Absent, Some("Away, \"on tour\""))),
+            ),
+            attendee(3, "Cas", Some((1, "Cello")), None),
+        ];
+        let report = AttendanceReportResponse::build(
+            &page("2025-09-02", "2025-09-02"),
+            date("2025-09-02"),
+            &attendees,
+        );
+
+        assert_eq!(
+            report.to_csv(),
+            "Section,First name,Last name,Status,Note\r\n\
+             Violin,Anna,Doe,attending,\r\n\
+             ,Bram,Doe,absent,\"Away, \"\"on tour\"\"\"\r\n\
+             Cello,Cas,Doe,,\r\n"
+        );
+    }
+
+    fn absent_member(member_id: i32, start_date: &str, end_date: &str) -> AbsentMember {
+        AbsentMember {
+            first_name: format!("Member {member_id}"),
+            last_name: "Doe".to_owned(),
+            absence: MemberAbsence {
+                id: member_id,
+                member_id,
+                start_date: date(start_date),
+                end_date: date(end_date),
+                reason: None,
+                visibility: AbsenceVisibility::Members,
+            },
+        }
+    }
+
+    fn absent_member_ids(response: &EventResponse) -> Vec<i32> {
+        response
+            .absent_members
+            .iter()
+            .map(|absent| absent.member_id)
+            .collect()
+    }
+
+    #[test]
+    fn events_report_absences_overlapping_any_of_their_days() {
+        let absences = [
+            absent_member(1, "2025-09-01", "2025-09-04"),
+            absent_member(2, "2025-09-01", "2025-09-05"),
+            absent_member(3, "2025-09-06", "2025-09-06"),
+            absent_member(4, "2025-09-07", "2025-09-10"),
+            absent_member(5, "2025-09-08", "2025-09-10"),
+        ];
+        let occurrence = EventOccurrence {
+            page: page("2025-09-05", "2025-09-07"),
+            times: None,
+        };
+        let response = EventResponse::from((&occurrence, absences.as_slice()));
+        assert_eq!(absent_member_ids(&response), [2, 3, 4]);
+    }
+
+    #[test]
+    fn recurring_events_report_the_absences_of_each_occurrence() {
+        let event = PageEvent {
+            page_id: 1,
+            starts_at: date("2025-09-02").and_hms_opt(19, 30, 0).unwrap(),
+            ends_at: date("2025-09-02").and_hms_opt(22, 0, 0).unwrap(),
+            time_zone: "Europe/Amsterdam".to_owned(),
+            venue_id: None,
+            recurrence_rule: Some("FREQ=WEEKLY;BYDAY=TU;COUNT=3".to_owned()),
+            last_date: Some(date("2025-09-16")),
+        };
+        let occurrences = EventOccurrence::expand(
+            &page("2025-09-02", "2025-09-02"),
+            &event,
+            None,
+            &[],
+            date("2025-09-01"),
+            date("2025-09-30"),
+        );
+        let absences = [
+            absent_member(1, "2025-09-08", "2025-09-12"),
+            absent_member(2, "2025-09-16", "2025-09-20"),
+        ];
+
+        let reported: Vec<Vec<i32>> = occurrences
+            .iter()
+            .map(|occurrence| EventResponse::from((occurrence, absences.as_slice())))
+            .map(|response| absent_member_ids(&response))
+            .collect();
+        assert_eq!(reported, [vec![], vec![1], vec![2]]);
+    }
+}
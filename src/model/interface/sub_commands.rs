@@ -16,6 +16,11 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::result::BackendResult;
+use crate::generic::validation::rules::{
+    is_email_address, is_not_blank, is_phone_number, is_postal_code,
+};
+use crate::generic::validation::{Validate, Validator};
 use serde::Deserialize;
 use utoipa::ToSchema;
 
@@ -35,6 +40,33 @@ pub struct DetailRegisterSubCommand {
     pub phone_number: String,
 }
 
+impl Validate for DetailRegisterSubCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check(
+                "firstName",
+                is_not_blank(&self.first_name),
+                "First name is required",
+            )
+            .check(
+                "lastName",
+                is_not_blank(&self.last_name),
+                "Last name is required",
+            )
+            .check(
+                "emailAddress",
+                is_email_address(&self.email_address),
+                "Not a valid email address",
+            )
+            .check(
+                "phoneNumber",
+                is_phone_number(&self.phone_number),
+                "Not a valid phone number",
+            )
+            .finish()
+    }
+}
+
 #[derive(Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct AddressRegisterSubCommand {
@@ -53,3 +85,60 @@ pub struct AddressRegisterSubCommand {
     #[schema(example = "Tubaton")]
     pub domicile: String,
 }
+
+impl Validate for AddressRegisterSubCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check("street", is_not_blank(&self.street), "Street is required")
+            .check(
+                "houseNumber",
+                self.house_number > 0,
+                "House number must be positive",
+            )
+            .check(
+                "postalCode",
+                is_postal_code(&self.postal_code),
+                "Not a valid postal code",
+            )
+            .check(
+                "domicile",
+                is_not_blank(&self.domicile),
+                "Domicile is required",
+            )
+            .finish()
+    }
+}
+
+/// Moves a page below another parent page and gives it a position among its new siblings
+#[derive(Deserialize, ToSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PageMoveSubCommand {
+    #[schema(example = 2)]
+    pub page_id: i32,
+
+    /// The new parent page, the page becomes a main menu page if not set
+    #[serde(default)]
+    #[schema(example = 1)]
+    pub parent_id: Option<i32>,
+
+    #[schema(example = 0)]
+    pub order_number: i32,
+
+    /// The version token of the page, if set the move is rejected if the page was modified by
+    /// someone else in the meantime
+    #[serde(default)]
+    #[schema(example = "FOOBAR")]
+    pub etag: Option<String>,
+}
+
+impl Validate for PageMoveSubCommand {
+    fn validate(&self) -> BackendResult<()> {
+        Validator::new()
+            .check(
+                "parentId",
+                self.parent_id != Some(self.page_id),
+                "A page can not be its own parent",
+            )
+            .finish()
+    }
+}
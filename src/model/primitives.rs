@@ -22,7 +22,7 @@ use diesel::backend::Backend;
 use diesel::deserialize::FromSql;
 use diesel::expression::AsExpression;
 use diesel::serialize::{Output, ToSql};
-use diesel::sql_types::Integer;
+use diesel::sql_types::{Integer, Text};
 use diesel::FromSqlRow;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -127,6 +127,176 @@ impl From<HashSet<Role>> for RoleComposition {
     }
 }
 
+/// The format in which the content of a page is written
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    FromSqlRow,
+    Eq,
+    PartialEq,
+    ToSchema,
+    AsExpression,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    /// A restricted subset of HTML
+    #[default]
+    Html,
+    /// CommonMark, including tables and strikethrough
+    Markdown,
+}
+
+impl ContentFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContentFormat::Html => "html",
+            ContentFormat::Markdown => "markdown",
+        }
+    }
+}
+
+impl<DB> FromSql<Text, DB> for ContentFormat
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> diesel::deserialize::Result<Self> {
+        match String::from_sql(bytes)?.as_str() {
+            "html" => Ok(ContentFormat::Html),
+            "markdown" => Ok(ContentFormat::Markdown),
+            v => Err(format!("Could not expand value into content format: {}", v).into()),
+        }
+    }
+}
+
+impl<DB> ToSql<Text, DB> for ContentFormat
+where
+    DB: Backend,
+    str: ToSql<Text, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> diesel::serialize::Result {
+        self.as_str().to_sql(out)
+    }
+}
+
+/// The response of a member to an occurrence of an event
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    FromSqlRow,
+    Eq,
+    PartialEq,
+    Hash,
+    ToSchema,
+    AsExpression,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "lowercase")]
+pub enum AttendanceStatus {
+    Attending,
+    Absent,
+    Maybe,
+}
+
+impl AttendanceStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AttendanceStatus::Attending => "attending",
+            AttendanceStatus::Absent => "absent",
+            AttendanceStatus::Maybe => "maybe",
+        }
+    }
+}
+
+impl<DB> FromSql<Text, DB> for AttendanceStatus
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> diesel::deserialize::Result<Self> {
+        match String::from_sql(bytes)?.as_str() {
+            "attending" => Ok(AttendanceStatus::Attending),
+            "absent" => Ok(AttendanceStatus::Absent),
+            "maybe" => Ok(AttendanceStatus::Maybe),
+            v => Err(format!("Could not expand value into attendance status: {}", v).into()),
+        }
+    }
+}
+
+impl<DB> ToSql<Text, DB> for AttendanceStatus
+where
+    DB: Backend,
+    str: ToSql<Text, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> diesel::serialize::Result {
+        self.as_str().to_sql(out)
+    }
+}
+
+/// Who sees the absence of a member, besides the member
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    FromSqlRow,
+    Eq,
+    PartialEq,
+    Hash,
+    ToSchema,
+    AsExpression,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "lowercase")]
+pub enum AbsenceVisibility {
+    /// All logged in members see the absence
+    Members,
+    /// Only the committee sees the absence
+    Committee,
+}
+
+impl AbsenceVisibility {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AbsenceVisibility::Members => "members",
+            AbsenceVisibility::Committee => "committee",
+        }
+    }
+}
+
+impl<DB> FromSql<Text, DB> for AbsenceVisibility
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> diesel::deserialize::Result<Self> {
+        match String::from_sql(bytes)?.as_str() {
+            "members" => Ok(AbsenceVisibility::Members),
+            "committee" => Ok(AbsenceVisibility::Committee),
+            v => Err(format!("Could not expand value into absence visibility: {}", v).into()),
+        }
+    }
+}
+
+impl<DB> ToSql<Text, DB> for AbsenceVisibility
+where
+    DB: Backend,
+    str: ToSql<Text, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> diesel::serialize::Result {
+        self.as_str().to_sql(out)
+    }
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct EventDate {
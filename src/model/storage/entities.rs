@@ -16,13 +16,21 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::recurrence::{self, Rule};
+use crate::generic::security::{generate_activation_string, hash_token};
+use crate::generic::slug;
+use crate::generic::validation::normalize;
 use crate::model::interface::commands::{
-    CreateMailTemplateCommand, CreatePageCommand, ImageUploadCommand,
-    RegisterMusicalInstrumentCommand, UpdateMailTemplateCommand, UpdateMusicalInstrumentCommand,
-    UpdatePageCommand, WorkgroupRegisterCommand, WorkgroupUpdateCommand,
+    CreateMailTemplateCommand, CreatePageCommand, ImageUploadCommand, RegisterAbsenceCommand,
+    RegisterAttendanceCommand, RegisterMusicalInstrumentCommand, RegisterVenueCommand,
+    SetPageEventCommand, UpdateAbsenceCommand, UpdateMailTemplateCommand,
+    UpdateMusicalInstrumentCommand, UpdatePageCommand, UpdateVenueCommand,
+    WorkgroupRegisterCommand, WorkgroupUpdateCommand,
 };
 use crate::model::interface::sub_commands;
+use crate::model::primitives::{AbsenceVisibility, AttendanceStatus, ContentFormat};
 use crate::model::storage::extended_entities::ExtendedMember;
+use chrono_tz::Tz;
 use diesel::{AsChangeset, Insertable, Queryable, Selectable};
 
 #[derive(Clone, Debug, Queryable, Selectable, Insertable)]
@@ -41,6 +49,9 @@ pub struct Member {
     pub allow_privacy_info_sharing: bool,
     pub nonce: String,
     pub description: Option<String>,
+    /// The version token, generated by the database on creation and renewed on every update
+    #[diesel(skip_insertion)]
+    pub etag: String,
 }
 
 impl From<&ExtendedMember> for Member {
@@ -58,6 +69,7 @@ impl From<&ExtendedMember> for Member {
             member_details_id: value.member_detail.id,
             member_address_details_id: value.member_address_detail.id,
             description: value.description.clone(),
+            etag: value.etag.clone(),
         }
     }
 }
@@ -90,7 +102,7 @@ impl From<&sub_commands::DetailRegisterSubCommand> for MemberDetail {
             first_name: input.first_name.clone(),
             last_name: input.last_name.clone(),
             email_address: input.email_address.clone(),
-            phone_number: input.phone_number.clone(),
+            phone_number: normalize::phone_number(&input.phone_number),
         }
     }
 }
@@ -127,7 +139,7 @@ impl From<&sub_commands::AddressRegisterSubCommand> for MemberAddressDetail {
             street: input.street.clone(),
             house_number: input.house_number.clone(),
             house_number_postfix: input.house_number_postfix.clone(),
-            postal_code: input.postal_code.clone(),
+            postal_code: normalize::postal_code(&input.postal_code),
             domicile: input.domicile.clone(),
         }
     }
@@ -139,6 +151,9 @@ pub struct Workgroup {
     #[diesel(skip_insertion)]
     pub id: i32,
     pub name: String,
+    /// The version token, generated by the database on creation and renewed on every update
+    #[diesel(skip_insertion)]
+    pub etag: String,
 }
 
 impl From<&WorkgroupRegisterCommand> for Workgroup {
@@ -147,6 +162,7 @@ impl From<&WorkgroupRegisterCommand> for Workgroup {
             id: 0, // Skipped during creation
 
             name: input.name.to_string(),
+            etag: String::new(), // Skipped during creation
         }
     }
 }
@@ -156,6 +172,7 @@ impl From<(&Workgroup, &WorkgroupUpdateCommand)> for Workgroup {
     fn from((origin, command): (&Workgroup, &WorkgroupUpdateCommand)) -> Self {
         let mut cloned = origin.clone();
         cloned.name = command.name.clone();
+        cloned.etag = crate::generate_asset_id();
         cloned
     }
 }
@@ -173,6 +190,46 @@ pub struct Page {
     pub title: String,
     pub order_number: i32,
     pub end_event_date: Option<chrono::NaiveDate>,
+    pub last_modified: chrono::NaiveDateTime,
+    /// Holds the content saved since the page was last published, the content asset holds the
+    /// published content
+    pub draft_content_asset: Option<String>,
+    /// The page is not visible to non-operators before this moment
+    pub publish_at: Option<chrono::NaiveDateTime>,
+    /// The page is not visible to non-operators from this moment
+    pub unpublish_at: Option<chrono::NaiveDateTime>,
+    /// The format of the published content
+    pub content_format: ContentFormat,
+    /// The format of the draft content, set together with the draft content asset
+    pub draft_content_format: Option<ContentFormat>,
+    /// Identifies the page among its sibling pages within the path of the page
+    pub slug: String,
+    /// Incremented on every change of the page, as the sequence of the event in calendar feeds
+    pub change_sequence: i32,
+}
+
+impl Page {
+    /// Verifies whether the moment lies within the publication window of the page
+    pub fn is_published_at(&self, moment: chrono::NaiveDateTime) -> bool {
+        self.publish_at
+            .is_none_or(|publish_at| publish_at <= moment)
+            && self
+                .unpublish_at
+                .is_none_or(|unpublish_at| unpublish_at > moment)
+    }
+
+    /// The asset holding the latest saved content, which is the published content if there
+    /// are no unpublished changes
+    pub fn latest_content_asset(&self) -> &String {
+        self.draft_content_asset
+            .as_ref()
+            .unwrap_or(&self.content_asset)
+    }
+
+    /// The format of the latest saved content
+    pub fn latest_content_format(&self) -> ContentFormat {
+        self.draft_content_format.unwrap_or(self.content_format)
+    }
 }
 
 impl From<&CreatePageCommand> for Page {
@@ -196,6 +253,14 @@ impl From<&CreatePageCommand> for Page {
                 .clone()
                 .map(|d| d.as_validated().ok())
                 .flatten(),
+            last_modified: chrono::Utc::now().naive_utc(),
+            draft_content_asset: None,
+            publish_at: None,
+            unpublish_at: None,
+            content_format: ContentFormat::default(),
+            draft_content_format: None,
+            slug: slug::slugify(&value.title),
+            change_sequence: 0,
         }
     }
 }
@@ -213,11 +278,110 @@ impl From<(&Page, &UpdatePageCommand)> for Page {
             .clone()
             .map(|d| d.as_validated().ok())
             .flatten();
+        // The slug follows the title, unless it is given explicitly
+        if let Some(slug) = &command.slug {
+            cloned.slug = slug.clone();
+        } else if command.title != origin.title {
+            cloned.slug = slug::slugify(&command.title);
+        }
         cloned.title = command.title.clone();
         cloned
     }
 }
 
+/// The times, venue and recurrence of an event page
+#[derive(Clone, Debug, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::page_events, treat_none_as_null = true)]
+pub struct PageEvent {
+    pub page_id: i32,
+    /// The start of the first occurrence, in the local time of the time zone
+    pub starts_at: chrono::NaiveDateTime,
+    /// The end of the first occurrence, in the local time of the time zone
+    pub ends_at: chrono::NaiveDateTime,
+    /// The IANA name of the time zone, e.g. Europe/Amsterdam
+    pub time_zone: String,
+    pub venue_id: Option<i32>,
+    /// The RRULE of a recurring event
+    pub recurrence_rule: Option<String>,
+    /// The last day of the last occurrence, not set if the event recurs endlessly
+    pub last_date: Option<chrono::NaiveDate>,
+}
+
+impl PageEvent {
+    /// The time zone of the event, falling back to UTC for unknown time zones
+    pub fn tz(&self) -> Tz {
+        self.time_zone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// The recurrence rule of the event, not set for events which do not recur
+    pub fn rule(&self) -> Option<Rule> {
+        self.recurrence_rule
+            .as_deref()
+            .and_then(|rule| rule.parse().ok())
+    }
+
+    /// The time each occurrence lasts
+    pub fn duration(&self) -> chrono::TimeDelta {
+        self.ends_at - self.starts_at
+    }
+}
+
+impl From<(i32, &SetPageEventCommand)> for PageEvent {
+    fn from((page_id, command): (i32, &SetPageEventCommand)) -> Self {
+        let mut event = Self {
+            page_id,
+            starts_at: command.starts_at,
+            ends_at: command.ends_at,
+            time_zone: command.time_zone.clone(),
+            venue_id: command.venue_id,
+            recurrence_rule: command.recurrence_rule.clone(),
+            last_date: None,
+        };
+        event.last_date = recurrence::last_date(
+            event.rule().as_ref(),
+            event.starts_at,
+            event.duration(),
+            event.tz(),
+        );
+        event
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::page_revisions)]
+pub struct PageRevision {
+    #[diesel(skip_insertion)]
+    pub id: i32,
+    pub page_id: i32,
+    /// The member who saved the revision, unset if the member is no longer registered
+    pub author_id: Option<i32>,
+    pub creation_time: chrono::NaiveDateTime,
+    /// The title of the page at the moment the revision was saved
+    pub title: String,
+    pub content: String,
+    pub content_format: ContentFormat,
+}
+
+impl PageRevision {
+    /// Creates a revision of the page holding the given content, which is saved now
+    pub fn new(
+        page: &Page,
+        author_id: Option<i32>,
+        content: &str,
+        content_format: ContentFormat,
+    ) -> Self {
+        Self {
+            id: 0, // Skipped during creation
+            page_id: page.id,
+            author_id,
+            creation_time: chrono::Utc::now().naive_utc(),
+            title: page.title.clone(),
+            content: content.to_owned(),
+            content_format,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Queryable, Selectable, Insertable, AsChangeset)]
 #[diesel(table_name = crate::schema::images)]
 pub struct Image {
@@ -225,6 +389,8 @@ pub struct Image {
     pub id: i32,
     pub title: String,
     pub asset: String,
+    /// The detected format of the image, absent for images uploaded before it was recorded
+    pub format: Option<String>,
 }
 
 impl From<&ImageUploadCommand> for Image {
@@ -234,6 +400,7 @@ impl From<&ImageUploadCommand> for Image {
 
             title: value.title.clone(),
             asset: crate::generate_asset_id(),
+            format: None,
         }
     }
 }
@@ -245,6 +412,9 @@ pub struct MusicalInstrument {
     pub id: i32,
     pub name: String,
     pub wikipedia_url: Option<String>,
+    /// The version token, generated by the database on creation and renewed on every update
+    #[diesel(skip_insertion)]
+    pub etag: String,
 }
 
 impl From<&RegisterMusicalInstrumentCommand> for MusicalInstrument {
@@ -254,6 +424,7 @@ impl From<&RegisterMusicalInstrumentCommand> for MusicalInstrument {
 
             name: value.name.clone(),
             wikipedia_url: value.wikipedia_url.clone(),
+            etag: String::new(), // Skipped during creation
         }
     }
 }
@@ -264,6 +435,42 @@ impl From<(&MusicalInstrument, &UpdateMusicalInstrumentCommand)> for MusicalInst
             id: origin.id,
             name: command.name.clone(),
             wikipedia_url: command.wikipedia_url.clone(),
+            etag: crate::generate_asset_id(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::venues, treat_none_as_null = true)]
+pub struct Venue {
+    #[diesel(skip_insertion)]
+    pub id: i32,
+    pub name: String,
+    pub address: Option<String>,
+    /// The version token, generated by the database on creation and renewed on every update
+    #[diesel(skip_insertion)]
+    pub etag: String,
+}
+
+impl From<&RegisterVenueCommand> for Venue {
+    fn from(value: &RegisterVenueCommand) -> Self {
+        Self {
+            id: 0, // Skipped during creation
+
+            name: value.name.clone(),
+            address: value.address.clone(),
+            etag: String::new(), // Skipped during creation
+        }
+    }
+}
+
+impl From<(&Venue, &UpdateVenueCommand)> for Venue {
+    fn from((origin, command): (&Venue, &UpdateVenueCommand)) -> Self {
+        Self {
+            id: origin.id,
+            name: command.name.clone(),
+            address: command.address.clone(),
+            etag: crate::generate_asset_id(),
         }
     }
 }
@@ -275,6 +482,9 @@ pub struct MailTemplate {
     pub id: i32,
     pub name: String,
     pub body: String,
+    /// The version token, generated by the database on creation and renewed on every update
+    #[diesel(skip_insertion)]
+    pub etag: String,
 }
 
 impl From<&CreateMailTemplateCommand> for MailTemplate {
@@ -284,6 +494,7 @@ impl From<&CreateMailTemplateCommand> for MailTemplate {
 
             name: command.name.clone(),
             body: command.body.clone(),
+            etag: String::new(), // Skipped during creation
         }
     }
 }
@@ -294,6 +505,149 @@ impl From<(&MailTemplate, &UpdateMailTemplateCommand)> for MailTemplate {
             id: origin.id,
             name: origin.name.clone(),
             body: command.body.clone(),
+            etag: crate::generate_asset_id(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::job_runs)]
+pub struct JobRun {
+    #[diesel(skip_insertion)]
+    pub id: i32,
+    pub job_name: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub finished_at: Option<chrono::NaiveDateTime>,
+    pub succeeded: Option<bool>,
+    pub error: Option<String>,
+    pub manual: bool,
+}
+
+impl JobRun {
+    /// Creates a run which is started now, but not yet finished
+    pub fn started(job_name: &str, manual: bool) -> Self {
+        Self {
+            id: 0, // Skipped during creation
+            job_name: job_name.to_owned(),
+            started_at: chrono::Utc::now().naive_utc(),
+            finished_at: None,
+            succeeded: None,
+            error: None,
+            manual,
+        }
+    }
+}
+
+/// The personal calendar feed of a member, which is accessed using its token instead of a login
+#[derive(Clone, Debug, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::calendar_feeds)]
+pub struct CalendarFeed {
+    pub member_id: i32,
+    /// The hash of the token, the token itself is only known to the member
+    pub token_hash: String,
+    pub creation_time: chrono::NaiveDateTime,
+}
+
+impl CalendarFeed {
+    /// Creates a feed for the member with a newly generated token, which is returned alongside
+    pub fn generate(member_id: i32) -> (Self, String) {
+        let token = generate_activation_string();
+        let feed = Self {
+            member_id,
+            token_hash: hash_token(&token),
+            creation_time: chrono::Utc::now().naive_utc(),
+        };
+        (feed, token)
+    }
+}
+
+/// The response of a member to an occurrence of an event
+#[derive(Clone, Debug, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::event_attendances, treat_none_as_null = true)]
+pub struct EventAttendance {
+    pub page_id: i32,
+    /// The day of the occurrence, which is the event date of events which do not recur
+    pub occurrence_date: chrono::NaiveDate,
+    pub member_id: i32,
+    pub status: AttendanceStatus,
+    pub note: Option<String>,
+    pub response_time: chrono::NaiveDateTime,
+}
+
+impl From<(i32, chrono::NaiveDate, i32, &RegisterAttendanceCommand)> for EventAttendance {
+    fn from(
+        (page_id, occurrence_date, member_id, command): (
+            i32,
+            chrono::NaiveDate,
+            i32,
+            &RegisterAttendanceCommand,
+        ),
+    ) -> Self {
+        Self {
+            page_id,
+            occurrence_date,
+            member_id,
+            status: command.status,
+            note: command
+                .note
+                .as_ref()
+                .map(|note| note.trim().to_owned())
+                .filter(|note| !note.is_empty()),
+            response_time: chrono::Utc::now().naive_utc(),
         }
     }
 }
+
+/// A period in which a member is away, both dates are included
+#[derive(Clone, Debug, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::member_absences, treat_none_as_null = true)]
+pub struct MemberAbsence {
+    #[diesel(skip_insertion)]
+    pub id: i32,
+    pub member_id: i32,
+    pub start_date: chrono::NaiveDate,
+    pub end_date: chrono::NaiveDate,
+    pub reason: Option<String>,
+    pub visibility: AbsenceVisibility,
+}
+
+impl MemberAbsence {
+    /// Whether the member is away on any of the days of the period
+    pub fn overlaps(&self, start_date: chrono::NaiveDate, end_date: chrono::NaiveDate) -> bool {
+        self.start_date <= end_date && start_date <= self.end_date
+    }
+}
+
+impl From<(i32, &RegisterAbsenceCommand)> for MemberAbsence {
+    fn from((member_id, command): (i32, &RegisterAbsenceCommand)) -> Self {
+        Self {
+            id: 0, // Skipped during creation
+
+            member_id,
+            start_date: command.start_date,
+            end_date: command.end_date,
+            reason: absence_reason(&command.reason),
+            visibility: command.visibility,
+        }
+    }
+}
+
+impl From<(&MemberAbsence, &UpdateAbsenceCommand)> for MemberAbsence {
+    fn from((origin, command): (&MemberAbsence, &UpdateAbsenceCommand)) -> Self {
+        Self {
+            id: origin.id,
+            member_id: origin.member_id,
+            start_date: command.start_date,
+            end_date: command.end_date,
+            reason: absence_reason(&command.reason),
+            visibility: command.visibility,
+        }
+    }
+}
+
+fn absence_reason(reason: &Option<String>) -> Option<String> {
+    reason
+        .as_ref()
+        .map(|reason| reason.trim().to_owned())
+        .filter(|reason| !reason.is_empty())
+}
@@ -17,17 +17,22 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::generic::lazy::{FIRST_OPERATOR_ACTIVATION_MINUTES, MEMBER_ACTIVATION_MINUTES};
+use crate::generic::recurrence;
 use crate::generic::security::generate_activation_string;
+use crate::generic::validation::normalize;
 use crate::model::interface::commands::{
     FirstOperatorRegisterCommand, MemberRegisterCommand, MemberUpdateAddressCommand,
     MemberUpdateCommand, MemberUpdatePrivacyInfoSharingCommand,
 };
-use crate::model::storage::entities::{Member, MemberAddressDetail, MemberDetail};
+use crate::model::storage::entities::{
+    EventAttendance, Member, MemberAbsence, MemberAddressDetail, MemberDetail, Page, PageEvent,
+    Venue,
+};
 use aes_gcm::aead::OsRng;
 use aes_gcm::{AeadCore, Aes256Gcm};
 use base64::engine::general_purpose;
 use base64::Engine;
+use chrono::{NaiveDate, TimeDelta};
 use std::ops::Add;
 
 #[derive(Clone, Debug)]
@@ -44,6 +49,8 @@ pub struct ExtendedMember {
     pub member_detail: MemberDetail,
     pub member_address_detail: MemberAddressDetail,
     pub description: Option<String>,
+    /// The version token of the member, renewed whenever the member is saved
+    pub etag: String,
 }
 
 impl Default for ExtendedMember {
@@ -74,6 +81,7 @@ impl Default for ExtendedMember {
                 domicile: "".to_owned(),
             },
             description: None,
+            etag: "".to_owned(),
         }
     }
 }
@@ -106,6 +114,7 @@ impl From<(&Member, &MemberDetail, &MemberAddressDetail)> for ExtendedMember {
             member_detail: member_detail.clone(),
             member_address_detail: member_address_detail.clone(),
             description: member.description.clone(),
+            etag: member.etag.clone(),
         }
     }
 }
@@ -133,12 +142,13 @@ impl From<(&Member, &MemberDetail)> for ExtendedMember {
             },
             member_address_detail: MemberAddressDetail::gdpr_fake(),
             description: member.description.clone(),
+            etag: member.etag.clone(),
         }
     }
 }
 
-impl From<&MemberRegisterCommand> for ExtendedMember {
-    fn from(value: &MemberRegisterCommand) -> Self {
+impl From<(&MemberRegisterCommand, TimeDelta)> for ExtendedMember {
+    fn from((value, activation_delta): (&MemberRegisterCommand, TimeDelta)) -> Self {
         let now = chrono::Utc::now();
         Self {
             id: 0, // Skipped during creation
@@ -149,16 +159,17 @@ impl From<&MemberRegisterCommand> for ExtendedMember {
             activated: false,
             creation_time: now.naive_utc(),
             activation_string: generate_activation_string(),
-            activation_time: now.add(*MEMBER_ACTIVATION_MINUTES).naive_utc(),
+            activation_time: now.add(activation_delta).naive_utc(),
             allow_privacy_info_sharing: false,
             nonce: Self::generate_encoded_nonce(),
             description: None,
+            etag: "".to_owned(),
         }
     }
 }
 
-impl From<&FirstOperatorRegisterCommand> for ExtendedMember {
-    fn from(value: &FirstOperatorRegisterCommand) -> Self {
+impl From<(&FirstOperatorRegisterCommand, TimeDelta)> for ExtendedMember {
+    fn from((value, activation_delta): (&FirstOperatorRegisterCommand, TimeDelta)) -> Self {
         let now = chrono::Utc::now();
         Self {
             id: 0, // Skipped during creation
@@ -169,10 +180,11 @@ impl From<&FirstOperatorRegisterCommand> for ExtendedMember {
             activated: false,
             creation_time: now.naive_utc(),
             activation_string: generate_activation_string(),
-            activation_time: now.add(*FIRST_OPERATOR_ACTIVATION_MINUTES).naive_utc(),
+            activation_time: now.add(activation_delta).naive_utc(),
             allow_privacy_info_sharing: false,
             nonce: Self::generate_encoded_nonce(),
             description: None,
+            etag: "".to_owned(),
         }
     }
 }
@@ -184,7 +196,7 @@ impl From<(&ExtendedMember, &MemberUpdateCommand)> for ExtendedMember {
         cloned.member_detail.first_name = command.first_name.clone();
         cloned.member_detail.last_name = command.last_name.clone();
         cloned.member_detail.email_address = command.email_address.clone();
-        cloned.member_detail.phone_number = command.phone_number.clone();
+        cloned.member_detail.phone_number = normalize::phone_number(&command.phone_number);
         cloned.musical_instrument_id = command.musical_instrument_id;
         cloned.description = command.description.clone();
         cloned
@@ -198,7 +210,7 @@ impl From<(&ExtendedMember, &MemberUpdateAddressCommand)> for ExtendedMember {
         cloned.member_address_detail.street = command.street.clone();
         cloned.member_address_detail.house_number = command.house_number.clone();
         cloned.member_address_detail.house_number_postfix = command.house_number_postfix.clone();
-        cloned.member_address_detail.postal_code = command.postal_code.clone();
+        cloned.member_address_detail.postal_code = normalize::postal_code(&command.postal_code);
         cloned.member_address_detail.domicile = command.domicile.clone();
         cloned
     }
@@ -238,3 +250,88 @@ impl From<(&Member, &MemberDetail)> for FacebookMember {
         }
     }
 }
+
+/// A single occurrence of an event page. The event dates of the page are the days on which the
+/// occurrence takes place, in the time zone of the event.
+#[derive(Clone, Debug)]
+pub struct EventOccurrence {
+    pub page: Page,
+    /// The times of the occurrence, not set for events lasting whole days
+    pub times: Option<OccurrenceTimes>,
+}
+
+#[derive(Clone, Debug)]
+pub struct OccurrenceTimes {
+    /// The start of the occurrence in UTC
+    pub starts_at: chrono::NaiveDateTime,
+    /// The end of the occurrence in UTC
+    pub ends_at: chrono::NaiveDateTime,
+    pub time_zone: String,
+    pub venue: Option<Venue>,
+    /// Set for the occurrences of a recurring event
+    pub recurring: bool,
+}
+
+impl EventOccurrence {
+    /// Expands the occurrences of the event which take place between the first and last day
+    pub fn expand(
+        page: &Page,
+        event: &PageEvent,
+        venue: Option<&Venue>,
+        exceptions: &[NaiveDate],
+        first_date: NaiveDate,
+        last_date: NaiveDate,
+    ) -> Vec<Self> {
+        let rule = event.rule();
+        let duration = event.duration();
+        let time_zone = event.tz();
+        recurrence::occurrences(
+            rule.as_ref(),
+            event.starts_at,
+            duration,
+            time_zone,
+            exceptions,
+            first_date,
+            last_date,
+        )
+        .into_iter()
+        .map(|start| {
+            let mut page = page.clone();
+            page.event_date = Some(start.date());
+            page.end_event_date = Some((start + duration).date());
+            Self {
+                page,
+                times: Some(OccurrenceTimes {
+                    starts_at: recurrence::to_utc(time_zone, start),
+                    ends_at: recurrence::to_utc(time_zone, start + duration),
+                    time_zone: event.time_zone.clone(),
+                    venue: venue.cloned(),
+                    recurring: rule.is_some(),
+                }),
+            }
+        })
+        .collect()
+    }
+}
+
+/// A member expected to respond to an occurrence of an event, or who responded to it
+#[derive(Clone, Debug)]
+pub struct Attendee {
+    pub member_id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub email_address: String,
+    pub musical_instrument_id: Option<i32>,
+    /// The name of the musical instrument, the section of the member
+    pub musical_instrument: Option<String>,
+    /// The response of the member, not set if the member did not respond yet
+    pub attendance: Option<EventAttendance>,
+}
+
+/// An absence together with the name of the member who is away
+#[derive(Clone, Debug)]
+pub struct AbsentMember {
+    pub first_name: String,
+    pub last_name: String,
+    pub absence: MemberAbsence,
+}
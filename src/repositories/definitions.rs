@@ -16,13 +16,19 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::rendering::Shortcode;
 use crate::generic::result::BackendResult;
 use crate::generic::security::ClaimRoles;
 use crate::generic::storage::session::Session;
-use crate::model::primitives::Role;
-use crate::model::storage::entities::{Image, MailTemplate, MusicalInstrument, Page, Workgroup};
-use crate::model::storage::extended_entities::{ExtendedMember, FacebookMember};
-use chrono::NaiveDate;
+use crate::model::primitives::{AbsenceVisibility, Role};
+use crate::model::storage::entities::{
+    CalendarFeed, EventAttendance, Image, JobRun, MailTemplate, MemberAbsence, MemberDetail,
+    MusicalInstrument, Page, PageEvent, PageRevision, Venue, Workgroup,
+};
+use crate::model::storage::extended_entities::{
+    AbsentMember, Attendee, EventOccurrence, ExtendedMember, FacebookMember,
+};
+use chrono::{NaiveDate, NaiveDateTime};
 
 pub trait PropertiesRepository {
     fn maybe_int_property(&self, session: &mut Session, key: &str) -> Option<i32>;
@@ -43,6 +49,11 @@ pub trait MemberRepository {
 
     fn find_extended_by_id(&self, session: &mut Session, id: i32) -> BackendResult<ExtendedMember>;
 
+    /// Locks the member until the end of the transaction (`SELECT ... FOR UPDATE`). Services
+    /// take the lock before verifying the etag of an entity, such that a concurrent update
+    /// can not slip in between verifying the etag and saving the entity.
+    fn lock_by_id(&self, session: &mut Session, id: i32) -> BackendResult<()>;
+
     fn find_extended_by_activation_string(
         &self,
         session: &mut Session,
@@ -55,6 +66,13 @@ pub trait MemberRepository {
         email_address: &str,
     ) -> BackendResult<ExtendedMember>;
 
+    /// Finds the identifier of the activated member with the email address, if any
+    fn find_id_by_email_address(
+        &self,
+        session: &mut Session,
+        email_address: &str,
+    ) -> BackendResult<Option<i32>>;
+
     fn find_workgroups(&self, session: &mut Session, id: i32) -> BackendResult<Vec<Workgroup>>;
 
     fn save(&self, session: &mut Session, member: ExtendedMember) -> BackendResult<()>;
@@ -84,6 +102,9 @@ pub trait WorkgroupRepository {
 
     fn find_by_id(&self, session: &mut Session, id: i32) -> BackendResult<Workgroup>;
 
+    /// Locks the workgroup until the end of the transaction, see [`MemberRepository::lock_by_id`]
+    fn lock_by_id(&self, session: &mut Session, id: i32) -> BackendResult<()>;
+
     fn save(&self, session: &mut Session, workgroup: Workgroup) -> BackendResult<()>;
 
     fn search(
@@ -179,6 +200,9 @@ pub trait PageRepository {
     /// Updates an existing page and stores it into the database
     fn update(&self, session: &mut Session, page: Page) -> BackendResult<()>;
 
+    /// Marks the content or metadata of the page as modified, renewing its etag
+    fn touch(&self, session: &mut Session, page_id: i32) -> BackendResult<()>;
+
     /// Sets the order of an existing page and stores it into the database
     fn set_order_by_id(
         &self,
@@ -198,6 +222,34 @@ pub trait PageRepository {
     /// Finds the page by the identifier
     fn find_by_id(&self, session: &mut Session, page_id: i32) -> BackendResult<Page>;
 
+    /// Locks the page until the end of the transaction, also while moving it within the page
+    /// tree, see [`MemberRepository::lock_by_id`]
+    fn lock_by_id(&self, session: &mut Session, page_id: i32) -> BackendResult<()>;
+
+    /// Finds the page by its path, given as the slugs of the page and of its ancestors
+    fn find_by_path(&self, session: &mut Session, slugs: &[&str]) -> BackendResult<Option<i32>>;
+
+    /// Finds the path of the page, consisting of the slugs of its ancestors and of the page
+    fn find_path_by_id(&self, session: &mut Session, page_id: i32) -> BackendResult<String>;
+
+    /// Verifies whether another page below the parent page already uses the slug
+    fn slug_exists(
+        &self,
+        session: &mut Session,
+        parent_id: Option<i32>,
+        slug: &str,
+        page_id: i32,
+    ) -> BackendResult<bool>;
+
+    /// Sets the slug of an existing page
+    fn set_slug_by_id(&self, session: &mut Session, page_id: i32, slug: &str) -> BackendResult<()>;
+
+    /// Redirects the former path of a page to the page, replacing any earlier redirect of the path
+    fn add_redirect(&self, session: &mut Session, path: &str, page_id: i32) -> BackendResult<()>;
+
+    /// Finds the page the former path redirects to
+    fn find_redirect(&self, session: &mut Session, path: &str) -> BackendResult<Option<i32>>;
+
     /// Lists the pages by the parent identifier
     fn list_by_parent_id(
         &self,
@@ -206,6 +258,12 @@ pub trait PageRepository {
         roles: &ClaimRoles,
     ) -> BackendResult<Vec<Page>>;
 
+    /// Lists all pages visible given the roles, ordered by their order number
+    fn list_visible(&self, session: &mut Session, roles: &ClaimRoles) -> BackendResult<Vec<Page>>;
+
+    /// Lists the identifiers of all pages together with the identifiers of their parent pages
+    fn list_hierarchy(&self, session: &mut Session) -> BackendResult<Vec<(i32, Option<i32>)>>;
+
     /// Finds the roles associated to a page
     fn find_associated_roles_by_id(
         &self,
@@ -213,6 +271,22 @@ pub trait PageRepository {
         page_id: i32,
     ) -> BackendResult<Vec<Role>>;
 
+    /// Records the entities referred to by shortcodes in the content of the page. References are
+    /// only added, as both the published content and the draft may refer to an entity.
+    fn add_references(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        shortcodes: &[Shortcode],
+    ) -> BackendResult<()>;
+
+    /// Finds the published and draft content assets of the pages referring to any of the entities
+    fn find_referring_content_assets(
+        &self,
+        session: &mut Session,
+        shortcodes: &[Shortcode],
+    ) -> BackendResult<Vec<String>>;
+
     /// Removes a page by the identifier
     fn delete(&self, session: &mut Session, page_id: i32) -> BackendResult<()>;
 
@@ -227,23 +301,52 @@ pub trait PageRepository {
         roles: &Vec<Role>,
     ) -> BackendResult<()>;
 
-    /// Searches for all pages meeting any of the allowed roles
+    /// Searches for all pages meeting any of the allowed roles, matching the term against the
+    /// title and the indexed content. Returns the pages ranked by relevance, together with their
+    /// rank and a snippet of the content, see [PageRepository::set_search_text]
     fn search(
         &self,
         conn: &mut Session,
         page_offset: usize,
         term: &str,
         roles: &ClaimRoles,
-    ) -> BackendResult<(usize, usize, Vec<Page>)>;
+    ) -> BackendResult<(usize, usize, Vec<(Page, f32, String)>)>;
 
-    /// Finds events given the roles between a start date and end date
+    /// Sets the plain text of the published content, as indexed for searching
+    fn set_search_text(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        search_text: &str,
+    ) -> BackendResult<()>;
+
+    /// Finds the occurrences of events given the roles between a start date and end date,
+    /// recurring events are expanded into an occurrence for each time they take place
     fn find_events(
         &self,
         session: &mut Session,
         roles: &ClaimRoles,
         start_date: &NaiveDate,
         end_date: &NaiveDate,
-    ) -> BackendResult<Vec<Page>>;
+    ) -> BackendResult<Vec<EventOccurrence>>;
+
+    /// Finds the times of an event page, alongside the dates of cancelled occurrences
+    fn find_event(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+    ) -> BackendResult<Option<(PageEvent, Vec<NaiveDate>)>>;
+
+    /// Sets the times of an event page, replacing the dates of cancelled occurrences
+    fn set_event(
+        &self,
+        session: &mut Session,
+        event: PageEvent,
+        exceptions: &[NaiveDate],
+    ) -> BackendResult<()>;
+
+    /// Removes the times of an event page, turning it back into an event lasting whole days
+    fn delete_event(&self, session: &mut Session, page_id: i32) -> BackendResult<()>;
 }
 
 /// Manages the image repository
@@ -298,6 +401,9 @@ pub trait MusicalInstrumentRepository {
     /// Finds a musical instrument from the database using the identifier
     fn find_by_id(&self, session: &mut Session, image_id: i32) -> BackendResult<MusicalInstrument>;
 
+    /// Locks the musical instrument until the end of the transaction
+    fn lock_by_id(&self, session: &mut Session, instrument_id: i32) -> BackendResult<()>;
+
     /// Searches for musical instruments matching with names matching the given term
     fn search(
         &self,
@@ -307,6 +413,114 @@ pub trait MusicalInstrumentRepository {
     ) -> BackendResult<(usize, usize, Vec<MusicalInstrument>)>;
 }
 
+/// Manages the responses of members to the occurrences of events
+pub trait AttendanceRepository {
+    /// Saves the response of a member, replacing a former response to the same occurrence
+    fn save(&self, session: &mut Session, attendance: EventAttendance) -> BackendResult<()>;
+
+    /// Finds the response of a member to an occurrence, if the member responded
+    fn find(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        member_id: i32,
+    ) -> BackendResult<Option<EventAttendance>>;
+
+    /// Lists the activated members having one of the roles of the event page, together with the
+    /// members who responded, ordered by musical instrument and name
+    fn list_attendees(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        page_roles: &[Role],
+    ) -> BackendResult<Vec<Attendee>>;
+
+    /// Lists the members who were reminded to respond to an occurrence
+    fn list_reminded(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+    ) -> BackendResult<Vec<i32>>;
+
+    /// Records that a member was reminded to respond to an occurrence
+    fn add_reminder(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        member_id: i32,
+    ) -> BackendResult<()>;
+
+    /// Removes the record of a reminder which could not be sent, such that it is sent again
+    fn remove_reminder(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        member_id: i32,
+    ) -> BackendResult<()>;
+}
+
+/// Manages the periods in which members are away
+pub trait AbsenceRepository {
+    /// Creates a new absence and stores it into the database
+    fn create(&self, session: &mut Session, absence: MemberAbsence) -> BackendResult<()>;
+
+    /// Updates an existing absence in the database
+    fn update(&self, session: &mut Session, absence: MemberAbsence) -> BackendResult<()>;
+
+    /// Removes an existing absence from the database
+    fn delete(&self, session: &mut Session, absence_id: i32) -> BackendResult<()>;
+
+    /// Finds an absence from the database using the identifier
+    fn find_by_id(&self, session: &mut Session, absence_id: i32) -> BackendResult<MemberAbsence>;
+
+    /// Lists the absences of a member, ordered by their start date
+    fn list_by_member_id(
+        &self,
+        session: &mut Session,
+        member_id: i32,
+    ) -> BackendResult<Vec<MemberAbsence>>;
+
+    /// Lists the absences with one of the visibilities which overlap the period, together with
+    /// the names of the members, ordered by their start date
+    fn list_overlapping(
+        &self,
+        session: &mut Session,
+        start_date: &NaiveDate,
+        end_date: &NaiveDate,
+        visibilities: &[AbsenceVisibility],
+    ) -> BackendResult<Vec<AbsentMember>>;
+
+    /// Lists the members who are away on the day, regardless of the visibility of the absences
+    fn list_absent_member_ids(
+        &self,
+        session: &mut Session,
+        date: &NaiveDate,
+    ) -> BackendResult<Vec<i32>>;
+}
+
+/// Manages the venues at which events take place
+pub trait VenueRepository {
+    /// Creates a new venue and stores it into the database
+    fn create(&self, session: &mut Session, venue: Venue) -> BackendResult<()>;
+
+    /// Updates an existing venue in the database
+    fn update(&self, session: &mut Session, venue: Venue) -> BackendResult<()>;
+
+    /// Removes an existing venue from the database, events at the venue lose their venue
+    fn delete(&self, session: &mut Session, venue_id: i32) -> BackendResult<()>;
+
+    /// Finds a venue from the database using the identifier
+    fn find_by_id(&self, session: &mut Session, venue_id: i32) -> BackendResult<Venue>;
+
+    /// Lists all venues ordered by name
+    fn list(&self, session: &mut Session) -> BackendResult<Vec<Venue>>;
+}
+
 /// Manages the email template repository
 pub trait MailTemplateRepository {
     /// Creates a new email template and stores it into the database
@@ -321,6 +535,81 @@ pub trait MailTemplateRepository {
     /// Finds an email template from the database using the identifier
     fn find_by_id(&self, session: &mut Session, image_id: i32) -> BackendResult<MailTemplate>;
 
+    /// Locks the email template until the end of the transaction
+    fn lock_by_id(&self, session: &mut Session, template_id: i32) -> BackendResult<()>;
+
     /// Lists all email templates stored in the databases
     fn list(&self, session: &mut Session) -> BackendResult<Vec<(i32, String)>>;
 }
+
+/// Manages the revisions of the content of pages, the authors are returned alongside the
+/// revisions, unless they are no longer registered
+pub trait PageRevisionRepository {
+    /// Stores a new revision, returning its identifier
+    fn create(&self, session: &mut Session, revision: PageRevision) -> BackendResult<i32>;
+
+    /// Finds a single revision of a page
+    fn find_by_id(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        revision_id: i32,
+    ) -> BackendResult<(PageRevision, Option<MemberDetail>)>;
+
+    /// Lists the revisions of a page, newest first
+    fn list_by_page_id(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+    ) -> BackendResult<Vec<(PageRevision, Option<MemberDetail>)>>;
+
+    /// Verifies whether any revision of the page exists
+    fn exists_for_page(&self, session: &mut Session, page_id: i32) -> BackendResult<bool>;
+
+    /// Removes the revisions of a page beyond the newest `max_count` revisions (0 keeps all) and
+    /// those created before `created_before`. The newest revision is always kept.
+    fn prune(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        max_count: u32,
+        created_before: Option<NaiveDateTime>,
+    ) -> BackendResult<usize>;
+}
+
+/// Manages the history of job runs
+pub trait JobRunRepository {
+    /// Finds the most recent run of the job, if the job has ever run
+    fn find_last(&self, session: &mut Session, job_name: &str) -> BackendResult<Option<JobRun>>;
+
+    /// Lists the most recent runs of the job, newest first
+    fn list_recent(
+        &self,
+        session: &mut Session,
+        job_name: &str,
+        limit: i64,
+    ) -> BackendResult<Vec<JobRun>>;
+}
+
+/// Manages the personal calendar feeds of members
+pub trait CalendarFeedRepository {
+    /// Finds the calendar feed of the member, if the member has one
+    fn find_by_member_id(
+        &self,
+        session: &mut Session,
+        member_id: i32,
+    ) -> BackendResult<Option<CalendarFeed>>;
+
+    /// Finds the calendar feed using its token, provided its member is still activated
+    fn find_by_token(
+        &self,
+        session: &mut Session,
+        token: &str,
+    ) -> BackendResult<Option<CalendarFeed>>;
+
+    /// Stores the calendar feed, replacing the former feed of the member and thereby its token
+    fn save(&self, session: &mut Session, feed: CalendarFeed) -> BackendResult<()>;
+
+    /// Removes the calendar feed of the member, revoking its token
+    fn delete_by_member_id(&self, session: &mut Session, member_id: i32) -> BackendResult<()>;
+}
@@ -0,0 +1,145 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::model::primitives::AbsenceVisibility;
+use crate::model::storage::entities::MemberAbsence;
+use crate::model::storage::extended_entities::AbsentMember;
+use crate::repositories::definitions::AbsenceRepository;
+use crate::schema::*;
+use actix_web::web::Data;
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use std::sync::Arc;
+
+pub struct Implementation {}
+
+impl AbsenceRepository for Implementation {
+    fn create(&self, session: &mut Session, absence: MemberAbsence) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::insert_into(member_absences::table)
+                .values(absence)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn update(&self, session: &mut Session, absence: MemberAbsence) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::update(member_absences::table)
+                .filter(member_absences::id.eq(absence.id))
+                .set(absence)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn delete(&self, session: &mut Session, absence_id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::delete(member_absences::table)
+                .filter(member_absences::id.eq(absence_id))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn find_by_id(&self, session: &mut Session, absence_id: i32) -> BackendResult<MemberAbsence> {
+        session.run(|conn| {
+            let absence = member_absences::table
+                .filter(member_absences::id.eq(absence_id))
+                .select(MemberAbsence::as_select())
+                .first::<MemberAbsence>(conn)?;
+            Ok(absence)
+        })
+    }
+
+    fn list_by_member_id(
+        &self,
+        session: &mut Session,
+        member_id: i32,
+    ) -> BackendResult<Vec<MemberAbsence>> {
+        session.run(|conn| {
+            let absences = member_absences::table
+                .filter(member_absences::member_id.eq(member_id))
+                .order_by(member_absences::start_date)
+                .select(MemberAbsence::as_select())
+                .load::<MemberAbsence>(conn)?;
+            Ok(absences)
+        })
+    }
+
+    fn list_overlapping(
+        &self,
+        session: &mut Session,
+        start_date: &NaiveDate,
+        end_date: &NaiveDate,
+        visibilities: &[AbsenceVisibility],
+    ) -> BackendResult<Vec<AbsentMember>> {
+        session.run(|conn| {
+            let absences: Vec<(String, String, MemberAbsence)> = member_absences::table
+                .inner_join(members::table.inner_join(member_details::table))
+                .filter(member_absences::start_date.le(end_date))
+                .filter(member_absences::end_date.ge(start_date))
+                .filter(member_absences::visibility.eq_any(visibilities))
+                .order_by((
+                    member_absences::start_date,
+                    member_details::last_name,
+                    member_details::first_name,
+                ))
+                .select((
+                    member_details::first_name,
+                    member_details::last_name,
+                    MemberAbsence::as_select(),
+                ))
+                .load(conn)?;
+            Ok(absences
+                .into_iter()
+                .map(|(first_name, last_name, absence)| AbsentMember {
+                    first_name,
+                    last_name,
+                    absence,
+                })
+                .collect())
+        })
+    }
+
+    fn list_absent_member_ids(
+        &self,
+        session: &mut Session,
+        date: &NaiveDate,
+    ) -> BackendResult<Vec<i32>> {
+        session.run(|conn| {
+            let member_ids = member_absences::table
+                .filter(member_absences::start_date.le(date))
+                .filter(member_absences::end_date.ge(date))
+                .select(member_absences::member_id)
+                .distinct()
+                .load::<i32>(conn)?;
+            Ok(member_ids)
+        })
+    }
+}
+
+impl Injectable<(), dyn AbsenceRepository> for Implementation {
+    fn make(_: &()) -> Data<dyn AbsenceRepository> {
+        let arc: Arc<dyn AbsenceRepository> = Arc::new(Self {});
+        Data::from(arc)
+    }
+}
@@ -0,0 +1,240 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::model::primitives::Role;
+use crate::model::storage::entities::EventAttendance;
+use crate::model::storage::extended_entities::Attendee;
+use crate::repositories::definitions::AttendanceRepository;
+use crate::schema::*;
+use actix_web::web::Data;
+use chrono::{NaiveDate, Utc};
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use std::sync::Arc;
+
+pub struct Implementation {}
+
+impl AttendanceRepository for Implementation {
+    fn save(&self, session: &mut Session, attendance: EventAttendance) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::insert_into(event_attendances::table)
+                .values(&attendance)
+                .on_conflict((
+                    event_attendances::page_id,
+                    event_attendances::occurrence_date,
+                    event_attendances::member_id,
+                ))
+                .do_update()
+                .set((
+                    event_attendances::status.eq(excluded(event_attendances::status)),
+                    event_attendances::note.eq(excluded(event_attendances::note)),
+                    event_attendances::response_time.eq(excluded(event_attendances::response_time)),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn find(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        member_id: i32,
+    ) -> BackendResult<Option<EventAttendance>> {
+        session.run(|conn| {
+            let attendance = event_attendances::table
+                .filter(event_attendances::page_id.eq(page_id))
+                .filter(event_attendances::occurrence_date.eq(occurrence_date))
+                .filter(event_attendances::member_id.eq(member_id))
+                .select(EventAttendance::as_select())
+                .first::<EventAttendance>(conn)
+                .optional()?;
+            Ok(attendance)
+        })
+    }
+
+    fn list_attendees(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        page_roles: &[Role],
+    ) -> BackendResult<Vec<Attendee>> {
+        // Every member has the public and member roles, operators are left out as every page is
+        // accessible to them
+        let roles: Vec<Role> = page_roles
+            .iter()
+            .copied()
+            .filter(|role| *role != Role::Operator)
+            .collect();
+        let open_to_all = roles.contains(&Role::Public) || roles.contains(&Role::Member);
+
+        let rows = session.run(|conn| {
+            let mut query = members::table
+                .inner_join(member_details::table)
+                .left_join(musical_instruments::table)
+                .left_join(
+                    event_attendances::table.on(event_attendances::member_id
+                        .eq(members::id)
+                        .and(event_attendances::page_id.eq(page_id))
+                        .and(event_attendances::occurrence_date.eq(occurrence_date))),
+                )
+                .select((
+                    members::id,
+                    member_details::first_name,
+                    member_details::last_name,
+                    member_details::email_address,
+                    members::musical_instrument_id,
+                    musical_instruments::name.nullable(),
+                    event_attendances::all_columns.nullable(),
+                ))
+                .into_boxed();
+
+            let responded = event_attendances::member_id.is_not_null();
+            if open_to_all {
+                query = query.filter(members::activated.eq(true).or(responded));
+            } else {
+                let by_member_role = member_role_associations::table
+                    .filter(member_role_associations::system_role.eq_any(roles.clone()))
+                    .select(member_role_associations::member_id);
+                let by_workgroup_role = workgroup_member_relationships::table
+                    .filter(
+                        workgroup_member_relationships::workgroup_id.eq_any(
+                            workgroup_role_associations::table
+                                .filter(workgroup_role_associations::system_role.eq_any(roles))
+                                .select(workgroup_role_associations::workgroup_id),
+                        ),
+                    )
+                    .select(workgroup_member_relationships::member_id);
+                query = query.filter(
+                    members::activated
+                        .eq(true)
+                        .and(
+                            members::id
+                                .eq_any(by_member_role)
+                                .or(members::id.eq_any(by_workgroup_role)),
+                        )
+                        .or(responded),
+                );
+            }
+
+            Ok(query
+                .order_by((
+                    musical_instruments::name,
+                    member_details::last_name,
+                    member_details::first_name,
+                ))
+                .load::<(
+                    i32,
+                    String,
+                    String,
+                    String,
+                    Option<i32>,
+                    Option<String>,
+                    Option<EventAttendance>,
+                )>(conn)?)
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(
+                    member_id,
+                    first_name,
+                    last_name,
+                    email_address,
+                    musical_instrument_id,
+                    musical_instrument,
+                    attendance,
+                )| Attendee {
+                    member_id,
+                    first_name,
+                    last_name,
+                    email_address,
+                    musical_instrument_id,
+                    musical_instrument,
+                    attendance,
+                },
+            )
+            .collect())
+    }
+
+    fn list_reminded(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+    ) -> BackendResult<Vec<i32>> {
+        session.run(|conn| {
+            Ok(event_attendance_reminders::table
+                .filter(event_attendance_reminders::page_id.eq(page_id))
+                .filter(event_attendance_reminders::occurrence_date.eq(occurrence_date))
+                .select(event_attendance_reminders::member_id)
+                .load::<i32>(conn)?)
+        })
+    }
+
+    fn add_reminder(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        member_id: i32,
+    ) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::insert_into(event_attendance_reminders::table)
+                .values((
+                    event_attendance_reminders::page_id.eq(page_id),
+                    event_attendance_reminders::occurrence_date.eq(occurrence_date),
+                    event_attendance_reminders::member_id.eq(member_id),
+                    event_attendance_reminders::sending_time.eq(Utc::now().naive_utc()),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn remove_reminder(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        member_id: i32,
+    ) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::delete(event_attendance_reminders::table)
+                .filter(event_attendance_reminders::page_id.eq(page_id))
+                .filter(event_attendance_reminders::occurrence_date.eq(occurrence_date))
+                .filter(event_attendance_reminders::member_id.eq(member_id))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+impl Injectable<(), dyn AttendanceRepository> for Implementation {
+    fn make(_: &()) -> Data<dyn AttendanceRepository> {
+        let arc: Arc<dyn AttendanceRepository> = Arc::new(Self {});
+        Data::from(arc)
+    }
+}
@@ -0,0 +1,97 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::generic::result::BackendResult;
+use crate::generic::security::hash_token;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::model::storage::entities::CalendarFeed;
+use crate::repositories::definitions::CalendarFeedRepository;
+use crate::schema::{calendar_feeds, members};
+use actix_web::web::Data;
+use diesel::upsert::excluded;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper};
+use std::sync::Arc;
+
+pub struct Implementation {}
+
+impl CalendarFeedRepository for Implementation {
+    fn find_by_member_id(
+        &self,
+        session: &mut Session,
+        member_id: i32,
+    ) -> BackendResult<Option<CalendarFeed>> {
+        session.run(|conn| {
+            let result = calendar_feeds::table
+                .filter(calendar_feeds::member_id.eq(member_id))
+                .select(CalendarFeed::as_select())
+                .first::<CalendarFeed>(conn)
+                .optional()?;
+            Ok(result)
+        })
+    }
+
+    fn find_by_token(
+        &self,
+        session: &mut Session,
+        token: &str,
+    ) -> BackendResult<Option<CalendarFeed>> {
+        session.run(|conn| {
+            let result = calendar_feeds::table
+                .inner_join(members::table)
+                .filter(calendar_feeds::token_hash.eq(hash_token(token)))
+                .filter(members::activated.eq(true))
+                .select(CalendarFeed::as_select())
+                .first::<CalendarFeed>(conn)
+                .optional()?;
+            Ok(result)
+        })
+    }
+
+    fn save(&self, session: &mut Session, feed: CalendarFeed) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::insert_into(calendar_feeds::table)
+                .values(&feed)
+                .on_conflict(calendar_feeds::member_id)
+                .do_update()
+                .set((
+                    calendar_feeds::token_hash.eq(excluded(calendar_feeds::token_hash)),
+                    calendar_feeds::creation_time.eq(excluded(calendar_feeds::creation_time)),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn delete_by_member_id(&self, session: &mut Session, member_id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::delete(calendar_feeds::table)
+                .filter(calendar_feeds::member_id.eq(member_id))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+impl Injectable<(), dyn CalendarFeedRepository> for Implementation {
+    fn make(_: &()) -> Data<dyn CalendarFeedRepository> {
+        let arc: Arc<dyn CalendarFeedRepository> = Arc::new(Self {});
+        Data::from(arc)
+    }
+}
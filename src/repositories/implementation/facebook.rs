@@ -16,8 +16,8 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::SEARCH_PAGE_SIZE;
 use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::Settings;
 use crate::generic::{search_helpers, Injectable};
 use crate::model::storage::extended_entities::FacebookMember;
 use crate::repositories::definitions::FacebookRepository;
@@ -101,10 +101,10 @@ impl Implementation {
     }
 }
 
-impl Injectable<(), dyn FacebookRepository> for Implementation {
-    fn make(_: &()) -> Data<dyn FacebookRepository> {
+impl Injectable<Settings, dyn FacebookRepository> for Implementation {
+    fn make(settings: &Settings) -> Data<dyn FacebookRepository> {
         let arc: Arc<dyn FacebookRepository> = Arc::new(Self {
-            page_size: *SEARCH_PAGE_SIZE,
+            page_size: settings.search.page_size,
         });
         Data::from(arc)
     }
@@ -16,8 +16,8 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::SEARCH_PAGE_SIZE;
 use crate::generic::result::BackendResult;
+use crate::generic::settings::Settings;
 use crate::generic::storage::session::Session;
 use crate::generic::{search_helpers, Injectable};
 use crate::model::primitives::Role;
@@ -143,10 +143,10 @@ impl ImageRepository for Implementation {
     }
 }
 
-impl Injectable<(), dyn ImageRepository> for Implementation {
-    fn make(_: &()) -> Data<dyn ImageRepository> {
+impl Injectable<Settings, dyn ImageRepository> for Implementation {
+    fn make(settings: &Settings) -> Data<dyn ImageRepository> {
         let arc: Arc<dyn ImageRepository> = Arc::new(Self {
-            page_size: *SEARCH_PAGE_SIZE,
+            page_size: settings.search.page_size,
         });
         Data::from(arc)
     }
@@ -0,0 +1,68 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::model::storage::entities::JobRun;
+use crate::repositories::definitions::JobRunRepository;
+use crate::schema::job_runs;
+use actix_web::web::Data;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper};
+use std::sync::Arc;
+
+pub struct Implementation {}
+
+impl JobRunRepository for Implementation {
+    fn find_last(&self, session: &mut Session, job_name: &str) -> BackendResult<Option<JobRun>> {
+        session.run(|conn| {
+            let result = job_runs::table
+                .filter(job_runs::job_name.eq(job_name))
+                .order_by(job_runs::started_at.desc())
+                .select(JobRun::as_select())
+                .first::<JobRun>(conn)
+                .optional()?;
+            Ok(result)
+        })
+    }
+
+    fn list_recent(
+        &self,
+        session: &mut Session,
+        job_name: &str,
+        limit: i64,
+    ) -> BackendResult<Vec<JobRun>> {
+        session.run(|conn| {
+            let result = job_runs::table
+                .filter(job_runs::job_name.eq(job_name))
+                .order_by(job_runs::started_at.desc())
+                .limit(limit)
+                .select(JobRun::as_select())
+                .load::<JobRun>(conn)?;
+            Ok(result)
+        })
+    }
+}
+
+impl Injectable<(), dyn JobRunRepository> for Implementation {
+    fn make(_: &()) -> Data<dyn JobRunRepository> {
+        let arc: Arc<dyn JobRunRepository> = Arc::new(Self {});
+        Data::from(arc)
+    }
+}
@@ -68,6 +68,17 @@ impl MailTemplateRepository for Implementation {
         })
     }
 
+    fn lock_by_id(&self, session: &mut Session, template_id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            mail_templates::table
+                .filter(mail_templates::id.eq(template_id))
+                .select(mail_templates::id)
+                .for_update()
+                .first::<i32>(conn)?;
+            Ok(())
+        })
+    }
+
     fn list(&self, session: &mut Session) -> BackendResult<Vec<(i32, String)>> {
         session.run(|conn| {
             let result: Vec<(i32, String)> = mail_templates::table
@@ -16,8 +16,8 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::SEARCH_PAGE_SIZE;
 use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::Settings;
 use crate::generic::storage::database::DatabaseConnection;
 use crate::generic::storage::session::Session;
 use crate::generic::{search_helpers, Injectable};
@@ -31,8 +31,8 @@ use crate::schema::{
 };
 use actix_web::web::Data;
 use diesel::{
-    BoolExpressionMethods, Connection, ExpressionMethods, PgTextExpressionMethods, QueryDsl,
-    RunQueryDsl, SelectableHelper,
+    BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension,
+    PgTextExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper,
 };
 use std::sync::Arc;
 
@@ -95,6 +95,17 @@ impl MemberRepository for Implementation {
         })
     }
 
+    fn lock_by_id(&self, session: &mut Session, id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            members::table
+                .filter(members::id.eq(id))
+                .select(members::id)
+                .for_update()
+                .first::<i32>(conn)?;
+            Ok(())
+        })
+    }
+
     fn find_extended_by_activation_string(
         &self,
         session: &mut Session,
@@ -165,6 +176,23 @@ impl MemberRepository for Implementation {
         })
     }
 
+    fn find_id_by_email_address(
+        &self,
+        session: &mut Session,
+        email_address: &str,
+    ) -> BackendResult<Option<i32>> {
+        session.run(|conn| {
+            let member_id = members::table
+                .inner_join(member_details::table)
+                .filter(members::activated.eq(true))
+                .filter(member_details::email_address.eq(email_address))
+                .select(members::id)
+                .first::<i32>(conn)
+                .optional()?;
+            Ok(member_id)
+        })
+    }
+
     fn find_workgroups(&self, session: &mut Session, id: i32) -> BackendResult<Vec<Workgroup>> {
         session.run(|conn| {
             let result: Vec<Workgroup> = QueryDsl::select(
@@ -188,6 +216,7 @@ impl MemberRepository for Implementation {
                     members::musical_instrument_id.eq(member.musical_instrument_id.clone()),
                     members::description.eq(member.description.clone()),
                     members::allow_privacy_info_sharing.eq(member.allow_privacy_info_sharing),
+                    members::etag.eq(crate::generate_asset_id()),
                 ))
                 .execute(conn)?;
 
@@ -359,10 +388,10 @@ impl Implementation {
     }
 }
 
-impl Injectable<(), dyn MemberRepository> for Implementation {
-    fn make(_: &()) -> Data<dyn MemberRepository> {
+impl Injectable<Settings, dyn MemberRepository> for Implementation {
+    fn make(settings: &Settings) -> Data<dyn MemberRepository> {
         let arc: Arc<dyn MemberRepository> = Arc::new(Self {
-            page_size: *SEARCH_PAGE_SIZE,
+            page_size: settings.search.page_size,
         });
         Data::from(arc)
     }
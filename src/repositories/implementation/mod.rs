@@ -16,15 +16,21 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+pub mod absence;
+pub mod attendance;
 pub mod authorization;
+pub mod calendar_feed;
 pub mod facebook;
 pub mod image;
+pub mod job_run;
 pub mod mail_template;
 pub mod member;
 pub mod member_picture;
 pub mod member_role;
 pub mod musical_instrument;
 pub mod page;
+pub mod page_revision;
 pub mod properties;
+pub mod venue;
 pub mod workgroup;
 pub mod workgroup_role;
@@ -16,8 +16,8 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::SEARCH_PAGE_SIZE;
 use crate::generic::result::BackendResult;
+use crate::generic::settings::Settings;
 use crate::generic::storage::session::Session;
 use crate::generic::{search_helpers, Injectable};
 use crate::model::storage::entities::MusicalInstrument;
@@ -71,6 +71,17 @@ impl MusicalInstrumentRepository for Implementation {
         })
     }
 
+    fn lock_by_id(&self, session: &mut Session, instrument_id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            musical_instruments::table
+                .filter(musical_instruments::id.eq(instrument_id))
+                .select(musical_instruments::id)
+                .for_update()
+                .first::<i32>(conn)?;
+            Ok(())
+        })
+    }
+
     fn search(
         &self,
         session: &mut Session,
@@ -98,10 +109,10 @@ impl MusicalInstrumentRepository for Implementation {
     }
 }
 
-impl Injectable<(), dyn MusicalInstrumentRepository> for Implementation {
-    fn make(_: &()) -> Data<dyn MusicalInstrumentRepository> {
+impl Injectable<Settings, dyn MusicalInstrumentRepository> for Implementation {
+    fn make(settings: &Settings) -> Data<dyn MusicalInstrumentRepository> {
         let arc: Arc<dyn MusicalInstrumentRepository> = Arc::new(Self {
-            page_size: *SEARCH_PAGE_SIZE,
+            page_size: settings.search.page_size,
         });
         Data::from(arc)
     }
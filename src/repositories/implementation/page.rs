@@ -16,22 +16,29 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::SEARCH_PAGE_SIZE;
-use crate::generic::result::BackendResult;
+use crate::generic::rendering::Shortcode;
+use crate::generic::result::{BackendError, BackendResult};
 use crate::generic::security::ClaimRoles;
+use crate::generic::settings::Settings;
 use crate::generic::storage::session::Session;
 use crate::generic::{search_helpers, Injectable};
 use crate::model::primitives::Role;
-use crate::model::storage::entities::Page;
+use crate::model::storage::entities::{Page, PageEvent, Venue};
+use crate::model::storage::extended_entities::EventOccurrence;
+use crate::model::traits::RoleContainer;
 use crate::repositories::definitions::PageRepository;
+use crate::schema::sql_types::Tsvector;
 use crate::schema::*;
 use actix_web::web::Data;
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveTime, Utc};
 use diesel::debug_query;
-use diesel::dsl::exists;
+use diesel::dsl::{exists, not};
 use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::query_builder::QueryId;
+use diesel::sql_types::{Bool, Nullable, SqlType, Text};
 use log::info;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 pub struct Implementation {
@@ -61,6 +68,20 @@ impl PageRepository for Implementation {
         })
     }
 
+    fn touch(&self, session: &mut Session, page_id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::update(pages::table)
+                .filter(pages::id.eq(page_id))
+                .set((
+                    pages::etag.eq(crate::generate_asset_id()),
+                    pages::last_modified.eq(Utc::now().naive_utc()),
+                    pages::change_sequence.eq(pages::change_sequence + 1),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
     fn set_order_by_id(
         &self,
         session: &mut Session,
@@ -101,6 +122,120 @@ impl PageRepository for Implementation {
         })
     }
 
+    fn lock_by_id(&self, session: &mut Session, page_id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            pages::table
+                .filter(pages::id.eq(page_id))
+                .select(pages::id)
+                .for_update()
+                .first::<i32>(conn)?;
+            Ok(())
+        })
+    }
+
+    fn find_by_path(&self, session: &mut Session, slugs: &[&str]) -> BackendResult<Option<i32>> {
+        session.run(|conn| {
+            let mut page_id = None;
+            for slug in slugs {
+                let query = pages::table
+                    .filter(pages::slug.eq(*slug))
+                    .select(pages::id)
+                    .into_boxed();
+                let query = match page_id {
+                    Some(parent_id) => query.filter(pages::parent_id.eq(parent_id)),
+                    None => query.filter(pages::parent_id.is_null()),
+                };
+                match query.first::<i32>(conn).optional()? {
+                    Some(id) => page_id = Some(id),
+                    None => return Ok(None),
+                }
+            }
+            Ok(page_id)
+        })
+    }
+
+    fn find_path_by_id(&self, session: &mut Session, page_id: i32) -> BackendResult<String> {
+        session.run(|conn| {
+            let mut slugs = Vec::new();
+            let mut visited = HashSet::new();
+            let mut current = Some(page_id);
+            while let Some(id) = current {
+                if !visited.insert(id) {
+                    return Err(BackendError::conflict(format!(
+                        "Page {id} is a sub page of itself"
+                    )));
+                }
+                let (parent_id, slug): (Option<i32>, String) = pages::table
+                    .filter(pages::id.eq(id))
+                    .select((pages::parent_id, pages::slug))
+                    .first(conn)?;
+                slugs.push(slug);
+                current = parent_id;
+            }
+            slugs.reverse();
+            Ok(slugs.join("/"))
+        })
+    }
+
+    fn slug_exists(
+        &self,
+        session: &mut Session,
+        parent_id: Option<i32>,
+        slug: &str,
+        page_id: i32,
+    ) -> BackendResult<bool> {
+        session.run(|conn| {
+            let query = pages::table
+                .filter(pages::slug.eq(slug).and(pages::id.ne(page_id)))
+                .into_boxed();
+            let query = match parent_id {
+                Some(parent_id) => query.filter(pages::parent_id.eq(parent_id)),
+                None => query.filter(pages::parent_id.is_null()),
+            };
+            Ok(query.count().get_result::<i64>(conn)? > 0)
+        })
+    }
+
+    fn set_slug_by_id(&self, session: &mut Session, page_id: i32, slug: &str) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::update(pages::table)
+                .filter(pages::id.eq(page_id))
+                .set(pages::slug.eq(slug))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn add_redirect(&self, session: &mut Session, path: &str, page_id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::insert_into(page_redirects::table)
+                .values((
+                    page_redirects::path.eq(path),
+                    page_redirects::page_id.eq(page_id),
+                    page_redirects::creation_time.eq(Utc::now().naive_utc()),
+                ))
+                .on_conflict(page_redirects::path)
+                .do_update()
+                .set((
+                    page_redirects::page_id.eq(page_id),
+                    page_redirects::creation_time.eq(Utc::now().naive_utc()),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn find_redirect(&self, session: &mut Session, path: &str) -> BackendResult<Option<i32>> {
+        session.run(|conn| {
+            let page_id = page_redirects::table
+                .filter(page_redirects::path.eq(path))
+                .select(page_redirects::page_id)
+                .first::<i32>(conn)
+                .optional()?;
+            Ok(page_id)
+        })
+    }
+
     fn list_by_parent_id(
         &self,
         session: &mut Session,
@@ -123,7 +258,8 @@ impl PageRepository for Implementation {
                         pages::parent_id
                             .eq(parent_id)
                             .or(pages::parent_id.is_null())
-                            .and(exists(sub_table)),
+                            .and(exists(sub_table))
+                            .and(publication_window(roles)),
                     )
                     .select(Page::as_select())
                     .order_by(pages::order_number);
@@ -131,7 +267,12 @@ impl PageRepository for Implementation {
                 q.load(conn)?
             } else {
                 let q = pages::table
-                    .filter(pages::parent_id.eq(parent_id).and(exists(sub_table)))
+                    .filter(
+                        pages::parent_id
+                            .eq(parent_id)
+                            .and(exists(sub_table))
+                            .and(publication_window(roles)),
+                    )
                     .select(Page::as_select())
                     .order_by(pages::order_number);
                 info!("{}", debug_query::<Pg, _>(&q).to_string());
@@ -142,6 +283,35 @@ impl PageRepository for Implementation {
         })
     }
 
+    fn list_visible(&self, session: &mut Session, roles: &ClaimRoles) -> BackendResult<Vec<Page>> {
+        session.run(|conn| {
+            let sub_table = page_access_policies::table
+                .select(page_access_policies::page_id)
+                .distinct()
+                .filter(
+                    roles
+                        .generate_policy_expression(&page_access_policies::system_role)
+                        .and(page_access_policies::page_id.eq(pages::id)),
+                );
+
+            let pages = pages::table
+                .filter(exists(sub_table).and(publication_window(roles)))
+                .select(Page::as_select())
+                .order_by((pages::order_number, pages::id))
+                .load(conn)?;
+            Ok(pages)
+        })
+    }
+
+    fn list_hierarchy(&self, session: &mut Session) -> BackendResult<Vec<(i32, Option<i32>)>> {
+        session.run(|conn| {
+            let hierarchy = pages::table
+                .select((pages::id, pages::parent_id))
+                .load(conn)?;
+            Ok(hierarchy)
+        })
+    }
+
     fn find_associated_roles_by_id(
         &self,
         session: &mut Session,
@@ -157,6 +327,80 @@ impl PageRepository for Implementation {
         })
     }
 
+    fn add_references(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        shortcodes: &[Shortcode],
+    ) -> BackendResult<()> {
+        let (image_ids, event_page_ids) = split_shortcodes(shortcodes);
+        session.run(|conn| {
+            if !image_ids.is_empty() {
+                let references: Vec<_> = image_ids
+                    .iter()
+                    .map(|image_id| {
+                        (
+                            page_image_references::page_id.eq(page_id),
+                            page_image_references::image_id.eq(image_id),
+                        )
+                    })
+                    .collect();
+                diesel::insert_into(page_image_references::table)
+                    .values(references)
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+            if !event_page_ids.is_empty() {
+                let references: Vec<_> = event_page_ids
+                    .iter()
+                    .map(|event_page_id| {
+                        (
+                            page_event_references::page_id.eq(page_id),
+                            page_event_references::event_page_id.eq(event_page_id),
+                        )
+                    })
+                    .collect();
+                diesel::insert_into(page_event_references::table)
+                    .values(references)
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn find_referring_content_assets(
+        &self,
+        session: &mut Session,
+        shortcodes: &[Shortcode],
+    ) -> BackendResult<Vec<String>> {
+        let (image_ids, event_page_ids) = split_shortcodes(shortcodes);
+        session.run(|conn| {
+            let mut content_assets: Vec<(String, Option<String>)> = page_image_references::table
+                .inner_join(pages::table)
+                .filter(page_image_references::image_id.eq_any(&image_ids))
+                .select((pages::content_asset, pages::draft_content_asset))
+                .load(conn)?;
+            content_assets.extend(
+                page_event_references::table
+                    .inner_join(pages::table)
+                    .filter(page_event_references::event_page_id.eq_any(&event_page_ids))
+                    .select((pages::content_asset, pages::draft_content_asset))
+                    .load::<(String, Option<String>)>(conn)?,
+            );
+
+            let mut asset_ids: Vec<String> = content_assets
+                .into_iter()
+                .flat_map(|(content_asset, draft_content_asset)| {
+                    std::iter::once(content_asset).chain(draft_content_asset)
+                })
+                .collect();
+            asset_ids.sort();
+            asset_ids.dedup();
+            Ok(asset_ids)
+        })
+    }
+
     fn delete(&self, session: &mut Session, page_id: i32) -> BackendResult<()> {
         session.run(|conn| {
             diesel::delete(pages::table)
@@ -210,8 +454,9 @@ impl PageRepository for Implementation {
         page_offset: usize,
         term: &str,
         roles: &ClaimRoles,
-    ) -> BackendResult<(usize, usize, Vec<Page>)> {
-        let like_search_string = search_helpers::create_like_string(term);
+    ) -> BackendResult<(usize, usize, Vec<(Page, f32, String)>)> {
+        let term = term.trim().to_owned();
+        let like_search_string = search_helpers::create_like_string(&term);
         let (total_count, pages) = session.run(|conn| {
             let sub_table =
                 QueryDsl::select(page_access_policies::table, page_access_policies::page_id)
@@ -222,48 +467,80 @@ impl PageRepository for Implementation {
                             .and(page_access_policies::page_id.eq(pages::id)),
                     );
 
-            let where_expression = pages::title
-                .ilike(like_search_string)
-                .and(exists(sub_table));
+            // Partial words still match on the title, as they did before content was indexed
+            let term_expression: Box<dyn BoxableExpression<pages::table, Pg, SqlType = Bool>> =
+                if term.is_empty() {
+                    Box::new(true.into_sql::<Bool>())
+                } else {
+                    Box::new(
+                        Matches::new(pages::search_vector, page_search_query(term.clone()))
+                            .or(pages::title.ilike(like_search_string)),
+                    )
+                };
+
+            let where_expression = term_expression
+                .and(exists(sub_table))
+                .and(publication_window(roles));
 
             let total_count: usize = pages::table
                 .filter(&where_expression)
                 .count()
                 .get_result::<i64>(conn)? as usize;
 
-            let result: Vec<(Page,)> = QueryDsl::select(
+            let rank = ts_rank(pages::search_vector, page_search_query(term.clone()));
+            let snippet = page_search_snippet(pages::search_text, page_search_query(term.clone()));
+            let result: Vec<(Page, f32, String)> = QueryDsl::select(
                 QueryDsl::limit(
                     pages::table
                         .filter(&where_expression)
-                        .order_by(pages::order_number),
+                        .order_by((rank.clone().desc(), pages::order_number)),
                     self.page_size as i64,
                 )
                 .offset((page_offset * self.page_size) as i64),
-                (Page::as_select(),),
+                (Page::as_select(), rank, snippet),
             )
             .load(conn)?;
 
-            Ok((total_count, result.iter().map(|(p,)| p.clone()).collect()))
+            Ok((total_count, result))
         })?;
         Ok((total_count, self.page_size, pages))
     }
 
+    fn set_search_text(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        search_text: &str,
+    ) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::update(pages::table)
+                .filter(pages::id.eq(page_id))
+                .set(pages::search_text.eq(search_text))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
     fn find_events(
         &self,
         session: &mut Session,
         roles: &ClaimRoles,
         start_date: &NaiveDate,
         end_date: &NaiveDate,
-    ) -> BackendResult<Vec<Page>> {
-        let mut pages = session.run(|conn| {
-            let sub_table =
+    ) -> BackendResult<Vec<EventOccurrence>> {
+        let (pages, timed_pages, events, venues, exceptions) = session.run(|conn| {
+            let sub_table = || {
                 QueryDsl::select(page_access_policies::table, page_access_policies::page_id)
                     .distinct()
                     .filter(
                         roles
                             .generate_policy_expression(&page_access_policies::system_role)
                             .and(page_access_policies::page_id.eq(pages::id)),
-                    );
+                    )
+            };
+            // Pages with event times are expanded below, their event dates only describe the
+            // first occurrence
+            let timed = page_events::table.filter(page_events::page_id.eq(pages::id));
 
             let event_date_filter = pages::event_date.ge(start_date).or(pages::event_date
                 .lt(start_date)
@@ -271,36 +548,228 @@ impl PageRepository for Implementation {
 
             let where_expression = event_date_filter
                 .and(pages::event_date.le(end_date))
-                .and(exists(sub_table));
+                .and(exists(sub_table()))
+                .and(publication_window(roles))
+                .and(not(exists(timed)));
 
             let result = debug_query::<Pg, _>(&where_expression);
             info!("{}", result.to_string());
 
-            Ok(pages::table
+            let pages = pages::table
                 .filter(&where_expression)
+                .select(Page::as_select())
                 .order_by(pages::event_date)
-                .load::<Page>(conn)?)
-        });
+                .load::<Page>(conn)?;
+
+            let window_end = end_date
+                .succ_opt()
+                .unwrap_or(NaiveDate::MAX)
+                .and_time(NaiveTime::MIN);
+            let occurring = page_events::table
+                .filter(page_events::page_id.eq(pages::id))
+                .filter(page_events::starts_at.lt(window_end))
+                .filter(
+                    page_events::last_date
+                        .is_null()
+                        .or(page_events::last_date.ge(start_date).assume_not_null()),
+                );
+            let timed_pages = pages::table
+                .filter(exists(occurring))
+                .filter(exists(sub_table()))
+                .filter(publication_window(roles))
+                .select(Page::as_select())
+                .load::<Page>(conn)?;
 
-        // If there are pages with no end event date, set the end event date to the event date
-        if let Ok(pages) = pages.as_mut() {
-            for page in &mut pages.iter_mut() {
-                if let Some(event_date) = &page.event_date {
-                    if page.end_event_date.is_none() {
-                        page.end_event_date = Some(event_date.clone())
-                    }
+            let page_ids: Vec<i32> = timed_pages.iter().map(|page| page.id).collect();
+            let events = page_events::table
+                .filter(page_events::page_id.eq_any(page_ids.clone()))
+                .select(PageEvent::as_select())
+                .load::<PageEvent>(conn)?;
+            let venue_ids: Vec<i32> = events.iter().filter_map(|event| event.venue_id).collect();
+            let venues = venues::table
+                .filter(venues::id.eq_any(venue_ids))
+                .select(Venue::as_select())
+                .load::<Venue>(conn)?;
+            let exceptions = page_event_exceptions::table
+                .filter(page_event_exceptions::page_id.eq_any(page_ids))
+                .select((
+                    page_event_exceptions::page_id,
+                    page_event_exceptions::occurrence_date,
+                ))
+                .load::<(i32, NaiveDate)>(conn)?;
+            Ok((pages, timed_pages, events, venues, exceptions))
+        })?;
+
+        // Pages without an end event date last a single day
+        let mut occurrences: Vec<EventOccurrence> = pages
+            .into_iter()
+            .map(|mut page| {
+                if page.end_event_date.is_none() {
+                    page.end_event_date = page.event_date;
                 }
-            }
+                EventOccurrence { page, times: None }
+            })
+            .collect();
+
+        for page in &timed_pages {
+            let Some(event) = events.iter().find(|event| event.page_id == page.id) else {
+                continue;
+            };
+            let venue = event
+                .venue_id
+                .and_then(|venue_id| venues.iter().find(|venue| venue.id == venue_id));
+            let exceptions: Vec<NaiveDate> = exceptions
+                .iter()
+                .filter(|(page_id, _)| *page_id == page.id)
+                .map(|(_, date)| *date)
+                .collect();
+            occurrences.extend(EventOccurrence::expand(
+                page,
+                event,
+                venue,
+                &exceptions,
+                *start_date,
+                *end_date,
+            ));
         }
 
-        pages
+        occurrences.sort_by_key(|occurrence| {
+            (
+                occurrence.page.event_date,
+                occurrence.times.as_ref().map(|times| times.starts_at),
+            )
+        });
+        Ok(occurrences)
+    }
+
+    fn find_event(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+    ) -> BackendResult<Option<(PageEvent, Vec<NaiveDate>)>> {
+        session.run(|conn| {
+            let Some(event) = page_events::table
+                .filter(page_events::page_id.eq(page_id))
+                .select(PageEvent::as_select())
+                .first::<PageEvent>(conn)
+                .optional()?
+            else {
+                return Ok(None);
+            };
+            let exceptions = page_event_exceptions::table
+                .filter(page_event_exceptions::page_id.eq(page_id))
+                .select(page_event_exceptions::occurrence_date)
+                .order_by(page_event_exceptions::occurrence_date)
+                .load::<NaiveDate>(conn)?;
+            Ok(Some((event, exceptions)))
+        })
+    }
+
+    fn set_event(
+        &self,
+        session: &mut Session,
+        event: PageEvent,
+        exceptions: &[NaiveDate],
+    ) -> BackendResult<()> {
+        session.run(|conn| {
+            let page_id = event.page_id;
+            diesel::insert_into(page_events::table)
+                .values(&event)
+                .on_conflict(page_events::page_id)
+                .do_update()
+                .set(&event)
+                .execute(conn)?;
+
+            diesel::delete(page_event_exceptions::table)
+                .filter(page_event_exceptions::page_id.eq(page_id))
+                .execute(conn)?;
+            let exceptions: Vec<_> = exceptions
+                .iter()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .map(|date| {
+                    (
+                        page_event_exceptions::page_id.eq(page_id),
+                        page_event_exceptions::occurrence_date.eq(*date),
+                    )
+                })
+                .collect();
+            diesel::insert_into(page_event_exceptions::table)
+                .values(&exceptions)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn delete_event(&self, session: &mut Session, page_id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::delete(page_events::table)
+                .filter(page_events::page_id.eq(page_id))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+diesel::infix_operator!(Matches, " @@ ", backend: Pg);
+
+/// A parsed full-text search query
+#[derive(QueryId, SqlType)]
+#[diesel(postgres_type(name = "tsquery", schema = "pg_catalog"))]
+pub struct Tsquery;
+
+diesel::define_sql_function! {
+    /// Parses the search term, using both the Dutch and English text search configurations
+    fn page_search_query(term: Text) -> Tsquery;
+}
+
+diesel::define_sql_function! {
+    fn ts_rank(vector: Tsvector, query: Tsquery) -> Float;
+}
+
+diesel::define_sql_function! {
+    /// Returns the fragments of the search text matching the query, see
+    /// [rendering::highlight](crate::generic::rendering::highlight)
+    fn page_search_snippet(document: Nullable<Text>, query: Tsquery) -> Text;
+}
+
+/// Limits the pages to those within their publication window, operators see all pages
+fn publication_window(
+    roles: &ClaimRoles,
+) -> Box<dyn BoxableExpression<pages::table, Pg, SqlType = Bool>> {
+    if roles.has_role(Role::Operator) {
+        return Box::new(true.into_sql::<Bool>());
+    }
+    let now = Utc::now().naive_utc();
+    Box::new(
+        pages::publish_at
+            .is_null()
+            .or(pages::publish_at.le(now).assume_not_null())
+            .and(
+                pages::unpublish_at
+                    .is_null()
+                    .or(pages::unpublish_at.gt(now).assume_not_null()),
+            ),
+    )
+}
+
+/// Splits the shortcodes into the referred image identifiers and event page identifiers
+fn split_shortcodes(shortcodes: &[Shortcode]) -> (Vec<i32>, Vec<i32>) {
+    let mut image_ids = Vec::new();
+    let mut event_page_ids = Vec::new();
+    for shortcode in shortcodes {
+        match *shortcode {
+            Shortcode::Image(image_id) => image_ids.push(image_id),
+            Shortcode::Event(page_id) => event_page_ids.push(page_id),
+        }
     }
+    (image_ids, event_page_ids)
 }
 
-impl Injectable<(), dyn PageRepository> for Implementation {
-    fn make(_: &()) -> Data<dyn PageRepository> {
+impl Injectable<Settings, dyn PageRepository> for Implementation {
+    fn make(settings: &Settings) -> Data<dyn PageRepository> {
         let arc: Arc<dyn PageRepository> = Arc::new(Self {
-            page_size: *SEARCH_PAGE_SIZE,
+            page_size: settings.search.page_size,
         });
         Data::from(arc)
     }
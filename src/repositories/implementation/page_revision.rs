@@ -0,0 +1,197 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::model::storage::entities::{MemberDetail, PageRevision};
+use crate::repositories::definitions::PageRevisionRepository;
+use crate::schema::{member_details, members, page_revisions};
+use actix_web::web::Data;
+use chrono::NaiveDateTime;
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use std::sync::Arc;
+
+pub struct Implementation {}
+
+impl PageRevisionRepository for Implementation {
+    fn create(&self, session: &mut Session, revision: PageRevision) -> BackendResult<i32> {
+        session.run(|conn| {
+            let revision_id: i32 = diesel::insert_into(page_revisions::table)
+                .values(revision)
+                .returning(page_revisions::id)
+                .get_result(conn)?;
+            Ok(revision_id)
+        })
+    }
+
+    fn find_by_id(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        revision_id: i32,
+    ) -> BackendResult<(PageRevision, Option<MemberDetail>)> {
+        session.run(|conn| {
+            let result = page_revisions::table
+                .left_join(members::table.inner_join(member_details::table))
+                .filter(page_revisions::page_id.eq(page_id))
+                .filter(page_revisions::id.eq(revision_id))
+                .select((
+                    PageRevision::as_select(),
+                    Option::<MemberDetail>::as_select(),
+                ))
+                .first::<(PageRevision, Option<MemberDetail>)>(conn)?;
+            Ok(result)
+        })
+    }
+
+    fn list_by_page_id(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+    ) -> BackendResult<Vec<(PageRevision, Option<MemberDetail>)>> {
+        session.run(|conn| {
+            let result = page_revisions::table
+                .left_join(members::table.inner_join(member_details::table))
+                .filter(page_revisions::page_id.eq(page_id))
+                .order_by((
+                    page_revisions::creation_time.desc(),
+                    page_revisions::id.desc(),
+                ))
+                .select((
+                    PageRevision::as_select(),
+                    Option::<MemberDetail>::as_select(),
+                ))
+                .load::<(PageRevision, Option<MemberDetail>)>(conn)?;
+            Ok(result)
+        })
+    }
+
+    fn exists_for_page(&self, session: &mut Session, page_id: i32) -> BackendResult<bool> {
+        session.run(|conn| {
+            let revisions = page_revisions::table.filter(page_revisions::page_id.eq(page_id));
+            Ok(diesel::select(exists(revisions)).get_result(conn)?)
+        })
+    }
+
+    fn prune(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        max_count: u32,
+        created_before: Option<NaiveDateTime>,
+    ) -> BackendResult<usize> {
+        session.run(|conn| {
+            let newest_first: Vec<(i32, NaiveDateTime)> = page_revisions::table
+                .filter(page_revisions::page_id.eq(page_id))
+                .order_by((
+                    page_revisions::creation_time.desc(),
+                    page_revisions::id.desc(),
+                ))
+                .select((page_revisions::id, page_revisions::creation_time))
+                .load(conn)?;
+
+            let expired = expired_revisions(&newest_first, max_count, created_before);
+            if expired.is_empty() {
+                return Ok(0);
+            }
+
+            let deleted = diesel::delete(page_revisions::table)
+                .filter(page_revisions::id.eq_any(expired))
+                .execute(conn)?;
+            Ok(deleted)
+        })
+    }
+}
+
+/// Selects the revisions to prune from the revisions of a page, ordered newest first. The
+/// newest revision is always kept, the others are pruned if they exceed the maximum count (0
+/// meaning unlimited) or were created before the given moment.
+fn expired_revisions(
+    newest_first: &[(i32, NaiveDateTime)],
+    max_count: u32,
+    created_before: Option<NaiveDateTime>,
+) -> Vec<i32> {
+    newest_first
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(index, (_, creation_time))| {
+            (max_count > 0 && *index >= max_count as usize)
+                || created_before.is_some_and(|before| *creation_time < before)
+        })
+        .map(|(_, (revision_id, _))| *revision_id)
+        .collect()
+}
+
+impl Injectable<(), dyn PageRevisionRepository> for Implementation {
+    fn make(_: &()) -> Data<dyn PageRevisionRepository> {
+        let arc: Arc<dyn PageRevisionRepository> = Arc::new(Self {});
+        Data::from(arc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn day(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 1, day)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    /// Five revisions, with identifiers 5 down to 1, created on the day equal to their identifier
+    fn revisions() -> Vec<(i32, NaiveDateTime)> {
+        (1..=5).rev().map(|id| (id, day(id as u32))).collect()
+    }
+
+    #[test]
+    fn keeps_everything_without_limits() {
+        assert!(expired_revisions(&revisions(), 0, None).is_empty());
+    }
+
+    #[test]
+    fn keeps_the_newest_revisions_up_to_the_maximum_count() {
+        assert_eq!(expired_revisions(&revisions(), 2, None), [3, 2, 1]);
+        assert!(expired_revisions(&revisions(), 5, None).is_empty());
+    }
+
+    #[test]
+    fn prunes_revisions_created_before_the_moment() {
+        assert_eq!(expired_revisions(&revisions(), 0, Some(day(3))), [2, 1]);
+    }
+
+    #[test]
+    fn combines_count_and_age() {
+        assert_eq!(expired_revisions(&revisions(), 4, Some(day(3))), [2, 1]);
+        assert_eq!(expired_revisions(&revisions(), 2, Some(day(2))), [3, 2, 1]);
+    }
+
+    #[test]
+    fn always_keeps_the_head() {
+        assert_eq!(
+            expired_revisions(&revisions(), 1, Some(day(31))),
+            [4, 3, 2, 1]
+        );
+        assert!(expired_revisions(&revisions()[..1], 1, Some(day(31))).is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::model::storage::entities::Venue;
+use crate::repositories::definitions::VenueRepository;
+use crate::schema::*;
+use actix_web::web::Data;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use std::sync::Arc;
+
+pub struct Implementation {}
+
+impl VenueRepository for Implementation {
+    fn create(&self, session: &mut Session, venue: Venue) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::insert_into(venues::table)
+                .values(venue)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn update(&self, session: &mut Session, venue: Venue) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::update(venues::table)
+                .filter(venues::id.eq(venue.id))
+                .set(venue)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn delete(&self, session: &mut Session, venue_id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            diesel::delete(venues::table)
+                .filter(venues::id.eq(venue_id))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn find_by_id(&self, session: &mut Session, venue_id: i32) -> BackendResult<Venue> {
+        session.run(|conn| {
+            let venue = venues::table
+                .filter(venues::id.eq(venue_id))
+                .select(Venue::as_select())
+                .first::<Venue>(conn)?;
+            Ok(venue)
+        })
+    }
+
+    fn list(&self, session: &mut Session) -> BackendResult<Vec<Venue>> {
+        session.run(|conn| {
+            let venues = venues::table
+                .order_by(venues::name)
+                .select(Venue::as_select())
+                .load::<Venue>(conn)?;
+            Ok(venues)
+        })
+    }
+}
+
+impl Injectable<(), dyn VenueRepository> for Implementation {
+    fn make(_: &()) -> Data<dyn VenueRepository> {
+        let arc: Arc<dyn VenueRepository> = Arc::new(Self {});
+        Data::from(arc)
+    }
+}
@@ -16,8 +16,8 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::SEARCH_PAGE_SIZE;
 use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::Settings;
 use crate::generic::storage::database::DatabaseConnection;
 use crate::generic::storage::session::Session;
 use crate::generic::{search_helpers, Injectable};
@@ -64,6 +64,17 @@ impl WorkgroupRepository for Implementation {
         })
     }
 
+    fn lock_by_id(&self, session: &mut Session, id: i32) -> BackendResult<()> {
+        session.run(|conn| {
+            workgroups::table
+                .filter(workgroups::id.eq(id))
+                .select(workgroups::id)
+                .for_update()
+                .first::<i32>(conn)?;
+            Ok(())
+        })
+    }
+
     fn save(&self, session: &mut Session, workgroup: Workgroup) -> BackendResult<()> {
         session.run(|conn| {
             diesel::update(workgroups::table)
@@ -286,10 +297,10 @@ impl Implementation {
     }
 }
 
-impl Injectable<(), dyn WorkgroupRepository> for Implementation {
-    fn make(_: &()) -> Data<dyn WorkgroupRepository> {
+impl Injectable<Settings, dyn WorkgroupRepository> for Implementation {
+    fn make(settings: &Settings) -> Data<dyn WorkgroupRepository> {
         let arc: Arc<dyn WorkgroupRepository> = Arc::new(Self {
-            page_size: *SEARCH_PAGE_SIZE,
+            page_size: settings.search.page_size,
         });
         Data::from(arc)
     }
@@ -19,6 +19,40 @@
 
 // @generated automatically by Diesel CLI.
 
+pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "tsvector", schema = "pg_catalog"))]
+    pub struct Tsvector;
+}
+
+diesel::table! {
+    calendar_feeds (member_id) {
+        member_id -> Int4,
+        token_hash -> Varchar,
+        creation_time -> Timestamp,
+    }
+}
+
+diesel::table! {
+    event_attendance_reminders (page_id, occurrence_date, member_id) {
+        page_id -> Int4,
+        occurrence_date -> Date,
+        member_id -> Int4,
+        sending_time -> Timestamp,
+    }
+}
+
+diesel::table! {
+    event_attendances (page_id, occurrence_date, member_id) {
+        page_id -> Int4,
+        occurrence_date -> Date,
+        member_id -> Int4,
+        status -> Varchar,
+        note -> Nullable<Text>,
+        response_time -> Timestamp,
+    }
+}
+
 diesel::table! {
     image_access_policies (image_id, system_role) {
         image_id -> Int4,
@@ -31,6 +65,19 @@ diesel::table! {
         id -> Int4,
         title -> Varchar,
         asset -> Varchar,
+        format -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    job_runs (id) {
+        id -> Int4,
+        job_name -> Varchar,
+        started_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        succeeded -> Nullable<Bool>,
+        error -> Nullable<Text>,
+        manual -> Bool,
     }
 }
 
@@ -39,6 +86,18 @@ diesel::table! {
         id -> Int4,
         name -> Varchar,
         body -> Text,
+        etag -> Varchar,
+    }
+}
+
+diesel::table! {
+    member_absences (id) {
+        id -> Int4,
+        member_id -> Int4,
+        start_date -> Date,
+        end_date -> Date,
+        reason -> Nullable<Text>,
+        visibility -> Varchar,
     }
 }
 
@@ -84,6 +143,7 @@ diesel::table! {
         allow_privacy_info_sharing -> Bool,
         nonce -> Varchar,
         description -> Nullable<Text>,
+        etag -> Varchar,
     }
 }
 
@@ -92,6 +152,7 @@ diesel::table! {
         id -> Int4,
         name -> Varchar,
         wikipedia_url -> Nullable<Varchar>,
+        etag -> Varchar,
     }
 }
 
@@ -103,6 +164,62 @@ diesel::table! {
 }
 
 diesel::table! {
+    page_event_exceptions (page_id, occurrence_date) {
+        page_id -> Int4,
+        occurrence_date -> Date,
+    }
+}
+
+diesel::table! {
+    page_event_references (page_id, event_page_id) {
+        page_id -> Int4,
+        event_page_id -> Int4,
+    }
+}
+
+diesel::table! {
+    page_events (page_id) {
+        page_id -> Int4,
+        starts_at -> Timestamp,
+        ends_at -> Timestamp,
+        time_zone -> Varchar,
+        venue_id -> Nullable<Int4>,
+        recurrence_rule -> Nullable<Varchar>,
+        last_date -> Nullable<Date>,
+    }
+}
+
+diesel::table! {
+    page_image_references (page_id, image_id) {
+        page_id -> Int4,
+        image_id -> Int4,
+    }
+}
+
+diesel::table! {
+    page_redirects (path) {
+        path -> Varchar,
+        page_id -> Int4,
+        creation_time -> Timestamp,
+    }
+}
+
+diesel::table! {
+    page_revisions (id) {
+        id -> Int4,
+        page_id -> Int4,
+        author_id -> Nullable<Int4>,
+        creation_time -> Timestamp,
+        title -> Varchar,
+        content -> Text,
+        content_format -> Varchar,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Tsvector;
+
     pages (id) {
         id -> Int4,
         content_asset -> Varchar,
@@ -113,6 +230,16 @@ diesel::table! {
         title -> Text,
         order_number -> Int4,
         end_event_date -> Nullable<Date>,
+        last_modified -> Timestamp,
+        draft_content_asset -> Nullable<Varchar>,
+        publish_at -> Nullable<Timestamp>,
+        unpublish_at -> Nullable<Timestamp>,
+        content_format -> Varchar,
+        draft_content_format -> Nullable<Varchar>,
+        search_text -> Nullable<Text>,
+        search_vector -> Tsvector,
+        slug -> Varchar,
+        change_sequence -> Int4,
     }
 }
 
@@ -123,6 +250,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    venues (id) {
+        id -> Int4,
+        name -> Varchar,
+        address -> Nullable<Varchar>,
+        etag -> Varchar,
+    }
+}
+
 diesel::table! {
     workgroup_member_relationships (workgroup_id, member_id) {
         workgroup_id -> Int4,
@@ -141,31 +277,58 @@ diesel::table! {
     workgroups (id) {
         id -> Int4,
         name -> Varchar,
+        etag -> Varchar,
     }
 }
 
+diesel::joinable!(calendar_feeds -> members (member_id));
+diesel::joinable!(event_attendance_reminders -> members (member_id));
+diesel::joinable!(event_attendance_reminders -> pages (page_id));
+diesel::joinable!(event_attendances -> members (member_id));
+diesel::joinable!(event_attendances -> pages (page_id));
 diesel::joinable!(image_access_policies -> images (image_id));
+diesel::joinable!(member_absences -> members (member_id));
 diesel::joinable!(member_role_associations -> members (member_id));
 diesel::joinable!(members -> member_address_details (member_address_details_id));
 diesel::joinable!(members -> member_details (member_details_id));
 diesel::joinable!(members -> musical_instruments (musical_instrument_id));
 diesel::joinable!(page_access_policies -> pages (page_id));
+diesel::joinable!(page_event_exceptions -> page_events (page_id));
+diesel::joinable!(page_event_references -> pages (page_id));
+diesel::joinable!(page_events -> pages (page_id));
+diesel::joinable!(page_image_references -> pages (page_id));
+diesel::joinable!(page_events -> venues (venue_id));
+diesel::joinable!(page_redirects -> pages (page_id));
+diesel::joinable!(page_revisions -> members (author_id));
+diesel::joinable!(page_revisions -> pages (page_id));
 diesel::joinable!(workgroup_member_relationships -> members (member_id));
 diesel::joinable!(workgroup_member_relationships -> workgroups (workgroup_id));
 diesel::joinable!(workgroup_role_associations -> workgroups (workgroup_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    calendar_feeds,
+    event_attendance_reminders,
+    event_attendances,
     image_access_policies,
     images,
+    job_runs,
     mail_templates,
+    member_absences,
     member_address_details,
     member_details,
     member_role_associations,
     members,
     musical_instruments,
     page_access_policies,
+    page_event_exceptions,
+    page_event_references,
+    page_events,
+    page_image_references,
+    page_redirects,
+    page_revisions,
     pages,
     properties,
+    venues,
     workgroup_member_relationships,
     workgroup_role_associations,
     workgroups,
@@ -16,18 +16,26 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::Precondition;
 use crate::generic::result::BackendResult;
+use crate::generic::security::ClaimRoles;
 use crate::generic::storage::session::Session;
+use crate::model::interface::client::UserClaims;
 use crate::model::interface::commands::{
     AssociateMemberToWorkgroupCommand, AssociateRoleCommand, CreateMailTemplateCommand,
     CreatePageCommand, DissociateMemberFromWorkgroupCommand, DissociateRoleCommand,
     FirstOperatorRegisterCommand, ImageUploadCommand, MemberActivationCommand,
     MemberImageUploadCommand, MemberRegisterCommand, MemberUpdateAddressCommand,
     MemberUpdateCommand, MemberUpdatePrivacyInfoSharingCommand, PublishImageCommand,
-    PublishPageCommand, RegisterMusicalInstrumentCommand, SendMailCommand,
-    UpdateMailTemplateCommand, UpdateMusicalInstrumentCommand, UpdatePageCommand,
-    WorkgroupRegisterCommand, WorkgroupUpdateCommand,
+    PublishPageCommand, RegisterAbsenceCommand, RegisterAttendanceCommand,
+    RegisterMusicalInstrumentCommand, RegisterVenueCommand, SendMailCommand, SetPageEventCommand,
+    UpdateAbsenceCommand, UpdateMailTemplateCommand, UpdateMusicalInstrumentCommand,
+    UpdatePageCommand, UpdatePageTreeCommand, UpdateVenueCommand, WorkgroupRegisterCommand,
+    WorkgroupUpdateCommand,
 };
+use crate::model::interface::responses::{CalendarFeedResponse, JobRunResponse};
+use crate::model::primitives::ContentFormat;
+use chrono::NaiveDate;
 
 /// Controls actions which can be performed on member data
 pub trait MemberCommandService {
@@ -44,6 +52,7 @@ pub trait MemberCommandService {
         &self,
         session: Session,
         member_id: i32,
+        precondition: &Precondition,
         command: &MemberUpdateCommand,
     ) -> BackendResult<()>;
 
@@ -52,6 +61,7 @@ pub trait MemberCommandService {
         &self,
         session: Session,
         member_id: i32,
+        precondition: &Precondition,
         command: &MemberUpdateAddressCommand,
     ) -> BackendResult<()>;
 
@@ -60,6 +70,7 @@ pub trait MemberCommandService {
         &self,
         session: Session,
         member_id: i32,
+        precondition: &Precondition,
         command: &MemberUpdatePrivacyInfoSharingCommand,
     ) -> BackendResult<()>;
 
@@ -102,6 +113,7 @@ pub trait WorkgroupCommandService {
         &self,
         session: Session,
         workgroup_id: i32,
+        precondition: &Precondition,
         command: &WorkgroupUpdateCommand,
     ) -> BackendResult<()>;
 
@@ -142,27 +154,53 @@ pub trait PageCommandService {
     /// Creates a new page
     fn create(&self, session: Session, command: &CreatePageCommand) -> BackendResult<()>;
 
-    /// Sets the content of a given page
-    fn set_content(&self, session: Session, page_id: i32, content: &str) -> BackendResult<()>;
+    /// Sets the draft content of a given page, keeping the content as a new revision. Without a
+    /// format, the format of the previously saved content is kept.
+    fn set_content(
+        &self,
+        session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+        author: &UserClaims,
+        format: Option<ContentFormat>,
+        content: &str,
+    ) -> BackendResult<()>;
+
+    /// Restores the content of a revision as draft content, keeping it as a new revision
+    fn restore_revision(
+        &self,
+        session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+        author: &UserClaims,
+        revision_id: i32,
+    ) -> BackendResult<()>;
 
     /// Updates a page
     fn update(
         &self,
         session: Session,
         page_id: i32,
+        precondition: &Precondition,
         command: &UpdatePageCommand,
     ) -> BackendResult<()>;
 
-    /// Publishes the page
+    /// Publishes the page, making the draft content visible within the publication window
     fn publish(
         &self,
         session: Session,
         page_id: i32,
+        precondition: &Precondition,
         command: &PublishPageCommand,
     ) -> BackendResult<()>;
 
     /// Unpublishes the page
-    fn unpublish(&self, session: Session, page_id: i32) -> BackendResult<()>;
+    fn unpublish(
+        &self,
+        session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+    ) -> BackendResult<()>;
 
     /// Deletes an existing page
     fn delete(&self, session: Session, page_id: i32) -> BackendResult<()>;
@@ -171,15 +209,43 @@ pub trait PageCommandService {
     fn set_default(&self, session: Session, page_id: i32) -> BackendResult<()>;
 
     /// Sets the order of the page
-    fn set_order(&self, session: Session, page_id: i32, order_number: i32) -> BackendResult<()>;
+    fn set_order(
+        &self,
+        session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+        order_number: i32,
+    ) -> BackendResult<()>;
 
     /// Sets the parent id of the page
     fn set_or_unset_parent_id(
         &self,
         session: Session,
         page_id: i32,
+        precondition: &Precondition,
         maybe_parent_id: Option<i32>,
     ) -> BackendResult<()>;
+
+    /// Moves and reorders the pages in the page tree, rejecting moves which would make a page a
+    /// sub page of itself
+    fn update_tree(&self, session: Session, command: &UpdatePageTreeCommand) -> BackendResult<()>;
+
+    /// Sets the times, venue and recurrence of an event page
+    fn set_event(
+        &self,
+        session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+        command: &SetPageEventCommand,
+    ) -> BackendResult<()>;
+
+    /// Removes the times, venue and recurrence of an event page, keeping its event dates
+    fn delete_event(
+        &self,
+        session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+    ) -> BackendResult<()>;
 }
 
 /// Controls actions which can be performed to manage images
@@ -216,6 +282,7 @@ pub trait MusicalInstrumentCommandService {
         &self,
         session: Session,
         musical_instrument_id: i32,
+        precondition: &Precondition,
         command: &UpdateMusicalInstrumentCommand,
     ) -> BackendResult<()>;
 
@@ -223,6 +290,24 @@ pub trait MusicalInstrumentCommandService {
     fn delete(&self, session: Session, musical_instrument_id: i32) -> BackendResult<()>;
 }
 
+/// Controls actions which can be performed to manage the venues of events
+pub trait VenueCommandService {
+    /// Registers a new venue
+    fn register(&self, session: Session, command: &RegisterVenueCommand) -> BackendResult<()>;
+
+    /// Updates a registered venue
+    fn update(
+        &self,
+        session: Session,
+        venue_id: i32,
+        precondition: &Precondition,
+        command: &UpdateVenueCommand,
+    ) -> BackendResult<()>;
+
+    /// Deletes a registered venue, events at the venue no longer refer to a venue
+    fn delete(&self, session: Session, venue_id: i32) -> BackendResult<()>;
+}
+
 /// Controls actions which can be performed to manage email templates
 pub trait MailTemplateCommandService {
     /// Creates a new email template
@@ -233,6 +318,7 @@ pub trait MailTemplateCommandService {
         &self,
         session: Session,
         mail_template_id: i32,
+        precondition: &Precondition,
         command: &UpdateMailTemplateCommand,
     ) -> BackendResult<()>;
 
@@ -245,3 +331,62 @@ pub trait MailingCommandService {
     /// Sends a new email
     fn send(&self, session: Session, command: &SendMailCommand) -> BackendResult<()>;
 }
+
+/// Controls actions which can be performed on the scheduled jobs. Jobs run on a blocking
+/// thread, hence the service needs to be shareable across threads.
+pub trait JobCommandService: Send + Sync {
+    /// Runs a job immediately, outside of its schedule, returning the outcome of the run
+    fn trigger(&self, job_name: &str) -> BackendResult<JobRunResponse>;
+}
+
+/// Controls actions which can be performed on the personal calendar feeds
+pub trait CalendarCommandService {
+    /// Creates the personal calendar feed of the member, or renews its token if the member
+    /// already has one, such that the former token no longer grants access
+    fn renew_feed(
+        &self,
+        session: Session,
+        claims: &UserClaims,
+    ) -> BackendResult<CalendarFeedResponse>;
+
+    /// Removes the personal calendar feed of the member, revoking its token
+    fn revoke_feed(&self, session: Session, claims: &UserClaims) -> BackendResult<()>;
+}
+
+/// Controls actions which can be performed on the responses of members to events
+pub trait AttendanceCommandService {
+    /// Registers the response of the member to the occurrence of the event starting on the day,
+    /// replacing a former response
+    fn register(
+        &self,
+        session: Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        claims: &UserClaims,
+        roles: &ClaimRoles,
+        command: &RegisterAttendanceCommand,
+    ) -> BackendResult<()>;
+}
+
+/// Controls actions which can be performed on the absences of the logged in member
+pub trait AbsenceCommandService {
+    /// Registers a period in which the member is away
+    fn register(
+        &self,
+        session: Session,
+        claims: &UserClaims,
+        command: &RegisterAbsenceCommand,
+    ) -> BackendResult<()>;
+
+    /// Updates an absence of the member
+    fn update(
+        &self,
+        session: Session,
+        absence_id: i32,
+        claims: &UserClaims,
+        command: &UpdateAbsenceCommand,
+    ) -> BackendResult<()>;
+
+    /// Removes an absence of the member
+    fn delete(&self, session: Session, absence_id: i32, claims: &UserClaims) -> BackendResult<()>;
+}
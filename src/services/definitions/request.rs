@@ -17,21 +17,27 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::generic::caching::Cached;
+use crate::generic::imaging::Variant;
 use crate::generic::result::BackendResult;
 use crate::generic::security::ClaimRoles;
 use crate::generic::storage::session::Session;
 use crate::model::interface::client::UserClaims;
 use crate::model::interface::requests::AuthorizationRequest;
 use crate::model::interface::responses::{
-    AuthorizationResponse, ExtendedPageResponse, FacebookResponse, ImageAssetIdResponse,
-    ImageMetaDataResponse, ImageResponse, MailTemplateNameResponse, MailTemplateResponse,
-    MemberAddressResponse, MemberPrivacyInfoSharingResponse, MemberResponse,
-    MusicalInstrumentResponse, PageResponse, WorkgroupResponse,
+    AbsenceResponse, AttendanceReportResponse, AttendanceResponse, AuthorizationResponse,
+    CalendarFeedResponse, EventResponse, ExtendedPageResponse, ExtendedPageRevisionResponse,
+    FacebookResponse, ImageAssetIdResponse, ImageMetaDataResponse, ImageResponse, JobResponse,
+    JobRunResponse, MailTemplateNameResponse, MailTemplateResponse, MemberAddressResponse,
+    MemberPrivacyInfoSharingResponse, MemberResponse, MusicalInstrumentResponse, PageEventResponse,
+    PageResponse, PageRevisionResponse, PageSearchResponse, PageTreeResponse, VenueResponse,
+    WorkgroupResponse,
 };
 use crate::model::interface::search::{SearchParams, SearchResult};
 use crate::model::primitives::{Role, RoleClass};
 use crate::model::traits::RoleContainer;
 use actix_web::cookie::Cookie;
+use chrono::NaiveDate;
 use serde::Serialize;
 
 /// Controls actions for data retrieval belonging to the setup process
@@ -113,7 +119,8 @@ pub trait MemberPictureRequestService {
         session: Session,
         member_id: i32,
         role_container: &dyn RoleContainer,
-    ) -> BackendResult<Option<ImageResponse>>;
+        variant: &Variant,
+    ) -> BackendResult<Option<Cached<ImageResponse>>>;
 
     fn find_asset_id_by_member_id(
         &self,
@@ -150,6 +157,15 @@ pub trait WorkgroupRequestService: SearchController<WorkgroupResponse> {
 /// Controls actions for data retrieval belonging to the facebook
 pub trait FacebookRequestService: SearchController<FacebookResponse> {}
 
+/// The outcome of looking up a page by its path
+pub enum PageLookup {
+    /// The page currently has the path
+    Found(Cached<ExtendedPageResponse>),
+    /// The path belonged to a page which has since moved or was renamed, the current path is
+    /// given
+    Moved(String),
+}
+
 /// Controls actions for data retrieval belonging to pages
 pub trait PageRequestService {
     /// Finds a page using the identifier
@@ -158,15 +174,32 @@ pub trait PageRequestService {
         session: Session,
         page_id: i32,
         roles: &ClaimRoles,
-    ) -> BackendResult<ExtendedPageResponse>;
+    ) -> BackendResult<Cached<ExtendedPageResponse>>;
+
+    /// Finds a page using its path, e.g. `concerts/spring-2025`, falling back to the redirects
+    /// of former paths
+    fn find_by_path(
+        &self,
+        session: Session,
+        path: &str,
+        roles: &ClaimRoles,
+    ) -> BackendResult<PageLookup>;
 
-    /// Finds a page's content using the identifier
+    /// Finds a page's published content using the identifier, rendered as sanitised HTML
     fn find_content_by_id(
         &self,
         session: Session,
         page_id: i32,
         roles: &ClaimRoles,
-    ) -> BackendResult<String>;
+    ) -> BackendResult<Cached<String>>;
+
+    /// Finds a page's latest saved content using the identifier, including unpublished changes
+    fn find_draft_content_by_id(
+        &self,
+        session: Session,
+        page_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Cached<String>>;
 
     /// Returns the default page, if there is a default page
     fn default(
@@ -183,16 +216,55 @@ pub trait PageRequestService {
         roles: &ClaimRoles,
     ) -> BackendResult<Vec<PageResponse>>;
 
-    /// Searches pages by page title and allowed roles
+    /// Returns the hierarchy of all pages visible given the roles
+    fn tree(&self, session: Session, roles: &ClaimRoles) -> BackendResult<Vec<PageTreeResponse>>;
+
+    /// Searches pages by page title and content and allowed roles, ranked by relevance
     fn search(
         &self,
         session: Session,
         params: &SearchParams,
         roles: &ClaimRoles,
-    ) -> BackendResult<SearchResult<PageResponse>>;
+    ) -> BackendResult<SearchResult<PageSearchResponse>>;
+
+    /// Finds the times, venue and recurrence of an event page, if the page has times
+    fn find_event(
+        &self,
+        session: Session,
+        page_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Option<PageEventResponse>>;
+
+    /// Returns all the event occurrences within a preconfigured period, together with the members
+    /// who are away during them as far as the roles may see their absences
+    fn events(&self, session: Session, roles: &ClaimRoles) -> BackendResult<Vec<EventResponse>>;
+
+    /// Lists the content revisions of a page, newest first
+    fn list_revisions(
+        &self,
+        session: Session,
+        page_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Vec<PageRevisionResponse>>;
+
+    /// Finds a content revision of a page, including its content
+    fn find_revision(
+        &self,
+        session: Session,
+        page_id: i32,
+        revision_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<ExtendedPageRevisionResponse>;
 
-    /// Returns all the events within a preconfigured period
-    fn events(&self, session: Session, roles: &ClaimRoles) -> BackendResult<Vec<PageResponse>>;
+    /// Returns the unified diff between the content of two revisions of a page
+    fn diff_revisions(
+        &self,
+        session: Session,
+        page_id: i32,
+        from_revision_id: i32,
+        to_revision_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<String>;
 }
 
 /// Controls actions for data retrieval belonging to images
@@ -205,13 +277,14 @@ pub trait ImageRequestService {
         roles: &ClaimRoles,
     ) -> BackendResult<ImageMetaDataResponse>;
 
-    /// Finds an images content using the identifier
+    /// Finds an images content using the identifier, returning the requested variant
     fn find_content_by_id(
         &self,
         session: Session,
         page_id: i32,
         roles: &ClaimRoles,
-    ) -> BackendResult<ImageResponse>;
+        variant: &Variant,
+    ) -> BackendResult<Cached<ImageResponse>>;
 
     /// Searches pages by page title and allowed roles
     fn search(
@@ -231,6 +304,12 @@ pub trait MusicalInstrumentRequestService: SearchController<MusicalInstrumentRes
     ) -> BackendResult<MusicalInstrumentResponse>;
 }
 
+/// Controls actions for data retrieval belonging to the venues of events
+pub trait VenueRequestService {
+    /// Lists all venues ordered by name
+    fn list(&self, session: Session) -> BackendResult<Vec<VenueResponse>>;
+}
+
 /// Controls actions for data retrieval belonging to email templates
 pub trait MailTemplateRequestService {
     /// Lists all the defined mail templates
@@ -244,8 +323,63 @@ pub trait MailTemplateRequestService {
     ) -> BackendResult<MailTemplateResponse>;
 }
 
+/// Controls actions for data retrieval belonging to the scheduled jobs
+pub trait JobRequestService {
+    /// Lists all jobs, including their schedule and most recent run
+    fn list(&self, session: Session) -> BackendResult<Vec<JobResponse>>;
+
+    /// Lists the most recent runs of a job, newest first
+    fn runs(&self, session: Session, job_name: &str) -> BackendResult<Vec<JobRunResponse>>;
+}
+
+/// Controls actions for data retrieval belonging to the calendar feeds
+pub trait CalendarRequestService {
+    /// Returns the iCalendar feed of the events which are visible to the public
+    fn public_feed(&self, session: Session) -> BackendResult<Cached<String>>;
+
+    /// Returns the iCalendar feed of the events which are visible to the member owning the token
+    fn personal_feed(&self, session: Session, token: &str) -> BackendResult<Cached<String>>;
+
+    /// Finds the personal calendar feed of the member, if the member has one
+    fn find_feed(
+        &self,
+        session: Session,
+        claims: &UserClaims,
+    ) -> BackendResult<Option<CalendarFeedResponse>>;
+}
+
+/// Controls actions for data retrieval belonging to the responses of members to events
+pub trait AttendanceRequestService {
+    /// Finds the response of the member to the occurrence of the event starting on the day, if
+    /// the member responded
+    fn find(
+        &self,
+        session: Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        claims: &UserClaims,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Option<AttendanceResponse>>;
+
+    /// Reports the responses to the occurrence of the event starting on the day by section,
+    /// including the members who did not respond yet
+    fn report(
+        &self,
+        session: Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        roles: &ClaimRoles,
+    ) -> BackendResult<AttendanceReportResponse>;
+}
+
 pub trait SearchController<T> {
     fn search(&self, session: Session, params: &SearchParams) -> BackendResult<SearchResult<T>>
     where
         T: Serialize;
 }
+
+/// Controls actions for data retrieval belonging to the absences of members
+pub trait AbsenceRequestService {
+    /// Lists the absences of the logged in member, ordered by their start date
+    fn list(&self, session: Session, claims: &UserClaims) -> BackendResult<Vec<AbsenceResponse>>;
+}
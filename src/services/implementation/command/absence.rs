@@ -0,0 +1,101 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::commands::{RegisterAbsenceCommand, UpdateAbsenceCommand};
+use crate::model::storage::entities::MemberAbsence;
+use crate::repositories::definitions::{AbsenceRepository, MemberRepository};
+use crate::services::definitions::command::AbsenceCommandService;
+use actix_web::web::Data;
+use std::sync::Arc;
+
+pub struct Implementation {
+    member_repository: Data<dyn MemberRepository>,
+    absence_repository: Data<dyn AbsenceRepository>,
+}
+
+impl AbsenceCommandService for Implementation {
+    fn register(
+        &self,
+        mut session: Session,
+        claims: &UserClaims,
+        command: &RegisterAbsenceCommand,
+    ) -> BackendResult<()> {
+        let member = self
+            .member_repository
+            .find_extended_by_email_address(&mut session, &claims.email_address)?;
+        let absence = MemberAbsence::from((member.id, command));
+        self.absence_repository.create(&mut session, absence)
+    }
+
+    fn update(
+        &self,
+        mut session: Session,
+        absence_id: i32,
+        claims: &UserClaims,
+        command: &UpdateAbsenceCommand,
+    ) -> BackendResult<()> {
+        let origin = self.find_own(&mut session, absence_id, claims)?;
+        let absence = MemberAbsence::from((&origin, command));
+        self.absence_repository.update(&mut session, absence)
+    }
+
+    fn delete(
+        &self,
+        mut session: Session,
+        absence_id: i32,
+        claims: &UserClaims,
+    ) -> BackendResult<()> {
+        let origin = self.find_own(&mut session, absence_id, claims)?;
+        self.absence_repository.delete(&mut session, origin.id)
+    }
+}
+
+impl Implementation {
+    /// Finds an absence of the logged in member, the absences of others are refused
+    fn find_own(
+        &self,
+        session: &mut Session,
+        absence_id: i32,
+        claims: &UserClaims,
+    ) -> BackendResult<MemberAbsence> {
+        let member = self
+            .member_repository
+            .find_extended_by_email_address(session, &claims.email_address)?;
+        let absence = self.absence_repository.find_by_id(session, absence_id)?;
+        if absence.member_id != member.id {
+            return Err(BackendError::forbidden());
+        }
+        Ok(absence)
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn AbsenceCommandService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn AbsenceCommandService> {
+        let implementation = Self {
+            member_repository: dependencies.member_repository.clone(),
+            absence_repository: dependencies.absence_repository.clone(),
+        };
+        let arc: Arc<dyn AbsenceCommandService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
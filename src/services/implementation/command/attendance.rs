@@ -0,0 +1,75 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::security::ClaimRoles;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::commands::RegisterAttendanceCommand;
+use crate::model::storage::entities::EventAttendance;
+use crate::repositories::definitions::{AttendanceRepository, MemberRepository};
+use crate::services::definitions::command::AttendanceCommandService;
+use crate::services::implementation::occurrence::OccurrenceFinder;
+use actix_web::web::Data;
+use chrono::{NaiveDate, Utc};
+use std::sync::Arc;
+
+pub struct Implementation {
+    member_repository: Data<dyn MemberRepository>,
+    attendance_repository: Data<dyn AttendanceRepository>,
+    occurrences: OccurrenceFinder,
+}
+
+impl AttendanceCommandService for Implementation {
+    fn register(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        claims: &UserClaims,
+        roles: &ClaimRoles,
+        command: &RegisterAttendanceCommand,
+    ) -> BackendResult<()> {
+        if occurrence_date < Utc::now().date_naive() {
+            return Err(BackendError::conflict(
+                "The occurrence has already taken place",
+            ));
+        }
+        self.occurrences
+            .find(&mut session, page_id, occurrence_date, roles)?;
+        let member = self
+            .member_repository
+            .find_extended_by_email_address(&mut session, &claims.email_address)?;
+        let attendance = EventAttendance::from((page_id, occurrence_date, member.id, command));
+        self.attendance_repository.save(&mut session, attendance)
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn AttendanceCommandService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn AttendanceCommandService> {
+        let implementation = Self {
+            member_repository: dependencies.member_repository.clone(),
+            attendance_repository: dependencies.attendance_repository.clone(),
+            occurrences: OccurrenceFinder::new(dependencies),
+        };
+        let arc: Arc<dyn AttendanceCommandService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
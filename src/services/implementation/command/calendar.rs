@@ -0,0 +1,69 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::responses::CalendarFeedResponse;
+use crate::model::storage::entities::CalendarFeed;
+use crate::repositories::definitions::{CalendarFeedRepository, MemberRepository};
+use crate::services::definitions::command::CalendarCommandService;
+use actix_web::web::Data;
+use std::sync::Arc;
+
+pub struct Implementation {
+    member_repository: Data<dyn MemberRepository>,
+    calendar_feed_repository: Data<dyn CalendarFeedRepository>,
+}
+
+impl CalendarCommandService for Implementation {
+    fn renew_feed(
+        &self,
+        mut session: Session,
+        claims: &UserClaims,
+    ) -> BackendResult<CalendarFeedResponse> {
+        let member = self
+            .member_repository
+            .find_extended_by_email_address(&mut session, &claims.email_address)?;
+        let (feed, token) = CalendarFeed::generate(member.id);
+        let response = CalendarFeedResponse::from(&feed).with_token(&token);
+        self.calendar_feed_repository.save(&mut session, feed)?;
+        Ok(response)
+    }
+
+    fn revoke_feed(&self, mut session: Session, claims: &UserClaims) -> BackendResult<()> {
+        let member = self
+            .member_repository
+            .find_extended_by_email_address(&mut session, &claims.email_address)?;
+        self.calendar_feed_repository
+            .delete_by_member_id(&mut session, member.id)
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn CalendarCommandService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn CalendarCommandService> {
+        let implementation = Self {
+            member_repository: dependencies.member_repository.clone(),
+            calendar_feed_repository: dependencies.calendar_feed_repository.clone(),
+        };
+        let arc: Arc<dyn CalendarCommandService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
@@ -16,7 +16,11 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::imaging::{self, DecodedImage};
+use crate::generic::rendering::Shortcode;
 use crate::generic::result::BackendResult;
+use crate::generic::settings::ImageSettings;
+use crate::generic::storage::assets::{variants, AssetStore};
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
 use crate::injection::ServiceDependencies;
@@ -24,24 +28,36 @@ use crate::model::interface::commands::{ImageUploadCommand, PublishImageCommand}
 use crate::model::storage::entities::Image;
 use crate::repositories::definitions::ImageRepository;
 use crate::services::definitions::command::ImageCommandService;
+use crate::services::implementation::content::ContentRenderer;
 use actix_web::web::Data;
-use image::EncodableLayout;
-use std::fs::OpenOptions;
-use std::io::Write;
+use image::imageops::FilterType;
+use image::ImageFormat;
+use log::warn;
 use std::sync::Arc;
 
 pub struct Implementation {
     image_repository: Data<dyn ImageRepository>,
+    asset_store: Data<dyn AssetStore>,
+    image_settings: ImageSettings,
+    renderer: ContentRenderer,
 }
 
 impl ImageCommandService for Implementation {
     fn upload(&self, mut session: Session, command: &ImageUploadCommand) -> BackendResult<String> {
-        let image = Image::from(command);
+        let decoded = imaging::decode(&command.data, self.image_settings.max_dimension)?;
+        // The metadata, such as GPS coordinates, is always stripped
+        let (format, data) = if self.image_settings.normalizes() {
+            self.normalize(decoded)?
+        } else {
+            let data = imaging::without_metadata(&command.data, &decoded)?;
+            (decoded.format, data)
+        };
+
+        let mut image = Image::from(command);
+        image.format = Some(imaging::format_name(format).to_owned());
         let asset = image.asset.clone();
         self.image_repository.create(&mut session, image)?;
-        let pb = crate::path_for_asset(&asset)?;
-        let mut w = OpenOptions::new().write(true).create_new(true).open(&pb)?;
-        w.write(&command.data.as_bytes())?;
+        self.asset_store.put(&asset, &data)?;
         Ok(asset)
     }
 
@@ -53,15 +69,47 @@ impl ImageCommandService for Implementation {
     ) -> BackendResult<()> {
         self.image_repository.reset_roles(&mut session, image_id)?;
         self.image_repository
-            .assign_roles(&mut session, image_id, &command.roles)
+            .assign_roles(&mut session, image_id, &command.roles)?;
+        self.renderer
+            .invalidate(&mut session, &[Shortcode::Image(image_id)])
     }
 
     fn unpublish(&self, mut session: Session, image_id: i32) -> BackendResult<()> {
-        self.image_repository.reset_roles(&mut session, image_id)
+        self.image_repository.reset_roles(&mut session, image_id)?;
+        self.renderer
+            .invalidate(&mut session, &[Shortcode::Image(image_id)])
     }
 
     fn delete(&self, mut session: Session, image_id: i32) -> BackendResult<()> {
-        self.image_repository.delete(&mut session, image_id)
+        let image = self.image_repository.find_by_id(&mut session, image_id)?;
+        self.renderer
+            .invalidate(&mut session, &[Shortcode::Image(image_id)])?;
+        self.image_repository.delete(&mut session, image_id)?;
+        let asset_store = self.asset_store.clone();
+        session.after_commit(move || {
+            if let Err(e) = asset_store
+                .delete(&image.asset)
+                .and_then(|_| variants::delete(asset_store.get_ref(), &image.asset))
+            {
+                warn!("Unable to delete the asset of image {image_id}: {e}");
+            }
+        });
+        Ok(())
+    }
+}
+
+impl Implementation {
+    /// Converts the image to the configured format and scales it down to the configured
+    /// maximum dimension, returning the resulting format and encoded data
+    fn normalize(&self, decoded: DecodedImage) -> BackendResult<(ImageFormat, Vec<u8>)> {
+        let format = imaging::normalized_format(self.image_settings.normalize_format)
+            .unwrap_or(decoded.format);
+        let max_dimension = self.image_settings.normalize_max_dimension;
+        let mut image = decoded.image;
+        if max_dimension > 0 && (image.width() > max_dimension || image.height() > max_dimension) {
+            image = image.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+        }
+        Ok((format, imaging::encode(&image, format)?))
     }
 }
 
@@ -69,6 +117,9 @@ impl Injectable<ServiceDependencies, dyn ImageCommandService> for Implementation
     fn make(dependencies: &ServiceDependencies) -> Data<dyn ImageCommandService> {
         let implementation = Self {
             image_repository: dependencies.image_repository.clone(),
+            asset_store: dependencies.asset_store.clone(),
+            image_settings: dependencies.settings.images.clone(),
+            renderer: ContentRenderer::new(dependencies),
         };
         let arc: Arc<dyn ImageCommandService> = Arc::new(implementation);
         Data::from(arc)
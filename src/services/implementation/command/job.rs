@@ -0,0 +1,52 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::commands::jobs::registry::JobRegistry;
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::responses::JobRunResponse;
+use crate::services::definitions::command::JobCommandService;
+use actix_web::web::Data;
+use std::sync::Arc;
+
+pub struct Implementation {
+    job_registry: Data<JobRegistry>,
+}
+
+impl JobCommandService for Implementation {
+    fn trigger(&self, job_name: &str) -> BackendResult<JobRunResponse> {
+        match self.job_registry.run(job_name, true)? {
+            Some(run) => Ok(JobRunResponse::from(&run)),
+            None => Err(BackendError::conflict(format!(
+                "Job {job_name} is already running"
+            ))),
+        }
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn JobCommandService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn JobCommandService> {
+        let implementation = Self {
+            job_registry: dependencies.job_registry.clone(),
+        };
+        let arc: Arc<dyn JobCommandService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
@@ -16,6 +16,7 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::Precondition;
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
@@ -45,11 +46,15 @@ impl MailTemplateCommandService for Implementation {
         &self,
         mut session: Session,
         mail_template_id: i32,
+        precondition: &Precondition,
         command: &UpdateMailTemplateCommand,
     ) -> BackendResult<()> {
+        self.mail_template_repository
+            .lock_by_id(&mut session, mail_template_id)?;
         let origin = self
             .mail_template_repository
             .find_by_id(&mut session, mail_template_id)?;
+        precondition.check(&origin.etag)?;
         let mail_template = MailTemplate::from((&origin, command));
         self.mail_template_repository
             .update(&mut session, mail_template)
@@ -16,8 +16,9 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::{SendEmailConfig, SEND_EMAIL_CONFIG};
+use crate::generic::metrics::{MailPurpose, Metrics};
 use crate::generic::result::BackendResult;
+use crate::generic::settings::SendEmailConfig;
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
 use crate::injection::ServiceDependencies;
@@ -26,7 +27,7 @@ use crate::model::interface::commands::SendMailCommand;
 use crate::model::storage::entities::MailTemplate;
 use crate::model::storage::extended_entities::ExtendedMember;
 use crate::repositories::definitions::{
-    MailTemplateRepository, MemberRepository, WorkgroupRepository,
+    AbsenceRepository, MailTemplateRepository, MemberRepository, WorkgroupRepository,
 };
 use crate::services::definitions::command::MailingCommandService;
 use actix_web::web::Data;
@@ -40,7 +41,9 @@ pub struct Implementation {
     mail_template_repository: Data<dyn MailTemplateRepository>,
     workgroup_repository: Data<dyn WorkgroupRepository>,
     member_repository: Data<dyn MemberRepository>,
+    absence_repository: Data<dyn AbsenceRepository>,
     send_email_config: SendEmailConfig,
+    metrics: Data<Metrics>,
 }
 
 impl MailingCommandService for Implementation {
@@ -48,7 +51,13 @@ impl MailingCommandService for Implementation {
         let mail_template = self
             .mail_template_repository
             .find_by_id(&mut session, command.mail_template_id)?;
-        let members = self.list_members_by_recipient_type(&mut session, command)?;
+        let mut members = self.list_members_by_recipient_type(&mut session, command)?;
+        if let Some(date) = &command.skip_absent_on {
+            let absent_member_ids = self
+                .absence_repository
+                .list_absent_member_ids(&mut session, date)?;
+            members.retain(|member| !absent_member_ids.contains(&member.id));
+        }
         self.render_and_send_email(command, mail_template, members)?;
         Ok(())
     }
@@ -118,7 +127,10 @@ impl Implementation {
             builder = builder.tls(Tls::None)
         }
         let relay = builder.build();
-        relay.send(&email)?;
+        let result = relay.send(&email);
+        self.metrics
+            .record_mail(MailPurpose::Mailing, result.is_ok());
+        result?;
         Ok(())
     }
 }
@@ -129,7 +141,9 @@ impl Injectable<ServiceDependencies, dyn MailingCommandService> for Implementati
             mail_template_repository: dependencies.mail_template_repository.clone(),
             workgroup_repository: dependencies.workgroup_repository.clone(),
             member_repository: dependencies.member_repository.clone(),
-            send_email_config: SEND_EMAIL_CONFIG.clone(),
+            absence_repository: dependencies.absence_repository.clone(),
+            send_email_config: dependencies.settings.send_email_config(),
+            metrics: dependencies.metrics.clone(),
         };
         let arc: Arc<dyn MailingCommandService> = Arc::new(implementation);
         Data::from(arc)
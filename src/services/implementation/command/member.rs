@@ -16,8 +16,11 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::{SendEmailConfig, SEND_EMAIL_CONFIG};
+use crate::generic::caching::Precondition;
+use crate::generic::metrics::{MailPurpose, Metrics};
 use crate::generic::result::BackendResult;
+use crate::generic::settings::SendEmailConfig;
+use crate::generic::storage::assets::{variants, AssetStore};
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
 use crate::injection::ServiceDependencies;
@@ -30,14 +33,19 @@ use crate::model::storage::extended_entities::ExtendedMember;
 use crate::repositories::definitions::{MemberRepository, MemberRoleRepository};
 use crate::services::definitions::command::MemberCommandService;
 use actix_web::web::Data;
+use chrono::TimeDelta;
 use lettre::transport::smtp::client::Tls;
 use lettre::{Message, SmtpTransport, Transport};
+use log::warn;
 use std::sync::Arc;
 
 pub struct Implementation {
     member_repository: Data<dyn MemberRepository>,
     member_role_repository: Data<dyn MemberRoleRepository>,
     send_activation_email_config: SendEmailConfig,
+    metrics: Data<Metrics>,
+    asset_store: Data<dyn AssetStore>,
+    activation_delta: TimeDelta,
 }
 
 impl MemberCommandService for Implementation {
@@ -46,7 +54,7 @@ impl MemberCommandService for Implementation {
         mut session: Session,
         command: &MemberRegisterCommand,
     ) -> BackendResult<i32> {
-        let extended_member = ExtendedMember::from(command);
+        let extended_member = ExtendedMember::from((command, self.activation_delta));
 
         let member_id = self
             .member_repository
@@ -67,11 +75,14 @@ impl MemberCommandService for Implementation {
         &self,
         mut session: Session,
         member_id: i32,
+        precondition: &Precondition,
         command: &MemberUpdateCommand,
     ) -> BackendResult<()> {
+        self.member_repository.lock_by_id(&mut session, member_id)?;
         let origin = self
             .member_repository
             .find_extended_by_id(&mut session, member_id)?;
+        precondition.check(&origin.etag)?;
         let new = ExtendedMember::from((&origin, command));
         self.member_repository.save(&mut session, new)?;
         Ok(())
@@ -81,11 +92,14 @@ impl MemberCommandService for Implementation {
         &self,
         mut session: Session,
         member_id: i32,
+        precondition: &Precondition,
         command: &MemberUpdateAddressCommand,
     ) -> BackendResult<()> {
+        self.member_repository.lock_by_id(&mut session, member_id)?;
         let origin = self
             .member_repository
             .find_extended_by_id(&mut session, member_id)?;
+        precondition.check(&origin.etag)?;
         let new = ExtendedMember::from((&origin, command));
         self.member_repository.save(&mut session, new)?;
         Ok(())
@@ -95,18 +109,36 @@ impl MemberCommandService for Implementation {
         &self,
         mut session: Session,
         member_id: i32,
+        precondition: &Precondition,
         command: &MemberUpdatePrivacyInfoSharingCommand,
     ) -> BackendResult<()> {
+        self.member_repository.lock_by_id(&mut session, member_id)?;
         let origin = self
             .member_repository
             .find_extended_by_id(&mut session, member_id)?;
+        precondition.check(&origin.etag)?;
         let new = ExtendedMember::from((&origin, command));
         self.member_repository.save(&mut session, new)?;
         Ok(())
     }
 
     fn unregister(&self, mut session: Session, member_id: i32) -> BackendResult<()> {
-        self.member_repository.unregister(&mut session, member_id)
+        let extended_member = self
+            .member_repository
+            .find_extended_by_id(&mut session, member_id)?;
+        self.member_repository.unregister(&mut session, member_id)?;
+        if let Some(asset_id) = extended_member.picture_asset_id {
+            let asset_store = self.asset_store.clone();
+            session.after_commit(move || {
+                if let Err(e) = asset_store
+                    .delete(&asset_id)
+                    .and_then(|_| variants::delete(asset_store.get_ref(), &asset_id))
+                {
+                    warn!("Unable to delete the picture of member {member_id}: {e}");
+                }
+            });
+        }
+        Ok(())
     }
 }
 
@@ -146,7 +178,10 @@ impl Implementation {
             builder = builder.tls(Tls::None)
         }
         let relay = builder.build();
-        relay.send(&email)?;
+        let result = relay.send(&email);
+        self.metrics
+            .record_mail(MailPurpose::Activation, result.is_ok());
+        result?;
         Ok(())
     }
 }
@@ -156,7 +191,10 @@ impl Injectable<ServiceDependencies, dyn MemberCommandService> for Implementatio
         let implementation = Self {
             member_repository: dependencies.member_repository.clone(),
             member_role_repository: dependencies.member_role_repository.clone(),
-            send_activation_email_config: SEND_EMAIL_CONFIG.clone(),
+            send_activation_email_config: dependencies.settings.send_email_config(),
+            metrics: dependencies.metrics.clone(),
+            asset_store: dependencies.asset_store.clone(),
+            activation_delta: dependencies.settings.activation.member_delta(),
         };
         let arc: Arc<dyn MemberCommandService> = Arc::new(implementation);
         Data::from(arc)
@@ -25,11 +25,13 @@ use crate::model::interface::responses::MemberResponse;
 use crate::repositories::definitions::MemberRepository;
 use crate::services::definitions::command::MemberActivationCommandService;
 use actix_web::web::Data;
+use aes_gcm::Aes256Gcm;
 use std::sync::Arc;
 use totp_rs::TOTP;
 
 pub struct Implementation {
     member_repository: Data<dyn MemberRepository>,
+    otp_cipher: Aes256Gcm,
 }
 
 impl MemberActivationCommandService for Implementation {
@@ -38,7 +40,7 @@ impl MemberActivationCommandService for Implementation {
             .member_repository
             .find_extended_by_activation_string(&mut session, &data.activation_string)?;
         let member_response = MemberResponse::from(&extended_member);
-        let totp: TOTP = member_response.try_into()?;
+        let totp: TOTP = member_response.totp(&self.otp_cipher)?;
         totp.check_current(&data.token)?;
         self.member_repository
             .activate_by_id(&mut session, *(&extended_member.id))?;
@@ -50,6 +52,7 @@ impl Injectable<ServiceDependencies, dyn MemberActivationCommandService> for Imp
     fn make(dependencies: &ServiceDependencies) -> Data<dyn MemberActivationCommandService> {
         let implementation = Self {
             member_repository: dependencies.member_repository.clone(),
+            otp_cipher: dependencies.settings.security.otp_cipher(),
         };
         let arc: Arc<dyn MemberActivationCommandService> = Arc::new(implementation);
         Data::from(arc)
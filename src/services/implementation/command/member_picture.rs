@@ -16,7 +16,9 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::imaging;
 use crate::generic::result::BackendResult;
+use crate::generic::storage::assets::{variants, AssetStore};
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
 use crate::injection::ServiceDependencies;
@@ -27,13 +29,14 @@ use actix_web::web::Data;
 use image::codecs::png::PngEncoder;
 use image::imageops::FilterType;
 use image::DynamicImage;
-use log::info;
-use std::fs::OpenOptions;
+use log::{info, warn};
 use std::sync::Arc;
 
 pub struct Implementation {
     member_repository: Data<dyn MemberRepository>,
     member_picture_repository: Data<dyn MemberPictureRepository>,
+    asset_store: Data<dyn AssetStore>,
+    max_dimension: u32,
 }
 
 impl MemberPictureCommandService for Implementation {
@@ -49,25 +52,32 @@ impl MemberPictureCommandService for Implementation {
         // Mark the already existing picture for deletion, if it exists
         let mark_for_deletion = extended_member.picture_asset_id.clone();
 
-        let dynamic_image = Self::load_alien_member_picture(&command.dynamic_image)?;
+        // Decoding applies the EXIF orientation before cropping, while encoding the picture
+        // again strips all metadata
+        let decoded = imaging::decode(&command.data, self.max_dimension)?;
+        let dynamic_image = Self::load_alien_member_picture(decoded.image);
 
         // Create a new asset identifier
         let asset_id = crate::generate_asset_id();
-        let pb = crate::path_for_asset(&asset_id)?;
-        let w = OpenOptions::new().write(true).create_new(true).open(&pb)?;
-        dynamic_image.write_with_encoder(PngEncoder::new(w))?;
+        let mut data = Vec::new();
+        dynamic_image.write_with_encoder(PngEncoder::new(&mut data))?;
+        self.asset_store.put(&asset_id, &data)?;
 
         self.member_picture_repository
             .save_by_member_id(&mut session, member_id, &asset_id)?;
 
-        info!(
-            "Stored asset into: {} for member: {member_id}",
-            pb.to_string_lossy()
-        );
+        info!("Stored asset: {asset_id} for member: {member_id}");
 
-        if let Some(asset_id) = mark_for_deletion {
-            let pb = crate::path_for_asset(&asset_id)?;
-            let _ = std::fs::remove_file(pb); // Ignore if this failed
+        if let Some(old_asset_id) = mark_for_deletion {
+            let asset_store = self.asset_store.clone();
+            session.after_commit(move || {
+                if let Err(e) = asset_store
+                    .delete(&old_asset_id)
+                    .and_then(|_| variants::delete(asset_store.get_ref(), &old_asset_id))
+                {
+                    warn!("Unable to delete the previous picture of member {member_id}: {e}");
+                }
+            });
         }
 
         Ok(asset_id)
@@ -75,12 +85,12 @@ impl MemberPictureCommandService for Implementation {
 }
 
 impl Implementation {
-    fn load_alien_member_picture(dynamic_image: &DynamicImage) -> BackendResult<DynamicImage> {
+    fn load_alien_member_picture(dynamic_image: DynamicImage) -> DynamicImage {
         // Create passport size image of 3.5 x 4.5 cm
-        let mut dynamic_image = dynamic_image.clone();
+        let mut dynamic_image = dynamic_image;
         dynamic_image = Self::crop_as_passport_image(dynamic_image);
         dynamic_image = Self::resize_passport_image(dynamic_image);
-        Ok(dynamic_image)
+        dynamic_image
     }
 
     fn crop_as_passport_image(dynamic_image: DynamicImage) -> DynamicImage {
@@ -124,8 +134,39 @@ impl Injectable<ServiceDependencies, dyn MemberPictureCommandService> for Implem
         let implementation = Self {
             member_repository: dependencies.member_repository.clone(),
             member_picture_repository: dependencies.member_picture_repository.clone(),
+            asset_store: dependencies.asset_store.clone(),
+            max_dimension: dependencies.settings.images.max_dimension,
         };
         let arc: Arc<dyn MemberPictureCommandService> = Arc::new(implementation);
         Data::from(arc)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::png::PngDecoder;
+    use image::{GenericImageView, ImageDecoder};
+    use std::io::Cursor;
+
+    /// A 32x16 JPEG carrying GPS coordinates and an EXIF orientation of 6, i.e. it is to be
+    /// rotated 90 degrees clockwise, moving its red left half to the top
+    const GPS_ROTATED: &[u8] = include_bytes!("../../../../tests/fixtures/exif-gps-rotated.jpg");
+
+    #[test]
+    fn passport_picture_honours_orientation() {
+        let decoded = imaging::decode(GPS_ROTATED, 8192).unwrap();
+        let picture = Implementation::load_alien_member_picture(decoded.image);
+        assert!(picture.height() > picture.width());
+        let top = picture.get_pixel(picture.width() / 2, picture.height() / 8);
+        assert!(top[0] > 200 && top[2] < 60);
+
+        let mut data = Vec::new();
+        picture
+            .write_with_encoder(PngEncoder::new(&mut data))
+            .unwrap();
+        assert!(!data.windows(4).any(|window| window == b"Exif"));
+        let mut decoder = PngDecoder::new(Cursor::new(&data)).unwrap();
+        assert!(decoder.exif_metadata().unwrap().is_none());
+    }
+}
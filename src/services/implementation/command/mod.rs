@@ -16,7 +16,11 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+pub mod absence;
+pub mod attendance;
+pub mod calendar;
 pub mod image;
+pub mod job;
 pub mod mail_template;
 pub mod mailing;
 pub mod member;
@@ -26,4 +30,5 @@ pub mod musical_instrument;
 pub mod page;
 pub mod role;
 pub mod setup;
+pub mod venue;
 pub mod workgroup;
@@ -16,6 +16,7 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::Precondition;
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
@@ -48,11 +49,15 @@ impl MusicalInstrumentCommandService for Implementation {
         &self,
         mut session: Session,
         musical_instrument_id: i32,
+        precondition: &Precondition,
         command: &UpdateMusicalInstrumentCommand,
     ) -> BackendResult<()> {
+        self.musical_instrument_repository
+            .lock_by_id(&mut session, musical_instrument_id)?;
         let origin = self
             .musical_instrument_repository
             .find_by_id(&mut session, musical_instrument_id)?;
+        precondition.check(&origin.etag)?;
         let page = MusicalInstrument::from((&origin, command));
         self.musical_instrument_repository
             .update(&mut session, page)
@@ -16,22 +16,42 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::Precondition;
+use crate::generic::rendering::{self, Shortcode};
 use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::RevisionSettings;
+use crate::generic::slug;
+use crate::generic::storage::assets::{renderings, AssetStore};
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
 use crate::injection::ServiceDependencies;
-use crate::model::interface::commands::{CreatePageCommand, PublishPageCommand, UpdatePageCommand};
-use crate::model::storage::entities::Page;
-use crate::repositories::definitions::{PageRepository, PropertiesRepository};
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::commands::{
+    CreatePageCommand, PublishPageCommand, SetPageEventCommand, UpdatePageCommand,
+    UpdatePageTreeCommand,
+};
+use crate::model::primitives::ContentFormat;
+use crate::model::storage::entities::{Page, PageEvent, PageRevision};
+use crate::repositories::definitions::{
+    MemberRepository, PageRepository, PageRevisionRepository, PropertiesRepository, VenueRepository,
+};
 use crate::services::definitions::command::PageCommandService;
+use crate::services::implementation::content::ContentRenderer;
 use actix_web::web::Data;
-use std::fs::OpenOptions;
-use std::io::Write;
+use chrono::{TimeDelta, Utc};
+use log::warn;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 
 pub struct Implementation {
     page_repository: Data<dyn PageRepository>,
+    page_revision_repository: Data<dyn PageRevisionRepository>,
+    member_repository: Data<dyn MemberRepository>,
     properties_repository: Data<dyn PropertiesRepository>,
+    venue_repository: Data<dyn VenueRepository>,
+    asset_store: Data<dyn AssetStore>,
+    renderer: ContentRenderer,
+    revision_settings: RevisionSettings,
 }
 
 impl PageCommandService for Implementation {
@@ -39,52 +59,190 @@ impl PageCommandService for Implementation {
         if let Some(event_date) = &command.event_date {
             event_date.validate()?;
         }
-        let page = Page::from(command);
+        let mut page = Page::from(command);
+        page.slug = self.unique_slug(&mut session, None, &page.slug, page.id)?;
 
         self.page_repository.create(&mut session, page)
     }
 
-    fn set_content(&self, mut session: Session, page_id: i32, content: &str) -> BackendResult<()> {
+    fn set_content(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+        author: &UserClaims,
+        format: Option<ContentFormat>,
+        content: &str,
+    ) -> BackendResult<()> {
+        self.page_repository.lock_by_id(&mut session, page_id)?;
         let page: Page = self.page_repository.find_by_id(&mut session, page_id)?;
-        let content_asset = page.content_asset;
-        let pb = crate::path_for_asset(&content_asset)?;
-        let mut w = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&pb)?;
-        w.write(&content.as_bytes())?;
-        Ok(())
+        precondition.check(&page.etag)?;
+        let format = format.unwrap_or(page.latest_content_format());
+        self.save_content(&mut session, &page, author, content, format)
+    }
+
+    fn restore_revision(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+        author: &UserClaims,
+        revision_id: i32,
+    ) -> BackendResult<()> {
+        self.page_repository.lock_by_id(&mut session, page_id)?;
+        let page: Page = self.page_repository.find_by_id(&mut session, page_id)?;
+        precondition.check(&page.etag)?;
+        let (revision, _) =
+            self.page_revision_repository
+                .find_by_id(&mut session, page_id, revision_id)?;
+        self.save_content(
+            &mut session,
+            &page,
+            author,
+            &revision.content,
+            revision.content_format,
+        )
     }
 
     fn update(
         &self,
         mut session: Session,
         page_id: i32,
+        precondition: &Precondition,
         command: &UpdatePageCommand,
     ) -> BackendResult<()> {
+        self.page_repository.lock_by_id(&mut session, page_id)?;
         let origin: Page = self.page_repository.find_by_id(&mut session, page_id)?;
-        let page = Page::from((&origin, command));
-        self.page_repository.update(&mut session, page)
+        precondition.check(&origin.etag)?;
+        let mut page = Page::from((&origin, command));
+        if page.slug != origin.slug {
+            if command.slug.is_none() {
+                page.slug =
+                    self.unique_slug(&mut session, origin.parent_id, &page.slug, page_id)?;
+            } else if self.page_repository.slug_exists(
+                &mut session,
+                origin.parent_id,
+                &page.slug,
+                page_id,
+            )? {
+                return Err(BackendError::conflict(
+                    "The slug is used by another page below the same parent page",
+                ));
+            }
+        }
+        let former_path = self
+            .page_repository
+            .find_path_by_id(&mut session, page_id)?;
+        self.page_repository.update(&mut session, page)?;
+        self.redirect_former_path(&mut session, page_id, &former_path)?;
+        self.renderer
+            .invalidate(&mut session, &[Shortcode::Event(page_id)])?;
+        self.page_repository.touch(&mut session, page_id)
     }
 
     fn publish(
         &self,
         mut session: Session,
         page_id: i32,
+        precondition: &Precondition,
         command: &PublishPageCommand,
     ) -> BackendResult<()> {
+        self.page_repository.lock_by_id(&mut session, page_id)?;
+        let page: Page = self.page_repository.find_by_id(&mut session, page_id)?;
+        precondition.check(&page.etag)?;
+
+        // The draft becomes the published content, replacing the previously published content
+        let mut published = page.clone();
+        let replaced_asset = published
+            .draft_content_asset
+            .take()
+            .map(|draft_asset| std::mem::replace(&mut published.content_asset, draft_asset));
+        if let Some(format) = published.draft_content_format.take() {
+            published.content_format = format;
+        }
+        published.publish_at = command.publish_at.map(|moment| moment.naive_utc());
+        published.unpublish_at = command.unpublish_at.map(|moment| moment.naive_utc());
+        self.page_repository
+            .update(&mut session, published.clone())?;
+
+        // Only the published content is searchable, such that drafts can not be found
+        let html = self.renderer.load(
+            &mut session,
+            page_id,
+            &published.content_asset,
+            published.content_format,
+        )?;
+        self.page_repository.set_search_text(
+            &mut session,
+            page_id,
+            &rendering::plain_text(&html),
+        )?;
+
         self.page_repository.reset_roles(&mut session, page_id)?;
         self.page_repository
-            .assign_roles(&mut session, page_id, &command.roles)
+            .assign_roles(&mut session, page_id, &command.roles)?;
+        self.renderer
+            .invalidate(&mut session, &[Shortcode::Event(page_id)])?;
+        self.page_repository.touch(&mut session, page_id)?;
+
+        if let Some(asset_id) = replaced_asset {
+            // The page refers to the replaced content until the publication is committed
+            let asset_store = self.asset_store.clone();
+            session.after_commit(move || {
+                let deleted = asset_store
+                    .delete(&asset_id)
+                    .and_then(|_| renderings::delete(asset_store.as_ref(), &asset_id));
+                if let Err(e) = deleted {
+                    warn!(
+                        "Unable to delete the previously published content of page {page_id}: {e}"
+                    );
+                }
+            });
+        }
+        Ok(())
     }
 
-    fn unpublish(&self, mut session: Session, page_id: i32) -> BackendResult<()> {
-        self.page_repository.reset_roles(&mut session, page_id)
+    fn unpublish(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+    ) -> BackendResult<()> {
+        self.page_repository.lock_by_id(&mut session, page_id)?;
+        let page: Page = self.page_repository.find_by_id(&mut session, page_id)?;
+        precondition.check(&page.etag)?;
+        let mut unpublished = page.clone();
+        unpublished.publish_at = None;
+        unpublished.unpublish_at = None;
+        self.page_repository.update(&mut session, unpublished)?;
+        self.page_repository.reset_roles(&mut session, page_id)?;
+        self.renderer
+            .invalidate(&mut session, &[Shortcode::Event(page_id)])?;
+        self.page_repository.touch(&mut session, page_id)
     }
 
     fn delete(&self, mut session: Session, page_id: i32) -> BackendResult<()> {
-        self.page_repository.delete(&mut session, page_id)
+        let page: Page = self.page_repository.find_by_id(&mut session, page_id)?;
+        self.renderer
+            .invalidate(&mut session, &[Shortcode::Event(page_id)])?;
+        self.page_repository.delete(&mut session, page_id)?;
+        let content_asset_ids = std::iter::once(page.content_asset).chain(page.draft_content_asset);
+        let rendering_ids = content_asset_ids
+            .clone()
+            .map(|asset_id| renderings::rendering_asset_id(&asset_id));
+        let asset_ids = content_asset_ids
+            .chain(rendering_ids)
+            .chain(page.icon_asset);
+        let asset_ids: Vec<String> = asset_ids.collect();
+        let asset_store = self.asset_store.clone();
+        session.after_commit(move || {
+            for asset_id in asset_ids {
+                if let Err(e) = asset_store.delete(&asset_id) {
+                    warn!("Unable to delete asset {asset_id} of page {page_id}: {e}");
+                }
+            }
+        });
+        Ok(())
     }
 
     fn set_default(&self, mut session: Session, page_id: i32) -> BackendResult<()> {
@@ -98,32 +256,297 @@ impl PageCommandService for Implementation {
         &self,
         mut session: Session,
         page_id: i32,
+        precondition: &Precondition,
         order_number: i32,
     ) -> BackendResult<()> {
-        // Verify that the page really exists
-        let _ = self.page_repository.find_by_id(&mut session, page_id)?;
+        self.page_repository.lock_by_id(&mut session, page_id)?;
+        let page: Page = self.page_repository.find_by_id(&mut session, page_id)?;
+        precondition.check(&page.etag)?;
         self.page_repository
-            .set_order_by_id(&mut session, page_id, order_number)
+            .set_order_by_id(&mut session, page_id, order_number)?;
+        self.page_repository.touch(&mut session, page_id)
     }
 
     fn set_or_unset_parent_id(
         &self,
         mut session: Session,
         page_id: i32,
+        precondition: &Precondition,
         maybe_parent_id: Option<i32>,
     ) -> BackendResult<()> {
-        // Verify that the page really exists
-        let _ = self.page_repository.find_by_id(&mut session, page_id)?;
+        self.page_repository.lock_by_id(&mut session, page_id)?;
+        let page: Page = self.page_repository.find_by_id(&mut session, page_id)?;
+        precondition.check(&page.etag)?;
 
         if let Some(parent_id) = maybe_parent_id {
-            // Verify that the parent page really exists and has no parent page itself
-            let parent_page = self.page_repository.find_by_id(&mut session, parent_id)?;
-            if let Some(_) = parent_page.parent_id {
+            // Verify that the parent page really exists
+            let _ = self.page_repository.find_by_id(&mut session, parent_id)?;
+        }
+        let mut parents: HashMap<i32, Option<i32>> = self
+            .page_repository
+            .list_hierarchy(&mut session)?
+            .into_iter()
+            .collect();
+        parents.insert(page_id, maybe_parent_id);
+        verify_acyclic(&parents)?;
+
+        let former_path = self
+            .page_repository
+            .find_path_by_id(&mut session, page_id)?;
+        self.move_page(&mut session, &page, maybe_parent_id)?;
+        self.redirect_former_path(&mut session, page_id, &former_path)?;
+        self.page_repository.touch(&mut session, page_id)
+    }
+
+    fn update_tree(
+        &self,
+        mut session: Session,
+        command: &UpdatePageTreeCommand,
+    ) -> BackendResult<()> {
+        let mut parents: HashMap<i32, Option<i32>> = self
+            .page_repository
+            .list_hierarchy(&mut session)?
+            .into_iter()
+            .collect();
+
+        for page_move in &command.moves {
+            if !parents.contains_key(&page_move.page_id) {
                 return Err(BackendError::bad());
             }
+            if let Some(parent_id) = page_move.parent_id {
+                if !parents.contains_key(&parent_id) {
+                    return Err(BackendError::bad());
+                }
+            }
+        }
+        // The pages are locked in the order of their ids, such that concurrent tree updates can
+        // not deadlock
+        let page_ids: BTreeSet<i32> = command.moves.iter().map(|m| m.page_id).collect();
+        for &page_id in &page_ids {
+            self.page_repository.lock_by_id(&mut session, page_id)?;
+        }
+
+        // All moves are verified before any of them is applied, should applying them fail
+        // nonetheless, the failed request rolls back the session
+        let mut moved = Vec::with_capacity(command.moves.len());
+        for page_move in &command.moves {
+            let page = self
+                .page_repository
+                .find_by_id(&mut session, page_move.page_id)?;
+            if let Some(etag) = &page_move.etag {
+                if etag != &page.etag {
+                    return Err(BackendError::precondition_failed());
+                }
+            }
+            let former_path = self
+                .page_repository
+                .find_path_by_id(&mut session, page.id)?;
+            parents.insert(page_move.page_id, page_move.parent_id);
+            moved.push((page, former_path));
         }
+        verify_acyclic(&parents)?;
+
+        for (page_move, (page, _)) in command.moves.iter().zip(&moved) {
+            self.move_page(&mut session, page, page_move.parent_id)?;
+            self.page_repository
+                .set_order_by_id(&mut session, page.id, page_move.order_number)?;
+            self.page_repository.touch(&mut session, page.id)?;
+        }
+        // The paths are compared once all pages are moved, as a page may move with its parent
+        for (page, former_path) in &moved {
+            self.redirect_former_path(&mut session, page.id, former_path)?;
+        }
+        Ok(())
+    }
+
+    fn set_event(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+        command: &SetPageEventCommand,
+    ) -> BackendResult<()> {
+        self.page_repository.lock_by_id(&mut session, page_id)?;
+        let mut page = self.page_repository.find_by_id(&mut session, page_id)?;
+        precondition.check(&page.etag)?;
+        if let Some(venue_id) = command.venue_id {
+            self.venue_repository.find_by_id(&mut session, venue_id)?;
+        }
+        // The event dates of the page describe the first occurrence, such that the page is still
+        // listed as an event by clients unaware of event times
+        page.event_date = Some(command.starts_at.date());
+        page.end_event_date = Some(command.ends_at.date());
+        self.page_repository.update(&mut session, page)?;
+        self.page_repository.set_event(
+            &mut session,
+            PageEvent::from((page_id, command)),
+            &command.exceptions,
+        )?;
+        self.renderer
+            .invalidate(&mut session, &[Shortcode::Event(page_id)])?;
+        self.page_repository.touch(&mut session, page_id)
+    }
+
+    fn delete_event(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        precondition: &Precondition,
+    ) -> BackendResult<()> {
+        self.page_repository.lock_by_id(&mut session, page_id)?;
+        let page = self.page_repository.find_by_id(&mut session, page_id)?;
+        precondition.check(&page.etag)?;
+        self.page_repository.delete_event(&mut session, page_id)?;
+        self.renderer
+            .invalidate(&mut session, &[Shortcode::Event(page_id)])?;
+        self.page_repository.touch(&mut session, page_id)
+    }
+}
+
+/// Verifies that following the parent pages from any page ends at a main menu page, rather than
+/// at a page which was already passed, which would make a page a sub page of itself
+fn verify_acyclic(parents: &HashMap<i32, Option<i32>>) -> BackendResult<()> {
+    let mut rooted = HashSet::new();
+    for &page_id in parents.keys() {
+        let mut path = HashSet::new();
+        let mut current = Some(page_id);
+        while let Some(id) = current.filter(|id| !rooted.contains(id)) {
+            if !path.insert(id) {
+                return Err(BackendError::conflict(format!(
+                    "Page {id} would become a sub page of itself"
+                )));
+            }
+            current = parents.get(&id).copied().flatten();
+        }
+        rooted.extend(path);
+    }
+    Ok(())
+}
+
+impl Implementation {
+    /// Returns the slug, or the slug followed by the lowest free number, such that it is unique
+    /// among the pages below the parent page
+    fn unique_slug(
+        &self,
+        session: &mut Session,
+        parent_id: Option<i32>,
+        slug: &str,
+        page_id: i32,
+    ) -> BackendResult<String> {
+        let base = if slug.is_empty() { "page" } else { slug };
+        let mut candidate = base.to_owned();
+        let mut number = 1;
+        while self
+            .page_repository
+            .slug_exists(session, parent_id, &candidate, page_id)?
+        {
+            number += 1;
+            candidate = slug::numbered(base, number);
+        }
+        Ok(candidate)
+    }
+
+    /// Moves the page below the parent page, renaming its slug if it is already used there
+    fn move_page(
+        &self,
+        session: &mut Session,
+        page: &Page,
+        maybe_parent_id: Option<i32>,
+    ) -> BackendResult<()> {
+        let slug = self.unique_slug(session, maybe_parent_id, &page.slug, page.id)?;
         self.page_repository
-            .set_or_unset_parent_id_by_id(&mut session, page_id, maybe_parent_id)
+            .set_or_unset_parent_id_by_id(session, page.id, maybe_parent_id)?;
+        if slug != page.slug {
+            self.page_repository
+                .set_slug_by_id(session, page.id, &slug)?;
+        }
+        Ok(())
+    }
+
+    /// Permanently redirects the former path of the page to the page, if its path changed. The
+    /// former paths of its sub pages are redirected through the former path of the page.
+    fn redirect_former_path(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        former_path: &str,
+    ) -> BackendResult<()> {
+        let path = self.page_repository.find_path_by_id(session, page_id)?;
+        if path != former_path {
+            self.page_repository
+                .add_redirect(session, former_path, page_id)?;
+        }
+        Ok(())
+    }
+
+    /// Stores the content as the draft of the page, which becomes visible once the page is
+    /// published, together with its rendering. Keeps the content as the new head revision and
+    /// prunes the revisions which fall outside the retention policy
+    fn save_content(
+        &self,
+        session: &mut Session,
+        page: &Page,
+        author: &UserClaims,
+        content: &str,
+        format: ContentFormat,
+    ) -> BackendResult<()> {
+        // Content saved before revisions were kept becomes the first revision, without an author
+        if !self
+            .page_revision_repository
+            .exists_for_page(session, page.id)?
+            && self.asset_store.exists(page.latest_content_asset())?
+        {
+            let data = self.asset_store.get(page.latest_content_asset())?;
+            let previous = String::from_utf8(data).map_err(BackendError::byte_conversion)?;
+            let mut revision =
+                PageRevision::new(page, None, &previous, page.latest_content_format());
+            revision.creation_time = page.last_modified;
+            self.page_revision_repository.create(session, revision)?;
+        }
+
+        // Members who are not found, such as removed members, are recorded without an author
+        let author_id = self
+            .member_repository
+            .find_id_by_email_address(session, &author.email_address)?;
+        self.page_revision_repository
+            .create(session, PageRevision::new(page, author_id, content, format))?;
+
+        let created_before = match self.revision_settings.max_age_days {
+            0 => None,
+            days => Some(Utc::now().naive_utc() - TimeDelta::days(days as i64)),
+        };
+        self.page_revision_repository.prune(
+            session,
+            page.id,
+            self.revision_settings.max_per_page,
+            created_before,
+        )?;
+
+        // Every save is written to a new asset, such that the previous draft stays intact
+        // until the save is committed
+        let draft_asset = crate::generate_asset_id();
+        self.asset_store.put(&draft_asset, content.as_bytes())?;
+        self.renderer
+            .store(session, page.id, &draft_asset, content, format)?;
+        let mut drafted = page.clone();
+        drafted.draft_content_asset = Some(draft_asset);
+        drafted.draft_content_format = Some(format);
+        self.page_repository.update(session, drafted)?;
+        self.page_repository.touch(session, page.id)?;
+
+        if let Some(asset_id) = page.draft_content_asset.clone() {
+            let asset_store = self.asset_store.clone();
+            let page_id = page.id;
+            session.after_commit(move || {
+                let deleted = asset_store
+                    .delete(&asset_id)
+                    .and_then(|_| renderings::delete(asset_store.as_ref(), &asset_id));
+                if let Err(e) = deleted {
+                    warn!("Unable to delete the previous draft of page {page_id}: {e}");
+                }
+            });
+        }
+        Ok(())
     }
 }
 
@@ -131,9 +554,32 @@ impl Injectable<ServiceDependencies, dyn PageCommandService> for Implementation
     fn make(dependencies: &ServiceDependencies) -> Data<dyn PageCommandService> {
         let implementation = Self {
             page_repository: dependencies.page_repository.clone(),
+            page_revision_repository: dependencies.page_revision_repository.clone(),
+            member_repository: dependencies.member_repository.clone(),
             properties_repository: dependencies.properties_repository.clone(),
+            venue_repository: dependencies.venue_repository.clone(),
+            asset_store: dependencies.asset_store.clone(),
+            renderer: ContentRenderer::new(dependencies),
+            revision_settings: dependencies.settings.revisions.clone(),
         };
         let arc: Arc<dyn PageCommandService> = Arc::new(implementation);
         Data::from(arc)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_acyclic_accepts_nested_pages() {
+        let parents = HashMap::from([(1, None), (2, Some(1)), (3, Some(2)), (4, Some(3))]);
+        assert!(verify_acyclic(&parents).is_ok());
+    }
+
+    #[test]
+    fn verify_acyclic_rejects_cycles() {
+        let parents = HashMap::from([(1, None), (2, Some(4)), (3, Some(2)), (4, Some(3))]);
+        assert!(verify_acyclic(&parents).is_err());
+    }
+}
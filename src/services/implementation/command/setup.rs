@@ -26,11 +26,13 @@ use crate::model::storage::extended_entities::ExtendedMember;
 use crate::repositories::definitions::{MemberRepository, MemberRoleRepository};
 use crate::services::definitions::command::SetupCommandService;
 use actix_web::web::Data;
+use chrono::TimeDelta;
 use std::sync::Arc;
 
 pub struct Implementation {
     member_repository: Data<dyn MemberRepository>,
     member_role_repository: Data<dyn MemberRoleRepository>,
+    activation_delta: TimeDelta,
 }
 
 impl SetupCommandService for Implementation {
@@ -40,7 +42,7 @@ impl SetupCommandService for Implementation {
         command: &FirstOperatorRegisterCommand,
     ) -> BackendResult<String> {
         if !self.has_operators(&mut session)? {
-            let extended_member = ExtendedMember::from(command);
+            let extended_member = ExtendedMember::from((command, self.activation_delta));
 
             let member_id = self
                 .member_repository
@@ -73,6 +75,7 @@ impl Injectable<ServiceDependencies, dyn SetupCommandService> for Implementation
         let implementation = Self {
             member_repository: dependencies.member_repository.clone(),
             member_role_repository: dependencies.member_role_repository.clone(),
+            activation_delta: dependencies.settings.activation.first_operator_delta(),
         };
         let arc: Arc<dyn SetupCommandService> = Arc::new(implementation);
         Data::from(arc)
@@ -0,0 +1,67 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::caching::Precondition;
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::commands::{RegisterVenueCommand, UpdateVenueCommand};
+use crate::model::storage::entities::Venue;
+use crate::repositories::definitions::VenueRepository;
+use crate::services::definitions::command::VenueCommandService;
+use actix_web::web::Data;
+use std::sync::Arc;
+
+pub struct Implementation {
+    venue_repository: Data<dyn VenueRepository>,
+}
+
+impl VenueCommandService for Implementation {
+    fn register(&self, mut session: Session, command: &RegisterVenueCommand) -> BackendResult<()> {
+        let venue = Venue::from(command);
+        self.venue_repository.create(&mut session, venue)
+    }
+
+    fn update(
+        &self,
+        mut session: Session,
+        venue_id: i32,
+        precondition: &Precondition,
+        command: &UpdateVenueCommand,
+    ) -> BackendResult<()> {
+        let origin = self.venue_repository.find_by_id(&mut session, venue_id)?;
+        precondition.check(&origin.etag)?;
+        let venue = Venue::from((&origin, command));
+        self.venue_repository.update(&mut session, venue)
+    }
+
+    fn delete(&self, mut session: Session, venue_id: i32) -> BackendResult<()> {
+        self.venue_repository.delete(&mut session, venue_id)
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn VenueCommandService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn VenueCommandService> {
+        let implementation = Self {
+            venue_repository: dependencies.venue_repository.clone(),
+        };
+        let arc: Arc<dyn VenueCommandService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
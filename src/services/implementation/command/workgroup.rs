@@ -16,6 +16,7 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::Precondition;
 use crate::generic::result::BackendResult;
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
@@ -48,11 +49,15 @@ impl WorkgroupCommandService for Implementation {
         &self,
         mut session: Session,
         workgroup_id: i32,
+        precondition: &Precondition,
         command: &WorkgroupUpdateCommand,
     ) -> BackendResult<()> {
+        self.workgroup_repository
+            .lock_by_id(&mut session, workgroup_id)?;
         let origin = self
             .workgroup_repository
             .find_by_id(&mut session, workgroup_id)?;
+        precondition.check(&origin.etag)?;
         let new = Workgroup::from((&origin, command));
         self.workgroup_repository.save(&mut session, new)
     }
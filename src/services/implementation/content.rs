@@ -0,0 +1,167 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Rendering of page content, shared by the page services
+
+use crate::generic::rendering::{self, Link, Shortcode};
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::settings::ContentSettings;
+use crate::generic::storage::assets::{renderings, AssetStore};
+use crate::generic::storage::session::Session;
+use crate::injection::ServiceDependencies;
+use crate::model::primitives::{ContentFormat, Role};
+use crate::repositories::definitions::{ImageRepository, PageRepository};
+use actix_web::web::Data;
+use chrono::Utc;
+use log::warn;
+
+pub struct ContentRenderer {
+    page_repository: Data<dyn PageRepository>,
+    image_repository: Data<dyn ImageRepository>,
+    asset_store: Data<dyn AssetStore>,
+    content_settings: ContentSettings,
+}
+
+impl ContentRenderer {
+    pub fn new(dependencies: &ServiceDependencies) -> Self {
+        Self {
+            page_repository: dependencies.page_repository.clone(),
+            image_repository: dependencies.image_repository.clone(),
+            asset_store: dependencies.asset_store.clone(),
+            content_settings: dependencies.settings.content.clone(),
+        }
+    }
+
+    /// Renders the content of the page and stores the rendering next to the content asset
+    pub fn store(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        asset_id: &str,
+        source: &str,
+        format: ContentFormat,
+    ) -> BackendResult<()> {
+        let (html, references) = self.render(session, source, format)?;
+        self.page_repository
+            .add_references(session, page_id, &references)?;
+        renderings::store(self.asset_store.as_ref(), asset_id, &html)
+    }
+
+    /// Returns the rendering of the content asset of the page, rendering it if it was not stored
+    /// yet
+    pub fn load(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        asset_id: &str,
+        format: ContentFormat,
+    ) -> BackendResult<String> {
+        let mut references = Vec::new();
+        let html = renderings::load(self.asset_store.as_ref(), asset_id, |source| {
+            let (html, found) = self.render(session, source, format)?;
+            references = found;
+            Ok(html)
+        })?;
+        self.page_repository
+            .add_references(session, page_id, &references)?;
+        Ok(html)
+    }
+
+    /// Discards the stored renderings of the pages referring to any of the entities, once the
+    /// transaction is committed, such that they are rendered again when requested next
+    pub fn invalidate(&self, session: &mut Session, shortcodes: &[Shortcode]) -> BackendResult<()> {
+        let asset_ids = self
+            .page_repository
+            .find_referring_content_assets(session, shortcodes)?;
+        if asset_ids.is_empty() {
+            return Ok(());
+        }
+        let asset_store = self.asset_store.clone();
+        session.after_commit(move || {
+            for asset_id in asset_ids {
+                if let Err(e) = renderings::delete(asset_store.as_ref(), &asset_id) {
+                    warn!("Unable to discard the rendering of content {asset_id}: {e}");
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Renders the content, returning the shortcodes it refers to alongside the rendering
+    fn render(
+        &self,
+        session: &mut Session,
+        source: &str,
+        format: ContentFormat,
+    ) -> BackendResult<(String, Vec<Shortcode>)> {
+        let mut references = Vec::new();
+        let mut failure: Option<BackendError> = None;
+        let html = rendering::render(source, format, |shortcode| {
+            references.push(shortcode);
+            match self.resolve(session, shortcode) {
+                Ok(link) => link,
+                Err(e) => {
+                    failure.get_or_insert(e);
+                    None
+                }
+            }
+        });
+        match failure {
+            Some(e) => Err(e),
+            None => Ok((html, references)),
+        }
+    }
+
+    /// The rendering is shared by everyone allowed to view the page, hence only entities which
+    /// are visible to the public are resolved, other shortcodes are left as written
+    fn resolve(&self, session: &mut Session, shortcode: Shortcode) -> BackendResult<Option<Link>> {
+        match shortcode {
+            Shortcode::Image(image_id) => {
+                let roles = self
+                    .image_repository
+                    .find_associated_roles_by_id(session, image_id)?;
+                if !roles.contains(&Role::Public) {
+                    return Ok(None);
+                }
+                let image = self.image_repository.find_by_id(session, image_id)?;
+                Ok(Some(Link::Image {
+                    id: image.id,
+                    title: image.title,
+                }))
+            }
+            Shortcode::Event(page_id) => {
+                let roles = self
+                    .page_repository
+                    .find_associated_roles_by_id(session, page_id)?;
+                if !roles.contains(&Role::Public) {
+                    return Ok(None);
+                }
+                let page = self.page_repository.find_by_id(session, page_id)?;
+                if !page.is_published_at(Utc::now().naive_utc()) {
+                    return Ok(None);
+                }
+                Ok(page.event_date.map(|event_date| Link::Page {
+                    href: self.content_settings.page_link(page.id),
+                    title: page.title,
+                    event_date: Some(event_date),
+                }))
+            }
+        }
+    }
+}
@@ -18,4 +18,6 @@
  */
 
 pub mod command;
+pub mod content;
+pub mod occurrence;
 pub mod request;
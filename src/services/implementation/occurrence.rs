@@ -0,0 +1,59 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Lookup of single occurrences of events, shared by the attendance services
+
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::security::ClaimRoles;
+use crate::generic::storage::session::Session;
+use crate::injection::ServiceDependencies;
+use crate::model::storage::entities::Page;
+use crate::repositories::definitions::PageRepository;
+use actix_web::web::Data;
+use chrono::NaiveDate;
+
+pub struct OccurrenceFinder {
+    page_repository: Data<dyn PageRepository>,
+}
+
+impl OccurrenceFinder {
+    pub fn new(dependencies: &ServiceDependencies) -> Self {
+        Self {
+            page_repository: dependencies.page_repository.clone(),
+        }
+    }
+
+    /// Finds the event page visible given the roles, of which an occurrence starts on the day.
+    /// Days on which the event does not start are refused, such as days left out by the
+    /// recurrence rule or the exceptions of the event.
+    pub fn find(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Page> {
+        self.page_repository
+            .find_events(session, roles, &occurrence_date, &occurrence_date)?
+            .into_iter()
+            .map(|occurrence| occurrence.page)
+            .find(|page| page.id == page_id && page.event_date == Some(occurrence_date))
+            .ok_or_else(BackendError::bad)
+    }
+}
@@ -0,0 +1,60 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::responses::AbsenceResponse;
+use crate::repositories::definitions::{AbsenceRepository, MemberRepository};
+use crate::services::definitions::request::AbsenceRequestService;
+use actix_web::web::Data;
+use std::sync::Arc;
+
+pub struct Implementation {
+    member_repository: Data<dyn MemberRepository>,
+    absence_repository: Data<dyn AbsenceRepository>,
+}
+
+impl AbsenceRequestService for Implementation {
+    fn list(
+        &self,
+        mut session: Session,
+        claims: &UserClaims,
+    ) -> BackendResult<Vec<AbsenceResponse>> {
+        let member = self
+            .member_repository
+            .find_extended_by_email_address(&mut session, &claims.email_address)?;
+        let absences = self
+            .absence_repository
+            .list_by_member_id(&mut session, member.id)?;
+        Ok(absences.iter().map(AbsenceResponse::from).collect())
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn AbsenceRequestService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn AbsenceRequestService> {
+        let implementation = Self {
+            member_repository: dependencies.member_repository.clone(),
+            absence_repository: dependencies.absence_repository.clone(),
+        };
+        let arc: Arc<dyn AbsenceRequestService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::security::ClaimRoles;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::responses::{AttendanceReportResponse, AttendanceResponse};
+use crate::model::primitives::Role;
+use crate::model::traits::RoleContainer;
+use crate::repositories::definitions::{AttendanceRepository, MemberRepository, PageRepository};
+use crate::services::definitions::request::AttendanceRequestService;
+use crate::services::implementation::occurrence::OccurrenceFinder;
+use actix_web::web::Data;
+use chrono::NaiveDate;
+use std::sync::Arc;
+
+pub struct Implementation {
+    page_repository: Data<dyn PageRepository>,
+    member_repository: Data<dyn MemberRepository>,
+    attendance_repository: Data<dyn AttendanceRepository>,
+    occurrences: OccurrenceFinder,
+}
+
+impl AttendanceRequestService for Implementation {
+    fn find(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        claims: &UserClaims,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Option<AttendanceResponse>> {
+        self.occurrences
+            .find(&mut session, page_id, occurrence_date, roles)?;
+        let member = self
+            .member_repository
+            .find_extended_by_email_address(&mut session, &claims.email_address)?;
+        let attendance =
+            self.attendance_repository
+                .find(&mut session, page_id, occurrence_date, member.id)?;
+        Ok(attendance.as_ref().map(AttendanceResponse::from))
+    }
+
+    fn report(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        occurrence_date: NaiveDate,
+        roles: &ClaimRoles,
+    ) -> BackendResult<AttendanceReportResponse> {
+        // The report reveals the notes of the members, which is left to the committee
+        let committee = [Role::OrchestraCommittee, Role::Director, Role::Operator];
+        if !committee.iter().any(|role| roles.has_role(*role)) {
+            return Err(BackendError::forbidden());
+        }
+        let page = self
+            .occurrences
+            .find(&mut session, page_id, occurrence_date, roles)?;
+        let page_roles = self
+            .page_repository
+            .find_associated_roles_by_id(&mut session, page_id)?;
+        let attendees = self.attendance_repository.list_attendees(
+            &mut session,
+            page_id,
+            occurrence_date,
+            &page_roles,
+        )?;
+        Ok(AttendanceReportResponse::build(
+            &page,
+            occurrence_date,
+            &attendees,
+        ))
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn AttendanceRequestService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn AttendanceRequestService> {
+        let implementation = Self {
+            page_repository: dependencies.page_repository.clone(),
+            member_repository: dependencies.member_repository.clone(),
+            attendance_repository: dependencies.attendance_repository.clone(),
+            occurrences: OccurrenceFinder::new(dependencies),
+        };
+        let arc: Arc<dyn AttendanceRequestService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
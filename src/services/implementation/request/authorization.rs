@@ -16,7 +16,7 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::TOKEN_EXPIRY_HIGH_WATER_MARK;
+use crate::generic::metrics::Metrics;
 use crate::generic::result::{BackendError, BackendResult};
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
@@ -30,6 +30,7 @@ use actix_jwt_auth_middleware::TokenSigner;
 use actix_web::cookie::time::OffsetDateTime;
 use actix_web::cookie::{Cookie, Expiration, SameSite};
 use actix_web::web::Data;
+use aes_gcm::Aes256Gcm;
 use chrono::{TimeDelta, Utc};
 use jwt_compact::alg::Ed25519;
 use jwt_compact::UntrustedToken;
@@ -42,45 +43,20 @@ pub struct Implementation {
     member_repository: Data<dyn MemberRepository>,
     authorization_repository: Data<dyn AuthorizationRepository>,
     token_signer: Data<TokenSigner<UserClaims, Ed25519>>,
+    otp_cipher: Aes256Gcm,
+    token_expiry_high_water_mark: TimeDelta,
+    metrics: Data<Metrics>,
 }
 
 impl AuthorizationRequestService for Implementation {
     fn login(
         &self,
-        mut session: Session,
+        session: Session,
         login_data: &AuthorizationRequest,
     ) -> BackendResult<AuthorizationResponse> {
-        let extended_member = self
-            .member_repository
-            .find_extended_by_email_address(&mut session, &login_data.email_address)?;
-
-        let totp: TOTP = MemberResponse::from(&extended_member).try_into()?;
-        let is_current = totp
-            .check_current(&login_data.token)
-            .map_err(|_| BackendError::forbidden())?;
-
-        if is_current {
-            let user_claims = UserClaims {
-                email_address: login_data.email_address.clone(),
-                roles: self
-                    .authorization_repository
-                    .find_composite_roles_by_member_id(&mut session, extended_member.id)?,
-            };
-
-            let mut access_cookie = self.token_signer.create_access_cookie(&user_claims)?;
-            let mut refresh_cookie = self.token_signer.create_refresh_cookie(&user_claims)?;
-            Self::set_cookie_site_policy(&mut access_cookie);
-            Self::set_cookie_site_policy(&mut refresh_cookie);
-            let cookies = vec![access_cookie, refresh_cookie];
-
-            Ok(AuthorizationResponse {
-                member: MemberResponse::from(&extended_member),
-                composite_roles: user_claims.roles,
-                cookies,
-            })
-        } else {
-            Err(BackendError::forbidden())
-        }
+        let result = self.authenticate(session, login_data);
+        self.metrics.record_login(result.is_ok());
+        result
     }
 
     fn refresh(
@@ -97,7 +73,7 @@ impl AuthorizationRequestService for Implementation {
         // If the refresh token nearly expires, the login procedure is transparently performed, to
         // ensure that user roles are still the same. If the access token nearly expires, then a new
         // access token is simply created, otherwise nothing is done.
-        let (new_user_claims, new_cookies) = if Self::token_nearly_expires(origin_refresh_token)? {
+        let (new_user_claims, new_cookies) = if self.token_nearly_expires(origin_refresh_token)? {
             info!(
                 "Refreshing tokens for member: {}",
                 &client_user_claims.email_address
@@ -108,7 +84,7 @@ impl AuthorizationRequestService for Implementation {
             Self::set_cookie_site_policy(&mut access_cookie);
             Self::set_cookie_site_policy(&mut refresh_cookie);
             (new_user_claims, vec![access_cookie, refresh_cookie])
-        } else if Self::token_nearly_expires(origin_access_token)? {
+        } else if self.token_nearly_expires(origin_access_token)? {
             let mut access_cookie = self
                 .token_signer
                 .create_access_cookie(&client_user_claims)?;
@@ -148,13 +124,50 @@ impl AuthorizationRequestService for Implementation {
 }
 
 impl Implementation {
-    fn token_nearly_expires(token: UntrustedToken) -> BackendResult<bool> {
+    fn authenticate(
+        &self,
+        mut session: Session,
+        login_data: &AuthorizationRequest,
+    ) -> BackendResult<AuthorizationResponse> {
+        let extended_member = self
+            .member_repository
+            .find_extended_by_email_address(&mut session, &login_data.email_address)?;
+
+        let totp: TOTP = MemberResponse::from(&extended_member).totp(&self.otp_cipher)?;
+        let is_current = totp
+            .check_current(&login_data.token)
+            .map_err(|_| BackendError::forbidden())?;
+
+        if is_current {
+            let user_claims = UserClaims {
+                email_address: login_data.email_address.clone(),
+                roles: self
+                    .authorization_repository
+                    .find_composite_roles_by_member_id(&mut session, extended_member.id)?,
+            };
+
+            let mut access_cookie = self.token_signer.create_access_cookie(&user_claims)?;
+            let mut refresh_cookie = self.token_signer.create_refresh_cookie(&user_claims)?;
+            Self::set_cookie_site_policy(&mut access_cookie);
+            Self::set_cookie_site_policy(&mut refresh_cookie);
+            let cookies = vec![access_cookie, refresh_cookie];
+
+            Ok(AuthorizationResponse {
+                member: MemberResponse::from(&extended_member),
+                composite_roles: user_claims.roles,
+                cookies,
+            })
+        } else {
+            Err(BackendError::forbidden())
+        }
+    }
+
+    fn token_nearly_expires(&self, token: UntrustedToken) -> BackendResult<bool> {
         let expiration = token
             .deserialize_claims_unchecked::<UserClaims>()?
             .expiration
             .ok_or(BackendError::bad())?;
-        let delta = TimeDelta::seconds((*TOKEN_EXPIRY_HIGH_WATER_MARK) as i64);
-        let high_water_mark = expiration.add(-delta);
+        let high_water_mark = expiration.add(-self.token_expiry_high_water_mark);
         Ok(high_water_mark.le(&Utc::now()))
     }
 
@@ -187,6 +200,11 @@ impl Injectable<ServiceDependencies, dyn AuthorizationRequestService> for Implem
             member_repository: dependencies.member_repository.clone(),
             authorization_repository: dependencies.authorization_repository.clone(),
             token_signer: dependencies.token_signer.clone(),
+            otp_cipher: dependencies.settings.security.otp_cipher(),
+            token_expiry_high_water_mark: TimeDelta::seconds(
+                dependencies.settings.security.token_expiry_high_water_mark as i64,
+            ),
+            metrics: dependencies.metrics.clone(),
         };
         let arc: Arc<dyn AuthorizationRequestService> = Arc::new(implementation);
         Data::from(arc)
@@ -0,0 +1,180 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::caching::{Cached, Visibility};
+use crate::generic::icalendar::{self, Event, EventTime};
+use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::security::ClaimRoles;
+use crate::generic::settings::{ContentSettings, EventSettings};
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::client::UserClaims;
+use crate::model::interface::responses::CalendarFeedResponse;
+use crate::model::primitives::Role;
+use crate::model::storage::extended_entities::EventOccurrence;
+use crate::repositories::definitions::{
+    AuthorizationRepository, CalendarFeedRepository, MemberRepository, PageRepository,
+};
+use crate::services::definitions::request::CalendarRequestService;
+use actix_web::web::Data;
+use chrono::{Days, Utc};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+pub struct Implementation {
+    page_repository: Data<dyn PageRepository>,
+    member_repository: Data<dyn MemberRepository>,
+    authorization_repository: Data<dyn AuthorizationRepository>,
+    calendar_feed_repository: Data<dyn CalendarFeedRepository>,
+    event_settings: EventSettings,
+    content_settings: ContentSettings,
+}
+
+impl CalendarRequestService for Implementation {
+    fn public_feed(&self, mut session: Session) -> BackendResult<Cached<String>> {
+        let roles = ClaimRoles::from(&None);
+        self.feed(&mut session, &roles, Visibility::Public)
+    }
+
+    fn personal_feed(&self, mut session: Session, token: &str) -> BackendResult<Cached<String>> {
+        let feed = self
+            .calendar_feed_repository
+            .find_by_token(&mut session, token)?
+            .ok_or_else(BackendError::forbidden)?;
+        // Operators would otherwise receive unpublished events, which do not belong in a calendar
+        let roles: Vec<Role> = self
+            .authorization_repository
+            .find_composite_roles_by_member_id(&mut session, feed.member_id)?
+            .into_iter()
+            .filter(|role| *role != Role::Operator)
+            .collect();
+        let roles = ClaimRoles::from(roles.as_slice());
+        self.feed(&mut session, &roles, Visibility::Private)
+    }
+
+    fn find_feed(
+        &self,
+        mut session: Session,
+        claims: &UserClaims,
+    ) -> BackendResult<Option<CalendarFeedResponse>> {
+        let member = self
+            .member_repository
+            .find_extended_by_email_address(&mut session, &claims.email_address)?;
+        let feed = self
+            .calendar_feed_repository
+            .find_by_member_id(&mut session, member.id)?;
+        Ok(feed.as_ref().map(CalendarFeedResponse::from))
+    }
+}
+
+impl Implementation {
+    /// Renders the events around today which are visible given the roles
+    fn feed(
+        &self,
+        session: &mut Session,
+        roles: &ClaimRoles,
+        visibility: Visibility,
+    ) -> BackendResult<Cached<String>> {
+        let today = Utc::now().date_naive();
+        let start_date = today
+            .checked_sub_days(Days::new(self.event_settings.calendar_past_days as u64))
+            .ok_or_else(BackendError::bad)?;
+        let end_date = today
+            .checked_add_days(Days::new(self.event_settings.calendar_future_days as u64))
+            .ok_or_else(BackendError::bad)?;
+        let occurrences =
+            self.page_repository
+                .find_events(session, roles, &start_date, &end_date)?;
+
+        let events: Vec<Event> = occurrences
+            .iter()
+            .filter_map(|occurrence| self.event(occurrence))
+            .collect();
+        let feed = icalendar::render(&self.event_settings.calendar_name, &events);
+        // The feed is composed of many pages, hence its entity tag is derived from its content
+        let etag = format!("{:x}", Sha256::digest(feed.as_bytes()));
+        let cached = Cached::new(feed, etag, visibility);
+        let last_modified = occurrences
+            .iter()
+            .map(|occurrence| occurrence.page.last_modified)
+            .max();
+        Ok(match last_modified {
+            Some(last_modified) => cached.with_last_modified(last_modified),
+            None => cached,
+        })
+    }
+
+    fn event(&self, occurrence: &EventOccurrence) -> Option<Event> {
+        let page = &occurrence.page;
+        let start_date = page.event_date?;
+        let link = self.content_settings.page_link(page.id);
+        let domain = &self.event_settings.calendar_domain;
+        let (uid, time, location) = match &occurrence.times {
+            None => (
+                format!("page-{}@{domain}", page.id),
+                EventTime::Days {
+                    start_date,
+                    end_date: page.end_event_date.unwrap_or(start_date),
+                },
+                None,
+            ),
+            Some(times) => {
+                // Every occurrence of a recurring event is a separate event in the feed
+                let uid = if times.recurring {
+                    format!("page-{}-{}@{domain}", page.id, start_date.format("%Y%m%d"))
+                } else {
+                    format!("page-{}@{domain}", page.id)
+                };
+                let location = times.venue.as_ref().map(|venue| match &venue.address {
+                    Some(address) => format!("{}, {address}", venue.name),
+                    None => venue.name.clone(),
+                });
+                let time = EventTime::Moments {
+                    starts_at: times.starts_at,
+                    ends_at: times.ends_at,
+                };
+                (uid, time, location)
+            }
+        };
+        Some(Event {
+            uid,
+            sequence: page.change_sequence,
+            summary: page.title.clone(),
+            time,
+            location,
+            last_modified: page.last_modified,
+            url: (link.starts_with("https://") || link.starts_with("http://")).then_some(link),
+        })
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn CalendarRequestService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn CalendarRequestService> {
+        let implementation = Self {
+            page_repository: dependencies.page_repository.clone(),
+            member_repository: dependencies.member_repository.clone(),
+            authorization_repository: dependencies.authorization_repository.clone(),
+            calendar_feed_repository: dependencies.calendar_feed_repository.clone(),
+            event_settings: dependencies.settings.events.clone(),
+            content_settings: dependencies.settings.content.clone(),
+        };
+        let arc: Arc<dyn CalendarRequestService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
@@ -16,8 +16,11 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::{Cached, Visibility};
+use crate::generic::imaging::{self, Variant};
 use crate::generic::result::{BackendError, BackendResult};
 use crate::generic::security::ClaimRoles;
+use crate::generic::storage::assets::{variants, AssetStore};
 use crate::generic::storage::session::Session;
 use crate::generic::{search_helpers, Injectable};
 use crate::injection::ServiceDependencies;
@@ -28,15 +31,16 @@ use crate::model::storage::entities::Image;
 use crate::model::traits::RoleContainer;
 use crate::repositories::definitions::ImageRepository;
 use crate::services::definitions::request::ImageRequestService;
-use actix_web::http::header::ContentType;
 use actix_web::web::Data;
+use image::ImageFormat;
 use std::collections::HashSet;
-use std::fs::OpenOptions;
 use std::io::Read;
 use std::sync::Arc;
 
 pub struct Implementation {
     image_repository: Data<dyn ImageRepository>,
+    asset_store: Data<dyn AssetStore>,
+    max_dimension: u32,
 }
 
 impl ImageRequestService for Implementation {
@@ -71,7 +75,8 @@ impl ImageRequestService for Implementation {
         mut session: Session,
         image_id: i32,
         roles: &ClaimRoles,
-    ) -> BackendResult<ImageResponse> {
+        variant: &Variant,
+    ) -> BackendResult<Cached<ImageResponse>> {
         let known_roles = self
             .image_repository
             .find_associated_roles_by_id(&mut session, image_id)?;
@@ -83,11 +88,39 @@ impl ImageRequestService for Implementation {
         }
 
         let image = self.image_repository.find_by_id(&mut session, image_id)?;
-        let content = Self::read_asset(&image.asset)?;
-        Ok(ImageResponse {
-            bytes: content,
-            content_type: ContentType::png(),
-        })
+        let format = match image.format.as_deref().and_then(imaging::parse_format_name) {
+            Some(format) => format,
+            None => {
+                let mut header = [0u8; 32];
+                let length = self
+                    .asset_store
+                    .stream(&image.asset)?
+                    .read(&mut header)
+                    .map_err(|e| BackendError::asset(format!("Asset {}: {e}", image.asset)))?;
+                imaging::guess_format(&header[..length]).unwrap_or(ImageFormat::Png)
+            }
+        };
+        let (format, content) = variants::load(
+            self.asset_store.get_ref(),
+            &image.asset,
+            format,
+            variant,
+            self.max_dimension,
+        )?;
+        let visibility = if known_roles.contains(&Role::Public) {
+            Visibility::Public
+        } else {
+            Visibility::Private
+        };
+        // Assets are never changed, a variant is identified by its asset identifier
+        Ok(Cached::new(
+            ImageResponse {
+                bytes: content,
+                content_type: imaging::content_type(format),
+            },
+            imaging::variant_etag(&image.asset, variant.width, format),
+            visibility,
+        ))
     }
 
     fn search(
@@ -116,14 +149,6 @@ impl ImageRequestService for Implementation {
 }
 
 impl Implementation {
-    fn read_asset(asset_id: &String) -> BackendResult<Vec<u8>> {
-        let pb = crate::path_for_asset(&asset_id)?;
-        let mut r = OpenOptions::new().read(true).open(&pb)?;
-        let mut buf = Vec::new();
-        let _ = r.read_to_end(&mut buf)?;
-        Ok(buf)
-    }
-
     fn merge_roles(&self, session: &mut Session, i: &Image) -> ImageMetaDataResponse {
         let roles = self
             .image_repository
@@ -137,6 +162,8 @@ impl Injectable<ServiceDependencies, dyn ImageRequestService> for Implementation
     fn make(dependencies: &ServiceDependencies) -> Data<dyn ImageRequestService> {
         let implementation = Self {
             image_repository: dependencies.image_repository.clone(),
+            asset_store: dependencies.asset_store.clone(),
+            max_dimension: dependencies.settings.images.max_dimension,
         };
         let arc: Arc<dyn ImageRequestService> = Arc::new(implementation);
         Data::from(arc)
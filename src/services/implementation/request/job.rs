@@ -0,0 +1,71 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::commands::jobs::registry::JobRegistry;
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::responses::{JobResponse, JobRunResponse};
+use crate::repositories::definitions::JobRunRepository;
+use crate::services::definitions::request::JobRequestService;
+use actix_web::web::Data;
+use std::sync::Arc;
+
+/// The amount of runs shown in the history of a job
+const RUN_HISTORY_SIZE: i64 = 25;
+
+pub struct Implementation {
+    job_registry: Data<JobRegistry>,
+    job_run_repository: Data<dyn JobRunRepository>,
+}
+
+impl JobRequestService for Implementation {
+    fn list(&self, mut session: Session) -> BackendResult<Vec<JobResponse>> {
+        let mut result = Vec::new();
+        for job in self.job_registry.jobs() {
+            let last_run = self
+                .job_run_repository
+                .find_last(&mut session, job.definition.name)?;
+            result.push(JobResponse::from((job, last_run.as_ref())));
+        }
+        Ok(result)
+    }
+
+    fn runs(&self, mut session: Session, job_name: &str) -> BackendResult<Vec<JobRunResponse>> {
+        let job = self.job_registry.find(job_name)?;
+        let runs = self.job_run_repository.list_recent(
+            &mut session,
+            job.definition.name,
+            RUN_HISTORY_SIZE,
+        )?;
+        Ok(runs.iter().map(JobRunResponse::from).collect())
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn JobRequestService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn JobRequestService> {
+        let implementation = Self {
+            job_registry: dependencies.job_registry.clone(),
+            job_run_repository: dependencies.job_run_repository.clone(),
+        };
+        let arc: Arc<dyn JobRequestService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}
@@ -16,7 +16,10 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::generic::caching::{Cached, Visibility};
+use crate::generic::imaging::{self, Variant};
 use crate::generic::result::{BackendError, BackendResult};
+use crate::generic::storage::assets::{variants, AssetStore};
 use crate::generic::storage::session::Session;
 use crate::generic::Injectable;
 use crate::injection::ServiceDependencies;
@@ -26,14 +29,14 @@ use crate::model::storage::extended_entities::ExtendedMember;
 use crate::model::traits::RoleContainer;
 use crate::repositories::definitions::MemberRepository;
 use crate::services::definitions::request::MemberPictureRequestService;
-use actix_web::http::header::ContentType;
 use actix_web::web::Data;
-use std::fs::OpenOptions;
-use std::io::Read;
+use image::ImageFormat;
 use std::sync::Arc;
 
 pub struct Implementation {
     member_repository: Data<dyn MemberRepository>,
+    asset_store: Data<dyn AssetStore>,
+    max_dimension: u32,
 }
 
 impl MemberPictureRequestService for Implementation {
@@ -42,17 +45,25 @@ impl MemberPictureRequestService for Implementation {
         mut session: Session,
         member_id: i32,
         role_container: &dyn RoleContainer,
-    ) -> BackendResult<Option<ImageResponse>> {
+        variant: &Variant,
+    ) -> BackendResult<Option<Cached<ImageResponse>>> {
         let result = if role_container.has_role(Role::Operator) {
-            self.handle_retrieve_member_picture_operator(&mut session, member_id)?
+            self.handle_retrieve_member_picture_operator(&mut session, member_id, variant)?
         } else if role_container.has_role(Role::Public) {
-            self.handle_retrieve_member_picture_dpia(&mut session, member_id)?
+            self.handle_retrieve_member_picture_dpia(&mut session, member_id, variant)?
         } else {
             return Err(BackendError::bad());
         };
-        Ok(result.map(|it| ImageResponse {
-            bytes: it,
-            content_type: ContentType::png(),
+        // Pictures are personal data, which must never be stored by shared caches
+        Ok(result.map(|(asset_id, format, bytes)| {
+            Cached::new(
+                ImageResponse {
+                    bytes,
+                    content_type: imaging::content_type(format),
+                },
+                imaging::variant_etag(&asset_id, variant.width, format),
+                Visibility::Private,
+            )
         }))
     }
 
@@ -86,51 +97,57 @@ impl Implementation {
         &self,
         session: &mut Session,
         member_id: i32,
-    ) -> BackendResult<Option<Vec<u8>>> {
+        variant: &Variant,
+    ) -> BackendResult<Option<(String, ImageFormat, Vec<u8>)>> {
         let extended_member = self
             .member_repository
             .find_extended_by_id(session, member_id)?;
-        Self::read_member_picture_asset(extended_member)
+        self.read_member_picture_asset(extended_member, variant)
     }
 
     fn handle_retrieve_member_picture_dpia(
         &self,
         session: &mut Session,
         member_id: i32,
-    ) -> BackendResult<Option<Vec<u8>>> {
+        variant: &Variant,
+    ) -> BackendResult<Option<(String, ImageFormat, Vec<u8>)>> {
         let extended_member = self
             .member_repository
             .find_extended_by_id(session, member_id)?;
         if extended_member.allow_privacy_info_sharing {
-            Self::read_member_picture_asset(extended_member)
+            self.read_member_picture_asset(extended_member, variant)
         } else {
             Ok(None)
         }
     }
 
     fn read_member_picture_asset(
+        &self,
         extended_member: ExtendedMember,
-    ) -> BackendResult<Option<Vec<u8>>> {
+        variant: &Variant,
+    ) -> BackendResult<Option<(String, ImageFormat, Vec<u8>)>> {
         if let Some(asset_id) = extended_member.picture_asset_id {
-            Ok(Some(Self::read_asset(&asset_id)?))
+            // Member pictures are always stored as PNG
+            let (format, data) = variants::load(
+                self.asset_store.get_ref(),
+                &asset_id,
+                ImageFormat::Png,
+                variant,
+                self.max_dimension,
+            )?;
+            Ok(Some((asset_id, format, data)))
         } else {
             Ok(None)
         }
     }
-
-    fn read_asset(asset_id: &String) -> BackendResult<Vec<u8>> {
-        let pb = crate::path_for_asset(&asset_id)?;
-        let mut r = OpenOptions::new().read(true).open(&pb)?;
-        let mut v = Vec::new();
-        r.read_to_end(&mut v)?;
-        Ok(v)
-    }
 }
 
 impl Injectable<ServiceDependencies, dyn MemberPictureRequestService> for Implementation {
     fn make(dependencies: &ServiceDependencies) -> Data<dyn MemberPictureRequestService> {
         let implementation = Self {
             member_repository: dependencies.member_repository.clone(),
+            asset_store: dependencies.asset_store.clone(),
+            max_dimension: dependencies.settings.images.max_dimension,
         };
 
         let arc: Arc<dyn MemberPictureRequestService> = Arc::new(implementation);
@@ -16,9 +16,13 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+pub mod absence;
+pub mod attendance;
 pub mod authorization;
+pub mod calendar;
 pub mod facebook;
 pub mod image;
+pub mod job;
 pub mod mail_template;
 pub mod member;
 pub mod member_picture;
@@ -26,4 +30,5 @@ pub mod musical_instrument;
 pub mod page;
 pub mod role;
 pub mod setup;
+pub mod venue;
 pub mod workgroup;
@@ -16,28 +16,40 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::generic::lazy::MAX_EVENT_DAYS;
+use crate::generic::caching::{Cached, Visibility};
 use crate::generic::result::{BackendError, BackendResult};
 use crate::generic::security::ClaimRoles;
+use crate::generic::storage::assets::AssetStore;
 use crate::generic::storage::session::Session;
-use crate::generic::{search_helpers, Injectable};
+use crate::generic::{search_helpers, slug, Injectable};
 use crate::injection::ServiceDependencies;
-use crate::model::interface::responses::{ExtendedPageResponse, PageResponse};
+use crate::model::interface::responses::{
+    EventResponse, ExtendedPageResponse, ExtendedPageRevisionResponse, PageEventResponse,
+    PageResponse, PageRevisionResponse, PageSearchResponse, PageTreeResponse,
+};
 use crate::model::interface::search::{SearchParams, SearchResult};
-use crate::model::primitives::Role;
+use crate::model::primitives::{AbsenceVisibility, Role};
+use crate::model::storage::entities::Page;
 use crate::model::traits::RoleContainer;
-use crate::repositories::definitions::{PageRepository, PropertiesRepository};
-use crate::services::definitions::request::PageRequestService;
+use crate::repositories::definitions::{
+    AbsenceRepository, PageRepository, PageRevisionRepository, PropertiesRepository,
+};
+use crate::services::definitions::request::{PageLookup, PageRequestService};
+use crate::services::implementation::content::ContentRenderer;
 use actix_web::web::Data;
 use chrono::{Days, Utc};
+use similar::TextDiff;
 use std::collections::HashSet;
-use std::fs::OpenOptions;
-use std::io::Read;
 use std::sync::Arc;
 
 pub struct Implementation {
     page_repository: Data<dyn PageRepository>,
+    absence_repository: Data<dyn AbsenceRepository>,
+    page_revision_repository: Data<dyn PageRevisionRepository>,
     properties_repository: Data<dyn PropertiesRepository>,
+    asset_store: Data<dyn AssetStore>,
+    renderer: ContentRenderer,
+    max_event_days: u32,
 }
 
 impl PageRequestService for Implementation {
@@ -46,25 +58,70 @@ impl PageRequestService for Implementation {
         mut session: Session,
         page_id: i32,
         roles: &ClaimRoles,
-    ) -> BackendResult<ExtendedPageResponse> {
-        let known_roles = self
-            .page_repository
-            .find_associated_roles_by_id(&mut session, page_id)?;
+    ) -> BackendResult<Cached<ExtendedPageResponse>> {
+        let (page, known_roles) = self.find_accessible(&mut session, page_id, roles)?;
 
-        if !roles.has_role(Role::Operator) {
-            let known_role_set: HashSet<Role> = HashSet::from_iter(known_roles.iter().cloned());
-            if roles.set().is_disjoint(&known_role_set) {
-                return Err(BackendError::forbidden());
-            }
-        }
+        // Operators receive the roles as well, which must never be served to others
+        let cached = if roles.has_role(Role::Operator) {
+            Cached::new(
+                ExtendedPageResponse::from((&page, &known_roles)),
+                format!("{}-operator", page.etag),
+                Visibility::Private,
+            )
+        } else {
+            Cached::new(
+                ExtendedPageResponse::from((&page, &vec![])),
+                page.etag.clone(),
+                Self::visibility(&known_roles),
+            )
+        };
+        Ok(cached.with_last_modified(page.last_modified))
+    }
 
-        let page = self.page_repository.find_by_id(&mut session, page_id)?;
+    fn find_by_path(
+        &self,
+        mut session: Session,
+        path: &str,
+        roles: &ClaimRoles,
+    ) -> BackendResult<PageLookup> {
+        let slugs = slug::segments(path);
+        if slugs.is_empty() {
+            return Err(BackendError::bad());
+        }
+        if let Some(page_id) = self.page_repository.find_by_path(&mut session, &slugs)? {
+            return Ok(PageLookup::Found(self.find_by_id(session, page_id, roles)?));
+        }
 
-        if roles.has_role(Role::Operator) {
-            Ok(ExtendedPageResponse::from((&page, &known_roles)))
-        } else {
-            Ok(ExtendedPageResponse::from((&page, &vec![])))
+        // Descendants of a moved page are redirected through the former path of the moved page,
+        // hence the longest former path which is a prefix of the path is used
+        for length in (1..=slugs.len()).rev() {
+            let former_path = slugs[..length].join("/");
+            let maybe_page_id = self
+                .page_repository
+                .find_redirect(&mut session, &former_path)?;
+            if let Some(page_id) = maybe_page_id {
+                let mut current_path = self
+                    .page_repository
+                    .find_path_by_id(&mut session, page_id)?;
+                for slug in &slugs[length..] {
+                    current_path.push('/');
+                    current_path.push_str(slug);
+                }
+                // A page may have returned to its former path, which must not redirect to itself
+                if current_path == slugs.join("/") {
+                    break;
+                }
+                // The current path is only revealed to those who may access the page it leads to,
+                // which is the moved page itself if the path does not resolve (yet)
+                let target_id = self
+                    .page_repository
+                    .find_by_path(&mut session, &slug::segments(&current_path))?
+                    .unwrap_or(page_id);
+                self.find_accessible(&mut session, target_id, roles)?;
+                return Ok(PageLookup::Moved(current_path));
+            }
         }
+        Err(BackendError::not_found())
     }
 
     fn find_content_by_id(
@@ -72,7 +129,7 @@ impl PageRequestService for Implementation {
         mut session: Session,
         page_id: i32,
         roles: &ClaimRoles,
-    ) -> BackendResult<String> {
+    ) -> BackendResult<Cached<String>> {
         let known_roles = self
             .page_repository
             .find_associated_roles_by_id(&mut session, page_id)?;
@@ -84,8 +141,32 @@ impl PageRequestService for Implementation {
         }
 
         let page = self.page_repository.find_by_id(&mut session, page_id)?;
-        let content = Self::read_asset(&page.content_asset)?;
-        Ok(content)
+        Self::verify_publication(&page, roles)?;
+        let content = self.renderer.load(
+            &mut session,
+            page_id,
+            &page.content_asset,
+            page.content_format,
+        )?;
+        Ok(
+            Cached::new(content, page.etag, Self::visibility(&known_roles))
+                .with_last_modified(page.last_modified),
+        )
+    }
+
+    fn find_draft_content_by_id(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Cached<String>> {
+        Self::require_operator(roles)?;
+        let page = self.page_repository.find_by_id(&mut session, page_id)?;
+        let content = self.read_asset(page.latest_content_asset())?;
+        Ok(
+            Cached::new(content, format!("{}-draft", page.etag), Visibility::Private)
+                .with_last_modified(page.last_modified),
+        )
     }
 
     fn default(
@@ -97,7 +178,7 @@ impl PageRequestService for Implementation {
             .properties_repository
             .maybe_int_property(&mut session, "default-page");
         if let Some(page_id) = maybe_page_id {
-            Ok(Some(self.find_by_id(session, page_id, roles)?))
+            Ok(Some(self.find_by_id(session, page_id, roles)?.value))
         } else {
             Ok(None)
         }
@@ -115,17 +196,26 @@ impl PageRequestService for Implementation {
         Ok(pages.iter().map(PageResponse::from).collect())
     }
 
+    fn tree(
+        &self,
+        mut session: Session,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Vec<PageTreeResponse>> {
+        let pages = self.page_repository.list_visible(&mut session, roles)?;
+        Ok(PageTreeResponse::build(&pages, None))
+    }
+
     fn search(
         &self,
         mut session: Session,
         params: &SearchParams,
         roles: &ClaimRoles,
-    ) -> BackendResult<SearchResult<PageResponse>> {
+    ) -> BackendResult<SearchResult<PageSearchResponse>> {
         let term = params.term.clone().unwrap_or_default();
         let (total_count, page_size, results) =
             self.page_repository
                 .search(&mut session, params.page_offset, &term, roles)?;
-        let rows: Vec<PageResponse> = results.iter().map(PageResponse::from).collect();
+        let rows: Vec<PageSearchResponse> = results.iter().map(PageSearchResponse::from).collect();
         let row_len = rows.len();
         Ok(SearchResult {
             total_count,
@@ -137,34 +227,183 @@ impl PageRequestService for Implementation {
         })
     }
 
-    fn events(&self, mut session: Session, roles: &ClaimRoles) -> BackendResult<Vec<PageResponse>> {
-        let max_event_days = *MAX_EVENT_DAYS;
+    fn find_event(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Option<PageEventResponse>> {
+        self.find_accessible(&mut session, page_id, roles)?;
+        let event = self.page_repository.find_event(&mut session, page_id)?;
+        Ok(event
+            .as_ref()
+            .map(|(event, exceptions)| PageEventResponse::from((event, exceptions))))
+    }
+
+    fn events(
+        &self,
+        mut session: Session,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Vec<EventResponse>> {
+        let max_event_days = self.max_event_days;
         let start_scan_date = Utc::now().date_naive();
+        let end_scan_date = start_scan_date
+            .checked_add_days(Days::new(max_event_days as u64))
+            .ok_or_else(BackendError::bad)?;
+        let occurrences = self.page_repository.find_events(
+            &mut session,
+            roles,
+            &start_scan_date,
+            &end_scan_date,
+        )?;
+        // Events which started before today may have absent members from before today as well
+        let dates = occurrences.iter().filter_map(|occurrence| {
+            let start_date = occurrence.page.event_date?;
+            Some((
+                start_date,
+                occurrence.page.end_event_date.unwrap_or(start_date),
+            ))
+        });
+        let start_absence_date = dates.clone().map(|(start_date, _)| start_date).min();
+        let end_absence_date = dates.map(|(_, end_date)| end_date).max();
+        let visibilities = Self::absence_visibilities(roles);
+        let absences = match (start_absence_date, end_absence_date) {
+            (Some(start_date), Some(end_date)) if !visibilities.is_empty() => self
+                .absence_repository
+                .list_overlapping(&mut session, &start_date, &end_date, &visibilities)?,
+            _ => vec![],
+        };
+        Ok(occurrences
+            .iter()
+            .map(|occurrence| EventResponse::from((occurrence, absences.as_slice())))
+            .collect())
+    }
 
-        if let Some(end_scan_date) =
-            start_scan_date.checked_add_days(Days::new(max_event_days as u64))
-        {
-            let events = self.page_repository.find_events(
-                &mut session,
-                roles,
-                &start_scan_date,
-                &end_scan_date,
-            );
-            events.map(|pages| pages.iter().map(PageResponse::from).collect())
-        } else {
-            Err(BackendError::bad())
-        }
+    fn list_revisions(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<Vec<PageRevisionResponse>> {
+        Self::require_operator(roles)?;
+        // Verify that the page really exists
+        let _ = self.page_repository.find_by_id(&mut session, page_id)?;
+        let revisions = self
+            .page_revision_repository
+            .list_by_page_id(&mut session, page_id)?;
+        Ok(revisions
+            .iter()
+            .map(|(revision, author)| PageRevisionResponse::from((revision, author)))
+            .collect())
+    }
+
+    fn find_revision(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        revision_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<ExtendedPageRevisionResponse> {
+        Self::require_operator(roles)?;
+        let (revision, author) =
+            self.page_revision_repository
+                .find_by_id(&mut session, page_id, revision_id)?;
+        Ok(ExtendedPageRevisionResponse::from((&revision, &author)))
+    }
+
+    fn diff_revisions(
+        &self,
+        mut session: Session,
+        page_id: i32,
+        from_revision_id: i32,
+        to_revision_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<String> {
+        Self::require_operator(roles)?;
+        let (from, _) =
+            self.page_revision_repository
+                .find_by_id(&mut session, page_id, from_revision_id)?;
+        let (to, _) =
+            self.page_revision_repository
+                .find_by_id(&mut session, page_id, to_revision_id)?;
+        let diff = TextDiff::from_lines(&from.content, &to.content)
+            .unified_diff()
+            .header(
+                &format!("revision {from_revision_id}"),
+                &format!("revision {to_revision_id}"),
+            )
+            .to_string();
+        Ok(diff)
     }
 }
 
 impl Implementation {
-    fn read_asset(asset_id: &String) -> BackendResult<String> {
-        let pb = crate::path_for_asset(&asset_id)?;
-        if pb.exists() {
-            let mut r = OpenOptions::new().read(true).open(&pb)?;
-            let mut buf = String::new();
-            let _ = r.read_to_string(&mut buf)?;
-            Ok(buf)
+    /// Drafts and revisions reveal the content of pages regardless of their roles and
+    /// publication, hence they are only shown to operators
+    fn require_operator(roles: &ClaimRoles) -> BackendResult<()> {
+        if roles.has_role(Role::Operator) {
+            Ok(())
+        } else {
+            Err(BackendError::forbidden())
+        }
+    }
+
+    /// The absences the roles may see, anonymous visitors see none of them
+    fn absence_visibilities(roles: &ClaimRoles) -> Vec<AbsenceVisibility> {
+        let committee = [Role::OrchestraCommittee, Role::Director, Role::Operator];
+        if committee.iter().any(|role| roles.has_role(*role)) {
+            vec![AbsenceVisibility::Members, AbsenceVisibility::Committee]
+        } else if roles.has_role(Role::Member) {
+            vec![AbsenceVisibility::Members]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Finds a page which the roles may access, alongside the roles associated with the page
+    fn find_accessible(
+        &self,
+        session: &mut Session,
+        page_id: i32,
+        roles: &ClaimRoles,
+    ) -> BackendResult<(Page, Vec<Role>)> {
+        let known_roles = self
+            .page_repository
+            .find_associated_roles_by_id(session, page_id)?;
+
+        if !roles.has_role(Role::Operator) {
+            let known_role_set: HashSet<Role> = HashSet::from_iter(known_roles.iter().cloned());
+            if roles.set().is_disjoint(&known_role_set) {
+                return Err(BackendError::forbidden());
+            }
+        }
+
+        let page = self.page_repository.find_by_id(session, page_id)?;
+        Self::verify_publication(&page, roles)?;
+        Ok((page, known_roles))
+    }
+
+    /// Pages outside their publication window are only available to operators
+    fn verify_publication(page: &Page, roles: &ClaimRoles) -> BackendResult<()> {
+        if roles.has_role(Role::Operator) || page.is_published_at(Utc::now().naive_utc()) {
+            Ok(())
+        } else {
+            Err(BackendError::forbidden())
+        }
+    }
+
+    fn visibility(known_roles: &[Role]) -> Visibility {
+        if known_roles.contains(&Role::Public) {
+            Visibility::Public
+        } else {
+            Visibility::Private
+        }
+    }
+
+    fn read_asset(&self, asset_id: &String) -> BackendResult<String> {
+        if self.asset_store.exists(asset_id)? {
+            let data = self.asset_store.get(asset_id)?;
+            String::from_utf8(data).map_err(BackendError::byte_conversion)
         } else {
             Ok("".to_owned())
         }
@@ -175,9 +414,37 @@ impl Injectable<ServiceDependencies, dyn PageRequestService> for Implementation
     fn make(dependencies: &ServiceDependencies) -> Data<dyn PageRequestService> {
         let implementation = Self {
             page_repository: dependencies.page_repository.clone(),
+            absence_repository: dependencies.absence_repository.clone(),
+            page_revision_repository: dependencies.page_revision_repository.clone(),
             properties_repository: dependencies.properties_repository.clone(),
+            asset_store: dependencies.asset_store.clone(),
+            renderer: ContentRenderer::new(dependencies),
+            max_event_days: dependencies.settings.events.max_days,
         };
         let arc: Arc<dyn PageRequestService> = Arc::new(implementation);
         Data::from(arc)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visibilities(roles: &[Role]) -> Vec<AbsenceVisibility> {
+        Implementation::absence_visibilities(&ClaimRoles::from(roles))
+    }
+
+    #[test]
+    fn absences_are_visible_according_to_the_roles() {
+        use AbsenceVisibility::{Committee, Members};
+        assert_eq!(visibilities(&[]), []);
+        assert_eq!(visibilities(&[Role::Public]), []);
+        assert_eq!(visibilities(&[Role::Member]), [Members]);
+        assert_eq!(
+            visibilities(&[Role::Member, Role::OrchestraCommittee]),
+            [Members, Committee]
+        );
+        assert_eq!(visibilities(&[Role::Director]), [Members, Committee]);
+        assert_eq!(visibilities(&[Role::Operator]), [Members, Committee]);
+    }
+}
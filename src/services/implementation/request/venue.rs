@@ -0,0 +1,48 @@
+/*
+ *  ONVP Backend - Backend API provider for the ONVP website
+ *
+ * Copyright (c) 2025.  Sjoerd van Leent
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::generic::result::BackendResult;
+use crate::generic::storage::session::Session;
+use crate::generic::Injectable;
+use crate::injection::ServiceDependencies;
+use crate::model::interface::responses::VenueResponse;
+use crate::repositories::definitions::VenueRepository;
+use crate::services::definitions::request::VenueRequestService;
+use actix_web::web::Data;
+use std::sync::Arc;
+
+pub struct Implementation {
+    venue_repository: Data<dyn VenueRepository>,
+}
+
+impl VenueRequestService for Implementation {
+    fn list(&self, mut session: Session) -> BackendResult<Vec<VenueResponse>> {
+        let venues = self.venue_repository.list(&mut session)?;
+        Ok(venues.iter().map(VenueResponse::from).collect())
+    }
+}
+
+impl Injectable<ServiceDependencies, dyn VenueRequestService> for Implementation {
+    fn make(dependencies: &ServiceDependencies) -> Data<dyn VenueRequestService> {
+        let implementation = Self {
+            venue_repository: dependencies.venue_repository.clone(),
+        };
+        let arc: Arc<dyn VenueRequestService> = Arc::new(implementation);
+        Data::from(arc)
+    }
+}